@@ -0,0 +1,54 @@
+//! Async hypertable management example for diesel-timescaledb
+//!
+//! Run with `cargo run --example async_hypertable --features async`.
+
+use diesel::prelude::*;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use diesel_timescaledb::prelude::*;
+use diesel_timescaledb::schema::chunks::{drop_old_chunks_async, get_chunk_info_async};
+
+table! {
+    async_metrics (time) {
+        time -> Timestamptz,
+        value -> Float8,
+    }
+}
+
+hypertable!(async_metrics, time);
+
+#[tokio::main]
+async fn main() {
+    let database_url = std::env::var("DATABASE_URL")
+        .unwrap_or_else(|_| "postgresql://localhost/timescale".to_string());
+    let mut conn = match AsyncPgConnection::establish(&database_url).await {
+        Ok(conn) => conn,
+        Err(e) => {
+            println!("Could not connect: {}", e);
+            return;
+        }
+    };
+
+    diesel::sql_query(
+        "CREATE TABLE IF NOT EXISTS async_metrics (time TIMESTAMPTZ NOT NULL, value FLOAT8)",
+    )
+    .execute(&mut conn)
+    .await
+    .expect("Error creating table");
+
+    async_metrics::table::create_hypertable_async(&mut conn)
+        .await
+        .expect("Error creating hypertable");
+
+    let chunks = get_chunk_info_async(&mut conn, "async_metrics")
+        .await
+        .expect("Error loading chunks");
+    println!("async_metrics has {} chunks", chunks.len());
+
+    drop_old_chunks_async(
+        &mut conn,
+        "async_metrics",
+        chrono::Utc::now() - chrono::Duration::days(30),
+    )
+    .await
+    .expect("Error dropping chunks");
+}
@@ -0,0 +1,85 @@
+//! Streaming insert example for diesel-timescaledb
+//!
+//! Run with `cargo run --example async_insert_sink --features async`.
+//!
+//! Feeds readings through an `InsertSink`, which batches them into
+//! multi-row inserts on an async connection.
+
+use chrono::{DateTime, Duration, Utc};
+use diesel::prelude::*;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use diesel_timescaledb::prelude::*;
+use diesel_timescaledb::InsertSink;
+use futures_util::SinkExt;
+
+table! {
+    sink_readings (timestamp, device_id) {
+        timestamp -> Timestamptz,
+        device_id -> Text,
+        value -> Float8,
+    }
+}
+
+hypertable!(sink_readings, timestamp);
+
+#[derive(Insertable)]
+#[diesel(table_name = sink_readings)]
+struct Reading {
+    timestamp: DateTime<Utc>,
+    device_id: String,
+    value: f64,
+}
+
+#[tokio::main]
+async fn main() {
+    let database_url = std::env::var("DATABASE_URL")
+        .unwrap_or_else(|_| "postgresql://localhost/timescale".to_string());
+    let mut conn = match AsyncPgConnection::establish(&database_url).await {
+        Ok(conn) => conn,
+        Err(e) => {
+            println!("Could not connect: {}", e);
+            return;
+        }
+    };
+
+    diesel::sql_query(
+        "CREATE TABLE IF NOT EXISTS sink_readings (
+             timestamp TIMESTAMPTZ NOT NULL,
+             device_id TEXT NOT NULL,
+             value DOUBLE PRECISION NOT NULL,
+             PRIMARY KEY (timestamp, device_id)
+         )",
+    )
+    .execute(&mut conn)
+    .await
+    .expect("Error creating table");
+
+    sink_readings::table::create_hypertable_async(&mut conn)
+        .await
+        .expect("Error creating hypertable");
+
+    // Batches of 500 rows, or whatever arrived within 200ms
+    let mut sink = InsertSink::new(
+        conn,
+        sink_readings::table,
+        500,
+        std::time::Duration::from_millis(200),
+    )
+    .expect("Error creating sink");
+
+    let start = Utc::now() - Duration::hours(1);
+    for i in 0..2_000 {
+        let reading = Reading {
+            timestamp: start + Duration::seconds(i),
+            device_id: format!("device-{}", i % 4),
+            value: (i % 100) as f64 / 4.0,
+        };
+        sink.feed(reading).await.expect("Error sending reading");
+    }
+
+    // Wait until everything sent so far is written
+    sink.flush().await.expect("Error flushing readings");
+
+    let written = sink.finish().await.expect("Error finishing sink");
+    println!("Inserted {} readings", written);
+}
@@ -0,0 +1,114 @@
+//! Bulk loading example for diesel-timescaledb
+//!
+//! Run with `cargo run --release --example bulk_copy`.
+//!
+//! Loads the same readings with batched `INSERT` statements and with `COPY`
+//! and prints how long each took.
+
+use chrono::{DateTime, Duration, Utc};
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+use diesel_timescaledb::bulk::copy_in;
+use std::time::Instant;
+
+table! {
+    bulk_readings (timestamp, device_id) {
+        timestamp -> Timestamptz,
+        device_id -> Text,
+        value -> Float8,
+    }
+}
+
+const ROWS: usize = 200_000;
+const BATCH_SIZE: usize = 5_000;
+
+fn readings(start: DateTime<Utc>) -> impl Iterator<Item = (DateTime<Utc>, String, f64)> {
+    (0..ROWS).map(move |i| {
+        (
+            start + Duration::seconds(i as i64),
+            format!("device-{}", i % 16),
+            (i % 100) as f64 / 4.0,
+        )
+    })
+}
+
+fn main() {
+    let database_url = std::env::var("DATABASE_URL")
+        .unwrap_or_else(|_| "postgresql://localhost/timescale".to_string());
+    let mut conn = match PgConnection::establish(&database_url) {
+        Ok(conn) => conn,
+        Err(e) => {
+            println!("Could not connect: {}", e);
+            return;
+        }
+    };
+
+    conn.batch_execute(
+        "DROP TABLE IF EXISTS bulk_readings;
+         CREATE TABLE bulk_readings (
+             timestamp TIMESTAMPTZ NOT NULL,
+             device_id TEXT NOT NULL,
+             value DOUBLE PRECISION NOT NULL,
+             PRIMARY KEY (timestamp, device_id)
+         )",
+    )
+    .expect("Error creating table");
+
+    // Batched multi-row INSERT statements
+    let start = Utc::now() - Duration::days(7);
+    let rows: Vec<_> = readings(start).collect();
+    let started = Instant::now();
+    for batch in rows.chunks(BATCH_SIZE) {
+        let values: Vec<_> = batch
+            .iter()
+            .map(|(timestamp, device_id, value)| {
+                (
+                    bulk_readings::timestamp.eq(timestamp),
+                    bulk_readings::device_id.eq(device_id),
+                    bulk_readings::value.eq(value),
+                )
+            })
+            .collect();
+        diesel::insert_into(bulk_readings::table)
+            .values(&values)
+            .execute(&mut conn)
+            .expect("Error inserting readings");
+    }
+    println!("INSERT: {} rows in {:?}", rows.len(), started.elapsed());
+
+    diesel::delete(bulk_readings::table)
+        .execute(&mut conn)
+        .expect("Error clearing table");
+
+    // COPY, streaming the rows from the iterator
+    let started = Instant::now();
+    let written = copy_in(
+        &mut conn,
+        bulk_readings::table,
+        (
+            bulk_readings::timestamp,
+            bulk_readings::device_id,
+            bulk_readings::value,
+        ),
+        readings(start),
+    )
+    .expect("Error copying readings");
+    println!("COPY:   {} rows in {:?}", written, started.elapsed());
+
+    // A rejected row aborts the whole COPY
+    let duplicate = rows[0].clone();
+    let result = copy_in(
+        &mut conn,
+        bulk_readings::table,
+        (
+            bulk_readings::timestamp,
+            bulk_readings::device_id,
+            bulk_readings::value,
+        ),
+        std::iter::once(duplicate),
+    );
+    println!("Duplicate row: {:?}", result.map_err(|e| e.to_string()));
+
+    conn.batch_execute("DROP TABLE bulk_readings")
+        .expect("Error dropping table");
+}
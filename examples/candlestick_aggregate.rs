@@ -0,0 +1,52 @@
+//! One-minute candlestick example for diesel-timescaledb
+//!
+//! Run with `cargo run --example candlestick_aggregate --features toolkit`.
+//! Requires the `timescaledb_toolkit` extension.
+
+use diesel::prelude::*;
+use diesel_timescaledb::functions::toolkit::{Candle, CandlestickAggregateSpec};
+use diesel_timescaledb::prelude::*;
+
+fn main() {
+    // Pre-compute the open, high, low and close of every minute of trades
+    let spec = CandlestickAggregateSpec {
+        source: "trades".to_string(),
+        time_column: "time".to_string(),
+        price_column: "price".to_string(),
+        volume_column: Some("quantity".to_string()),
+        bucket: 1.minutes(),
+    };
+    let config = spec
+        .to_continuous_aggregate_config("trades_1m")
+        .expect("Invalid candlestick parameters")
+        .with_refresh_interval("1 minute");
+    for statement in config.create_sql().expect("Invalid aggregate") {
+        println!("{}", statement);
+    }
+
+    let database_url = std::env::var("DATABASE_URL")
+        .unwrap_or_else(|_| "postgresql://localhost/timescale".to_string());
+    let mut conn = match PgConnection::establish(&database_url) {
+        Ok(conn) => conn,
+        Err(e) => {
+            println!("Could not connect: {}", e);
+            return;
+        }
+    };
+
+    config
+        .create(&mut conn)
+        .expect("Error creating continuous aggregate");
+
+    let candles: Vec<Candle> = spec
+        .candles_query("trades_1m")
+        .expect("Invalid view name")
+        .load(&mut conn)
+        .expect("Error reading candles");
+    for candle in candles.iter().take(5) {
+        println!(
+            "{} O {:.2} H {:.2} L {:.2} C {:.2} V {:?}",
+            candle.bucket, candle.open, candle.high, candle.low, candle.close, candle.volume
+        );
+    }
+}
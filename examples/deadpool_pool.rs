@@ -0,0 +1,28 @@
+//! Async connection pooling example for diesel-timescaledb
+//!
+//! Run with `cargo run --example deadpool_pool --features deadpool`.
+
+use diesel_timescaledb::pool::build_pool;
+use diesel_timescaledb::version::installed_version;
+
+#[tokio::main]
+async fn main() {
+    let database_url = std::env::var("DATABASE_URL")
+        .unwrap_or_else(|_| "postgresql://localhost/timescale".to_string());
+
+    let pool = build_pool(database_url, 8).expect("Error building pool");
+    println!("Pool status: {:?}", pool.status());
+
+    // Checking out a connection requires a running TimescaleDB instance
+    match pool.get().await {
+        Ok(conn) => {
+            let version = conn
+                .interact(installed_version)
+                .await
+                .expect("Connection panicked")
+                .expect("Error reading TimescaleDB version");
+            println!("TimescaleDB version: {}", version);
+        }
+        Err(e) => println!("Could not get a connection: {}", e),
+    }
+}
@@ -0,0 +1,71 @@
+//! Gapfilling example for diesel-timescaledb
+//!
+//! Run with `cargo run --example gapfilling`.
+//! Requires TimescaleDB, which provides `time_bucket_gapfill`.
+
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use diesel::prelude::*;
+use diesel_timescaledb::dsl::TimeRange;
+use diesel_timescaledb::functions::utilities::time_bucket_gapfill_expr;
+use diesel_timescaledb::functions::{interpolate, locf};
+use diesel_timescaledb::prelude::*;
+
+table! {
+    readings (time) {
+        time -> Timestamptz,
+        value -> Float8,
+    }
+}
+
+/// A bucket with its average, carried forward average and interpolated average.
+type GapfilledRow = (DateTime<Utc>, Option<f64>, Option<f64>, Option<f64>);
+
+fn main() {
+    let database_url = std::env::var("DATABASE_URL")
+        .unwrap_or_else(|_| "postgresql://localhost/timescale".to_string());
+    let mut conn = match PgConnection::establish(&database_url) {
+        Ok(conn) => conn,
+        Err(e) => {
+            println!("Could not connect: {}", e);
+            return;
+        }
+    };
+
+    // Readings in only two of the last six hours
+    diesel::sql_query(
+        "CREATE TEMPORARY TABLE readings AS 
+         SELECT date_trunc('hour', now()) - n * INTERVAL '1 hour' AS time, n::float8 AS value 
+         FROM unnest(ARRAY[1, 4]) AS n",
+    )
+    .execute(&mut conn)
+    .expect("Error creating sample data");
+
+    let end = Utc::now()
+        .duration_trunc(Duration::hours(1))
+        .expect("Error truncating timestamp");
+    let range = TimeRange::new(end - Duration::hours(6), end).expect("Invalid time range");
+    let bucket = time_bucket_gapfill_expr(1.hours(), readings::time, range);
+
+    let query = readings::table
+        .filter(readings::time.ge(range.start()))
+        .filter(readings::time.lt(range.end()))
+        .group_by(bucket.clone())
+        .select((
+            bucket.clone(),
+            diesel::dsl::avg(readings::value),
+            locf(diesel::dsl::avg(readings::value)),
+            interpolate(diesel::dsl::avg(readings::value)),
+        ))
+        .order(bucket);
+    println!(
+        "Gapfill query: {}",
+        diesel::debug_query::<diesel::pg::Pg, _>(&query)
+    );
+
+    let rows: Vec<GapfilledRow> = query
+        .load(&mut conn)
+        .expect("Error loading gapfilled buckets");
+    for (bucket, avg, carried, interpolated) in rows {
+        println!("{} {:?} {:?} {:?}", bucket, avg, carried, interpolated);
+    }
+}
@@ -0,0 +1,77 @@
+//! Latency percentiles example for diesel-timescaledb
+//!
+//! Run with `cargo run --example latency_percentiles --features toolkit`.
+//! Requires the `timescaledb_toolkit` extension.
+
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel_timescaledb::dsl::TimeBucket;
+use diesel_timescaledb::functions::toolkit::{
+    approx_percentile, approx_percentile_array, approx_percentile_tdigest, percentile_agg, tdigest,
+    QuantileValues, LATENCY_QUANTILES,
+};
+use diesel_timescaledb::prelude::*;
+
+table! {
+    requests (id) {
+        id -> Int4,
+        timestamp -> Timestamptz,
+        latency_ms -> Float8,
+    }
+}
+
+fn main() {
+    let database_url = std::env::var("DATABASE_URL")
+        .unwrap_or_else(|_| "postgresql://localhost/timescale".to_string());
+    let mut conn = match PgConnection::establish(&database_url) {
+        Ok(conn) => conn,
+        Err(e) => {
+            println!("Could not connect: {}", e);
+            return;
+        }
+    };
+
+    // p50, p90 and p99 per hour from a single percentile summary
+    let bucket = TimeBucket::new(1.hours(), requests::timestamp);
+    let rows: Vec<(DateTime<Utc>, Vec<f64>)> = requests::table
+        .group_by(bucket.clone())
+        .select((
+            bucket.clone(),
+            approx_percentile_array(
+                LATENCY_QUANTILES.to_vec(),
+                percentile_agg(requests::latency_ms),
+            ),
+        ))
+        .load(&mut conn)
+        .expect("Error loading latency percentiles");
+
+    for (bucket, values) in rows {
+        let quantiles =
+            QuantileValues::new(&LATENCY_QUANTILES, values).expect("Unexpected result length");
+        println!(
+            "{}: p50={:?} p90={:?} p99={:?}",
+            bucket,
+            quantiles.get(0.5),
+            quantiles.get(0.9),
+            quantiles.get(0.99)
+        );
+    }
+
+    // p95 per hour, estimated from both summary types
+    let rows: Vec<(DateTime<Utc>, Option<f64>, Option<f64>)> = requests::table
+        .group_by(bucket.clone())
+        .select((
+            bucket,
+            approx_percentile(0.95, percentile_agg(requests::latency_ms)),
+            approx_percentile_tdigest(0.95, tdigest(100, requests::latency_ms)),
+        ))
+        .load(&mut conn)
+        .expect("Error loading p95 latencies");
+
+    for (bucket, uddsketch_p95, tdigest_p95) in rows {
+        println!(
+            "{}: p95={:?} (t-digest: {:?})",
+            bucket, uddsketch_p95, tdigest_p95
+        );
+    }
+}
@@ -0,0 +1,47 @@
+//! Pre-downsampled chart data example for diesel-timescaledb
+//!
+//! Run with `cargo run --example lttb_aggregate --features toolkit`.
+//! Requires the `timescaledb_toolkit` extension.
+
+use diesel::prelude::*;
+use diesel_timescaledb::functions::toolkit::{LttbAggregateSpec, TimeValue};
+use diesel_timescaledb::prelude::*;
+
+fn main() {
+    // Keep at most 200 points per day of readings
+    let spec = LttbAggregateSpec {
+        source: "sensor_readings".to_string(),
+        time_column: "time".to_string(),
+        value_column: "value".to_string(),
+        bucket: 1.days(),
+        resolution: 200,
+    };
+    let config = spec
+        .to_continuous_aggregate_config("sensor_readings_chart")
+        .expect("Invalid downsampling parameters")
+        .with_refresh_interval("1 hour");
+    for statement in config.create_sql().expect("Invalid aggregate") {
+        println!("{}", statement);
+    }
+
+    let database_url = std::env::var("DATABASE_URL")
+        .unwrap_or_else(|_| "postgresql://localhost/timescale".to_string());
+    let mut conn = match PgConnection::establish(&database_url) {
+        Ok(conn) => conn,
+        Err(e) => {
+            println!("Could not connect: {}", e);
+            return;
+        }
+    };
+
+    config
+        .create(&mut conn)
+        .expect("Error creating continuous aggregate");
+
+    let points: Vec<TimeValue> = spec
+        .points_query("sensor_readings_chart")
+        .expect("Invalid view name")
+        .load(&mut conn)
+        .expect("Error reading downsampled points");
+    println!("Read {} pre-downsampled points", points.len());
+}
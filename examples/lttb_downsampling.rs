@@ -0,0 +1,38 @@
+//! LTTB downsampling example for diesel-timescaledb
+//!
+//! Run with `cargo run --example lttb_downsampling --features toolkit`.
+//! Requires the `timescaledb_toolkit` extension.
+
+use diesel::prelude::*;
+use diesel_timescaledb::functions::toolkit::{lttb_query, TimeValue};
+
+fn main() {
+    // Downsample a million-point series to 500 points for a chart
+    let query = lttb_query("sensor_readings", "time", "value", 500)
+        .expect("Invalid downsampling parameters");
+    println!("LTTB query: {}", query);
+
+    let database_url = std::env::var("DATABASE_URL")
+        .unwrap_or_else(|_| "postgresql://localhost/timescale".to_string());
+    let mut conn = match PgConnection::establish(&database_url) {
+        Ok(conn) => conn,
+        Err(e) => {
+            println!("Could not connect: {}", e);
+            return;
+        }
+    };
+
+    diesel::sql_query(
+        "CREATE TEMPORARY TABLE sensor_readings AS 
+         SELECT now() - n * INTERVAL '1 second' AS time, sin(n / 1000.0) AS value 
+         FROM generate_series(1, 1000000) AS n",
+    )
+    .execute(&mut conn)
+    .expect("Error creating sample data");
+
+    let points: Vec<TimeValue> = query.load(&mut conn).expect("Error downsampling");
+    println!("Downsampled 1000000 rows to {} points", points.len());
+    for point in points.iter().take(5) {
+        println!("{} {}", point.time, point.value);
+    }
+}
@@ -0,0 +1,40 @@
+//! Connection pooling example for diesel-timescaledb
+//!
+//! Run with `cargo run --example r2d2_pool --features r2d2`.
+
+use diesel_timescaledb::r2d2::{build_r2d2_pool, TimescaleCustomizer};
+use diesel_timescaledb::schema::chunks::get_chunk_info;
+use std::thread;
+
+fn main() {
+    let database_url = std::env::var("DATABASE_URL")
+        .unwrap_or_else(|_| "postgresql://localhost/timescale".to_string());
+
+    let customizer = TimescaleCustomizer::new().with_create_extension(true);
+    let pool = match build_r2d2_pool(database_url, 4, customizer) {
+        Ok(pool) => pool,
+        Err(e) => {
+            println!("Could not build pool: {}", e);
+            return;
+        }
+    };
+    println!("Pool state: {:?}", pool.state());
+
+    // Check out several connections at once, one per thread
+    let handles: Vec<_> = (0..4)
+        .map(|worker| {
+            let pool = pool.clone();
+            thread::spawn(move || {
+                let mut conn = pool.get().expect("Error checking out a connection");
+                match get_chunk_info(&mut conn, "metrics") {
+                    Ok(chunks) => println!("Worker {}: {} chunks", worker, chunks.len()),
+                    Err(e) => println!("Worker {}: {}", worker, e),
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().expect("Worker panicked");
+    }
+}
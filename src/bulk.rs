@@ -0,0 +1,299 @@
+//! Bulk loading with `COPY ... FROM STDIN`.
+//!
+//! `COPY` streams rows to the server without a statement, bind parameters
+//! or a round trip per batch, and is the fastest way to ingest large amounts
+//! of time-series data into a hypertable. TimescaleDB routes copied rows to
+//! their chunks like inserted rows.
+//!
+//! [`copy_in`] takes the target table and columns from Diesel's schema, so
+//! their names are already known to be valid and are quoted by Diesel.
+//! [`copy_in_named`] takes them as strings, for tables that are only known
+//! at runtime, and validates them first. Rows implement [`CopyRow`], which
+//! tuples of [`CopyValue`]s do.
+
+use crate::error::TimescaleError;
+use crate::schema::{SqlIdentifier, ValidationError};
+use diesel::pg::{CopyFormat, CopyTarget, PgConnection};
+use diesel::prelude::*;
+use diesel::query_builder::AstPass;
+use diesel::Table;
+use stand_in::named_target;
+use std::cell::RefCell;
+use std::io::{BufWriter, Write};
+
+/// A value that can be written as a field of a CSV `COPY` row.
+pub trait CopyValue {
+    /// Write the value as a CSV field; `None` writes `NULL`.
+    fn write_copy_value(&self, out: &mut dyn Write) -> std::io::Result<()>;
+}
+
+/// A row that can be streamed with [`copy_in`].
+///
+/// The fields must be written in the order of the target columns.
+pub trait CopyRow {
+    /// Write the row as a CSV line, including the trailing newline.
+    fn write_copy_row(&self, out: &mut dyn Write) -> std::io::Result<()>;
+}
+
+impl CopyValue for str {
+    fn write_copy_value(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        // Quoting every string keeps the empty string apart from NULL
+        out.write_all(b"\"")?;
+        for (i, part) in self.split('"').enumerate() {
+            if i > 0 {
+                out.write_all(b"\"\"")?;
+            }
+            out.write_all(part.as_bytes())?;
+        }
+        out.write_all(b"\"")
+    }
+}
+
+impl CopyValue for String {
+    fn write_copy_value(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        self.as_str().write_copy_value(out)
+    }
+}
+
+impl<T: CopyValue + ?Sized> CopyValue for &T {
+    fn write_copy_value(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        (**self).write_copy_value(out)
+    }
+}
+
+impl<T: CopyValue> CopyValue for Option<T> {
+    fn write_copy_value(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        match self {
+            Some(value) => value.write_copy_value(out),
+            None => Ok(()),
+        }
+    }
+}
+
+impl CopyValue for chrono::DateTime<chrono::Utc> {
+    fn write_copy_value(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        write!(
+            out,
+            "{}",
+            self.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true)
+        )
+    }
+}
+
+impl CopyValue for crate::types::TimestampTz {
+    fn write_copy_value(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        self.0.write_copy_value(out)
+    }
+}
+
+macro_rules! copy_value_display {
+    ($($ty:ty),*) => {
+        $(
+            impl CopyValue for $ty {
+                fn write_copy_value(&self, out: &mut dyn Write) -> std::io::Result<()> {
+                    write!(out, "{}", self)
+                }
+            }
+        )*
+    };
+}
+
+copy_value_display!(bool, i16, i32, i64, f32, f64);
+
+macro_rules! copy_row_tuple {
+    ($first:ident $(, $rest:ident)*) => {
+        impl<$first: CopyValue, $($rest: CopyValue),*> CopyRow for ($first, $($rest,)*) {
+            #[allow(non_snake_case)]
+            fn write_copy_row(&self, out: &mut dyn Write) -> std::io::Result<()> {
+                let ($first, $($rest,)*) = self;
+                $first.write_copy_value(out)?;
+                $(
+                    out.write_all(b",")?;
+                    $rest.write_copy_value(out)?;
+                )*
+                out.write_all(b"\n")
+            }
+        }
+    };
+}
+
+copy_row_tuple!(A);
+copy_row_tuple!(A, B);
+copy_row_tuple!(A, B, C);
+copy_row_tuple!(A, B, C, D);
+copy_row_tuple!(A, B, C, D, E);
+copy_row_tuple!(A, B, C, D, E, F);
+copy_row_tuple!(A, B, C, D, E, F, G);
+copy_row_tuple!(A, B, C, D, E, F, G, H);
+
+/// Stream rows into `columns` of a table with `COPY ... FROM STDIN` and
+/// return the number of rows written.
+///
+/// Rows are pulled from the iterator while they are sent, so they never
+/// have to be held in memory at once. `COPY` is a single statement: if any
+/// row is rejected, for example because a value does not parse or violates
+/// a constraint, no row is written and the error is returned.
+///
+/// ```rust,ignore
+/// let rows = readings.iter().map(|r| (r.time, r.device_id.as_str(), r.value));
+/// let written = copy_in(
+///     &mut conn,
+///     metrics::table,
+///     (metrics::timestamp, metrics::device_id, metrics::value),
+///     rows,
+/// )?;
+/// ```
+pub fn copy_in<T, C, I>(
+    conn: &mut PgConnection,
+    table: T,
+    columns: C,
+    rows: I,
+) -> QueryResult<usize>
+where
+    T: Table,
+    C: CopyTarget<Table = T>,
+    I: IntoIterator,
+    I::Item: CopyRow,
+{
+    // The callback may only borrow its state, so the iterator is taken out
+    // of a cell when the data is sent
+    let rows = RefCell::new(Some(rows.into_iter()));
+
+    diesel::copy_from(table)
+        .from_raw_data(columns, |out: &mut dyn Write| {
+            let serialization_error =
+                |e: std::io::Error| diesel::result::Error::SerializationError(Box::new(e));
+            // Every write is sent as its own chunk of copy data, so the rows
+            // are buffered into larger chunks
+            let mut out = BufWriter::with_capacity(64 * 1024, out);
+            for row in rows.borrow_mut().take().into_iter().flatten() {
+                row.write_copy_row(&mut out).map_err(serialization_error)?;
+            }
+            out.flush().map_err(serialization_error)
+        })
+        .with_format(CopyFormat::Csv)
+        .execute(conn)
+}
+
+/// Stream rows into `columns` of the table named `table`, like [`copy_in`].
+///
+/// The table name may be qualified with a schema, as in `analytics.metrics`.
+/// It and the columns are validated as [`SqlIdentifier`]s before anything is
+/// sent, and they are quoted, so names keep their case.
+///
+/// ```rust,ignore
+/// let rows = readings.iter().map(|r| (r.time, r.device_id.as_str(), r.value));
+/// let written = copy_in_named(
+///     &mut conn,
+///     "metrics",
+///     &["timestamp", "device_id", "value"],
+///     rows,
+/// )?;
+/// ```
+pub fn copy_in_named<I>(
+    conn: &mut PgConnection,
+    table: &str,
+    columns: &[&str],
+    rows: I,
+) -> Result<usize, TimescaleError>
+where
+    I: IntoIterator,
+    I::Item: CopyRow,
+{
+    let table = SqlIdentifier::new(table)?;
+    if columns.is_empty() {
+        return Err(ValidationError::InvalidParameter(
+            "COPY needs at least one column".to_string(),
+        )
+        .into());
+    }
+    let columns = columns
+        .iter()
+        .map(|column| Ok(SqlIdentifier::column(column)?.escaped()))
+        .collect::<Result<Vec<_>, ValidationError>>()?;
+
+    let _target = NamedTarget::set(format!("{} ({})", table.escaped(), columns.join(", ")));
+    Ok(copy_in(conn, named_target::table, NamedTarget, rows)?)
+}
+
+// Diesel only copies into a `CopyTarget`, which renders itself without an
+// instance, so the validated names of a `copy_in_named` call are handed to
+// it through a thread-local for the duration of the call
+thread_local! {
+    static NAMED_TARGET: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Stands in for the table of a [`NamedTarget`], which renders the actual
+/// name.
+mod stand_in {
+    diesel::table! {
+        named_target (id) {
+            id -> Integer,
+        }
+    }
+}
+
+/// The table and columns of the running [`copy_in_named`] call.
+struct NamedTarget;
+
+/// Restores the previous target, if any, when a [`copy_in_named`] call ends.
+struct NamedTargetGuard(Option<String>);
+
+impl NamedTarget {
+    fn set(target: String) -> NamedTargetGuard {
+        NamedTargetGuard(NAMED_TARGET.with(|named| named.replace(Some(target))))
+    }
+}
+
+impl Drop for NamedTargetGuard {
+    fn drop(&mut self) {
+        NAMED_TARGET.with(|named| *named.borrow_mut() = self.0.take());
+    }
+}
+
+impl CopyTarget for NamedTarget {
+    type Table = named_target::table;
+    // The SQL type of the stand-in table, which is never read
+    type SqlType = diesel::sql_types::Integer;
+
+    fn walk_target(mut pass: AstPass<'_, '_, diesel::pg::Pg>) -> QueryResult<()> {
+        NAMED_TARGET.with(|named| match &*named.borrow() {
+            Some(target) => {
+                pass.push_sql(target);
+                Ok(())
+            }
+            None => Err(diesel::result::Error::QueryBuilderError(
+                "COPY target used outside of copy_in_named".into(),
+            )),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn csv(row: impl CopyRow) -> String {
+        let mut out = Vec::new();
+        row.write_copy_row(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn strings_are_quoted_so_empty_strings_stay_apart_from_null() {
+        assert_eq!(
+            csv(("say \"hi\", then leave", "", None::<&str>)),
+            "\"say \"\"hi\"\", then leave\",\"\",\n"
+        );
+    }
+
+    #[test]
+    fn timestamps_and_numbers_are_written_in_parseable_forms() {
+        let time = chrono::Utc.with_ymd_and_hms(2026, 3, 14, 10, 0, 0).unwrap();
+        assert_eq!(
+            csv((time, 42_i64, 0.5_f64, true, Some(7_i32))),
+            "2026-03-14T10:00:00Z,42,0.5,true,7\n"
+        );
+    }
+}
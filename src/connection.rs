@@ -1,9 +1,295 @@
 //! Connection utilities for TimescaleDB with Diesel.
 
+use crate::error::TimescaleError;
+#[cfg(feature = "r2d2")]
+use crate::r2d2::{TimescalePool, TimescalePooledConnection};
+use crate::version::{extension_version, installed_version, TimescaleVersion};
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
+use diesel::sql_types::{Array, BigInt, Bool, Nullable, Text};
+use std::time::Duration;
+
+#[derive(QueryableByName)]
+struct PlanLine {
+    #[diesel(sql_type = Text, column_name = "QUERY PLAN")]
+    line: String,
+}
+
+#[derive(QueryableByName)]
+struct SessionTimezone {
+    #[diesel(sql_type = Text)]
+    timezone: String,
+}
+
+#[derive(QueryableByName)]
+struct License {
+    #[diesel(sql_type = Nullable<Text>)]
+    license: Option<String>,
+}
+
+#[derive(QueryableByName)]
+struct Excluded {
+    #[diesel(sql_type = Bool)]
+    excluded: bool,
+}
+
+/// The chunks of one hypertable that a plan scans.
+#[derive(QueryableByName)]
+struct HypertableScan {
+    #[diesel(sql_type = BigInt)]
+    total_chunks: i64,
+    #[diesel(sql_type = BigInt)]
+    scanned_chunks: i64,
+}
+
+/// Resolve the hypertables read by a query and count the chunks its plan
+/// scans, from the output of `EXPLAIN (VERBOSE, FORMAT JSON)`.
+///
+/// Every plan node with a `Relation Name` is resolved by oid, from its
+/// `Schema`, and matched against the hypertables and their chunks. A chunk
+/// counts as scanned unless every node scanning it reports zero
+/// `Actual Loops`. The relations named in the query are resolved too, so a
+/// hypertable whose chunks were all excluded is returned with no scanned
+/// chunks. The internal hypertables holding compressed data are skipped.
+fn hypertable_scans(
+    conn: &mut PgConnection,
+    plan: &str,
+    query: &str,
+) -> QueryResult<Vec<HypertableScan>> {
+    diesel::sql_query(
+        "WITH nodes AS (
+             SELECT to_regclass(format('%I.%I', node->>'Schema', node->>'Relation Name'))
+                        AS relation,
+                    COALESCE((node->>'Actual Loops')::float8, 1) AS loops
+             FROM jsonb_path_query($1::jsonb,
+                 'strict $.** ? (exists (@.\"Relation Name\"))') AS node
+         ),
+         relations AS (
+             SELECT relation FROM nodes
+             UNION
+             SELECT to_regclass(name) FROM unnest($2::text[]) AS name
+         ),
+         chunks AS (
+             SELECT c.id, c.hypertable_id,
+                    to_regclass(format('%I.%I', c.schema_name, c.table_name)) AS relation
+             FROM _timescaledb_catalog.chunk c
+             WHERE NOT c.dropped
+         ),
+         hypertables AS (
+             SELECT h.id
+             FROM _timescaledb_catalog.hypertable h
+             JOIN relations r
+               ON r.relation = to_regclass(format('%I.%I', h.schema_name, h.table_name))
+             UNION
+             SELECT c.hypertable_id
+             FROM chunks c
+             JOIN nodes n ON n.relation = c.relation
+         )
+         SELECT (SELECT count(*) FROM chunks c WHERE c.hypertable_id = h.id)
+                    AS total_chunks,
+                (SELECT count(DISTINCT c.id)
+                 FROM chunks c
+                 JOIN nodes n ON n.relation = c.relation
+                 WHERE c.hypertable_id = h.id AND n.loops > 0) AS scanned_chunks
+         FROM hypertables h
+         WHERE NOT EXISTS (
+             SELECT 1 FROM _timescaledb_catalog.hypertable p
+             WHERE p.compressed_hypertable_id = h.id
+         )",
+    )
+    .bind::<Text, _>(plan)
+    .bind::<Array<Text>, _>(query_relation_names(query))
+    .load(conn)
+}
+
+/// SQL keywords that can follow a relation in a `FROM` list, and so are not
+/// aliases.
+const CLAUSE_KEYWORDS: &[&str] = &[
+    "cross",
+    "except",
+    "fetch",
+    "for",
+    "full",
+    "group",
+    "having",
+    "inner",
+    "intersect",
+    "join",
+    "left",
+    "limit",
+    "natural",
+    "offset",
+    "on",
+    "order",
+    "returning",
+    "right",
+    "tablesample",
+    "union",
+    "using",
+    "where",
+    "window",
+];
+
+/// A token of a query, as far as finding its relations is concerned.
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    /// A keyword or an unquoted identifier.
+    Word(&'a str),
+    /// A quoted identifier, with its quotes.
+    Quoted(&'a str),
+    /// Any other character outside string literals.
+    Punct(char),
+}
+
+fn tokenize(query: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut chars = query.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        if c == '\'' || c == '"' {
+            // Doubled quotes escape themselves, so they close and reopen
+            let mut end = query.len();
+            while let Some((i, next)) = chars.next() {
+                if next == c {
+                    if chars.peek().map(|&(_, after)| after) == Some(c) {
+                        chars.next();
+                    } else {
+                        end = i + 1;
+                        break;
+                    }
+                }
+            }
+            if c == '"' {
+                tokens.push(Token::Quoted(&query[start..end]));
+            }
+            continue;
+        }
+        if c.is_alphabetic() || c == '_' {
+            let mut end = start + c.len_utf8();
+            while let Some(&(i, next)) = chars.peek() {
+                if !(next.is_alphanumeric() || next == '_' || next == '$') {
+                    break;
+                }
+                end = i + next.len_utf8();
+                chars.next();
+            }
+            tokens.push(Token::Word(&query[start..end]));
+            continue;
+        }
+        tokens.push(Token::Punct(c));
+    }
+    tokens
+}
+
+/// Get the names of the relations after `FROM` and `JOIN` in a query, as
+/// written.
+///
+/// Subqueries and function calls are skipped, and at most three dotted parts
+/// are kept, so each name can be passed to `to_regclass`. Names that are not
+/// relations, such as CTE names, are ignored there.
+fn query_relation_names(query: &str) -> Vec<String> {
+    let tokens = tokenize(query);
+    let mut names = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let starts_list = matches!(tokens[i], Token::Word(word)
+            if word.eq_ignore_ascii_case("from") || word.eq_ignore_ascii_case("join"));
+        i += 1;
+        if !starts_list {
+            continue;
+        }
+        loop {
+            if matches!(tokens.get(i), Some(Token::Word(word)) if word.eq_ignore_ascii_case("only"))
+            {
+                i += 1;
+            }
+            let mut parts = Vec::new();
+            while let Some(Token::Word(part) | Token::Quoted(part)) = tokens.get(i) {
+                parts.push(*part);
+                i += 1;
+                if tokens.get(i) != Some(&Token::Punct('.')) {
+                    break;
+                }
+                i += 1;
+            }
+            if parts.is_empty() {
+                // A subquery, whose own FROM is found by the outer loop
+                break;
+            }
+            if tokens.get(i) == Some(&Token::Punct('(')) {
+                // A function call: skip its arguments
+                let mut depth = 0;
+                while let Some(token) = tokens.get(i) {
+                    i += 1;
+                    match token {
+                        Token::Punct('(') => depth += 1,
+                        Token::Punct(')') => depth -= 1,
+                        _ => {}
+                    }
+                    if depth == 0 {
+                        break;
+                    }
+                }
+            } else if parts.len() <= 3 {
+                names.push(parts.join("."));
+            }
+
+            // Skip an alias to reach a comma that continues the list
+            if matches!(tokens.get(i), Some(Token::Word(word)) if word.eq_ignore_ascii_case("as")) {
+                i += 1;
+            }
+            match tokens.get(i) {
+                Some(Token::Quoted(_)) => i += 1,
+                Some(Token::Word(word))
+                    if !CLAUSE_KEYWORDS
+                        .iter()
+                        .any(|kw| word.eq_ignore_ascii_case(kw)) =>
+                {
+                    i += 1
+                }
+                _ => {}
+            }
+            if tokens.get(i) != Some(&Token::Punct(',')) {
+                break;
+            }
+            i += 1;
+        }
+    }
+    names
+}
+
+/// How many chunks a query scanned, as measured by
+/// [`TimescaleDbConnection::chunk_scan_stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkScanStats {
+    /// Chunks of the hypertables the query reads from.
+    pub total_chunks: i64,
+    /// Chunks that were actually scanned.
+    pub scanned_chunks: i64,
+    /// Chunks excluded at planning time, at executor startup or at runtime.
+    pub excluded_chunks: i64,
+}
+
+impl ChunkScanStats {
+    /// Fraction of the chunks that were excluded, between 0 and 1, or 0 if
+    /// the hypertables have no chunks.
+    pub fn exclusion_ratio(&self) -> f64 {
+        if self.total_chunks == 0 {
+            return 0.0;
+        }
+        self.excluded_chunks as f64 / self.total_chunks as f64
+    }
+}
 
 /// A wrapper around `PgConnection` with TimescaleDB-specific functionality.
+///
+/// Like `PgConnection`, the wrapper is not `Clone`: a connection owns its
+/// socket and its session state, such as settings, prepared statements and
+/// the open transaction, none of which can be duplicated. Code that needs
+/// more than one connection should use a pool; with the `r2d2` feature,
+/// [`try_clone_via_pool`](Self::try_clone_via_pool) checks out another one.
 pub struct TimescaleDbConnection {
     connection: PgConnection,
 }
@@ -20,6 +306,95 @@ impl TimescaleDbConnection {
         Ok(Self::new(connection))
     }
 
+    /// Establish a connection and create the TimescaleDB extension if it is
+    /// not enabled in the database yet.
+    ///
+    /// See [`ensure_extension`](Self::ensure_extension) for the privileges
+    /// this needs.
+    pub fn establish_with_extension(database_url: &str) -> Result<Self, TimescaleError> {
+        let mut connection = Self::establish(database_url)?;
+        connection.ensure_extension()?;
+        Ok(connection)
+    }
+
+    /// Run `CREATE EXTENSION IF NOT EXISTS timescaledb CASCADE`.
+    ///
+    /// Creating the extension needs the `CREATE` privilege on the database
+    /// and `timescaledb` in `shared_preload_libraries`; the error from the
+    /// server names what is missing. Nothing happens if the extension is
+    /// already enabled, whatever its version.
+    pub fn ensure_extension(&mut self) -> QueryResult<()> {
+        diesel::sql_query("CREATE EXTENSION IF NOT EXISTS timescaledb CASCADE")
+            .execute(&mut self.connection)?;
+        Ok(())
+    }
+
+    /// Prepare the database for restoring a logical dump, with
+    /// `timescaledb_pre_restore()`.
+    ///
+    /// Restoring a `pg_dump` of a TimescaleDB database takes three steps, in
+    /// this order:
+    ///
+    /// 1. call `pre_restore` on the target database,
+    /// 2. run `pg_restore` or `psql` with the dump,
+    /// 3. call [`post_restore`](Self::post_restore).
+    ///
+    /// `timescaledb_pre_restore()` stops the background workers and sets
+    /// `timescaledb.restoring` to `on`, for this session and as a database
+    /// default that new sessions such as `pg_restore`'s pick up. While it is
+    /// set, TimescaleDB does not maintain its catalog on inserts and DDL, so
+    /// the dump's catalog rows are restored as they were. This session keeps
+    /// the setting too: don't use it for regular work until `post_restore`.
+    pub fn pre_restore(&mut self) -> QueryResult<()> {
+        diesel::sql_query("SELECT timescaledb_pre_restore()").execute(&mut self.connection)?;
+        Ok(())
+    }
+
+    /// Finish restoring a logical dump, with `timescaledb_post_restore()`.
+    ///
+    /// This resets `timescaledb.restoring`, for this session and as the
+    /// database default, and restarts the background workers. Call it after
+    /// the dump has been restored and after [`pre_restore`](Self::pre_restore);
+    /// skipping it leaves the database with the restoring mode on for every
+    /// new session and no policies running. Run `ANALYZE` afterwards, since a
+    /// restore does not collect statistics.
+    pub fn post_restore(&mut self) -> QueryResult<()> {
+        diesel::sql_query("SELECT timescaledb_post_restore()").execute(&mut self.connection)?;
+        Ok(())
+    }
+
+    /// Get the version of the TimescaleDB extension enabled in the database,
+    /// as recorded in `pg_extension`, such as `2.14.2`.
+    ///
+    /// Returns `NotFound` if the extension is not enabled.
+    pub fn timescaledb_version(&mut self) -> QueryResult<String> {
+        extension_version(&mut self.connection)
+    }
+
+    /// Check that the enabled TimescaleDB is at least `major.minor` and
+    /// return its version.
+    ///
+    /// Fails with `ExtensionNotInstalled` if the extension is not enabled, and
+    /// with `UnsupportedVersion` naming `feature` if it is older, so an
+    /// application can check once after connecting instead of running into a
+    /// missing function later.
+    pub fn require_min_version(
+        &mut self,
+        feature: &str,
+        major: u32,
+        minor: u32,
+    ) -> Result<TimescaleVersion, TimescaleError> {
+        let version = match installed_version(&mut self.connection) {
+            Ok(version) => version,
+            Err(diesel::result::Error::NotFound) => {
+                return Err(TimescaleError::ExtensionNotInstalled)
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        version.require(feature, major, minor)
+    }
+
     /// Get a reference to the underlying PostgreSQL connection.
     pub fn connection(&self) -> &PgConnection {
         &self.connection
@@ -29,6 +404,277 @@ impl TimescaleDbConnection {
     pub fn connection_mut(&mut self) -> &mut PgConnection {
         &mut self.connection
     }
+
+    /// Get another connection to the same database from `pool`.
+    ///
+    /// This is not a clone: the new connection has its own session, so
+    /// settings and the open transaction of this connection are not carried
+    /// over. It is returned to the pool when dropped. Fails if the pool
+    /// cannot provide a connection within its timeout.
+    #[cfg(feature = "r2d2")]
+    pub fn try_clone_via_pool(
+        &self,
+        pool: &TimescalePool,
+    ) -> Result<TimescalePooledConnection, diesel::r2d2::PoolError> {
+        pool.get()
+    }
+
+    /// Get the time zone of the session, as used by `time_bucket` with a
+    /// time zone and to render `timestamptz` values as text.
+    ///
+    /// The name is an IANA zone such as `Europe/Berlin` or `UTC`, or a POSIX
+    /// zone specification if the session was configured with one.
+    pub fn session_timezone(&mut self) -> QueryResult<String> {
+        let row = diesel::sql_query("SELECT current_setting('TimeZone') AS timezone")
+            .get_result::<SessionTimezone>(&mut self.connection)?;
+        Ok(row.timezone)
+    }
+
+    /// Get the edition of the loaded TimescaleDB, from the `timescaledb.license`
+    /// setting.
+    ///
+    /// This is `apache` for the Apache-2.0 licensed edition and `timescale`
+    /// for the community edition, which adds features such as compression
+    /// policies and continuous aggregate policies. Fails with
+    /// [`TimescaleError::ExtensionNotInstalled`] if TimescaleDB is not loaded
+    /// in the session.
+    pub fn edition(&mut self) -> Result<String, TimescaleError> {
+        let row =
+            diesel::sql_query("SELECT current_setting('timescaledb.license', true) AS license")
+                .get_result::<License>(&mut self.connection)?;
+
+        row.license
+            .filter(|license| !license.is_empty())
+            .ok_or(TimescaleError::ExtensionNotInstalled)
+    }
+
+    /// Run a script of semicolon-separated statements in one round trip.
+    ///
+    /// The script is sent as a single simple query and runs in a transaction,
+    /// so a failing statement rolls back the whole script. This is meant for
+    /// setup scripts and test fixtures, such as creating the extension, a
+    /// table, its hypertable and its policies at once.
+    ///
+    /// Nothing is bound or escaped: the script must be trusted DDL and never
+    /// contain user input. Statements that cannot run in a transaction, like
+    /// `CREATE INDEX CONCURRENTLY` or `CALL refresh_continuous_aggregate`,
+    /// fail.
+    pub fn batch_execute(&mut self, sql: &str) -> QueryResult<()> {
+        self.connection
+            .transaction(|conn| diesel::connection::SimpleConnection::batch_execute(conn, sql))
+    }
+
+    /// Run `f` with chunk-wise aggregation turned on or off.
+    ///
+    /// Chunk-wise aggregation computes partial aggregates per chunk before
+    /// combining them, which can speed up `time_bucket` `GROUP BY` queries
+    /// considerably. `timescaledb.enable_chunkwise_aggregation` is set with
+    /// `SET LOCAL` semantics in a transaction around `f`, so the previous
+    /// value is restored when the transaction ends, whether `f` succeeds or not.
+    ///
+    /// The setting was introduced in TimescaleDB 2.12. On older versions the
+    /// optimization does not exist and `f` is run without changing anything.
+    pub fn with_chunkwise_aggregation<T, F>(&mut self, enabled: bool, f: F) -> QueryResult<T>
+    where
+        F: FnOnce(&mut PgConnection) -> QueryResult<T>,
+    {
+        let supported = installed_version(&mut self.connection)?.at_least(2, 12);
+
+        self.connection.transaction(|conn| {
+            if supported {
+                diesel::sql_query(
+                    "SELECT set_config('timescaledb.enable_chunkwise_aggregation', $1, true)",
+                )
+                .bind::<Text, _>(enabled.to_string())
+                .execute(conn)?;
+            }
+            f(conn)
+        })
+    }
+
+    /// Run `f` with a `lock_timeout`, so statements waiting for a lock fail
+    /// instead of queuing behind long-running transactions.
+    ///
+    /// This is meant for DDL on busy tables, such as `create_hypertable` with
+    /// `migrate_data`, enabling compression or creating indexes: while such a
+    /// statement waits for its lock, every later query on the table waits
+    /// behind it. `lock_timeout` is set with `SET LOCAL` semantics in a
+    /// transaction around `f`, so the previous value is restored when the
+    /// transaction ends. The timeout is rounded down to whole milliseconds, with
+    /// a minimum of one; a zero timeout disables the limit, as in PostgreSQL.
+    ///
+    /// # Retries
+    ///
+    /// A timeout aborts the whole transaction with a `lock_timeout` error, and
+    /// everything `f` did is rolled back. Retrying means calling
+    /// `with_lock_timeout` again, not retrying inside `f`, and `f` must be safe
+    /// to run more than once. Back off between attempts, since the blocking
+    /// transaction may still be running.
+    pub fn with_lock_timeout<T, F>(&mut self, timeout: Duration, f: F) -> QueryResult<T>
+    where
+        F: FnOnce(&mut PgConnection) -> QueryResult<T>,
+    {
+        let millis = if timeout.is_zero() {
+            0
+        } else {
+            timeout.as_millis().max(1)
+        };
+
+        self.connection.transaction(|conn| {
+            diesel::sql_query("SELECT set_config('lock_timeout', $1, true)")
+                .bind::<Text, _>(format!("{}ms", millis))
+                .execute(conn)?;
+            f(conn)
+        })
+    }
+
+    /// Run `f` in a transaction, and run it again when the transaction fails
+    /// with a serialization failure or a deadlock.
+    ///
+    /// Background jobs such as compression lock chunks that application
+    /// writes also need, which can end in a deadlock, and under
+    /// `REPEATABLE READ` or `SERIALIZABLE` isolation concurrent transactions
+    /// can fail with SQLSTATE `40001`. Either way, PostgreSQL has rolled back
+    /// the transaction and it can simply be run again. Other errors are
+    /// returned right away.
+    ///
+    /// `f` is retried up to `retries` times, waiting `backoff` before the
+    /// first retry and twice as long before each one after that. The result of
+    /// the last attempt is returned. Everything `f` did in a failed attempt is
+    /// rolled back, but `f` must be safe to run more than once. Call this
+    /// outside of a transaction: inside one, `f` runs in a savepoint, and
+    /// retrying from a savepoint does not recover from a serialization
+    /// failure.
+    ///
+    /// Diesel does not report the SQLSTATE of a deadlock (`40P01`), so
+    /// deadlocks are recognized by PostgreSQL's `deadlock detected` message,
+    /// which requires the server to report messages in English.
+    pub fn execute_with_retry<T, F>(
+        &mut self,
+        retries: u32,
+        backoff: Duration,
+        mut f: F,
+    ) -> QueryResult<T>
+    where
+        F: FnMut(&mut PgConnection) -> QueryResult<T>,
+    {
+        let mut delay = backoff;
+        let mut attempt = 0;
+        loop {
+            match self.connection.transaction(&mut f) {
+                Err(e) if attempt < retries && is_retryable(&e) => {
+                    std::thread::sleep(delay);
+                    delay = delay.saturating_mul(2);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Get the query plan of a query, as reported by `EXPLAIN`.
+    ///
+    /// The query is planned but not executed.
+    pub fn explain(&mut self, query: &str) -> QueryResult<String> {
+        let lines = diesel::sql_query(format!("EXPLAIN {}", query))
+            .load::<PlanLine>(&mut self.connection)?;
+        Ok(lines
+            .into_iter()
+            .map(|line| line.line)
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+
+    /// Check whether the planner excludes any chunks from a query.
+    ///
+    /// This is intended for tests that should fail when a query regresses to
+    /// scanning every chunk. A query excludes chunks if its plan reports
+    /// `Chunks excluded during startup` or `runtime`, or if it scans fewer
+    /// chunks of a hypertable than the hypertable has. The query is planned
+    /// but not executed, so only exclusion decided at planning time or
+    /// executor startup is detected.
+    ///
+    /// The hypertables are resolved from the relations in the plan and from
+    /// the names after `FROM` and `JOIN` in the query, so a hypertable whose
+    /// chunks were all excluded, and which the plan therefore does not name,
+    /// still counts as long as it has chunks.
+    pub fn query_excludes_chunks(&mut self, query: &str) -> QueryResult<bool> {
+        let plan = diesel::sql_query(format!("EXPLAIN (VERBOSE, FORMAT JSON) {}", query))
+            .get_result::<PlanLine>(&mut self.connection)?
+            .line;
+
+        let marked = diesel::sql_query(
+            "SELECT EXISTS (
+                 SELECT 1 FROM jsonb_path_query($1::jsonb,
+                     'lax $.** ? (@.\"Chunks excluded during startup\" > 0
+                         || @.\"Chunks excluded during runtime\" > 0)')
+             ) AS excluded",
+        )
+        .bind::<Text, _>(&plan)
+        .get_result::<Excluded>(&mut self.connection)?;
+        if marked.excluded {
+            return Ok(true);
+        }
+
+        let scans = hypertable_scans(&mut self.connection, &plan, query)?;
+        Ok(scans
+            .iter()
+            .any(|scan| scan.scanned_chunks < scan.total_chunks))
+    }
+
+    /// Run a query with `EXPLAIN ANALYZE` and count the chunks it scanned
+    /// and the chunks that were excluded.
+    ///
+    /// A chunk counts as scanned if any node of the plan scanning it was
+    /// executed. Chunks excluded when the query was planned or when the
+    /// executor started do not appear in the plan, and chunks excluded at
+    /// runtime appear with zero `Actual Loops`; both count as excluded. The
+    /// plan is read as JSON, so this does not depend on the shape of the
+    /// text plan, and its relations are matched to the hypertables by oid.
+    /// As in [`query_excludes_chunks`](Self::query_excludes_chunks), the
+    /// relations named in the query are resolved too, so a hypertable whose
+    /// chunks were all excluded still counts its chunks.
+    ///
+    /// Unlike [`explain`](Self::explain), the query is executed. It runs in a
+    /// transaction that is rolled back, so data-modifying statements leave
+    /// no changes behind, but they still take their locks while running.
+    pub fn chunk_scan_stats(&mut self, query: &str) -> QueryResult<ChunkScanStats> {
+        let mut plan = String::new();
+        let result = self.connection.transaction::<(), _, _>(|conn| {
+            plan = diesel::sql_query(format!(
+                "EXPLAIN (ANALYZE, VERBOSE, COSTS OFF, TIMING OFF, SUMMARY OFF, FORMAT JSON) {}",
+                query
+            ))
+            .get_result::<PlanLine>(conn)?
+            .line;
+            Err(diesel::result::Error::RollbackTransaction)
+        });
+        match result {
+            Err(diesel::result::Error::RollbackTransaction) => {}
+            Err(e) => return Err(e),
+            Ok(()) => {}
+        }
+
+        let scans = hypertable_scans(&mut self.connection, &plan, query)?;
+        let total: i64 = scans.iter().map(|scan| scan.total_chunks).sum();
+        let scanned: i64 = scans.iter().map(|scan| scan.scanned_chunks).sum();
+        Ok(ChunkScanStats {
+            total_chunks: total,
+            scanned_chunks: scanned,
+            excluded_chunks: total - scanned,
+        })
+    }
+}
+
+/// Whether a failed transaction can be run again as is.
+fn is_retryable(error: &diesel::result::Error) -> bool {
+    use diesel::result::{DatabaseErrorKind, Error};
+
+    match error {
+        Error::DatabaseError(DatabaseErrorKind::SerializationFailure, _) => true,
+        Error::DatabaseError(_, info) => info.message().starts_with("deadlock detected"),
+        _ => false,
+    }
 }
 
 impl std::ops::Deref for TimescaleDbConnection {
@@ -44,3 +690,46 @@ impl std::ops::DerefMut for TimescaleDbConnection {
         &mut self.connection
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relation_names_follow_from_and_join() {
+        assert_eq!(
+            query_relation_names(
+                "SELECT * FROM public.metrics m JOIN \"Devices\" AS d ON d.id = m.device_id \
+                 WHERE m.time > now() - INTERVAL '1 day'"
+            ),
+            vec!["public.metrics", "\"Devices\""]
+        );
+    }
+
+    #[test]
+    fn relation_names_cover_comma_lists_and_skip_calls() {
+        assert_eq!(
+            query_relation_names(
+                "SELECT a.value FROM metrics a, generate_series(1, 3) g, \
+                 readings WHERE a.value > 0"
+            ),
+            vec!["metrics", "readings"]
+        );
+    }
+
+    #[test]
+    fn relation_names_ignore_string_literals_and_subquery_parens() {
+        assert_eq!(
+            query_relation_names(
+                "SELECT 'from nowhere' FROM (SELECT * FROM ONLY raw_metrics) sub \
+                 WHERE note = 'it''s from them'"
+            ),
+            vec!["raw_metrics"]
+        );
+    }
+
+    #[test]
+    fn relation_names_skip_names_with_too_many_parts() {
+        assert!(query_relation_names("SELECT * FROM a.b.c.d").is_empty());
+    }
+}
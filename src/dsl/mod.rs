@@ -1,9 +1,17 @@
 //! Domain Specific Language (DSL) extensions for TimescaleDB queries.
 
-use crate::schema::{SqlIdentifier, TimeInterval, ValidationError};
-use diesel::expression::AsExpression;
+use crate::schema::{validate_timezone, SqlIdentifier, TimeInterval, ValidationError};
+use crate::statement::SqlStatement;
+use diesel::dsl::{Asc, Desc, Filter, GroupBy, Order, Select};
+use diesel::expression::{
+    is_aggregate, is_contained_in_group_by, AppearsOnTable, AsExpression, Expression,
+    IsContainedInGroupBy, MixedAggregates, SelectableExpression, ValidGrouping,
+};
+use diesel::pg::{Pg, PgQueryBuilder};
 use diesel::prelude::*;
-use diesel::sql_types::Timestamptz;
+use diesel::query_builder::{AsQuery, AstPass, QueryBuilder, QueryFragment, QueryId};
+use diesel::query_dsl::methods::{FilterDsl, GroupByDsl, OrderDsl, SelectDsl};
+use diesel::sql_types::{SqlType, Timestamptz};
 
 /// Extension trait for building time-series queries.
 pub trait TimescaleQueryDsl: Sized {
@@ -34,7 +42,12 @@ pub trait TimescaleQueryDsl: Sized {
     where
         Expr: Expression;
 
-    /// Add a time range filter to the query.
+    /// Add a half-open time range filter (`>= start AND < end`) to the query.
+    ///
+    /// This is the same as [`time_range_half_open`](Self::time_range_half_open).
+    /// Half-open ranges are the time-series convention: adjacent ranges such
+    /// as `[00:00, 01:00)` and `[01:00, 02:00)` never both contain a row that
+    /// falls exactly on the boundary, so bucketed data is not counted twice.
     fn time_range<Expr, V>(
         self,
         time_column: Expr,
@@ -44,6 +57,85 @@ pub trait TimescaleQueryDsl: Sized {
     where
         Expr: Expression,
         V: AsExpression<Timestamptz>;
+
+    /// Add a half-open time range filter (`>= start AND < end`) to the query.
+    fn time_range_half_open<Expr, V>(
+        self,
+        time_column: Expr,
+        start: V,
+        end: V,
+    ) -> TimeRangeQuery<Self, Expr, V>
+    where
+        Expr: Expression,
+        V: AsExpression<Timestamptz>;
+
+    /// Add a closed time range filter (`>= start AND <= end`) to the query.
+    ///
+    /// Rows exactly at `end` are included. Prefer
+    /// [`time_range_half_open`](Self::time_range_half_open) when the ranges of
+    /// consecutive queries or buckets share their boundaries.
+    fn time_range_closed<Expr, V>(
+        self,
+        time_column: Expr,
+        start: V,
+        end: V,
+    ) -> TimeRangeQuery<Self, Expr, V>
+    where
+        Expr: Expression,
+        V: AsExpression<Timestamptz>;
+
+    /// Keep only rows from the last `interval`, as `time >= now() - interval`.
+    ///
+    /// The cutoff is computed by the database with `now()`, so client clock
+    /// skew does not matter, and the interval is bound as a parameter. The
+    /// comparison is on the bare time column, so TimescaleDB excludes the
+    /// chunks outside the window at executor startup. The result is an
+    /// ordinary filtered Diesel query and can be bucketed and aggregated
+    /// further.
+    fn recent<Expr>(self, time_column: Expr, interval: TimeInterval) -> Filter<Self, Recent<Expr>>
+    where
+        Expr: Expression,
+        Self: FilterDsl<Recent<Expr>>;
+
+    /// Keep only rows whose space-partitioning column equals `value`, such as
+    /// the rows of one tenant of a hypertable partitioned by `tenant_id`.
+    ///
+    /// Combined with [`time_range`](Self::time_range) or
+    /// [`recent`](Self::recent), the query filters on both dimensions, so
+    /// TimescaleDB excludes the chunks of other partitions as well as those
+    /// outside the time window. The value is bound as a parameter, and the
+    /// column must be a column of the schema rather than an arbitrary
+    /// expression: chunks are only excluded for a comparison on the bare
+    /// partitioning column.
+    fn partition_filter<Col, V>(
+        self,
+        column: Col,
+        value: V,
+    ) -> Filter<Self, PartitionFilter<Col, V::Expression>>
+    where
+        Col: Column,
+        V: AsExpression<Col::SqlType>,
+        Col::SqlType: SqlType,
+        Self: FilterDsl<PartitionFilter<Col, V::Expression>>;
+
+    /// Order the query by the time column, newest rows first.
+    ///
+    /// The ordering is on the bare column, which lets TimescaleDB use the time
+    /// index of each chunk and scan the chunks in time order (ordered append)
+    /// instead of sorting all rows. Combined with a `LIMIT` this reads only
+    /// the newest chunks.
+    fn order_by_time_desc<Expr>(self, time_column: Expr) -> Order<Self, Desc<Expr>>
+    where
+        Expr: Expression + ExpressionMethods,
+        Self: OrderDsl<Desc<Expr>>;
+
+    /// Order the query by the time column, oldest rows first.
+    ///
+    /// See [`order_by_time_desc`](Self::order_by_time_desc).
+    fn order_by_time_asc<Expr>(self, time_column: Expr) -> Order<Self, Asc<Expr>>
+    where
+        Expr: Expression + ExpressionMethods,
+        Self: OrderDsl<Asc<Expr>>;
 }
 
 impl<T> TimescaleQueryDsl for T {
@@ -84,6 +176,38 @@ impl<T> TimescaleQueryDsl for T {
         start: V,
         end: V,
     ) -> TimeRangeQuery<Self, Expr, V>
+    where
+        Expr: Expression,
+        V: AsExpression<Timestamptz>,
+    {
+        self.time_range_half_open(time_column, start, end)
+    }
+
+    fn time_range_half_open<Expr, V>(
+        self,
+        time_column: Expr,
+        start: V,
+        end: V,
+    ) -> TimeRangeQuery<Self, Expr, V>
+    where
+        Expr: Expression,
+        V: AsExpression<Timestamptz>,
+    {
+        TimeRangeQuery {
+            query: self,
+            time_column,
+            start,
+            end,
+            end_bound: RangeEnd::Exclusive,
+        }
+    }
+
+    fn time_range_closed<Expr, V>(
+        self,
+        time_column: Expr,
+        start: V,
+        end: V,
+    ) -> TimeRangeQuery<Self, Expr, V>
     where
         Expr: Expression,
         V: AsExpression<Timestamptz>,
@@ -93,10 +217,462 @@ impl<T> TimescaleQueryDsl for T {
             time_column,
             start,
             end,
+            end_bound: RangeEnd::Inclusive,
+        }
+    }
+
+    fn recent<Expr>(self, time_column: Expr, interval: TimeInterval) -> Filter<Self, Recent<Expr>>
+    where
+        Expr: Expression,
+        Self: FilterDsl<Recent<Expr>>,
+    {
+        self.filter(Recent {
+            time_column,
+            interval,
+        })
+    }
+
+    fn partition_filter<Col, V>(
+        self,
+        column: Col,
+        value: V,
+    ) -> Filter<Self, PartitionFilter<Col, V::Expression>>
+    where
+        Col: Column,
+        V: AsExpression<Col::SqlType>,
+        Col::SqlType: SqlType,
+        Self: FilterDsl<PartitionFilter<Col, V::Expression>>,
+    {
+        self.filter(PartitionFilter::new(column, value.as_expression()))
+    }
+
+    fn order_by_time_desc<Expr>(self, time_column: Expr) -> Order<Self, Desc<Expr>>
+    where
+        Expr: Expression + ExpressionMethods,
+        Self: OrderDsl<Desc<Expr>>,
+    {
+        self.order(time_column.desc())
+    }
+
+    fn order_by_time_asc<Expr>(self, time_column: Expr) -> Order<Self, Asc<Expr>>
+    where
+        Expr: Expression + ExpressionMethods,
+        Self: OrderDsl<Asc<Expr>>,
+    {
+        self.order(time_column.asc())
+    }
+}
+
+/// Implement the expression traits of a bucket expression over a time column.
+///
+/// The bucket has the SQL type of its column and can be used in `GROUP BY`.
+/// When the query is grouped by the bucket, alone or as the first of several
+/// grouping expressions, the bucket is a valid selection next to aggregates.
+/// Each bucket type renders itself with its own `QueryFragment`.
+macro_rules! bucket_expression {
+    ($bucket:ident) => {
+        impl<TimeColumn> QueryId for $bucket<TimeColumn> {
+            type QueryId = ();
+
+            const HAS_STATIC_QUERY_ID: bool = false;
+        }
+
+        impl<TimeColumn> Expression for $bucket<TimeColumn>
+        where
+            TimeColumn: Expression,
+        {
+            type SqlType = TimeColumn::SqlType;
+        }
+
+        impl<TimeColumn, QS> AppearsOnTable<QS> for $bucket<TimeColumn>
+        where
+            TimeColumn: AppearsOnTable<QS>,
+            Self: Expression,
+        {
+        }
+
+        impl<TimeColumn, QS> SelectableExpression<QS> for $bucket<TimeColumn>
+        where
+            TimeColumn: SelectableExpression<QS>,
+            Self: AppearsOnTable<QS>,
+        {
+        }
+
+        impl<TimeColumn> ValidGrouping<()> for $bucket<TimeColumn>
+        where
+            TimeColumn: ValidGrouping<()>,
+        {
+            type IsAggregate = TimeColumn::IsAggregate;
+        }
+
+        impl<TimeColumn> ValidGrouping<$bucket<TimeColumn>> for $bucket<TimeColumn> {
+            type IsAggregate = is_aggregate::Yes;
+        }
+
+        impl<TimeColumn, Other> ValidGrouping<($bucket<TimeColumn>, Other)>
+            for $bucket<TimeColumn>
+        {
+            type IsAggregate = is_aggregate::Yes;
+        }
+
+        impl<TimeColumn, Column> IsContainedInGroupBy<Column> for $bucket<TimeColumn> {
+            type Output = is_contained_in_group_by::No;
+        }
+    };
+}
+
+/// A `time_bucket(interval, column)` expression.
+///
+/// The column is rendered by Diesel itself, so table-qualified and aliased
+/// columns keep their correct qualification. Unlike the plain
+/// [`time_bucket`](fn@crate::functions::time_bucket) function, this expression can
+/// be used in `GROUP BY` and selected alongside aggregates of the same query.
+///
+/// The validated interval is rendered as an `INTERVAL '...'` literal rather
+/// than a bind parameter: PostgreSQL only matches a selected bucket to the
+/// `GROUP BY` bucket if both expressions are textually identical, and two
+/// separate parameters never are. The same goes for the optional time zone.
+#[derive(Debug, Clone)]
+pub struct TimeBucket<TimeColumn> {
+    interval: TimeInterval,
+    time_column: TimeColumn,
+    timezone: Option<String>,
+}
+
+impl<TimeColumn> TimeBucket<TimeColumn> {
+    /// Create a new time bucket expression.
+    pub fn new(interval: TimeInterval, time_column: TimeColumn) -> Self {
+        Self {
+            interval,
+            time_column,
+            timezone: None,
+        }
+    }
+
+    /// Align the buckets to local time in `timezone`, rendering
+    /// `time_bucket(interval, column, 'timezone')`.
+    ///
+    /// Daily and larger buckets then start at local midnight and follow
+    /// daylight saving time changes. The time zone must be an IANA name such
+    /// as `Europe/Berlin` or `Etc/GMT+3`, see [`validate_timezone`]. Requires
+    /// TimescaleDB 2.8 or later.
+    pub fn with_timezone(mut self, timezone: &str) -> Result<Self, ValidationError> {
+        validate_timezone(timezone)?;
+        self.timezone = Some(timezone.to_string());
+        Ok(self)
+    }
+}
+
+impl<TimeColumn> QueryFragment<Pg> for TimeBucket<TimeColumn>
+where
+    TimeColumn: QueryFragment<Pg>,
+{
+    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, Pg>) -> QueryResult<()> {
+        // The SQL text depends on the interval value
+        out.unsafe_to_cache_prepared();
+        out.push_sql("time_bucket(INTERVAL '");
+        out.push_sql(&self.interval.to_postgres_interval());
+        out.push_sql("', ");
+        self.time_column.walk_ast(out.reborrow())?;
+        if let Some(timezone) = &self.timezone {
+            out.push_sql(", '");
+            out.push_sql(timezone);
+            out.push_sql("'");
+        }
+        out.push_sql(")");
+        Ok(())
+    }
+}
+
+bucket_expression!(TimeBucket);
+
+/// A `time_bucket_gapfill(interval, column, start, finish)` expression.
+///
+/// Like [`TimeBucket`], this can be used in `GROUP BY` and selected alongside
+/// aggregates, and renders its interval, start and finish as literals so the
+/// selected and grouped expressions match. Every bucket of the range gets a
+/// row, with `NULL` aggregates unless they are wrapped in
+/// [`locf`](fn@crate::functions::locf) or
+/// [`interpolate`](fn@crate::functions::interpolate).
+#[derive(Debug, Clone)]
+pub struct TimeBucketGapfill<TimeColumn> {
+    interval: TimeInterval,
+    time_column: TimeColumn,
+    range: TimeRange,
+}
+
+impl<TimeColumn> TimeBucketGapfill<TimeColumn> {
+    /// Create a new gapfill bucket expression over the given range.
+    pub fn new(interval: TimeInterval, time_column: TimeColumn, range: TimeRange) -> Self {
+        Self {
+            interval,
+            time_column,
+            range,
+        }
+    }
+}
+
+impl<TimeColumn> QueryFragment<Pg> for TimeBucketGapfill<TimeColumn>
+where
+    TimeColumn: QueryFragment<Pg>,
+{
+    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, Pg>) -> QueryResult<()> {
+        // The SQL text depends on the interval and range values
+        out.unsafe_to_cache_prepared();
+        out.push_sql("time_bucket_gapfill(INTERVAL '");
+        out.push_sql(&self.interval.to_postgres_interval());
+        out.push_sql("', ");
+        self.time_column.walk_ast(out.reborrow())?;
+        out.push_sql(", TIMESTAMPTZ '");
+        out.push_sql(&self.range.start().to_rfc3339());
+        out.push_sql("', TIMESTAMPTZ '");
+        out.push_sql(&self.range.end().to_rfc3339());
+        out.push_sql("')");
+        Ok(())
+    }
+}
+
+bucket_expression!(TimeBucketGapfill);
+
+/// The bucket width of an integer time column, in the column's own unit.
+///
+/// An integer time column has no unit of its own, so the constructors record
+/// what the column stores: `IntegerBucketWidth::milliseconds(&1.hours())` is
+/// a one-hour bucket for a column of epoch milliseconds and renders as
+/// `3600000`. Use [`units`](Self::units) for columns that are not timestamps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntegerBucketWidth(i64);
+
+impl IntegerBucketWidth {
+    /// A width of `width` in the column's own unit.
+    pub fn units(width: i64) -> Result<Self, ValidationError> {
+        if width <= 0 {
+            return Err(ValidationError::InvalidParameter(format!(
+                "Integer bucket width must be positive, got {}",
+                width
+            )));
+        }
+        Ok(Self(width))
+    }
+
+    /// The width of `interval` for a column storing epoch nanoseconds.
+    pub fn nanoseconds(interval: &TimeInterval) -> Result<Self, ValidationError> {
+        let micros = interval.bucket_width_micros()?;
+        micros
+            .checked_mul(1_000)
+            .ok_or_else(|| Self::out_of_range(interval))
+            .and_then(Self::units)
+    }
+
+    /// The width of `interval` for a column storing epoch microseconds.
+    pub fn microseconds(interval: &TimeInterval) -> Result<Self, ValidationError> {
+        Self::units(interval.bucket_width_micros()?)
+    }
+
+    /// The width of `interval` for a column storing epoch milliseconds.
+    pub fn milliseconds(interval: &TimeInterval) -> Result<Self, ValidationError> {
+        Self::whole_units(interval, 1_000, "milliseconds")
+    }
+
+    /// The width of `interval` for a column storing epoch seconds.
+    pub fn seconds(interval: &TimeInterval) -> Result<Self, ValidationError> {
+        Self::whole_units(interval, 1_000_000, "seconds")
+    }
+
+    /// Get the width in the column's unit.
+    pub fn value(&self) -> i64 {
+        self.0
+    }
+
+    fn whole_units(
+        interval: &TimeInterval,
+        micros_per_unit: i64,
+        unit: &str,
+    ) -> Result<Self, ValidationError> {
+        let micros = interval.bucket_width_micros()?;
+        if micros % micros_per_unit != 0 {
+            return Err(ValidationError::InvalidInterval(format!(
+                "Interval '{}' is not a whole number of {}",
+                interval.to_postgres_interval(),
+                unit
+            )));
+        }
+        Self::units(micros / micros_per_unit)
+    }
+
+    fn out_of_range(interval: &TimeInterval) -> ValidationError {
+        ValidationError::InvalidInterval(format!(
+            "Interval out of range: {}",
+            interval.to_postgres_interval()
+        ))
+    }
+}
+
+/// A `time_bucket(width, column)` expression over an integer time column.
+///
+/// Like [`TimeBucket`], the width is rendered as a literal so the expression
+/// can be selected and used in `GROUP BY` of the same query.
+#[derive(Debug, Clone)]
+pub struct IntegerTimeBucket<TimeColumn> {
+    width: IntegerBucketWidth,
+    time_column: TimeColumn,
+}
+
+impl<TimeColumn> IntegerTimeBucket<TimeColumn> {
+    /// Create a new integer time bucket expression.
+    pub fn new(width: IntegerBucketWidth, time_column: TimeColumn) -> Self {
+        Self { width, time_column }
+    }
+}
+
+impl<TimeColumn> QueryFragment<Pg> for IntegerTimeBucket<TimeColumn>
+where
+    TimeColumn: QueryFragment<Pg>,
+{
+    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, Pg>) -> QueryResult<()> {
+        // The SQL text depends on the width value
+        out.unsafe_to_cache_prepared();
+        out.push_sql("time_bucket(");
+        out.push_sql(&self.width.value().to_string());
+        out.push_sql(", ");
+        self.time_column.walk_ast(out.reborrow())?;
+        out.push_sql(")");
+        Ok(())
+    }
+}
+
+bucket_expression!(IntegerTimeBucket);
+
+/// A `time >= now() - interval` predicate, see [`TimescaleQueryDsl::recent`].
+#[derive(Debug, Clone)]
+pub struct Recent<TimeColumn> {
+    time_column: TimeColumn,
+    interval: TimeInterval,
+}
+
+impl<TimeColumn> Recent<TimeColumn> {
+    /// Create a new predicate keeping rows from the last `interval`.
+    pub fn new(time_column: TimeColumn, interval: TimeInterval) -> Self {
+        Self {
+            time_column,
+            interval,
         }
     }
 }
 
+impl<TimeColumn> QueryId for Recent<TimeColumn> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<TimeColumn> Expression for Recent<TimeColumn>
+where
+    TimeColumn: Expression,
+{
+    type SqlType = diesel::sql_types::Bool;
+}
+
+impl<TimeColumn> QueryFragment<Pg> for Recent<TimeColumn>
+where
+    TimeColumn: QueryFragment<Pg>,
+{
+    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, Pg>) -> QueryResult<()> {
+        self.time_column.walk_ast(out.reborrow())?;
+        out.push_sql(" >= now() - ");
+        out.push_bind_param::<diesel::sql_types::Interval, _>(&self.interval)?;
+        Ok(())
+    }
+}
+
+impl<TimeColumn, QS> AppearsOnTable<QS> for Recent<TimeColumn>
+where
+    TimeColumn: AppearsOnTable<QS>,
+    Self: Expression,
+{
+}
+
+impl<TimeColumn, QS> SelectableExpression<QS> for Recent<TimeColumn>
+where
+    TimeColumn: SelectableExpression<QS>,
+    Self: AppearsOnTable<QS>,
+{
+}
+
+impl<TimeColumn, GroupBy> ValidGrouping<GroupBy> for Recent<TimeColumn>
+where
+    TimeColumn: ValidGrouping<GroupBy>,
+{
+    type IsAggregate = TimeColumn::IsAggregate;
+}
+
+/// A `column = value` predicate on a space-partitioning column, see
+/// [`TimescaleQueryDsl::partition_filter`].
+#[derive(Debug, Clone)]
+pub struct PartitionFilter<PartitionColumn, Value> {
+    column: PartitionColumn,
+    value: Value,
+}
+
+impl<PartitionColumn, Value> PartitionFilter<PartitionColumn, Value> {
+    /// Create a new predicate keeping the rows of one partition value.
+    pub fn new(column: PartitionColumn, value: Value) -> Self {
+        Self { column, value }
+    }
+}
+
+impl<PartitionColumn, Value> QueryId for PartitionFilter<PartitionColumn, Value> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<PartitionColumn, Value> Expression for PartitionFilter<PartitionColumn, Value>
+where
+    PartitionColumn: Expression,
+    Value: Expression,
+{
+    type SqlType = diesel::sql_types::Bool;
+}
+
+impl<PartitionColumn, Value> QueryFragment<Pg> for PartitionFilter<PartitionColumn, Value>
+where
+    PartitionColumn: QueryFragment<Pg>,
+    Value: QueryFragment<Pg>,
+{
+    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, Pg>) -> QueryResult<()> {
+        self.column.walk_ast(out.reborrow())?;
+        out.push_sql(" = ");
+        self.value.walk_ast(out.reborrow())?;
+        Ok(())
+    }
+}
+
+impl<PartitionColumn, Value, QS> AppearsOnTable<QS> for PartitionFilter<PartitionColumn, Value>
+where
+    PartitionColumn: AppearsOnTable<QS>,
+    Value: AppearsOnTable<QS>,
+    Self: Expression,
+{
+}
+
+impl<PartitionColumn, Value, QS> SelectableExpression<QS>
+    for PartitionFilter<PartitionColumn, Value>
+where
+    PartitionColumn: SelectableExpression<QS>,
+    Value: SelectableExpression<QS>,
+    Self: AppearsOnTable<QS>,
+{
+}
+
+impl<PartitionColumn, Value, GroupBy> ValidGrouping<GroupBy>
+    for PartitionFilter<PartitionColumn, Value>
+where
+    PartitionColumn: ValidGrouping<GroupBy>,
+{
+    type IsAggregate = PartitionColumn::IsAggregate;
+}
+
 /// A query with time bucketing applied.
 #[derive(Debug, Clone)]
 pub struct TimeBucketQuery<Query, TimeColumn> {
@@ -125,15 +701,143 @@ impl<Query, TimeColumn> TimeBucketQuery<Query, TimeColumn> {
     pub fn interval_sql(&self) -> String {
         self.interval.to_postgres_interval()
     }
+
+    /// Log a warning if the table of the time column is not a hypertable.
+    ///
+    /// A bucketed query on a plain table still runs, but without chunk
+    /// exclusion, which is easy to miss when a migration forgot
+    /// `create_hypertable`. The check costs a round trip, so it only runs in
+    /// debug builds with the `log` feature enabled; otherwise the query is
+    /// returned unchanged without touching the connection.
+    ///
+    /// ```rust,ignore
+    /// let rows: Vec<(TimestampTz, Option<f64>)> = metrics::table
+    ///     .time_bucket(metrics::timestamp, 1.hours())
+    ///     .warn_if_not_hypertable(&mut conn)?
+    ///     .select_bucketed(avg(metrics::value))
+    ///     .load(&mut conn)?;
+    /// ```
+    pub fn warn_if_not_hypertable(self, conn: &mut PgConnection) -> QueryResult<Self>
+    where
+        TimeColumn: Column + QueryFragment<Pg>,
+    {
+        if !cfg!(all(debug_assertions, feature = "log")) {
+            return Ok(self);
+        }
+
+        // The column renders qualified with its table, as `"metrics"."time"`,
+        // so the table name is what precedes the column name
+        let mut query_builder = PgQueryBuilder::default();
+        self.time_column.to_sql(&mut query_builder, &Pg)?;
+        let column = query_builder.finish();
+        let suffix = format!(".\"{}\"", TimeColumn::NAME);
+        let Some(table) = column.strip_suffix(&suffix) else {
+            return Ok(self);
+        };
+
+        if !crate::schema::hypertable_exists(conn, table)? {
+            crate::log_warning!(
+                "time_bucket query on {}, which is not a hypertable; chunk exclusion does not apply",
+                table
+            );
+        }
+        Ok(self)
+    }
+
+    /// Get the `time_bucket` expression over the time column.
+    pub fn bucket(&self) -> TimeBucket<TimeColumn>
+    where
+        TimeColumn: Clone,
+    {
+        TimeBucket::new(self.interval.clone(), self.time_column.clone())
+    }
+
+    /// Group the underlying query by the bucket.
+    pub fn into_grouped(self) -> GroupBy<Query, TimeBucket<TimeColumn>>
+    where
+        TimeColumn: Expression,
+        Query: GroupByDsl<TimeBucket<TimeColumn>>,
+    {
+        let bucket = TimeBucket::new(self.interval, self.time_column);
+        self.query.group_by(bucket)
+    }
+
+    /// Group the underlying query by the bucket and select the bucket
+    /// followed by `aggregates`.
+    ///
+    /// The result is an ordinary Diesel query:
+    ///
+    /// ```rust,ignore
+    /// let rows: Vec<(TimestampTz, Option<f64>)> = metrics::table
+    ///     .time_bucket(metrics::timestamp, 1.hours())
+    ///     .select_bucketed(avg(metrics::value))
+    ///     .load(&mut conn)?;
+    /// ```
+    ///
+    /// Several aggregates are selected with a tuple, such as
+    /// `(avg(value), max(value))`, and load as a nested tuple
+    /// `(bucket, (avg, max))`. The interval is rendered as a literal, see
+    /// [`TimeBucket`]. Rows are not ordered; add `.order_by(...)` on the
+    /// bucket if needed.
+    #[allow(clippy::type_complexity)]
+    pub fn select_bucketed<Aggregates>(
+        self,
+        aggregates: Aggregates,
+    ) -> Select<GroupBy<Query, TimeBucket<TimeColumn>>, (TimeBucket<TimeColumn>, Aggregates)>
+    where
+        TimeColumn: Expression + Clone,
+        Aggregates: Expression,
+        Query: GroupByDsl<TimeBucket<TimeColumn>>,
+        GroupBy<Query, TimeBucket<TimeColumn>>: SelectDsl<(TimeBucket<TimeColumn>, Aggregates)>,
+    {
+        let bucket = self.bucket();
+        self.into_grouped().select((bucket, aggregates))
+    }
+}
+
+/// How the end of a time range is bounded.
+///
+/// The start of a range is always inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeEnd {
+    /// `time < end`, giving the half-open range `[start, end)`.
+    Exclusive,
+    /// `time <= end`, giving the closed range `[start, end]`.
+    Inclusive,
+}
+
+impl RangeEnd {
+    /// Get the SQL comparison operator applied to the end of the range.
+    pub fn operator(&self) -> &'static str {
+        match self {
+            RangeEnd::Exclusive => "<",
+            RangeEnd::Inclusive => "<=",
+        }
+    }
 }
 
 /// A query with time range filtering applied.
+///
+/// Ranges created with `time_range` and `time_range_half_open` are half-open
+/// (`[start, end)`); ranges created with `time_range_closed` also include rows
+/// exactly at `end`. [`exclusive_end`](Self::exclusive_end) and
+/// [`inclusive_end`](Self::inclusive_end) change the bound afterwards.
+///
+/// The range query can be loaded like the query it wraps, with the range
+/// applied as a `WHERE` filter and both bounds bound as parameters:
+///
+/// ```rust,ignore
+/// let rows: Vec<Metric> = metrics::table
+///     .time_range(metrics::timestamp, start, end)
+///     .load(&mut conn)?;
+/// ```
 #[derive(Debug, Clone)]
 pub struct TimeRangeQuery<Query, TimeColumn, Value> {
     query: Query,
     time_column: TimeColumn,
     start: Value,
     end: Value,
+    end_bound: RangeEnd,
 }
 
 impl<Query, TimeColumn, Value> TimeRangeQuery<Query, TimeColumn, Value> {
@@ -156,25 +860,391 @@ impl<Query, TimeColumn, Value> TimeRangeQuery<Query, TimeColumn, Value> {
     pub fn end(&self) -> &Value {
         &self.end
     }
-}
 
-/// Trait for queries that can be executed with time-series optimizations.
-pub trait TimescaleExecuteDsl<Conn> {
-    /// Execute the query with TimescaleDB optimizations enabled.
-    fn execute_timescale(self, conn: &mut Conn) -> QueryResult<usize>;
-}
+    /// Get how the end of the range is bounded.
+    pub fn end_bound(&self) -> RangeEnd {
+        self.end_bound
+    }
 
-/// Common time-series query patterns.
-pub mod patterns {
-    use super::*;
+    /// Exclude rows exactly at `end`, giving the half-open range `[start, end)`.
+    pub fn exclusive_end(mut self) -> Self {
+        self.end_bound = RangeEnd::Exclusive;
+        self
+    }
 
-    /// Helper for creating common time-series aggregation queries.
-    /// This version ensures type safety and prevents SQL injection.
+    /// Include rows exactly at `end`, giving the closed range `[start, end]`.
+    pub fn inclusive_end(mut self) -> Self {
+        self.end_bound = RangeEnd::Inclusive;
+        self
+    }
+
+    /// Get the filter predicate of the range.
+    pub fn predicate(&self) -> TimeRangePredicate<TimeColumn, Value::Expression>
+    where
+        TimeColumn: Clone,
+        Value: AsExpression<Timestamptz> + Clone,
+    {
+        TimeRangePredicate {
+            time_column: self.time_column.clone(),
+            start: self.start.clone().as_expression(),
+            end: self.end.clone().as_expression(),
+            end_bound: self.end_bound,
+        }
+    }
+
+    /// Apply the range filter to the underlying query.
+    pub fn into_filtered(self) -> Filter<Query, TimeRangePredicate<TimeColumn, Value::Expression>>
+    where
+        Value: AsExpression<Timestamptz>,
+        Query: FilterDsl<TimeRangePredicate<TimeColumn, Value::Expression>>,
+    {
+        let predicate = TimeRangePredicate {
+            time_column: self.time_column,
+            start: self.start.as_expression(),
+            end: self.end.as_expression(),
+            end_bound: self.end_bound,
+        };
+        self.query.filter(predicate)
+    }
+}
+
+impl<Query, TimeColumn, Value> AsQuery for TimeRangeQuery<Query, TimeColumn, Value>
+where
+    Value: AsExpression<Timestamptz>,
+    Query: FilterDsl<TimeRangePredicate<TimeColumn, Value::Expression>>,
+    Filter<Query, TimeRangePredicate<TimeColumn, Value::Expression>>: AsQuery,
+{
+    type SqlType =
+        <Filter<Query, TimeRangePredicate<TimeColumn, Value::Expression>> as AsQuery>::SqlType;
+    type Query =
+        <Filter<Query, TimeRangePredicate<TimeColumn, Value::Expression>> as AsQuery>::Query;
+
+    /// Apply the range filter, so loading the range query only returns rows
+    /// inside the range.
+    fn as_query(self) -> Self::Query {
+        self.into_filtered().as_query()
+    }
+}
+
+impl<Query, TimeColumn, Value, Conn> RunQueryDsl<Conn>
+    for TimeRangeQuery<Query, TimeColumn, Value>
+{
+}
+
+/// The predicate of a [`TimeRangeQuery`].
+///
+/// Both bounds are compared directly against the bare time column, as in
+/// `"time" >= $1 AND "time" < $2`. TimescaleDB can only exclude chunks by
+/// their time constraints when the column is not wrapped in a function or
+/// cast, so this is the form that lets the planner skip chunks outside the
+/// range.
+#[derive(Debug, Clone)]
+pub struct TimeRangePredicate<TimeColumn, Value> {
+    time_column: TimeColumn,
+    start: Value,
+    end: Value,
+    end_bound: RangeEnd,
+}
+
+impl<TimeColumn, Value> QueryId for TimeRangePredicate<TimeColumn, Value> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<TimeColumn, Value> Expression for TimeRangePredicate<TimeColumn, Value>
+where
+    TimeColumn: Expression,
+    Value: Expression,
+{
+    type SqlType = diesel::sql_types::Bool;
+}
+
+impl<TimeColumn, Value> QueryFragment<Pg> for TimeRangePredicate<TimeColumn, Value>
+where
+    TimeColumn: QueryFragment<Pg>,
+    Value: QueryFragment<Pg>,
+{
+    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, Pg>) -> QueryResult<()> {
+        out.push_sql("(");
+        self.time_column.walk_ast(out.reborrow())?;
+        out.push_sql(" >= ");
+        self.start.walk_ast(out.reborrow())?;
+        out.push_sql(" AND ");
+        self.time_column.walk_ast(out.reborrow())?;
+        out.push_sql(" ");
+        out.push_sql(self.end_bound.operator());
+        out.push_sql(" ");
+        self.end.walk_ast(out.reborrow())?;
+        out.push_sql(")");
+        Ok(())
+    }
+}
+
+impl<TimeColumn, Value, QS> AppearsOnTable<QS> for TimeRangePredicate<TimeColumn, Value>
+where
+    TimeColumn: AppearsOnTable<QS>,
+    Value: AppearsOnTable<QS>,
+    Self: Expression,
+{
+}
+
+impl<TimeColumn, Value, QS> SelectableExpression<QS> for TimeRangePredicate<TimeColumn, Value>
+where
+    TimeColumn: SelectableExpression<QS>,
+    Value: SelectableExpression<QS>,
+    Self: AppearsOnTable<QS>,
+{
+}
+
+impl<TimeColumn, Value, GroupBy> ValidGrouping<GroupBy> for TimeRangePredicate<TimeColumn, Value>
+where
+    TimeColumn: ValidGrouping<GroupBy>,
+    Value: ValidGrouping<GroupBy>,
+    TimeColumn::IsAggregate: MixedAggregates<Value::IsAggregate>,
+{
+    type IsAggregate = <TimeColumn::IsAggregate as MixedAggregates<Value::IsAggregate>>::Output;
+}
+
+/// A half-open time range `[start, end)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeRange {
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+}
+
+impl TimeRange {
+    /// Create a new time range, validating that `start` is not after `end`.
+    pub fn new(
+        start: chrono::DateTime<chrono::Utc>,
+        end: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Self, ValidationError> {
+        if start > end {
+            return Err(ValidationError::InvalidParameter(format!(
+                "Time range start {} is after its end {}",
+                start, end
+            )));
+        }
+
+        Ok(Self { start, end })
+    }
+
+    /// Get the inclusive start of the range.
+    pub fn start(&self) -> chrono::DateTime<chrono::Utc> {
+        self.start
+    }
+
+    /// Get the exclusive end of the range.
+    pub fn end(&self) -> chrono::DateTime<chrono::Utc> {
+        self.end
+    }
+}
+
+/// A `generate_series(start, end, INTERVAL '...')` expression producing one
+/// timestamp per step, for joining bucketed data against a dense time axis.
+///
+/// `start` and `end` are bound as parameters and `end` is inclusive, as in
+/// `generate_series`: it is part of the series if it falls on a step. The
+/// steps are added in the session time zone, so `1 day` steps stay at local
+/// midnight across daylight saving time changes. The expression can be
+/// selected on its own, loaded with [`load`](Self::load), or used as a
+/// subquery:
+///
+/// ```rust,ignore
+/// let spine = TimeSpine::new(start, end, 1.hours())?;
+/// let hours: Vec<DateTime<Utc>> = spine.load(&mut conn)?;
+///
+/// let covered = metrics::table
+///     .filter(metrics::timestamp.eq_any(diesel::select(spine)))
+///     .load::<Metric>(&mut conn)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct TimeSpine {
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+    interval: TimeInterval,
+}
+
+impl TimeSpine {
+    /// Create a new spine, validating that `start` is not after `end` and
+    /// that the interval is positive.
+    pub fn new(
+        start: chrono::DateTime<chrono::Utc>,
+        end: chrono::DateTime<chrono::Utc>,
+        interval: TimeInterval,
+    ) -> Result<Self, ValidationError> {
+        TimeRange::new(start, end)?;
+        interval.require_positive("Spine interval")?;
+
+        Ok(Self {
+            start,
+            end,
+            interval,
+        })
+    }
+
+    /// Get the first timestamp of the spine.
+    pub fn start(&self) -> chrono::DateTime<chrono::Utc> {
+        self.start
+    }
+
+    /// Get the inclusive end of the spine.
+    pub fn end(&self) -> chrono::DateTime<chrono::Utc> {
+        self.end
+    }
+
+    /// Get the step between the timestamps of the spine.
+    pub fn interval(&self) -> &TimeInterval {
+        &self.interval
+    }
+
+    /// Load the timestamps of the spine.
+    pub fn load(
+        &self,
+        conn: &mut diesel::pg::PgConnection,
+    ) -> QueryResult<Vec<chrono::DateTime<chrono::Utc>>> {
+        diesel::select(self.clone()).load(conn)
+    }
+}
+
+impl QueryId for TimeSpine {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl Expression for TimeSpine {
+    type SqlType = Timestamptz;
+}
+
+impl QueryFragment<Pg> for TimeSpine {
+    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, Pg>) -> QueryResult<()> {
+        // The SQL text depends on the interval value
+        out.unsafe_to_cache_prepared();
+        out.push_sql("generate_series(");
+        out.push_bind_param::<Timestamptz, _>(&self.start)?;
+        out.push_sql(", ");
+        out.push_bind_param::<Timestamptz, _>(&self.end)?;
+        out.push_sql(", INTERVAL '");
+        out.push_sql(&self.interval.to_postgres_interval());
+        out.push_sql("')");
+        Ok(())
+    }
+}
+
+impl<QS> AppearsOnTable<QS> for TimeSpine {}
+
+impl<QS> SelectableExpression<QS> for TimeSpine {}
+
+impl<GroupBy> ValidGrouping<GroupBy> for TimeSpine {
+    type IsAggregate = is_aggregate::Never;
+}
+
+/// An iterator over the bucket start timestamps `time_bucket` produces for a
+/// time range.
+///
+/// Buckets are aligned like `time_bucket(interval, ts)` without an origin,
+/// that is to TimescaleDB's default origin of Monday 2000-01-03 00:00 UTC. The
+/// first bucket is the one containing the start of the range, and buckets are
+/// yielded until the end of the range. This is the client-side complement to
+/// `time_bucket_gapfill`, for example to generate the expected buckets of a
+/// backfill.
+///
+/// Months and years vary in length and are bucketed by the calendar, so only
+/// intervals of weeks or smaller are supported.
+#[derive(Debug, Clone)]
+pub struct TimeBucketIterator {
+    next: Option<i64>,
+    end: i64,
+    width: i64,
+}
+
+impl TimeBucketIterator {
+    /// Create an iterator over the buckets of `interval` within `range`.
+    pub fn new(interval: &TimeInterval, range: &TimeRange) -> Result<Self, ValidationError> {
+        let width = interval.bucket_width_micros()?;
+        let origin = crate::schema::DEFAULT_BUCKET_ORIGIN_MICROS;
+        let start = (range.start.timestamp_micros() - origin).div_euclid(width) * width + origin;
+
+        Ok(Self {
+            next: Some(start),
+            end: range.end.timestamp_micros(),
+            width,
+        })
+    }
+}
+
+impl Iterator for TimeBucketIterator {
+    type Item = chrono::DateTime<chrono::Utc>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bucket = self.next.filter(|bucket| *bucket < self.end)?;
+        self.next = bucket.checked_add(self.width);
+        chrono::DateTime::from_timestamp_micros(bucket)
+    }
+}
+
+/// Trait for queries that can be executed with time-series optimizations.
+pub trait TimescaleExecuteDsl<Conn> {
+    /// Execute the query with TimescaleDB optimizations enabled.
+    fn execute_timescale(self, conn: &mut Conn) -> QueryResult<usize>;
+}
+
+/// Common time-series query patterns.
+pub mod patterns {
+    use super::*;
+    use diesel::sql_types::{Array, Double, Integer};
+
+    /// A time bucket together with the histogram of its values.
+    ///
+    /// Result row for [`TimeSeriesAggregation::histogram_query`].
+    #[derive(Debug, Clone, PartialEq, QueryableByName)]
+    pub struct BucketHistogramRow {
+        #[diesel(sql_type = Timestamptz)]
+        pub bucket: chrono::DateTime<chrono::Utc>,
+        #[diesel(sql_type = Array<Integer>)]
+        pub histogram: Vec<i32>,
+    }
+
+    /// A time bucket together with all values that fell into it.
+    ///
+    /// Result row for [`TimeSeriesAggregation::array_agg_query`].
+    #[derive(Debug, Clone, PartialEq, QueryableByName)]
+    pub struct BucketSamples {
+        #[diesel(sql_type = Timestamptz)]
+        pub bucket: chrono::DateTime<chrono::Utc>,
+        #[diesel(sql_type = Array<Double>)]
+        pub samples: Vec<f64>,
+    }
+
+    /// Runtime parameters of an aggregation query: the bucket width and how
+    /// far back from now to aggregate.
+    ///
+    /// Both intervals are bound as parameters, so a single prepared statement
+    /// serves every combination of values.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct QueryParams {
+        pub bucket: TimeInterval,
+        pub lookback: TimeInterval,
+    }
+
+    impl QueryParams {
+        /// Create new query parameters.
+        pub fn new(bucket: TimeInterval, lookback: TimeInterval) -> Self {
+            Self { bucket, lookback }
+        }
+    }
+
+    /// Helper for creating common time-series aggregation queries.
+    /// This version ensures type safety and prevents SQL injection.
+    #[derive(Debug, Clone)]
     pub struct TimeSeriesAggregation {
         pub table_name: SqlIdentifier,
         pub time_column: SqlIdentifier,
         pub value_column: SqlIdentifier,
         pub bucket_interval: TimeInterval,
+        /// Origin the buckets are aligned to, TimescaleDB's default when unset.
+        pub origin: Option<chrono::DateTime<chrono::Utc>>,
     }
 
     impl TimeSeriesAggregation {
@@ -190,9 +1260,10 @@ pub mod patterns {
         ) -> Result<Self, ValidationError> {
             Ok(Self {
                 table_name: SqlIdentifier::new(table_name)?,
-                time_column: SqlIdentifier::new(time_column)?,
-                value_column: SqlIdentifier::new(value_column)?,
+                time_column: SqlIdentifier::column(time_column)?,
+                value_column: SqlIdentifier::column(value_column)?,
                 bucket_interval: TimeInterval::from_string(bucket_interval)?,
+                origin: None,
             })
         }
 
@@ -208,6 +1279,37 @@ pub mod patterns {
                 time_column,
                 value_column,
                 bucket_interval,
+                origin: None,
+            }
+        }
+
+        /// Align the buckets to `origin` instead of TimescaleDB's default origin.
+        ///
+        /// By default buckets are aligned to Monday 2000-01-03 00:00 UTC, so
+        /// weekly buckets start on Mondays at midnight UTC. An origin shifts
+        /// every bucket boundary to `origin` plus a multiple of the interval,
+        /// e.g. weeks starting on Sundays or fiscal days starting at 06:00.
+        /// Month and year buckets are aligned to the origin's day of month, so
+        /// an origin on the 1st keeps them on calendar months.
+        pub fn with_origin(mut self, origin: chrono::DateTime<chrono::Utc>) -> Self {
+            self.origin = Some(origin);
+            self
+        }
+
+        /// Build the `time_bucket` expression of the aggregation.
+        fn bucket_sql(&self) -> String {
+            match &self.origin {
+                Some(origin) => format!(
+                    "time_bucket(INTERVAL '{}', {}, TIMESTAMPTZ '{}')",
+                    self.bucket_interval.to_postgres_interval(),
+                    self.time_column.escaped(),
+                    origin.to_rfc3339()
+                ),
+                None => format!(
+                    "time_bucket(INTERVAL '{}', {})",
+                    self.bucket_interval.to_postgres_interval(),
+                    self.time_column.escaped()
+                ),
             }
         }
 
@@ -217,12 +1319,11 @@ pub mod patterns {
         /// All identifiers are properly escaped to prevent SQL injection.
         pub fn avg_query(&self) -> String {
             format!(
-                "SELECT time_bucket(INTERVAL '{}', {}) as bucket, avg({}) as average 
+                "SELECT {} as bucket, avg({}) as average 
                  FROM {} 
                  GROUP BY bucket 
                  ORDER BY bucket",
-                self.bucket_interval.to_postgres_interval(),
-                self.time_column.escaped(),
+                self.bucket_sql(),
                 self.value_column.escaped(),
                 self.table_name.escaped()
             )
@@ -234,12 +1335,11 @@ pub mod patterns {
         /// All identifiers are properly escaped to prevent SQL injection.
         pub fn sum_query(&self) -> String {
             format!(
-                "SELECT time_bucket(INTERVAL '{}', {}) as bucket, sum({}) as total 
+                "SELECT {} as bucket, sum({}) as total 
                  FROM {} 
                  GROUP BY bucket 
                  ORDER BY bucket",
-                self.bucket_interval.to_postgres_interval(),
-                self.time_column.escaped(),
+                self.bucket_sql(),
                 self.value_column.escaped(),
                 self.table_name.escaped()
             )
@@ -251,14 +1351,760 @@ pub mod patterns {
         /// All identifiers are properly escaped to prevent SQL injection.
         pub fn count_query(&self) -> String {
             format!(
-                "SELECT time_bucket(INTERVAL '{}', {}) as bucket, count(*) as count 
+                "SELECT {} as bucket, count(*) as count 
+                 FROM {} 
+                 GROUP BY bucket 
+                 ORDER BY bucket",
+                self.bucket_sql(),
+                self.table_name.escaped()
+            )
+        }
+
+        /// Build an average aggregation over the lookback window of `params`.
+        ///
+        /// Unlike [`avg_query`](Self::avg_query), the bucket interval and the
+        /// lookback window are bound parameters; the bucket interval configured
+        /// on the aggregation is not used.
+        pub fn avg_query_with(&self, params: &QueryParams) -> SqlStatement {
+            self.parameterized_query(
+                &format!("avg({}) as average", self.value_column.escaped()),
+                params,
+            )
+        }
+
+        /// Build a sum aggregation over the lookback window of `params`.
+        ///
+        /// See [`avg_query_with`](Self::avg_query_with).
+        pub fn sum_query_with(&self, params: &QueryParams) -> SqlStatement {
+            self.parameterized_query(
+                &format!("sum({}) as total", self.value_column.escaped()),
+                params,
+            )
+        }
+
+        /// Build a count aggregation over the lookback window of `params`.
+        ///
+        /// See [`avg_query_with`](Self::avg_query_with).
+        pub fn count_query_with(&self, params: &QueryParams) -> SqlStatement {
+            self.parameterized_query("count(*) as count", params)
+        }
+
+        fn parameterized_query(&self, aggregate: &str, params: &QueryParams) -> SqlStatement {
+            let origin = match &self.origin {
+                Some(origin) => format!(", TIMESTAMPTZ '{}'", origin.to_rfc3339()),
+                None => String::new(),
+            };
+
+            // The bucket is grouped by its alias, so its parameter appears once
+            SqlStatement::new("SELECT time_bucket(")
+                .bind(params.bucket.clone())
+                .sql(format!(
+                    ", {}{}) as bucket, {} FROM {} WHERE {} >= now() - ",
+                    self.time_column.escaped(),
+                    origin,
+                    aggregate,
+                    self.table_name.escaped(),
+                    self.time_column.escaped()
+                ))
+                .bind(params.lookback.clone())
+                .sql(" GROUP BY bucket ORDER BY bucket")
+        }
+
+        /// Build a query string counting the non-NULL values in each bucket.
+        ///
+        /// Unlike [`count_query`](Self::count_query), which counts rows, rows
+        /// whose value is NULL are not counted.
+        ///
+        /// # Security
+        /// All identifiers are properly escaped to prevent SQL injection.
+        pub fn count_non_null_query(&self) -> String {
+            format!(
+                "SELECT {} as bucket, count({}) as count 
+                 FROM {} 
+                 GROUP BY bucket 
+                 ORDER BY bucket",
+                self.bucket_sql(),
+                self.value_column.escaped(),
+                self.table_name.escaped()
+            )
+        }
+
+        /// Build a query string counting the NULL values in each bucket.
+        ///
+        /// Together with [`count_non_null_query`](Self::count_non_null_query)
+        /// this distinguishes missing values from zeros.
+        ///
+        /// # Security
+        /// All identifiers are properly escaped to prevent SQL injection.
+        pub fn null_count_query(&self) -> String {
+            format!(
+                "SELECT {} as bucket, count(*) - count({}) as null_count 
                  FROM {} 
                  GROUP BY bucket 
                  ORDER BY bucket",
+                self.bucket_sql(),
+                self.value_column.escaped(),
+                self.table_name.escaped()
+            )
+        }
+
+        /// Build a query string computing a histogram of the values in each bucket.
+        ///
+        /// Values in `[min, max)` are counted into `num_buckets` equal-width bins.
+        /// As with TimescaleDB's `histogram`, each array has `num_buckets + 2`
+        /// entries: the first counts values below `min` and the last values at or
+        /// above `max`.
+        ///
+        /// # Security
+        /// All identifiers are properly escaped to prevent SQL injection, and the
+        /// bounds are validated to be finite numbers.
+        pub fn histogram_query(
+            &self,
+            min: f64,
+            max: f64,
+            num_buckets: i32,
+        ) -> Result<String, ValidationError> {
+            if !min.is_finite() || !max.is_finite() || min >= max {
+                return Err(ValidationError::InvalidParameter(
+                    "Histogram bounds must be finite with min < max".to_string(),
+                ));
+            }
+            if num_buckets <= 0 {
+                return Err(ValidationError::InvalidParameter(
+                    "Histogram must have at least one bucket".to_string(),
+                ));
+            }
+
+            Ok(format!(
+                "SELECT {} as bucket, histogram({}, {:?}, {:?}, {}) as histogram 
+                 FROM {} 
+                 GROUP BY bucket 
+                 ORDER BY bucket",
+                self.bucket_sql(),
+                self.value_column.escaped(),
+                min,
+                max,
+                num_buckets,
+                self.table_name.escaped()
+            ))
+        }
+
+        /// Build a query string computing the rate of change per second in each bucket.
+        ///
+        /// The rate is the difference between the last and the first value of
+        /// the bucket, divided by the bucket width in seconds, which suits gauges
+        /// on TimescaleDB without Toolkit. Counters that reset need Toolkit's
+        /// `counter_agg` instead, since a reset inside a bucket makes the
+        /// difference wrong. The result loads into [`BucketedValue`]; the value
+        /// column is expected to be `double precision`.
+        ///
+        /// # Security
+        /// All identifiers are properly escaped to prevent SQL injection.
+        pub fn rate_of_change_query(&self) -> String {
+            format!(
+                "SELECT {} as bucket, (last({}, {}) - first({}, {})) / extract(epoch FROM INTERVAL '{}')::float8 as value
+                 FROM {}
+                 GROUP BY bucket
+                 ORDER BY bucket",
+                self.bucket_sql(),
+                self.value_column.escaped(),
+                self.time_column.escaped(),
+                self.value_column.escaped(),
+                self.time_column.escaped(),
                 self.bucket_interval.to_postgres_interval(),
+                self.table_name.escaped()
+            )
+        }
+
+        /// Build a query string collecting every value in each bucket into an array.
+        ///
+        /// The values are ordered by the time column inside the aggregate, so each
+        /// array is in time order regardless of how the rows were scanned. NULL
+        /// values are skipped (a bucket of only NULLs yields an empty array) so the
+        /// result loads into [`BucketSamples`]; the value column is expected to be
+        /// `double precision`.
+        ///
+        /// # Security
+        /// All identifiers are properly escaped to prevent SQL injection.
+        pub fn array_agg_query(&self) -> String {
+            format!(
+                "SELECT {} as bucket, coalesce(array_agg({} ORDER BY {}) FILTER (WHERE {} IS NOT NULL), '{{}}') as samples 
+                 FROM {} 
+                 GROUP BY bucket 
+                 ORDER BY bucket",
+                self.bucket_sql(),
+                self.value_column.escaped(),
                 self.time_column.escaped(),
+                self.value_column.escaped(),
                 self.table_name.escaped()
             )
         }
     }
+
+    /// A time bucket together with its aggregated value, if any row fell into it.
+    ///
+    /// Result row for [`BucketSpineQuery`] and
+    /// [`TimeSeriesAggregation::rate_of_change_query`].
+    #[derive(Debug, Clone, PartialEq, QueryableByName)]
+    pub struct BucketedValue {
+        #[diesel(sql_type = Timestamptz)]
+        pub bucket: chrono::DateTime<chrono::Utc>,
+        #[diesel(sql_type = diesel::sql_types::Nullable<Double>)]
+        pub value: Option<f64>,
+    }
+
+    pub use crate::schema::RollupAggregate;
+
+    /// Builder for a bucketed aggregate aligned to a dense series of buckets.
+    ///
+    /// Every bucket of the time range appears in the result, and buckets
+    /// without any rows have a `None` value. The bucket series is generated
+    /// with `generate_series` and left-joined against the aggregate, so unlike
+    /// `time_bucket_gapfill` it works with any interval, including months and
+    /// years, and needs no further query restrictions.
+    ///
+    /// Buckets are aligned like `time_bucket` without an origin. The series is
+    /// generated in UTC, which is also how `time_bucket` buckets `timestamptz`
+    /// values, so month buckets start on the first of the month in UTC.
+    #[derive(Debug, Clone)]
+    pub struct BucketSpineQuery {
+        table_name: SqlIdentifier,
+        time_column: SqlIdentifier,
+        value_column: SqlIdentifier,
+        bucket_interval: TimeInterval,
+        range: TimeRange,
+        aggregate: RollupAggregate,
+    }
+
+    impl BucketSpineQuery {
+        /// Create a new spine query averaging the value column.
+        pub fn new(
+            table_name: &str,
+            time_column: &str,
+            value_column: &str,
+            bucket_interval: TimeInterval,
+            range: TimeRange,
+        ) -> Result<Self, ValidationError> {
+            bucket_interval.require_positive("Bucket interval")?;
+
+            Ok(Self {
+                table_name: SqlIdentifier::new(table_name)?,
+                time_column: SqlIdentifier::column(time_column)?,
+                value_column: SqlIdentifier::column(value_column)?,
+                bucket_interval,
+                range,
+                aggregate: RollupAggregate::Avg,
+            })
+        }
+
+        /// Set the aggregate computed per bucket.
+        pub fn with_aggregate(mut self, aggregate: RollupAggregate) -> Self {
+            self.aggregate = aggregate;
+            self
+        }
+
+        /// Get the table the values are read from.
+        pub fn table_name(&self) -> &SqlIdentifier {
+            &self.table_name
+        }
+
+        /// Get the time column the buckets are computed from.
+        pub fn time_column(&self) -> &SqlIdentifier {
+            &self.time_column
+        }
+
+        /// Get the column that is aggregated per bucket.
+        pub fn value_column(&self) -> &SqlIdentifier {
+            &self.value_column
+        }
+
+        /// Get the width of the buckets.
+        pub fn bucket_interval(&self) -> &TimeInterval {
+            &self.bucket_interval
+        }
+
+        /// Get the time range the buckets cover.
+        pub fn range(&self) -> TimeRange {
+            self.range
+        }
+
+        /// Get the aggregate computed per bucket.
+        pub fn aggregate(&self) -> RollupAggregate {
+            self.aggregate
+        }
+
+        /// Build the statement, which loads into [`BucketedValue`] rows.
+        pub fn to_statement(&self) -> SqlStatement {
+            let interval = format!("INTERVAL '{}'", self.bucket_interval.to_postgres_interval());
+
+            SqlStatement::new(format!(
+                "WITH spine AS (\
+                 SELECT generate_series(\
+                 time_bucket({interval}, ",
+                interval = interval
+            ))
+            .bind(self.range.start())
+            .sql(") AT TIME ZONE 'UTC', (")
+            .bind(self.range.end())
+            .sql(format!(
+                " - INTERVAL '1 microsecond') AT TIME ZONE 'UTC', {interval}\
+                 ) AT TIME ZONE 'UTC' AS bucket), \
+                 buckets AS (\
+                 SELECT time_bucket({interval}, {time}) AS bucket, {aggregate}({value})::float8 AS value \
+                 FROM {table} WHERE {time} >= ",
+                interval = interval,
+                time = self.time_column.escaped(),
+                aggregate = self.aggregate.function_name(),
+                value = self.value_column.escaped(),
+                table = self.table_name.escaped()
+            ))
+            .bind(self.range.start())
+            .sql(format!(" AND {} < ", self.time_column.escaped()))
+            .bind(self.range.end())
+            .sql(
+                " GROUP BY 1) \
+                 SELECT spine.bucket, buckets.value \
+                 FROM spine LEFT JOIN buckets ON buckets.bucket = spine.bucket \
+                 ORDER BY spine.bucket",
+            )
+        }
+    }
+
+    /// Result row for [`BucketJoinQuery`]: the aggregates of both sides in a
+    /// bucket.
+    #[derive(Debug, Clone, PartialEq, QueryableByName)]
+    pub struct JoinedBucket {
+        #[diesel(sql_type = Timestamptz)]
+        pub bucket: chrono::DateTime<chrono::Utc>,
+        #[diesel(sql_type = diesel::sql_types::Nullable<Double>)]
+        pub left: Option<f64>,
+        #[diesel(sql_type = diesel::sql_types::Nullable<Double>)]
+        pub right: Option<f64>,
+    }
+
+    /// Builder for a query that aggregates two tables into the same buckets
+    /// and joins them on the bucket, such as hourly averages of two metrics
+    /// from different sources.
+    ///
+    /// Each side is aggregated over the time range on its own, with the range
+    /// as a predicate on its time column, so both hypertables only scan the
+    /// chunks in range. Only buckets with rows on both sides are returned.
+    /// Both sides must use the same bucket interval and origin, so their
+    /// bucket boundaries line up.
+    #[derive(Debug, Clone)]
+    pub struct BucketJoinQuery {
+        pub left: TimeSeriesAggregation,
+        pub right: TimeSeriesAggregation,
+        pub range: TimeRange,
+        pub left_aggregate: RollupAggregate,
+        pub right_aggregate: RollupAggregate,
+    }
+
+    impl BucketJoinQuery {
+        /// Create a new join averaging the value column of both sides.
+        pub fn new(
+            left: TimeSeriesAggregation,
+            right: TimeSeriesAggregation,
+            range: TimeRange,
+        ) -> Result<Self, ValidationError> {
+            if left.bucket_interval != right.bucket_interval || left.origin != right.origin {
+                return Err(ValidationError::InvalidParameter(format!(
+                    "Both sides of a bucket join must use the same buckets, got '{}' and '{}'",
+                    left.bucket_interval.to_postgres_interval(),
+                    right.bucket_interval.to_postgres_interval()
+                )));
+            }
+            left.bucket_interval.require_positive("Bucket interval")?;
+
+            Ok(Self {
+                left,
+                right,
+                range,
+                left_aggregate: RollupAggregate::Avg,
+                right_aggregate: RollupAggregate::Avg,
+            })
+        }
+
+        /// Set the aggregates computed per bucket on each side.
+        pub fn with_aggregates(mut self, left: RollupAggregate, right: RollupAggregate) -> Self {
+            self.left_aggregate = left;
+            self.right_aggregate = right;
+            self
+        }
+
+        /// Build the statement, which loads into [`JoinedBucket`] rows.
+        pub fn to_statement(&self) -> SqlStatement {
+            let side = |statement: SqlStatement,
+                        name: &str,
+                        aggregation: &TimeSeriesAggregation,
+                        aggregate: RollupAggregate| {
+                statement
+                    .sql(format!(
+                        "{name} AS (\
+                         SELECT {bucket} AS bucket, {aggregate}({value})::float8 AS value \
+                         FROM {table} WHERE {time} >= ",
+                        name = name,
+                        bucket = aggregation.bucket_sql(),
+                        aggregate = aggregate.function_name(),
+                        value = aggregation.value_column.escaped(),
+                        table = aggregation.table_name.escaped(),
+                        time = aggregation.time_column.escaped()
+                    ))
+                    .bind(self.range.start())
+                    .sql(format!(" AND {} < ", aggregation.time_column.escaped()))
+                    .bind(self.range.end())
+                    .sql(" GROUP BY 1)")
+            };
+
+            let statement = side(
+                SqlStatement::new("WITH "),
+                "left_buckets",
+                &self.left,
+                self.left_aggregate,
+            );
+            side(
+                statement.sql(", "),
+                "right_buckets",
+                &self.right,
+                self.right_aggregate,
+            )
+            .sql(
+                " SELECT left_buckets.bucket, left_buckets.value AS left, right_buckets.value AS right \
+                 FROM left_buckets JOIN right_buckets ON right_buckets.bucket = left_buckets.bucket \
+                 ORDER BY left_buckets.bucket",
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::patterns::*;
+    use super::IntegerBucketWidth;
+    use super::TimeBucket;
+    use super::TimeSpine;
+    use super::TimescaleQueryDsl;
+    use super::{TimeBucketIterator, TimeRange};
+    use crate::schema::IntervalExt;
+    use crate::schema::{TimeInterval, TimeUnit, ValidationError};
+    use crate::statement::BindValue;
+    use chrono::TimeZone;
+    use diesel::debug_query;
+    use diesel::pg::Pg;
+    use diesel::prelude::*;
+    use diesel::query_builder::AsQuery;
+
+    diesel::table! {
+        metrics (id) {
+            id -> Integer,
+            timestamp -> Timestamptz,
+            value -> Double,
+        }
+    }
+
+    #[test]
+    fn time_bucket_renders_the_column_through_diesel() {
+        let bucket = TimeBucket::new(1.hours(), metrics::timestamp);
+        let query = metrics::table.select(bucket);
+        assert_eq!(
+            debug_query::<Pg, _>(&query).to_string(),
+            r#"SELECT time_bucket(INTERVAL '1 hours', "metrics"."timestamp") FROM "metrics" -- binds: []"#
+        );
+    }
+
+    #[test]
+    fn time_bucket_qualifies_the_column_of_an_aliased_table() {
+        let recent = diesel::alias!(metrics as recent_metrics);
+        let bucket = TimeBucket::new(1.hours(), recent.field(metrics::timestamp));
+        let query = recent.select(bucket);
+        assert_eq!(
+            debug_query::<Pg, _>(&query).to_string(),
+            r#"SELECT time_bucket(INTERVAL '1 hours', "recent_metrics"."timestamp") FROM "metrics" AS "recent_metrics" -- binds: []"#
+        );
+    }
+
+    fn aggregation() -> TimeSeriesAggregation {
+        TimeSeriesAggregation::new("metrics", "timestamp", "value", "1 hours").unwrap()
+    }
+
+    /// Collapse the whitespace of a generated query onto one line.
+    fn normalized(sql: &str) -> String {
+        sql.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    #[test]
+    fn histogram_query_buckets_the_histogram() {
+        let sql = aggregation().histogram_query(0.0, 100.0, 10).unwrap();
+        assert_eq!(
+            normalized(&sql),
+            r#"SELECT time_bucket(INTERVAL '1 hours', "timestamp") as bucket, histogram("value", 0.0, 100.0, 10) as histogram FROM "metrics" GROUP BY bucket ORDER BY bucket"#
+        );
+    }
+
+    #[test]
+    fn rate_of_change_query_divides_the_bucket_delta_by_its_width() {
+        assert_eq!(
+            normalized(&aggregation().rate_of_change_query()),
+            r#"SELECT time_bucket(INTERVAL '1 hours', "timestamp") as bucket, (last("value", "timestamp") - first("value", "timestamp")) / extract(epoch FROM INTERVAL '1 hours')::float8 as value FROM "metrics" GROUP BY bucket ORDER BY bucket"#
+        );
+    }
+
+    #[test]
+    fn histogram_query_rejects_invalid_bounds() {
+        let aggregation = aggregation();
+        assert!(aggregation.histogram_query(10.0, 10.0, 4).is_err());
+        assert!(aggregation.histogram_query(f64::NAN, 10.0, 4).is_err());
+        assert!(aggregation.histogram_query(0.0, 10.0, 0).is_err());
+    }
+
+    fn utc(day: u32, hour: u32, minute: u32) -> chrono::DateTime<chrono::Utc> {
+        chrono::Utc
+            .with_ymd_and_hms(2026, 3, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn time_range_rejects_a_start_after_its_end() {
+        let result = TimeRange::new(utc(14, 12, 0), utc(14, 11, 0));
+        assert!(matches!(result, Err(ValidationError::InvalidParameter(_))));
+        assert!(TimeRange::new(utc(14, 11, 0), utc(14, 11, 0)).is_ok());
+    }
+
+    #[test]
+    fn bucket_iterator_starts_at_the_bucket_containing_the_range_start() {
+        let range = TimeRange::new(utc(14, 10, 20), utc(14, 11, 5)).unwrap();
+        let buckets: Vec<_> =
+            TimeBucketIterator::new(&TimeInterval::new(15, TimeUnit::Minutes), &range)
+                .unwrap()
+                .collect();
+        assert_eq!(
+            buckets,
+            vec![
+                utc(14, 10, 15),
+                utc(14, 10, 30),
+                utc(14, 10, 45),
+                utc(14, 11, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn bucket_iterator_over_an_empty_range_is_empty() {
+        let range = TimeRange::new(utc(14, 10, 0), utc(14, 10, 0)).unwrap();
+        let mut buckets =
+            TimeBucketIterator::new(&TimeInterval::new(1, TimeUnit::Hours), &range).unwrap();
+        assert_eq!(buckets.next(), None);
+    }
+
+    #[test]
+    fn bucket_iterator_rejects_calendar_intervals() {
+        let range = TimeRange::new(utc(1, 0, 0), utc(31, 0, 0)).unwrap();
+        let result = TimeBucketIterator::new(&TimeInterval::new(1, TimeUnit::Months), &range);
+        assert!(matches!(result, Err(ValidationError::InvalidInterval(_))));
+    }
+
+    #[test]
+    fn count_non_null_query_counts_the_value_column() {
+        assert_eq!(
+            normalized(&aggregation().count_non_null_query()),
+            r#"SELECT time_bucket(INTERVAL '1 hours', "timestamp") as bucket, count("value") as count FROM "metrics" GROUP BY bucket ORDER BY bucket"#
+        );
+    }
+
+    #[test]
+    fn null_count_query_subtracts_the_non_null_values() {
+        assert_eq!(
+            normalized(&aggregation().null_count_query()),
+            r#"SELECT time_bucket(INTERVAL '1 hours', "timestamp") as bucket, count(*) - count("value") as null_count FROM "metrics" GROUP BY bucket ORDER BY bucket"#
+        );
+    }
+
+    #[test]
+    fn parameterized_queries_bind_both_intervals() {
+        let params = QueryParams::new(15.minutes(), 1.days());
+        let statement = aggregation().avg_query_with(&params);
+        assert_eq!(
+            statement.placeholder_sql(),
+            r#"SELECT time_bucket($1, "timestamp") as bucket, avg("value") as average FROM "metrics" WHERE "timestamp" >= now() - $2 GROUP BY bucket ORDER BY bucket"#
+        );
+        assert_eq!(
+            statement.binds(),
+            vec![
+                &BindValue::Interval(15.minutes()),
+                &BindValue::Interval(1.days())
+            ]
+        );
+    }
+
+    #[test]
+    fn parameterized_queries_share_their_text_across_parameters() {
+        let aggregation = aggregation();
+        let hourly = aggregation.count_query_with(&QueryParams::new(1.hours(), 7.days()));
+        let daily = aggregation.count_query_with(&QueryParams::new(1.days(), 30.days()));
+        assert_eq!(hourly.placeholder_sql(), daily.placeholder_sql());
+        assert_ne!(hourly.binds(), daily.binds());
+    }
+
+    #[test]
+    fn half_open_range_compares_the_bare_time_column() {
+        let query = metrics::table
+            .time_range(metrics::timestamp, utc(14, 0, 0), utc(15, 0, 0))
+            .into_filtered()
+            .select(metrics::id);
+        assert_eq!(
+            debug_query::<Pg, _>(&query).to_string(),
+            r#"SELECT "metrics"."id" FROM "metrics" WHERE ("metrics"."timestamp" >= $1 AND "metrics"."timestamp" < $2) -- binds: [2026-03-14T00:00:00Z, 2026-03-15T00:00:00Z]"#
+        );
+    }
+
+    #[test]
+    fn closed_range_predicate_includes_the_end() {
+        let predicate = metrics::table
+            .time_range_closed(metrics::timestamp, utc(14, 0, 0), utc(15, 0, 0))
+            .predicate();
+        let query = metrics::table.filter(predicate).select(metrics::id);
+        assert_eq!(
+            debug_query::<Pg, _>(&query).to_string(),
+            r#"SELECT "metrics"."id" FROM "metrics" WHERE ("metrics"."timestamp" >= $1 AND "metrics"."timestamp" <= $2) -- binds: [2026-03-14T00:00:00Z, 2026-03-15T00:00:00Z]"#
+        );
+    }
+
+    #[test]
+    fn recent_compares_the_bare_column_against_now() {
+        let query = metrics::table
+            .recent(metrics::timestamp, 7.days())
+            .select(metrics::id);
+        assert_eq!(
+            debug_query::<Pg, _>(&query).to_string(),
+            r#"SELECT "metrics"."id" FROM "metrics" WHERE "metrics"."timestamp" >= now() - $1 -- binds: [TimeInterval { components: [(7, Days)], negative: false }]"#
+        );
+    }
+
+    #[test]
+    fn select_bucketed_groups_by_the_bucket() {
+        let query = metrics::table
+            .time_bucket(metrics::timestamp, 1.hours())
+            .select_bucketed((
+                diesel::dsl::avg(metrics::value),
+                diesel::dsl::max(metrics::value),
+            ));
+        assert_eq!(
+            debug_query::<Pg, _>(&query).to_string(),
+            r#"SELECT time_bucket(INTERVAL '1 hours', "metrics"."timestamp"), avg("metrics"."value"), max("metrics"."value") FROM "metrics" GROUP BY time_bucket(INTERVAL '1 hours', "metrics"."timestamp") -- binds: []"#
+        );
+    }
+
+    #[test]
+    fn time_spine_binds_its_bounds_and_inlines_the_interval() {
+        let spine = TimeSpine::new(utc(14, 10, 0), utc(14, 13, 0), 1.hours()).unwrap();
+        assert_eq!(
+            debug_query::<Pg, _>(&diesel::select(spine)).to_string(),
+            "SELECT generate_series($1, $2, INTERVAL '1 hours') \
+             -- binds: [2026-03-14T10:00:00Z, 2026-03-14T13:00:00Z]"
+        );
+    }
+
+    #[test]
+    fn time_spine_exposes_its_validated_bounds() {
+        let spine = TimeSpine::new(utc(14, 10, 0), utc(14, 13, 0), 1.hours()).unwrap();
+        assert_eq!(
+            (spine.start(), spine.end()),
+            (utc(14, 10, 0), utc(14, 13, 0))
+        );
+        assert_eq!(spine.interval(), &1.hours());
+    }
+
+    #[test]
+    fn bucket_spine_query_rejects_non_positive_intervals() {
+        let range = TimeRange::new(utc(14, 0, 0), utc(15, 0, 0)).unwrap();
+        for interval in [0.hours(), 1.hours().negated()] {
+            assert!(matches!(
+                super::patterns::BucketSpineQuery::new("metrics", "ts", "value", interval, range),
+                Err(ValidationError::InvalidInterval(_))
+            ));
+        }
+        let query =
+            super::patterns::BucketSpineQuery::new("metrics", "ts", "value", 1.days(), range)
+                .unwrap();
+        assert_eq!(query.bucket_interval(), &1.days());
+        assert_eq!(query.table_name().escaped(), "\"metrics\"");
+    }
+
+    #[test]
+    fn time_spine_rejects_reversed_bounds_and_empty_steps() {
+        assert!(matches!(
+            TimeSpine::new(utc(14, 13, 0), utc(14, 10, 0), 1.hours()),
+            Err(ValidationError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            TimeSpine::new(utc(14, 10, 0), utc(14, 13, 0), 0.hours()),
+            Err(ValidationError::InvalidInterval(_))
+        ));
+    }
+
+    #[test]
+    fn range_query_filters_when_used_as_a_query() {
+        let range =
+            metrics::table.time_range_closed(metrics::timestamp, utc(14, 0, 0), utc(15, 0, 0));
+        assert_eq!(
+            debug_query::<Pg, _>(&range.clone().as_query()).to_string(),
+            r#"SELECT "metrics"."id", "metrics"."timestamp", "metrics"."value" FROM "metrics" WHERE ("metrics"."timestamp" >= $1 AND "metrics"."timestamp" <= $2) -- binds: [2026-03-14T00:00:00Z, 2026-03-15T00:00:00Z]"#
+        );
+        assert_eq!(
+            debug_query::<Pg, _>(&range.exclusive_end().as_query()).to_string(),
+            r#"SELECT "metrics"."id", "metrics"."timestamp", "metrics"."value" FROM "metrics" WHERE ("metrics"."timestamp" >= $1 AND "metrics"."timestamp" < $2) -- binds: [2026-03-14T00:00:00Z, 2026-03-15T00:00:00Z]"#
+        );
+    }
+
+    #[test]
+    fn integer_bucket_widths_follow_the_column_unit() {
+        let hour = 1.hours();
+        assert_eq!(
+            IntegerBucketWidth::nanoseconds(&hour).unwrap().value(),
+            3_600_000_000_000
+        );
+        assert_eq!(
+            IntegerBucketWidth::microseconds(&hour).unwrap().value(),
+            3_600_000_000
+        );
+        assert_eq!(
+            IntegerBucketWidth::milliseconds(&hour).unwrap().value(),
+            3_600_000
+        );
+        assert_eq!(IntegerBucketWidth::seconds(&hour).unwrap().value(), 3_600);
+        assert_eq!(
+            IntegerBucketWidth::nanoseconds(&7.days()).unwrap().value(),
+            604_800_000_000_000
+        );
+        assert_eq!(
+            IntegerBucketWidth::nanoseconds(&250.microseconds())
+                .unwrap()
+                .value(),
+            250_000
+        );
+        assert_eq!(
+            IntegerBucketWidth::microseconds(&250.microseconds())
+                .unwrap()
+                .value(),
+            250
+        );
+    }
+
+    #[test]
+    fn integer_bucket_widths_reject_partial_and_empty_units() {
+        assert!(matches!(
+            IntegerBucketWidth::milliseconds(&1500.microseconds()),
+            Err(ValidationError::InvalidInterval(_))
+        ));
+        assert!(matches!(
+            IntegerBucketWidth::seconds(&90.milliseconds()),
+            Err(ValidationError::InvalidInterval(_))
+        ));
+        assert!(IntegerBucketWidth::nanoseconds(&0.seconds()).is_err());
+        assert!(IntegerBucketWidth::microseconds(&1.months()).is_err());
+        assert!(matches!(
+            IntegerBucketWidth::units(0),
+            Err(ValidationError::InvalidParameter(_))
+        ));
+    }
 }
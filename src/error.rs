@@ -0,0 +1,162 @@
+//! Error types for TimescaleDB operations.
+
+use crate::schema::ValidationError;
+use crate::version::TimescaleVersion;
+use std::fmt;
+
+/// An error from a TimescaleDB operation.
+///
+/// Operations that validate their input or check the installed version
+/// before talking to the database return this type, so an invalid
+/// identifier or interval can be told apart from an error reported by the
+/// database. Errors from Diesel convert into it with `?`.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum TimescaleError {
+    /// The input was rejected before anything was sent to the database.
+    Validation(ValidationError),
+    /// The installed TimescaleDB version does not support the operation.
+    ///
+    /// `removed` is the first version without the feature, for features
+    /// TimescaleDB has dropped since `required`.
+    UnsupportedVersion {
+        feature: String,
+        required: TimescaleVersion,
+        removed: Option<TimescaleVersion>,
+        installed: TimescaleVersion,
+    },
+    /// The TimescaleDB extension is not installed in the database.
+    ExtensionNotInstalled,
+    /// The database returned an error.
+    Database(diesel::result::Error),
+    /// A connection to the database could not be established.
+    Connection(diesel::ConnectionError),
+}
+
+/// A `Result` with [`TimescaleError`] as the error type.
+pub type TimescaleResult<T> = Result<T, TimescaleError>;
+
+impl fmt::Display for TimescaleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimescaleError::Validation(e) => write!(f, "Validation error: {}", e),
+            TimescaleError::UnsupportedVersion {
+                feature,
+                required,
+                removed: None,
+                installed,
+            } => write!(
+                f,
+                "{} requires TimescaleDB {} or later (installed: {})",
+                feature, required, installed
+            ),
+            TimescaleError::UnsupportedVersion {
+                feature,
+                required,
+                removed: Some(removed),
+                installed,
+            } => write!(
+                f,
+                "{} requires TimescaleDB {} or later and was removed in {} (installed: {})",
+                feature, required, removed, installed
+            ),
+            TimescaleError::ExtensionNotInstalled => write!(
+                f,
+                "The TimescaleDB extension is not installed in this database \
+                 (run CREATE EXTENSION timescaledb)"
+            ),
+            TimescaleError::Database(e) => write!(f, "Database error: {}", e),
+            TimescaleError::Connection(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for TimescaleError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TimescaleError::Validation(e) => Some(e),
+            TimescaleError::UnsupportedVersion { .. } => None,
+            TimescaleError::ExtensionNotInstalled => None,
+            TimescaleError::Database(e) => Some(e),
+            TimescaleError::Connection(e) => Some(e),
+        }
+    }
+}
+
+impl From<ValidationError> for TimescaleError {
+    fn from(e: ValidationError) -> Self {
+        TimescaleError::Validation(e)
+    }
+}
+
+impl From<diesel::result::Error> for TimescaleError {
+    fn from(e: diesel::result::Error) -> Self {
+        TimescaleError::Database(e)
+    }
+}
+
+impl From<diesel::ConnectionError> for TimescaleError {
+    fn from(e: diesel::ConnectionError) -> Self {
+        TimescaleError::Connection(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::TimeInterval;
+    use std::error::Error;
+
+    #[test]
+    fn invalid_interval_converts_to_a_validation_error() {
+        fn parse(interval: &str) -> TimescaleResult<TimeInterval> {
+            Ok(TimeInterval::from_string(interval)?)
+        }
+
+        let error = parse("every tuesday").unwrap_err();
+        assert!(matches!(
+            error,
+            TimescaleError::Validation(ValidationError::InvalidInterval(_))
+        ));
+        assert!(error.source().is_some());
+        assert!(error.to_string().starts_with("Validation error: "));
+    }
+
+    #[test]
+    fn database_errors_are_kept_apart_from_validation_errors() {
+        let error = TimescaleError::from(diesel::result::Error::NotFound);
+        assert!(matches!(
+            error,
+            TimescaleError::Database(diesel::result::Error::NotFound)
+        ));
+        assert_eq!(error.to_string(), "Database error: Record not found");
+    }
+
+    #[test]
+    fn unsupported_version_names_the_feature_and_versions() {
+        let error = TimescaleError::UnsupportedVersion {
+            feature: "Hypercore".to_string(),
+            required: TimescaleVersion::new(2, 18, 0),
+            removed: None,
+            installed: TimescaleVersion::new(2, 14, 2),
+        };
+        assert_eq!(
+            error.to_string(),
+            "Hypercore requires TimescaleDB 2.18.0 or later (installed: 2.14.2)"
+        );
+    }
+
+    #[test]
+    fn unsupported_version_names_the_release_that_removed_the_feature() {
+        let error = TimescaleError::UnsupportedVersion {
+            feature: "Distributed hypertables".to_string(),
+            required: TimescaleVersion::new(2, 0, 0),
+            removed: Some(TimescaleVersion::new(2, 14, 0)),
+            installed: TimescaleVersion::new(2, 15, 1),
+        };
+        assert_eq!(
+            error.to_string(),
+            "Distributed hypertables requires TimescaleDB 2.0.0 or later and was removed in 2.14.0 (installed: 2.15.1)"
+        );
+    }
+}
@@ -4,6 +4,9 @@
 use diesel::expression::functions::define_sql_function;
 use diesel::sql_types::*;
 
+#[cfg(feature = "toolkit")]
+pub mod toolkit;
+
 // Time bucket functions for aggregating time-series data
 define_sql_function! {
     /// Groups timestamps into buckets of a specified interval.
@@ -21,14 +24,77 @@ define_sql_function! {
 
 define_sql_function! {
     /// Groups timestamps into buckets with a specified origin.
+    #[sql_name = "time_bucket"]
     fn time_bucket_with_origin(interval: Interval, timestamp: Timestamptz, origin: Timestamptz) -> Timestamptz;
 }
 
+define_sql_function! {
+    /// Groups timestamps into buckets shifted by an offset interval.
+    ///
+    /// The offset moves every bucket boundary, e.g. hourly buckets starting at
+    /// half past the hour with a `30 minutes` offset.
+    #[sql_name = "time_bucket"]
+    fn time_bucket_with_offset(interval: Interval, timestamp: Timestamptz, offset: Interval) -> Timestamptz;
+}
+
+define_sql_function! {
+    /// Groups timestamps into buckets aligned to local time in a time zone.
+    ///
+    /// Daily and larger buckets start at local midnight, and follow daylight
+    /// saving time changes, so a day bucket may be 23 or 25 hours long.
+    /// Requires TimescaleDB 2.8 or later.
+    #[sql_name = "time_bucket"]
+    fn time_bucket_with_timezone(interval: Interval, timestamp: Timestamptz, timezone: Text) -> Timestamptz;
+}
+
 define_sql_function! {
     /// Groups integer values into buckets.
     fn time_bucket_int(bucket_width: Integer, timestamp: Bigint) -> Bigint;
 }
 
+// Gapfilling functions
+define_sql_function! {
+    /// Groups timestamps into buckets like `time_bucket`, and adds a row for
+    /// every bucket between `start` and `finish` that has no data.
+    ///
+    /// The aggregates of the added rows are `NULL` unless they are wrapped in
+    /// [`locf`](fn@locf) or [`interpolate`](fn@interpolate). The query must
+    /// be grouped by the gapfill bucket; see
+    /// [`utilities::time_bucket_gapfill_expr`] for an expression that can be
+    /// used in Diesel's `group_by`.
+    ///
+    /// # Example SQL
+    /// ```sql
+    /// SELECT time_bucket_gapfill('1 hour', timestamp_col, now() - INTERVAL '1 day', now()),
+    ///        locf(avg(value))
+    /// FROM metrics
+    /// WHERE timestamp_col > now() - INTERVAL '1 day'
+    /// GROUP BY 1;
+    /// ```
+    fn time_bucket_gapfill(
+        interval: Interval,
+        timestamp: Timestamptz,
+        start: Timestamptz,
+        finish: Timestamptz
+    ) -> Timestamptz;
+}
+
+define_sql_function! {
+    /// Fills the value of a gapfilled bucket with the last value before it
+    /// (last observation carried forward).
+    ///
+    /// Buckets before the first observation stay `NULL`.
+    fn locf<ST: SqlType + SingleValue>(value: Nullable<ST>) -> Nullable<ST>;
+}
+
+define_sql_function! {
+    /// Fills the value of a gapfilled bucket by linear interpolation between
+    /// the values of the surrounding buckets.
+    ///
+    /// Buckets without data on both sides stay `NULL`.
+    fn interpolate<ST: SqlType + SingleValue>(value: Nullable<ST>) -> Nullable<ST>;
+}
+
 // Hypertable management functions
 define_sql_function! {
     /// Creates a hypertable from a regular PostgreSQL table.
@@ -75,23 +141,68 @@ define_sql_function! {
 }
 
 // Statistical and analytical functions
+define_sql_function! {
+    /// Returns the value of the row with the earliest time in a group.
+    ///
+    /// The value may be of any SQL type; the result is nullable since an
+    /// empty group has no first row.
+    ///
+    /// # Example SQL
+    /// ```sql
+    /// SELECT device_id, first(value, timestamp_col)
+    /// FROM metrics
+    /// GROUP BY device_id;
+    /// ```
+    #[aggregate]
+    fn first<ST: SqlType + SingleValue + IntoNullable<Nullable: SingleValue>>(value: ST, time: Timestamptz) -> ST::Nullable;
+}
+
+define_sql_function! {
+    /// Returns the value of the row with the latest time in a group.
+    ///
+    /// The value may be of any SQL type; the result is nullable since an
+    /// empty group has no last row.
+    #[aggregate]
+    fn last<ST: SqlType + SingleValue + IntoNullable<Nullable: SingleValue>>(value: ST, time: Timestamptz) -> ST::Nullable;
+}
+
 define_sql_function! {
     /// Calculates the first value in a time-ordered set for numeric values.
+    ///
+    /// Renders `first(value, time)`.
+    #[deprecated(since = "0.2.0", note = "use `first`, which accepts values of any type")]
+    #[aggregate]
+    #[sql_name = "first"]
     fn first_numeric(value: Double, time: Timestamptz) -> Nullable<Double>;
 }
 
 define_sql_function! {
     /// Calculates the last value in a time-ordered set for numeric values.
+    ///
+    /// Renders `last(value, time)`.
+    #[deprecated(since = "0.2.0", note = "use `last`, which accepts values of any type")]
+    #[aggregate]
+    #[sql_name = "last"]
     fn last_numeric(value: Double, time: Timestamptz) -> Nullable<Double>;
 }
 
 define_sql_function! {
     /// Calculates the first value in a time-ordered set for integer values.
+    ///
+    /// Renders `first(value, time)`.
+    #[deprecated(since = "0.2.0", note = "use `first`, which accepts values of any type")]
+    #[aggregate]
+    #[sql_name = "first"]
     fn first_integer(value: Integer, time: Timestamptz) -> Nullable<Integer>;
 }
 
 define_sql_function! {
     /// Calculates the last value in a time-ordered set for integer values.
+    ///
+    /// Renders `last(value, time)`.
+    #[deprecated(since = "0.2.0", note = "use `last`, which accepts values of any type")]
+    #[aggregate]
+    #[sql_name = "last"]
     fn last_integer(value: Integer, time: Timestamptz) -> Nullable<Integer>;
 }
 
@@ -105,7 +216,10 @@ define_sql_function! {
 /// interacting with the database more convenient.
 pub mod utilities {
     use super::*;
-    use crate::schema::TimeInterval;
+    use crate::dsl::{
+        IntegerBucketWidth, IntegerTimeBucket, TimeBucket, TimeBucketGapfill, TimeRange,
+    };
+    use crate::schema::{TimeInterval, ValidationError};
     use diesel::expression::SqlLiteral;
     use diesel::prelude::*;
 
@@ -174,4 +288,377 @@ pub mod utilities {
             timestamp_expr,
         )
     }
+
+    /// Creates a `time_bucket` SQL expression for an integer time column.
+    ///
+    /// The width declares the column's unit, so the same interval gives the
+    /// right width for nanosecond, microsecond, millisecond or second
+    /// columns. It is rendered as a literal, so the expression can be
+    /// selected and used in `group_by` of the same query.
+    ///
+    /// ```rust,ignore
+    /// // 604800000000000 for a column of epoch nanoseconds
+    /// let width = IntegerBucketWidth::nanoseconds(&7.days())?;
+    /// let week = time_bucket_integer_expr(width, events::ts);
+    /// ```
+    pub fn time_bucket_integer_expr<T>(
+        width: IntegerBucketWidth,
+        time_column: T,
+    ) -> IntegerTimeBucket<T>
+    where
+        T: Expression,
+    {
+        IntegerTimeBucket::new(width, time_column)
+    }
+
+    /// Creates a `time_bucket` SQL expression whose bucket boundaries are
+    /// shifted by `offset`.
+    ///
+    /// Both intervals are converted with `to_postgres_interval()` and rendered
+    /// as interval literals, so this is equivalent to
+    /// `time_bucket(INTERVAL '1 hours', ts, INTERVAL '30 minutes')`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// // Hourly buckets starting at :30
+    /// let bucket = time_bucket_with_offset_expr(
+    ///     TimeInterval::new(1, TimeUnit::Hours),
+    ///     metrics::timestamp,
+    ///     TimeInterval::new(30, TimeUnit::Minutes),
+    /// );
+    /// ```
+    pub fn time_bucket_with_offset_expr<T>(
+        interval: TimeInterval,
+        timestamp_expr: T,
+        offset: TimeInterval,
+    ) -> time_bucket_with_offset<SqlLiteral<Interval>, T, SqlLiteral<Interval>>
+    where
+        T: Expression<SqlType = Timestamptz>,
+    {
+        time_bucket_with_offset(
+            diesel::dsl::sql::<Interval>(&format!(
+                "INTERVAL '{}'",
+                interval.to_postgres_interval()
+            )),
+            timestamp_expr,
+            diesel::dsl::sql::<Interval>(&format!("INTERVAL '{}'", offset.to_postgres_interval())),
+        )
+    }
+
+    /// Creates a `time_bucket` SQL expression whose buckets are aligned to
+    /// local time in `timezone`, such as `Europe/Berlin`.
+    ///
+    /// This returns a [`TimeBucket`] expression, which renders the interval
+    /// and the time zone as literals and can be used in `group_by` and
+    /// selected alongside aggregates. The time zone must be an IANA name
+    /// (`UTC`, `America/Argentina/Buenos_Aires`, `Etc/GMT+3`), see
+    /// [`validate_timezone`](crate::schema::validate_timezone).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// // Days starting at midnight in Berlin, 23 hours long on the day DST starts
+    /// let day = time_bucket_tz_expr(1.days(), metrics::timestamp, "Europe/Berlin")?;
+    ///
+    /// let daily: Vec<(TimestampTz, Option<f64>)> = metrics::table
+    ///     .group_by(day.clone())
+    ///     .select((day, diesel::dsl::avg(metrics::value)))
+    ///     .load(&mut conn)?;
+    /// ```
+    pub fn time_bucket_tz_expr<T>(
+        interval: TimeInterval,
+        timestamp_expr: T,
+        timezone: &str,
+    ) -> Result<TimeBucket<T>, ValidationError>
+    where
+        T: Expression<SqlType = Timestamptz>,
+    {
+        TimeBucket::new(interval, timestamp_expr).with_timezone(timezone)
+    }
+
+    /// Convert the buckets of loaded rows to the time zone they were bucketed
+    /// in, for display.
+    ///
+    /// `timestamptz` results, such as the buckets of [`time_bucket_tz_expr`],
+    /// load as UTC instants, so a Berlin day bucket loads as `22:00` or
+    /// `23:00` UTC the day before. Converting it to `tz` shows it as local
+    /// midnight, on either side of a daylight saving time change. `tz` should
+    /// be the zone the query bucketed in, such as the `chrono_tz::Tz` parsed
+    /// from the same name; any `chrono` time zone works. The instants are not
+    /// changed, only their offset.
+    ///
+    /// ```rust,ignore
+    /// let day = time_bucket_tz_expr(1.days(), metrics::timestamp, "Europe/Berlin")?;
+    /// let daily: Vec<(TimestampTz, Option<f64>)> = metrics::table
+    ///     .group_by(day.clone())
+    ///     .select((day, diesel::dsl::avg(metrics::value)))
+    ///     .load(&mut conn)?;
+    ///
+    /// for (bucket, value) in localize_buckets(daily, &chrono_tz::Europe::Berlin) {
+    ///     println!("{}: {:?}", bucket.format("%Y-%m-%d %H:%M %Z"), value);
+    /// }
+    /// ```
+    pub fn localize_buckets<Tz, B, V, I>(rows: I, tz: &Tz) -> Vec<(chrono::DateTime<Tz>, V)>
+    where
+        Tz: chrono::TimeZone,
+        B: Into<chrono::DateTime<chrono::Utc>>,
+        I: IntoIterator<Item = (B, V)>,
+    {
+        rows.into_iter()
+            .map(|(bucket, value)| (bucket.into().with_timezone(tz), value))
+            .collect()
+    }
+
+    /// Creates a `time_bucket_gapfill` SQL expression that returns a row for
+    /// every bucket of `range`, including buckets without data.
+    ///
+    /// Unlike [`time_bucket_expr`], this returns a [`TimeBucketGapfill`]
+    /// expression, which can be used in `group_by` and selected alongside
+    /// [`locf`](fn@locf) and [`interpolate`](fn@interpolate) aggregates. The
+    /// range should match the query's time
+    /// filter: rows outside it are still bucketed, but gaps are only filled
+    /// inside it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let range = TimeRange::new(now - Duration::days(1), now)?;
+    /// let bucket = time_bucket_gapfill_expr(1.hours(), metrics::timestamp, range);
+    ///
+    /// let rows: Vec<(TimestampTz, Option<f64>)> = metrics::table
+    ///     .filter(metrics::timestamp.ge(range.start()))
+    ///     .filter(metrics::timestamp.lt(range.end()))
+    ///     .group_by(bucket.clone())
+    ///     .select((bucket, locf(diesel::dsl::avg(metrics::value))))
+    ///     .load(&mut conn)?;
+    /// ```
+    pub fn time_bucket_gapfill_expr<T>(
+        interval: TimeInterval,
+        timestamp_expr: T,
+        range: TimeRange,
+    ) -> TimeBucketGapfill<T>
+    where
+        T: Expression<SqlType = Timestamptz>,
+    {
+        TimeBucketGapfill::new(interval, timestamp_expr, range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::utilities::*;
+    use super::{first, last};
+    use super::{interpolate, locf};
+    use crate::dsl::IntegerBucketWidth;
+    use crate::dsl::TimeRange;
+    use crate::schema::IntervalExt;
+    use crate::schema::ValidationError;
+    use chrono::{TimeZone, Utc};
+    use diesel::debug_query;
+    use diesel::pg::Pg;
+    use diesel::prelude::*;
+
+    diesel::table! {
+        metrics (timestamp) {
+            timestamp -> Timestamptz,
+            value -> Double,
+        }
+    }
+
+    #[test]
+    fn time_bucket_with_offset_renders_the_offset() {
+        let bucket = time_bucket_with_offset_expr(1.hours(), metrics::timestamp, 30.minutes());
+        assert_eq!(
+            debug_query::<Pg, _>(&metrics::table.select(bucket)).to_string(),
+            r#"SELECT time_bucket(INTERVAL '1 hours', "metrics"."timestamp", INTERVAL '30 minutes') FROM "metrics" -- binds: []"#
+        );
+    }
+
+    fn range() -> TimeRange {
+        TimeRange::new(
+            Utc.with_ymd_and_hms(2026, 3, 14, 10, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2026, 3, 14, 14, 0, 0).unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn gapfill_bucket_renders_its_range_and_groups() {
+        let bucket = time_bucket_gapfill_expr(1.hours(), metrics::timestamp, range());
+        let query = metrics::table.group_by(bucket.clone()).select((
+            bucket,
+            locf(diesel::dsl::avg(metrics::value)),
+            interpolate(diesel::dsl::max(metrics::value)),
+        ));
+        let gapfill = r#"time_bucket_gapfill(INTERVAL '1 hours', "metrics"."timestamp", TIMESTAMPTZ '2026-03-14T10:00:00+00:00', TIMESTAMPTZ '2026-03-14T14:00:00+00:00')"#;
+        assert_eq!(
+            debug_query::<Pg, _>(&query).to_string(),
+            format!(
+                r#"SELECT {gapfill}, locf(avg("metrics"."value")), interpolate(max("metrics"."value")) FROM "metrics" GROUP BY {gapfill} -- binds: []"#
+            )
+        );
+    }
+
+    #[test]
+    fn time_bucket_tz_renders_the_time_zone_literal() {
+        let bucket = time_bucket_tz_expr(1.days(), metrics::timestamp, "Europe/Berlin").unwrap();
+        assert_eq!(
+            debug_query::<Pg, _>(&metrics::table.select(bucket)).to_string(),
+            r#"SELECT time_bucket(INTERVAL '1 days', "metrics"."timestamp", 'Europe/Berlin') FROM "metrics" -- binds: []"#
+        );
+    }
+
+    #[test]
+    fn time_bucket_tz_rejects_names_that_are_not_time_zones() {
+        for timezone in ["", "Europe/Berlin'); DROP TABLE metrics; --", "/Berlin"] {
+            assert!(
+                matches!(
+                    time_bucket_tz_expr(1.days(), metrics::timestamp, timezone),
+                    Err(ValidationError::InvalidTimezone(_))
+                ),
+                "{:?}",
+                timezone
+            );
+        }
+    }
+
+    diesel::table! {
+        readings (timestamp) {
+            timestamp -> Timestamptz,
+            device -> Integer,
+            value -> Double,
+            label -> Text,
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn typed_first_and_last_render_the_timescale_aggregates() {
+        use super::{first_integer, first_numeric, last_integer, last_numeric};
+
+        let numeric: first_numeric<readings::value, readings::timestamp> =
+            first_numeric(readings::value, readings::timestamp);
+        let query = readings::table.select((
+            numeric,
+            last_numeric(readings::value, readings::timestamp),
+            first_integer(readings::device, readings::timestamp),
+            last_integer(readings::device, readings::timestamp),
+        ));
+        assert_eq!(
+            debug_query::<Pg, _>(&query).to_string(),
+            r#"SELECT first("readings"."value", "readings"."timestamp"), last("readings"."value", "readings"."timestamp"), first("readings"."device", "readings"."timestamp"), last("readings"."device", "readings"."timestamp") FROM "readings" -- binds: []"#
+        );
+    }
+
+    #[test]
+    fn first_and_last_group_numeric_and_text_columns() {
+        let query = readings::table.group_by(readings::device).select((
+            readings::device,
+            first(readings::value, readings::timestamp),
+            last(readings::label, readings::timestamp),
+        ));
+        assert_eq!(
+            debug_query::<Pg, _>(&query).to_string(),
+            r#"SELECT "readings"."device", first("readings"."value", "readings"."timestamp"), last("readings"."label", "readings"."timestamp") FROM "readings" GROUP BY "readings"."device" -- binds: []"#
+        );
+    }
+
+    diesel::table! {
+        events (ts) {
+            ts -> BigInt,
+            value -> Double,
+        }
+    }
+
+    #[test]
+    fn integer_time_bucket_renders_the_width_in_the_column_unit() {
+        let nanos = time_bucket_integer_expr(
+            IntegerBucketWidth::nanoseconds(&1.seconds()).unwrap(),
+            events::ts,
+        );
+        let micros = time_bucket_integer_expr(
+            IntegerBucketWidth::microseconds(&1.seconds()).unwrap(),
+            events::ts,
+        );
+        let query = events::table
+            .group_by(nanos.clone())
+            .select((nanos, diesel::dsl::count(events::value)));
+        assert_eq!(
+            debug_query::<Pg, _>(&query).to_string(),
+            r#"SELECT time_bucket(1000000000, "events"."ts"), count("events"."value") FROM "events" GROUP BY time_bucket(1000000000, "events"."ts") -- binds: []"#
+        );
+        assert_eq!(
+            debug_query::<Pg, _>(&events::table.select(micros)).to_string(),
+            r#"SELECT time_bucket(1000000, "events"."ts") FROM "events" -- binds: []"#
+        );
+    }
+
+    /// Day buckets of Berlin around a DST change, as loaded in UTC.
+    fn berlin_days(days: &[(u32, u32, u32)]) -> Vec<(crate::types::TimestampTz, Option<f64>)> {
+        days.iter()
+            .enumerate()
+            .map(|(i, &(month, day, hour))| {
+                (
+                    crate::types::TimestampTz(
+                        Utc.with_ymd_and_hms(2026, month, day, hour, 0, 0).unwrap(),
+                    ),
+                    Some(i as f64),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn localized_buckets_start_at_berlin_midnight_across_spring_dst() {
+        use chrono::{Offset, Timelike};
+
+        // DST starts on 2026-03-29, so that day is 23 hours long
+        let rows = berlin_days(&[(3, 27, 23), (3, 28, 23), (3, 29, 22)]);
+        let local = localize_buckets(rows, &chrono_tz::Europe::Berlin);
+
+        let days: Vec<String> = local
+            .iter()
+            .map(|(bucket, _)| bucket.format("%Y-%m-%d %H:%M %Z").to_string())
+            .collect();
+        assert_eq!(
+            days,
+            vec![
+                "2026-03-28 00:00 CET",
+                "2026-03-29 00:00 CET",
+                "2026-03-30 00:00 CEST"
+            ]
+        );
+        assert!(local.iter().all(|(bucket, _)| bucket.hour() == 0));
+        assert_eq!(local[0].0.offset().fix().local_minus_utc(), 3600);
+        assert_eq!(local[2].0.offset().fix().local_minus_utc(), 7200);
+        assert_eq!((local[2].0 - local[1].0).num_hours(), 23);
+        assert_eq!(local[1].1, Some(1.0));
+    }
+
+    #[test]
+    fn localized_buckets_start_at_berlin_midnight_across_fall_dst() {
+        // DST ends on 2026-10-25, so that day is 25 hours long
+        let rows = berlin_days(&[(10, 23, 22), (10, 24, 22), (10, 25, 23)]);
+        let local = localize_buckets(rows, &chrono_tz::Europe::Berlin);
+
+        let days: Vec<String> = local
+            .iter()
+            .map(|(bucket, _)| bucket.format("%Y-%m-%d %H:%M %Z").to_string())
+            .collect();
+        assert_eq!(
+            days,
+            vec![
+                "2026-10-24 00:00 CEST",
+                "2026-10-25 00:00 CEST",
+                "2026-10-26 00:00 CET"
+            ]
+        );
+        assert_eq!((local[2].0 - local[1].0).num_hours(), 25);
+        // Only the offset changes, not the instant
+        assert_eq!(
+            local[2].0.with_timezone(&Utc),
+            Utc.with_ymd_and_hms(2026, 10, 25, 23, 0, 0).unwrap()
+        );
+    }
 }
@@ -0,0 +1,585 @@
+//! Functions from the TimescaleDB Toolkit extension.
+//!
+//! This module is available with the `toolkit` feature and requires the
+//! `timescaledb_toolkit` extension to be installed in the database.
+
+use crate::schema::{ContinuousAggregateConfig, SqlIdentifier, TimeInterval, ValidationError};
+use crate::statement::SqlStatement;
+use diesel::expression::functions::define_sql_function;
+use diesel::prelude::*;
+use diesel::query_builder::QueryId;
+use diesel::sql_types::*;
+
+/// The Toolkit `TimeWeightSummary` type, the state of a time-weighted aggregate.
+#[derive(Debug, Clone, Copy, Default, QueryId, SqlType)]
+#[diesel(postgres_type(name = "timeweightsummary"))]
+pub struct TimeWeightSummary;
+
+define_sql_function! {
+    /// Aggregates values into a time-weighted summary.
+    ///
+    /// `method` is the weighting method, `'linear'` or `'locf'` (last
+    /// observation carried forward).
+    #[aggregate]
+    fn time_weight(method: Text, ts: Timestamptz, value: Double) -> TimeWeightSummary;
+}
+
+define_sql_function! {
+    /// Calculates the time-weighted average of a summary.
+    fn average(summary: TimeWeightSummary) -> Nullable<Double>;
+}
+
+define_sql_function! {
+    /// Calculates the integral (area under the curve) of a time-weighted summary.
+    ///
+    /// `unit` is the time unit the integral is expressed in, one of
+    /// `'microsecond'`, `'millisecond'`, `'second'`, `'minute'`, `'hour'`,
+    /// `'day'` or `'week'`. For a power gauge in watts, an integral in
+    /// `'hour'` units is the energy in watt-hours.
+    fn integral(summary: TimeWeightSummary, unit: Text) -> Nullable<Double>;
+}
+
+define_sql_function! {
+    /// Calculates the integral of a time-weighted summary over a bucket,
+    /// interpolating the values at the bucket boundaries.
+    ///
+    /// `start` and `interval` are the bucket's start and width. `prev` and
+    /// `next` are the summaries of the neighbouring buckets, usually obtained
+    /// with `LAG` and `LEAD`, and are used to interpolate the value at the
+    /// bucket edges. `unit` is the time unit of the result, as in
+    /// [`integral`](fn@integral).
+    fn interpolated_integral(
+        summary: TimeWeightSummary,
+        start: Timestamptz,
+        interval: Interval,
+        prev: Nullable<TimeWeightSummary>,
+        next: Nullable<TimeWeightSummary>,
+        unit: Text
+    ) -> Nullable<Double>;
+}
+
+/// The Toolkit `UddSketch` type, a percentile summary with bounded relative error.
+#[derive(Debug, Clone, Copy, Default, QueryId, SqlType)]
+#[diesel(postgres_type(name = "uddsketch"))]
+pub struct PercentileSummary;
+
+define_sql_function! {
+    /// Aggregates values into a percentile summary with the default accuracy.
+    #[aggregate]
+    fn percentile_agg(value: Double) -> PercentileSummary;
+}
+
+define_sql_function! {
+    /// Estimates several quantiles from one percentile summary.
+    ///
+    /// The result holds one estimate per quantile, in the order of
+    /// `quantiles`; see [`QuantileValues`] to keep the two together.
+    fn approx_percentile_array(quantiles: Array<Double>, summary: PercentileSummary) -> Array<Double>;
+}
+
+define_sql_function! {
+    /// Estimates one quantile, between 0 and 1, from a percentile summary.
+    ///
+    /// `approx_percentile(0.95, percentile_agg(latency))` is the p95 latency.
+    fn approx_percentile(percentile: Double, summary: PercentileSummary) -> Nullable<Double>;
+}
+
+/// The Toolkit `TDigest` type, a percentile summary that is most accurate
+/// at the extreme quantiles.
+#[derive(Debug, Clone, Copy, Default, QueryId, SqlType)]
+#[diesel(postgres_type(name = "tdigest"))]
+pub struct TDigestSummary;
+
+define_sql_function! {
+    /// Aggregates values into a t-digest with the given number of buckets.
+    ///
+    /// More buckets give more accurate estimates at the cost of a larger
+    /// summary; 100 is a common choice.
+    #[aggregate]
+    fn tdigest(buckets: Integer, value: Double) -> TDigestSummary;
+}
+
+define_sql_function! {
+    /// Estimates one quantile, between 0 and 1, from a t-digest.
+    #[sql_name = "approx_percentile"]
+    fn approx_percentile_tdigest(percentile: Double, digest: TDigestSummary) -> Nullable<Double>;
+}
+
+define_sql_function! {
+    /// Estimates several quantiles from one t-digest.
+    #[sql_name = "approx_percentile_array"]
+    fn approx_percentile_array_tdigest(quantiles: Array<Double>, digest: TDigestSummary) -> Array<Double>;
+}
+
+/// The quantiles usually reported for latencies: p50, p90 and p99.
+pub const LATENCY_QUANTILES: [f64; 3] = [0.5, 0.9, 0.99];
+
+/// Quantile estimates aligned with the quantiles they were requested for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantileValues {
+    quantiles: Vec<f64>,
+    values: Vec<f64>,
+}
+
+impl QuantileValues {
+    /// Pair the result of
+    /// [`approx_percentile_array`](fn@approx_percentile_array) with its input
+    /// quantiles.
+    pub fn new(quantiles: &[f64], values: Vec<f64>) -> Result<Self, ValidationError> {
+        if quantiles.len() != values.len() {
+            return Err(ValidationError::InvalidParameter(format!(
+                "Expected {} quantile values, got {}",
+                quantiles.len(),
+                values.len()
+            )));
+        }
+
+        Ok(Self {
+            quantiles: quantiles.to_vec(),
+            values,
+        })
+    }
+
+    /// Get the estimate for the given quantile, if it was requested.
+    pub fn get(&self, quantile: f64) -> Option<f64> {
+        self.quantiles
+            .iter()
+            .position(|requested| *requested == quantile)
+            .map(|index| self.values[index])
+    }
+
+    /// Iterate over `(quantile, estimate)` pairs in request order.
+    pub fn iter(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.quantiles
+            .iter()
+            .copied()
+            .zip(self.values.iter().copied())
+    }
+
+    /// Get the estimates in request order.
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+}
+
+/// The Toolkit `Timevector_TSTZ_F64` type, a series of `(time, value)` points.
+#[derive(Debug, Clone, Copy, Default, QueryId, SqlType)]
+#[diesel(postgres_type(name = "timevector_tstz_f64"))]
+pub struct Timevector;
+
+define_sql_function! {
+    /// Downsamples a series with the Largest-Triangle-Three-Buckets algorithm.
+    ///
+    /// The result keeps `resolution` points chosen to preserve the visual
+    /// shape of the series, which makes it well suited for charts. Use
+    /// [`lttb_query`] to expand the points back into rows.
+    #[aggregate]
+    fn lttb(ts: Timestamptz, value: Double, resolution: Integer) -> Timevector;
+}
+
+/// A point of a downsampled series.
+///
+/// Result row for [`lttb_query`].
+#[derive(Debug, Clone, PartialEq, QueryableByName)]
+pub struct TimeValue {
+    #[diesel(sql_type = Timestamptz)]
+    pub time: chrono::DateTime<chrono::Utc>,
+    #[diesel(sql_type = Double)]
+    pub value: f64,
+}
+
+/// Build a query downsampling a whole column to `resolution` points with `lttb`.
+///
+/// The timevector is expanded with `unnest`, so the query loads into
+/// [`TimeValue`] rows in time order.
+pub fn lttb_query(
+    table_name: &str,
+    time_column: &str,
+    value_column: &str,
+    resolution: i32,
+) -> Result<SqlStatement, ValidationError> {
+    let table_identifier = SqlIdentifier::new(table_name)?;
+    let time_identifier = SqlIdentifier::column(time_column)?;
+    let value_identifier = SqlIdentifier::column(value_column)?;
+    if resolution < 3 {
+        return Err(ValidationError::InvalidParameter(
+            "LTTB resolution must be at least 3".to_string(),
+        ));
+    }
+
+    Ok(SqlStatement::new(format!(
+        "SELECT time, value FROM unnest((SELECT lttb({}, {}, {}) FROM {}))",
+        time_identifier.escaped(),
+        value_identifier.escaped(),
+        resolution,
+        table_identifier.escaped()
+    )))
+}
+
+/// A continuous aggregate storing an LTTB-downsampled series per bucket.
+///
+/// The generated aggregate buckets `time_column` into a `bucket` column and
+/// downsamples each bucket of `value_column` to at most `resolution` points
+/// in a `points` timevector column, so charts read a few points per bucket
+/// instead of the raw rows. Use [`points_query`](Self::points_query) to read
+/// the points back as rows. Requires the `timescaledb_toolkit` extension,
+/// and a TimescaleDB version whose continuous aggregates accept Toolkit
+/// aggregates:
+///
+/// ```rust,ignore
+/// let spec = LttbAggregateSpec {
+///     source: "sensor_readings".to_string(),
+///     time_column: "time".to_string(),
+///     value_column: "value".to_string(),
+///     bucket: 1.days(),
+///     resolution: 200,
+/// };
+/// spec.to_continuous_aggregate_config("sensor_readings_chart")?
+///     .with_refresh_interval("1 hour")
+///     .create(&mut conn)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct LttbAggregateSpec {
+    pub source: String,
+    pub time_column: String,
+    pub value_column: String,
+    pub bucket: TimeInterval,
+    pub resolution: i32,
+}
+
+impl LttbAggregateSpec {
+    /// Build the continuous aggregate configuration for this downsampling.
+    ///
+    /// The configuration checks the bucketed time column, and refresh
+    /// settings can be added with its builder methods.
+    pub fn to_continuous_aggregate_config(
+        &self,
+        view_name: &str,
+    ) -> Result<ContinuousAggregateConfig, ValidationError> {
+        let _view_identifier = SqlIdentifier::new(view_name)?;
+        let source_identifier = SqlIdentifier::new(&self.source)?;
+        let time_identifier = SqlIdentifier::column(&self.time_column)?;
+        let value_identifier = SqlIdentifier::column(&self.value_column)?;
+
+        self.bucket.require_positive("Bucket interval")?;
+        if self.resolution < 3 {
+            return Err(ValidationError::InvalidParameter(
+                "LTTB resolution must be at least 3".to_string(),
+            ));
+        }
+
+        let query = format!(
+            "SELECT time_bucket(INTERVAL '{}', {time}) AS bucket, lttb({time}, {}, {}) AS points 
+         FROM {} 
+         GROUP BY bucket",
+            self.bucket.to_postgres_interval(),
+            value_identifier.escaped(),
+            self.resolution,
+            source_identifier.escaped(),
+            time = time_identifier.escaped(),
+        );
+
+        Ok(ContinuousAggregateConfig::new(view_name, query).with_time_column(&self.time_column))
+    }
+
+    /// Build a query reading the downsampled points of an aggregate created
+    /// from this spec, as [`TimeValue`] rows in time order.
+    pub fn points_query(&self, view_name: &str) -> Result<SqlStatement, ValidationError> {
+        let view_identifier = SqlIdentifier::new(view_name)?;
+
+        Ok(SqlStatement::new(format!(
+            "SELECT p.time, p.value FROM {}, unnest(points) AS p ORDER BY p.time",
+            view_identifier.escaped()
+        )))
+    }
+}
+
+/// The Toolkit `CounterSummary` type, the state of a counter aggregate.
+#[derive(Debug, Clone, Copy, Default, QueryId, SqlType)]
+#[diesel(postgres_type(name = "countersummary"))]
+pub struct CounterSummary;
+
+define_sql_function! {
+    /// Aggregates samples of a monotonic counter into a counter summary.
+    ///
+    /// A sample lower than the one before it is treated as a counter reset,
+    /// so the accessors count the increase across the reset rather than a
+    /// drop.
+    #[aggregate]
+    fn counter_agg(ts: Timestamptz, value: Double) -> CounterSummary;
+}
+
+define_sql_function! {
+    /// Gets the per-second rate of increase of a counter, adjusted for resets.
+    fn rate(summary: CounterSummary) -> Nullable<Double>;
+}
+
+define_sql_function! {
+    /// Gets the total increase of a counter, adjusted for resets.
+    fn delta(summary: CounterSummary) -> Nullable<Double>;
+}
+
+define_sql_function! {
+    /// Gets the instantaneous per-second rate between the last two samples.
+    fn irate_right(summary: CounterSummary) -> Nullable<Double>;
+}
+
+define_sql_function! {
+    /// Gets the instantaneous per-second rate between the first two samples.
+    fn irate_left(summary: CounterSummary) -> Nullable<Double>;
+}
+
+/// The rate of a counter in one bucket.
+///
+/// Result row for [`counter_rate_query`].
+#[derive(Debug, Clone, PartialEq, QueryableByName)]
+pub struct BucketRate {
+    #[diesel(sql_type = Timestamptz)]
+    pub bucket: chrono::DateTime<chrono::Utc>,
+    /// `None` for buckets with a single sample.
+    #[diesel(sql_type = Nullable<Double>)]
+    pub rate: Option<f64>,
+}
+
+/// Build a query computing the per-second rate of a counter column per bucket.
+///
+/// This is `rate(counter_agg(time, value))` grouped by
+/// `time_bucket(bucket, time)`, and loads into [`BucketRate`] rows in time
+/// order. Each bucket is aggregated on its own, so an increase between the
+/// last sample of one bucket and the first of the next is not counted.
+pub fn counter_rate_query(
+    table_name: &str,
+    time_column: &str,
+    value_column: &str,
+    bucket: TimeInterval,
+) -> Result<SqlStatement, ValidationError> {
+    let table_identifier = SqlIdentifier::new(table_name)?;
+    let time_identifier = SqlIdentifier::column(time_column)?;
+    let value_identifier = SqlIdentifier::column(value_column)?;
+
+    Ok(SqlStatement::new(format!(
+        "SELECT time_bucket(INTERVAL '{}', {time}) AS bucket, rate(counter_agg({time}, {value})) AS rate 
+         FROM {} GROUP BY bucket ORDER BY bucket",
+        bucket.to_postgres_interval(),
+        table_identifier.escaped(),
+        time = time_identifier.escaped(),
+        value = value_identifier.escaped(),
+    )))
+}
+
+/// The Toolkit `Candlestick` type, the open, high, low and close prices of
+/// a bucket with their times and the traded volume.
+#[derive(Debug, Clone, Copy, Default, QueryId, SqlType)]
+#[diesel(postgres_type(name = "candlestick"))]
+pub struct Candlestick;
+
+define_sql_function! {
+    /// Aggregates trades into a candlestick.
+    ///
+    /// `volume` may be `NULL`, in which case the candlestick has no volume
+    /// and no volume-weighted average price.
+    #[aggregate]
+    fn candlestick_agg(ts: Timestamptz, price: Double, volume: Nullable<Double>) -> Candlestick;
+}
+
+define_sql_function! {
+    /// Gets the first price of a candlestick.
+    fn open(candlestick: Candlestick) -> Double;
+}
+
+define_sql_function! {
+    /// Gets the highest price of a candlestick.
+    fn high(candlestick: Candlestick) -> Double;
+}
+
+define_sql_function! {
+    /// Gets the lowest price of a candlestick.
+    fn low(candlestick: Candlestick) -> Double;
+}
+
+define_sql_function! {
+    /// Gets the last price of a candlestick.
+    fn close(candlestick: Candlestick) -> Double;
+}
+
+define_sql_function! {
+    /// Gets the total volume of a candlestick.
+    fn volume(candlestick: Candlestick) -> Nullable<Double>;
+}
+
+define_sql_function! {
+    /// Gets the volume-weighted average price of a candlestick.
+    fn vwap(candlestick: Candlestick) -> Nullable<Double>;
+}
+
+/// The prices of one bucket of a candlestick aggregate.
+///
+/// Result row for [`CandlestickAggregateSpec::candles_query`].
+#[derive(Debug, Clone, PartialEq, QueryableByName)]
+pub struct Candle {
+    #[diesel(sql_type = Timestamptz)]
+    pub bucket: chrono::DateTime<chrono::Utc>,
+    #[diesel(sql_type = Double)]
+    pub open: f64,
+    #[diesel(sql_type = Double)]
+    pub high: f64,
+    #[diesel(sql_type = Double)]
+    pub low: f64,
+    #[diesel(sql_type = Double)]
+    pub close: f64,
+    /// `None` if the aggregate has no volume column.
+    #[diesel(sql_type = Nullable<Double>)]
+    pub volume: Option<f64>,
+}
+
+/// A continuous aggregate storing a candlestick of trades per bucket.
+///
+/// The generated aggregate buckets `time_column` into a `bucket` column and
+/// aggregates `price_column`, and `volume_column` if set, with
+/// `candlestick_agg` into a `candlestick` column. Use
+/// [`candles_query`](Self::candles_query) to read the open, high, low and
+/// close prices back as rows. Requires the `timescaledb_toolkit` extension:
+///
+/// ```rust,ignore
+/// let spec = CandlestickAggregateSpec {
+///     source: "trades".to_string(),
+///     time_column: "time".to_string(),
+///     price_column: "price".to_string(),
+///     volume_column: Some("quantity".to_string()),
+///     bucket: 1.minutes(),
+/// };
+/// spec.to_continuous_aggregate_config("trades_1m")?
+///     .with_refresh_interval("1 minute")
+///     .create(&mut conn)?;
+/// let candles: Vec<Candle> = spec.candles_query("trades_1m")?.load(&mut conn)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct CandlestickAggregateSpec {
+    pub source: String,
+    pub time_column: String,
+    pub price_column: String,
+    pub volume_column: Option<String>,
+    pub bucket: TimeInterval,
+}
+
+impl CandlestickAggregateSpec {
+    /// Build the continuous aggregate configuration for these candlesticks.
+    ///
+    /// The configuration checks the bucketed time column, and refresh
+    /// settings can be added with its builder methods.
+    pub fn to_continuous_aggregate_config(
+        &self,
+        view_name: &str,
+    ) -> Result<ContinuousAggregateConfig, ValidationError> {
+        let _view_identifier = SqlIdentifier::new(view_name)?;
+        let source_identifier = SqlIdentifier::new(&self.source)?;
+        let time_identifier = SqlIdentifier::column(&self.time_column)?;
+        let price_identifier = SqlIdentifier::column(&self.price_column)?;
+        let volume = match &self.volume_column {
+            Some(column) => SqlIdentifier::column(column)?.escaped(),
+            None => "NULL::float8".to_string(),
+        };
+
+        self.bucket.require_positive("Bucket interval")?;
+
+        let query = format!(
+            "SELECT time_bucket(INTERVAL '{}', {time}) AS bucket, candlestick_agg({time}, {}, {}) AS candlestick 
+         FROM {} 
+         GROUP BY bucket",
+            self.bucket.to_postgres_interval(),
+            price_identifier.escaped(),
+            volume,
+            source_identifier.escaped(),
+            time = time_identifier.escaped(),
+        );
+
+        Ok(ContinuousAggregateConfig::new(view_name, query).with_time_column(&self.time_column))
+    }
+
+    /// Build a query reading the candles of an aggregate created from this
+    /// spec, as [`Candle`] rows in time order.
+    pub fn candles_query(&self, view_name: &str) -> Result<SqlStatement, ValidationError> {
+        let view_identifier = SqlIdentifier::new(view_name)?;
+
+        Ok(SqlStatement::new(format!(
+            "SELECT bucket, open(candlestick) AS open, high(candlestick) AS high, 
+                low(candlestick) AS low, close(candlestick) AS close, 
+                volume(candlestick) AS volume 
+         FROM {} ORDER BY bucket",
+            view_identifier.escaped()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::debug_query;
+    use diesel::pg::Pg;
+
+    table! {
+        requests (id) {
+            id -> Int4,
+            latency_ms -> Float8,
+        }
+    }
+
+    #[test]
+    fn p95_renders_over_a_percentile_summary() {
+        let query = requests::table.select(approx_percentile(
+            0.95,
+            percentile_agg(requests::latency_ms),
+        ));
+        assert_eq!(
+            debug_query::<Pg, _>(&query).to_string(),
+            "SELECT approx_percentile($1, percentile_agg(\"requests\".\"latency_ms\")) \
+             FROM \"requests\" -- binds: [0.95]"
+        );
+    }
+
+    #[test]
+    fn quantile_values_pair_estimates_with_their_quantiles() {
+        let quantiles = QuantileValues::new(&LATENCY_QUANTILES, vec![12.0, 48.5, 190.0]).unwrap();
+        assert_eq!(quantiles.get(0.9), Some(48.5));
+        assert_eq!(quantiles.get(0.95), None);
+        assert_eq!(
+            quantiles.iter().collect::<Vec<_>>(),
+            vec![(0.5, 12.0), (0.9, 48.5), (0.99, 190.0)]
+        );
+    }
+
+    #[test]
+    fn quantile_values_reject_a_length_mismatch() {
+        assert!(matches!(
+            QuantileValues::new(&LATENCY_QUANTILES, vec![12.0]),
+            Err(ValidationError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn counter_rate_query_buckets_the_counter_aggregate() {
+        let statement = counter_rate_query(
+            "http_requests",
+            "ts",
+            "total",
+            TimeInterval::new(5, crate::schema::TimeUnit::Minutes),
+        )
+        .unwrap();
+        let sql = statement.to_string();
+        assert!(sql.starts_with(
+            "SELECT time_bucket(INTERVAL '5 minutes', \"ts\") AS bucket, \
+             rate(counter_agg(\"ts\", \"total\")) AS rate"
+        ));
+        assert!(sql.ends_with("FROM \"http_requests\" GROUP BY bucket ORDER BY bucket"));
+
+        assert!(matches!(
+            counter_rate_query(
+                "http_requests",
+                "ts; DROP",
+                "total",
+                TimeInterval::new(5, crate::schema::TimeUnit::Minutes)
+            ),
+            Err(ValidationError::InvalidIdentifier(_))
+        ));
+    }
+}
@@ -28,13 +28,47 @@
 //! //     .load(&mut conn)?;
 //! ```
 
+/// Log a warning through the `log` crate, or do nothing without the `log`
+/// feature.
+macro_rules! log_warning {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "log")]
+        log::warn!($($arg)*);
+        #[cfg(not(feature = "log"))]
+        let _ = format_args!($($arg)*);
+    }};
+}
+pub(crate) use log_warning;
+
+pub mod bulk;
 pub mod connection;
 pub mod dsl;
+pub mod error;
 pub mod functions;
+pub mod migration;
+#[cfg(feature = "deadpool")]
+pub mod pool;
 pub mod prelude;
+#[cfg(feature = "r2d2")]
+pub mod r2d2;
 pub mod schema;
+#[cfg(feature = "async")]
+pub mod sink;
+pub mod statement;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod types;
+pub mod version;
 
 // Re-export commonly used items
 pub use connection::TimescaleDbConnection;
+pub use error::{TimescaleError, TimescaleResult};
+#[cfg(feature = "deadpool")]
+pub use pool::{TimescaleDeadpool, TimescaleManager};
+#[cfg(feature = "r2d2")]
+pub use r2d2::{TimescaleConnectionManager, TimescalePool, TimescalePooledConnection};
+#[cfg(feature = "async")]
+pub use sink::InsertSink;
+pub use statement::{SqlStatement, TimescaleFunctionCall};
 pub use types::*;
+pub use version::TimescaleVersion;
@@ -0,0 +1,242 @@
+//! Up and down SQL for Diesel migrations.
+//!
+//! A [`MigrationPair`] holds the statements of a DDL helper together with
+//! the statements that undo them, rendered with their values inlined, so
+//! they can be written to the `up.sql` and `down.sql` files of a migration.
+//! Nothing is executed.
+//!
+//! ```rust,ignore
+//! let pair = MigrationPair::create_hypertable::<metrics::table>()?
+//!     .and(MigrationPair::retention_policy::<metrics::table>(90.days()));
+//! std::fs::write("migrations/2026-01-01-000000_metrics/up.sql", pair.up_sql())?;
+//! std::fs::write("migrations/2026-01-01-000000_metrics/down.sql", pair.down_sql())?;
+//! ```
+//!
+//! Not every operation can be undone cleanly. TimescaleDB cannot turn a
+//! hypertable back into a plain table, and it only disables compression on
+//! a hypertable without compressed chunks; the constructors document what
+//! their down statements do in those cases.
+
+use crate::schema::{
+    continuous_aggregate_policy_sql, remove_continuous_aggregate_policy_sql, CompressionConfig,
+    ContinuousAggregateConfig, Hypertable, SqlIdentifier, TimeInterval, ValidationError,
+};
+use crate::statement::SqlStatement;
+use crate::version::TimescaleVersion;
+
+/// Statements that apply a change, and the statements that revert it.
+///
+/// The down statements are in the order they have to run, which is the
+/// reverse of the up statements they undo.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MigrationPair {
+    pub up: Vec<String>,
+    pub down: Vec<String>,
+}
+
+impl MigrationPair {
+    /// Render `up` and `down` statements with their values inlined.
+    pub fn new(up: &[SqlStatement], down: &[SqlStatement]) -> Self {
+        Self {
+            up: up.iter().map(ToString::to_string).collect(),
+            down: down.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    /// Append the statements of `other`, which runs after this pair's up
+    /// statements and whose down statements run before this pair's.
+    pub fn and(mut self, other: MigrationPair) -> Self {
+        self.up.extend(other.up);
+        self.down.splice(0..0, other.down);
+        self
+    }
+
+    /// Get the contents of an `up.sql` file, one statement per line.
+    pub fn up_sql(&self) -> String {
+        join_statements(&self.up)
+    }
+
+    /// Get the contents of a `down.sql` file, one statement per line.
+    pub fn down_sql(&self) -> String {
+        join_statements(&self.down)
+    }
+
+    /// Convert the table of `T` to a hypertable.
+    ///
+    /// TimescaleDB cannot convert a hypertable back to a plain table, so the
+    /// down statement drops the table, with its data. This fits a migration
+    /// that also creates the table; otherwise leave the down statement out.
+    pub fn create_hypertable<T: Hypertable>() -> Result<Self, ValidationError> {
+        let up = T::hypertable_config()?.create_sql()?;
+        let table_identifier = SqlIdentifier::new(T::TABLE_NAME)?;
+        let down = SqlStatement::new(format!("DROP TABLE {};", table_identifier.escaped()));
+        Ok(Self::new(&[up], &[down]))
+    }
+
+    /// Enable compression, and add the policy if one is configured.
+    ///
+    /// The down statements remove the policy and disable compression, which
+    /// fails while the hypertable has compressed chunks; decompress them
+    /// before running the down migration.
+    pub fn compression(config: &CompressionConfig) -> Result<Self, ValidationError> {
+        Ok(Self::new(&config.create_sql()?, &config.disable_sql()?))
+    }
+
+    /// Add a compression policy to the hypertable of `T`.
+    ///
+    /// Removing the policy leaves chunks that it compressed compressed.
+    pub fn compression_policy<T: Hypertable>(compress_after: TimeInterval) -> Self {
+        Self::new(
+            &[T::add_compression_policy_sql(compress_after)],
+            &[T::remove_compression_policy_sql()],
+        )
+    }
+
+    /// Add a retention policy to the hypertable of `T`.
+    ///
+    /// Chunks the policy dropped before the down migration are gone.
+    pub fn retention_policy<T: Hypertable>(drop_after: TimeInterval) -> Self {
+        Self::new(
+            &[T::add_retention_policy_sql(drop_after)],
+            &[T::remove_retention_policy_sql()],
+        )
+    }
+
+    /// Add a reorder policy to the hypertable of `T`.
+    pub fn reorder_policy<T: Hypertable>(index_name: &str) -> Result<Self, ValidationError> {
+        Ok(Self::new(
+            &[T::add_reorder_policy_sql(index_name)?],
+            &[T::remove_reorder_policy_sql()],
+        ))
+    }
+
+    /// Create a continuous aggregate, with its refresh policy and real-time
+    /// view if configured.
+    ///
+    /// The statements are rendered for `version`, see
+    /// [`ContinuousAggregateConfig::create_sql_for`] and
+    /// [`ContinuousAggregateConfig::realtime_view_sql`]. The down statements
+    /// drop the real-time view, then the aggregate and its materialized data.
+    pub fn continuous_aggregate(
+        config: &ContinuousAggregateConfig,
+        version: TimescaleVersion,
+    ) -> Result<Self, ValidationError> {
+        let mut up = config.create_sql_for(version)?;
+        up.extend(config.realtime_view_sql(version)?);
+        Ok(Self::new(&up, &config.drop_sql()?))
+    }
+
+    /// Add a refresh policy to a continuous aggregate.
+    pub fn continuous_aggregate_policy(
+        view_name: &str,
+        start_offset: Option<TimeInterval>,
+        end_offset: Option<TimeInterval>,
+        schedule_interval: TimeInterval,
+    ) -> Result<Self, ValidationError> {
+        Ok(Self::new(
+            &[continuous_aggregate_policy_sql(
+                view_name,
+                start_offset,
+                end_offset,
+                schedule_interval,
+            )?],
+            &[remove_continuous_aggregate_policy_sql(view_name)?],
+        ))
+    }
+}
+
+fn join_statements(statements: &[String]) -> String {
+    let mut sql = statements.join("\n");
+    if !sql.is_empty() {
+        sql.push('\n');
+    }
+    sql
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::IntervalExt;
+
+    diesel::table! {
+        readings (ts) {
+            ts -> Timestamptz,
+            value -> Double,
+        }
+    }
+
+    crate::hypertable!(readings, ts, chunk_interval = "1 day");
+
+    fn pair(up: &[&str], down: &[&str]) -> MigrationPair {
+        MigrationPair {
+            up: up.iter().map(|sql| sql.to_string()).collect(),
+            down: down.iter().map(|sql| sql.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn and_appends_up_and_prepends_down() {
+        let combined = pair(&["CREATE a;"], &["DROP a;"]).and(pair(&["CREATE b;"], &["DROP b;"]));
+        assert_eq!(combined.up, vec!["CREATE a;", "CREATE b;"]);
+        assert_eq!(combined.down, vec!["DROP b;", "DROP a;"]);
+    }
+
+    #[test]
+    fn sql_files_have_one_statement_per_line() {
+        let pair = pair(&["CREATE a;", "CREATE b;"], &["DROP a;"]);
+        assert_eq!(pair.up_sql(), "CREATE a;\nCREATE b;\n");
+        assert_eq!(pair.down_sql(), "DROP a;\n");
+        assert_eq!(MigrationPair::default().up_sql(), "");
+    }
+
+    #[test]
+    fn create_hypertable_is_undone_by_dropping_the_table() {
+        let pair = MigrationPair::create_hypertable::<readings::table>().unwrap();
+        assert_eq!(
+            pair.up,
+            vec![
+                "SELECT create_hypertable('\"readings\"', 'ts', \
+                 chunk_time_interval => INTERVAL '1 days');"
+            ]
+        );
+        assert_eq!(pair.down, vec!["DROP TABLE \"readings\";"]);
+    }
+
+    #[test]
+    fn policies_are_undone_in_reverse_order() {
+        let pair = MigrationPair::compression_policy::<readings::table>(7.days()).and(
+            MigrationPair::retention_policy::<readings::table>(90.days()),
+        );
+        assert_eq!(
+            pair.down,
+            vec![
+                "SELECT remove_retention_policy('\"readings\"', if_exists => true);",
+                "SELECT remove_compression_policy('\"readings\"', if_exists => true);",
+            ]
+        );
+    }
+
+    #[test]
+    fn continuous_aggregate_creates_the_real_time_view_it_drops() {
+        let config = ContinuousAggregateConfig::new(
+            "readings_hourly",
+            "SELECT time_bucket('1 hour', ts) AS bucket, avg(value) AS average \
+             FROM readings GROUP BY bucket",
+        )
+        .with_refresh_interval("1 hour")
+        .with_realtime_view("bucket");
+        let pair =
+            MigrationPair::continuous_aggregate(&config, TimescaleVersion::new(2, 13, 0)).unwrap();
+
+        assert_eq!(pair.up.len(), 3);
+        assert!(pair.up[0].starts_with("CREATE MATERIALIZED VIEW \"readings_hourly\""));
+        assert!(pair.up[1].starts_with("SELECT add_continuous_aggregate_policy("));
+        assert!(pair.up[2].starts_with(&format!(
+            "CREATE VIEW \"readings_hourly{}\"",
+            crate::schema::REALTIME_VIEW_SUFFIX
+        )));
+        assert_eq!(pair.down.len(), 2);
+        assert!(pair.down[0].starts_with("DROP VIEW IF EXISTS"));
+        assert_eq!(pair.down[1], "DROP MATERIALIZED VIEW \"readings_hourly\";");
+    }
+}
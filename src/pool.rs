@@ -0,0 +1,78 @@
+//! Async connection pooling with `deadpool`.
+//!
+//! This module is available with the `deadpool` feature. It wraps the
+//! `deadpool-diesel` PostgreSQL manager and additionally verifies, whenever a
+//! connection is recycled, that the TimescaleDB extension is still installed.
+
+use crate::version::installed_version;
+use deadpool::managed::{self, Metrics, RecycleError, RecycleResult};
+use diesel::pg::PgConnection;
+
+pub use deadpool_diesel::{Error, Runtime};
+
+/// A pooled connection, used through `interact`.
+pub type TimescaleConnection = deadpool_diesel::Connection<PgConnection>;
+
+/// A `deadpool` pool of TimescaleDB connections.
+pub type TimescaleDeadpool = managed::Pool<TimescaleManager>;
+
+/// A `deadpool` manager for TimescaleDB connections.
+///
+/// Connections are created and verified like those of
+/// `deadpool_diesel::postgres::Manager`. On recycle the manager also checks
+/// that the TimescaleDB extension is installed, so connections to a database
+/// without the extension are discarded instead of being handed out.
+#[derive(Debug)]
+pub struct TimescaleManager {
+    inner: deadpool_diesel::postgres::Manager,
+}
+
+impl TimescaleManager {
+    /// Create a new manager which establishes connections to the given database URL.
+    pub fn new(database_url: impl Into<String>, runtime: Runtime) -> Self {
+        Self {
+            inner: deadpool_diesel::postgres::Manager::new(database_url, runtime),
+        }
+    }
+
+    /// Create a new manager with a specific `deadpool-diesel` configuration.
+    pub fn from_config(
+        database_url: impl Into<String>,
+        runtime: Runtime,
+        config: deadpool_diesel::ManagerConfig<PgConnection>,
+    ) -> Self {
+        Self {
+            inner: deadpool_diesel::postgres::Manager::from_config(database_url, runtime, config),
+        }
+    }
+}
+
+impl managed::Manager for TimescaleManager {
+    type Type = TimescaleConnection;
+    type Error = Error;
+
+    async fn create(&self) -> Result<Self::Type, Self::Error> {
+        self.inner.create().await
+    }
+
+    async fn recycle(&self, obj: &mut Self::Type, metrics: &Metrics) -> RecycleResult<Self::Error> {
+        self.inner.recycle(obj, metrics).await?;
+
+        obj.interact(installed_version)
+            .await
+            .map_err(|e| RecycleError::message(format!("Panic: {:?}", e)))?
+            .map_err(|e| RecycleError::Backend(Error::Ping(e)))?;
+
+        Ok(())
+    }
+}
+
+/// Build a pool of TimescaleDB connections on the Tokio runtime.
+pub fn build_pool(
+    database_url: impl Into<String>,
+    max_size: usize,
+) -> Result<TimescaleDeadpool, managed::BuildError> {
+    TimescaleDeadpool::builder(TimescaleManager::new(database_url, Runtime::Tokio1))
+        .max_size(max_size)
+        .build()
+}
@@ -1,8 +1,12 @@
 //! Prelude module for convenient imports.
 
 pub use crate::connection::TimescaleDbConnection;
-pub use crate::dsl::{patterns::*, TimescaleQueryDsl};
+pub use crate::dsl::{patterns::*, TimeBucket, TimescaleQueryDsl};
 pub use crate::functions::*;
 pub use crate::hypertable;
-pub use crate::schema::{ContinuousAggregateConfig, Hypertable};
+pub use crate::schema::{
+    BucketGapfill, CompressionConfig, CompressionOrderBy, ContinuousAggregateConfig,
+    DynamicHypertable, GapfillFill, Hypertable, HypertableConfig, IntervalExt, MultiMetric,
+    NullsOrder, PivotQuery, RollupAggregate, RollupSpec, SortDirection,
+};
 pub use crate::types::{TimeDimension, TimestampTz};
@@ -0,0 +1,126 @@
+//! Connection pooling with `r2d2`.
+//!
+//! This module is available with the `r2d2` feature. It builds on
+//! `diesel::r2d2` with a connection manager that hands out
+//! [`TimescaleDbConnection`]s, and a connection customizer that verifies,
+//! whenever a new connection is established, that the TimescaleDB extension
+//! is installed.
+//!
+//! Pooled connections dereference to `TimescaleDbConnection`, which in turn
+//! dereferences to `PgConnection`, so every helper of this crate that takes a
+//! `&mut PgConnection` can be passed `&mut conn` directly. Diesel's query
+//! methods are generic over the connection and need the `PgConnection`
+//! itself, as `query.load(conn.connection_mut())` or `query.load(&mut **conn)`.
+
+use crate::connection::TimescaleDbConnection;
+use crate::version::installed_version;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::r2d2::{
+    ConnectionManager, CustomizeConnection, Error, ManageConnection, Pool, PoolError,
+    PooledConnection,
+};
+
+/// An `r2d2` connection manager for [`TimescaleDbConnection`]s.
+///
+/// Connections are established, validated and checked for breakage by
+/// Diesel's `ConnectionManager<PgConnection>`, and wrapped once established.
+#[derive(Debug)]
+pub struct TimescaleConnectionManager {
+    manager: ConnectionManager<PgConnection>,
+}
+
+impl TimescaleConnectionManager {
+    /// Create a manager connecting to `database_url`.
+    pub fn new(database_url: impl Into<String>) -> Self {
+        Self {
+            manager: ConnectionManager::new(database_url),
+        }
+    }
+}
+
+impl ManageConnection for TimescaleConnectionManager {
+    type Connection = TimescaleDbConnection;
+    type Error = Error;
+
+    fn connect(&self) -> Result<TimescaleDbConnection, Error> {
+        self.manager.connect().map(TimescaleDbConnection::new)
+    }
+
+    fn is_valid(&self, conn: &mut TimescaleDbConnection) -> Result<(), Error> {
+        self.manager.is_valid(conn.connection_mut())
+    }
+
+    fn has_broken(&self, conn: &mut TimescaleDbConnection) -> bool {
+        self.manager.has_broken(conn.connection_mut())
+    }
+}
+
+/// An `r2d2` pool of TimescaleDB connections.
+pub type TimescalePool = Pool<TimescaleConnectionManager>;
+
+/// A connection checked out of a [`TimescalePool`].
+pub type TimescalePooledConnection = PooledConnection<TimescaleConnectionManager>;
+
+/// A connection customizer that prepares new connections for TimescaleDB.
+///
+/// On every newly established connection it optionally runs
+/// `CREATE EXTENSION IF NOT EXISTS timescaledb`, and then checks that the
+/// extension is installed. A connection that fails either step is discarded
+/// by the pool instead of being handed out.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TimescaleCustomizer {
+    /// Create the extension if it is not installed yet.
+    pub create_extension: bool,
+}
+
+impl TimescaleCustomizer {
+    /// Create a customizer that only checks for the extension.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create the extension on connect if it is not installed yet.
+    ///
+    /// Creating an extension requires the appropriate privileges, so this is
+    /// meant for development and test databases.
+    pub fn with_create_extension(mut self, create_extension: bool) -> Self {
+        self.create_extension = create_extension;
+        self
+    }
+}
+
+impl CustomizeConnection<PgConnection, Error> for TimescaleCustomizer {
+    fn on_acquire(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        if self.create_extension {
+            diesel::sql_query("CREATE EXTENSION IF NOT EXISTS timescaledb")
+                .execute(conn)
+                .map_err(Error::QueryError)?;
+        }
+
+        installed_version(conn).map_err(Error::QueryError)?;
+        Ok(())
+    }
+}
+
+impl CustomizeConnection<TimescaleDbConnection, Error> for TimescaleCustomizer {
+    fn on_acquire(&self, conn: &mut TimescaleDbConnection) -> Result<(), Error> {
+        <Self as CustomizeConnection<PgConnection, Error>>::on_acquire(self, conn.connection_mut())
+    }
+}
+
+/// Build a pool of TimescaleDB connections.
+///
+/// The pool establishes its initial connections before returning, so this
+/// fails if the database cannot be reached or does not have the TimescaleDB
+/// extension.
+pub fn build_r2d2_pool(
+    database_url: impl Into<String>,
+    max_size: u32,
+    customizer: TimescaleCustomizer,
+) -> Result<TimescalePool, PoolError> {
+    Pool::builder()
+        .max_size(max_size)
+        .connection_customizer(Box::new(customizer))
+        .build(TimescaleConnectionManager::new(database_url))
+}
@@ -1,29 +1,87 @@
 //! Schema management for TimescaleDB hypertables and related structures.
 
+use crate::error::TimescaleError;
+use crate::statement::SqlStatement;
+use crate::version::{installed_version, TimescaleVersion};
+use diesel::deserialize::FromSqlRow;
+use diesel::expression::AsExpression;
 use diesel::prelude::*;
-use diesel::sql_types::{Nullable, Text, Timestamptz};
+use diesel::sql_types::{
+    Array, BigInt, Bool, Double, Float, Integer, Interval, Nullable, SmallInt, Text, Timestamptz,
+};
+use std::collections::HashMap;
 use std::fmt;
 
 /// A validated SQL identifier that prevents SQL injection attacks.
+///
+/// The identifier may be qualified with a schema, as in `analytics.metrics`.
+/// Each segment is validated on its own and quoted separately by
+/// [`escaped`](Self::escaped).
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SqlIdentifier(String);
 
 impl SqlIdentifier {
-    /// Create a new validated SQL identifier.
+    /// Create a new validated SQL identifier, optionally qualified with a
+    /// schema as `schema.name`.
     pub fn new(identifier: &str) -> Result<Self, ValidationError> {
-        validate_sql_identifier(identifier)?;
+        let segments: Vec<&str> = identifier.split('.').collect();
+        if segments.len() > 2 {
+            return Err(ValidationError::InvalidIdentifier(format!(
+                "Identifier '{}' has too many parts (expected 'name' or 'schema.name')",
+                identifier
+            )));
+        }
+        for segment in segments {
+            validate_sql_identifier(segment)?;
+        }
         Ok(SqlIdentifier(identifier.to_string()))
     }
 
-    /// Get the escaped identifier suitable for use in SQL queries.
+    /// Create a validated identifier that cannot be qualified with a schema,
+    /// for the name of a column or of an output column alias.
+    pub fn column(identifier: &str) -> Result<Self, ValidationError> {
+        if identifier.contains('.') {
+            return Err(ValidationError::InvalidIdentifier(format!(
+                "Column '{}' cannot be qualified with a schema",
+                identifier
+            )));
+        }
+        Self::new(identifier)
+    }
+
+    /// Create a validated identifier of `name` in `schema`.
+    pub fn qualified(schema: &str, name: &str) -> Result<Self, ValidationError> {
+        validate_sql_identifier(schema)?;
+        validate_sql_identifier(name)?;
+        Ok(SqlIdentifier(format!("{}.{}", schema, name)))
+    }
+
+    /// Get the escaped identifier suitable for use in SQL queries, with each
+    /// segment quoted: `"analytics"."metrics"`.
     pub fn escaped(&self) -> String {
-        format!("\"{}\"", self.0.replace('"', "\"\""))
+        self.0
+            .split('.')
+            .map(|segment| format!("\"{}\"", segment.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(".")
     }
 
     /// Get the raw identifier as a string slice.
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    /// Get the schema of a qualified identifier.
+    pub fn schema(&self) -> Option<&str> {
+        self.0.split_once('.').map(|(schema, _)| schema)
+    }
+
+    /// Get the identifier without its schema.
+    pub fn name(&self) -> &str {
+        self.0
+            .split_once('.')
+            .map_or(self.0.as_str(), |(_, name)| name)
+    }
 }
 
 impl fmt::Display for SqlIdentifier {
@@ -34,14 +92,28 @@ impl fmt::Display for SqlIdentifier {
 }
 
 /// Represents a time interval for TimescaleDB operations.
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// Intervals are non-negative unless explicitly negated with
+/// [`TimeInterval::negated`], which is useful for origin and offset
+/// calculations that go backwards in time.
+///
+/// An interval has one or more components, such as `1 days 12 hours`; most
+/// intervals have a single one, created with [`TimeInterval::new`]. A
+/// `TimeInterval` can be bound directly as an SQL `interval`, and loaded
+/// from one unless its components have different signs.
+#[derive(Debug, Clone, PartialEq, Eq, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Interval)]
 pub struct TimeInterval {
-    value: u64,
-    unit: TimeUnit,
+    components: Vec<(u64, TimeUnit)>,
+    negative: bool,
 }
 
-/// Units of time for intervals.
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// TimescaleDB's default `time_bucket` origin (2000-01-03 00:00:00 UTC) in
+/// microseconds since the Unix epoch.
+pub(crate) const DEFAULT_BUCKET_ORIGIN_MICROS: i64 = 946_857_600_000_000;
+
+/// Units of time for intervals, ordered from the smallest to the largest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum TimeUnit {
     Microseconds,
     Milliseconds,
@@ -54,70 +126,476 @@ pub enum TimeUnit {
     Years,
 }
 
+impl TimeUnit {
+    /// Units with a fixed length, from the largest to the smallest.
+    const FIXED_UNITS: [TimeUnit; 7] = [
+        TimeUnit::Weeks,
+        TimeUnit::Days,
+        TimeUnit::Hours,
+        TimeUnit::Minutes,
+        TimeUnit::Seconds,
+        TimeUnit::Milliseconds,
+        TimeUnit::Microseconds,
+    ];
+
+    /// Get the length of one unit in microseconds, if it has a fixed length.
+    fn microseconds_per_unit(&self) -> Option<i64> {
+        match self {
+            TimeUnit::Microseconds => Some(1),
+            TimeUnit::Milliseconds => Some(1_000),
+            TimeUnit::Seconds => Some(1_000_000),
+            TimeUnit::Minutes => Some(60_000_000),
+            TimeUnit::Hours => Some(3_600_000_000),
+            TimeUnit::Days => Some(86_400_000_000),
+            TimeUnit::Weeks => Some(604_800_000_000),
+            TimeUnit::Months | TimeUnit::Years => None,
+        }
+    }
+
+    /// Get the duration of `value` of this unit.
+    ///
+    /// Months and years have no fixed length and return `None`, as does a
+    /// duration too large for `chrono::Duration`. A day is always 24 hours
+    /// here, while PostgreSQL applies days in the session time zone.
+    pub fn to_chrono_duration(&self, value: u64) -> Option<chrono::Duration> {
+        let micros = i64::try_from(value)
+            .ok()?
+            .checked_mul(self.microseconds_per_unit()?)?;
+        Some(chrono::Duration::microseconds(micros))
+    }
+
+    /// Express a duration in the largest unit that represents it exactly.
+    ///
+    /// Returns `(value, unit)`, e.g. 90 minutes is `(90, Minutes)` and 14 days
+    /// is `(2, Weeks)`. Months and years are never chosen. Negative durations
+    /// and durations with a sub-microsecond remainder return `None`.
+    pub fn best_fit(duration: chrono::Duration) -> Option<(u64, TimeUnit)> {
+        let micros = duration.num_microseconds()?;
+        if micros < 0 || duration != chrono::Duration::microseconds(micros) {
+            return None;
+        }
+
+        let unit = Self::FIXED_UNITS
+            .into_iter()
+            .find(|unit| {
+                let per_unit = unit.microseconds_per_unit().unwrap_or(1);
+                micros != 0 && micros % per_unit == 0
+            })
+            .unwrap_or(TimeUnit::Microseconds);
+        let per_unit = unit.microseconds_per_unit().unwrap_or(1);
+        Some(((micros / per_unit) as u64, unit))
+    }
+}
+
 impl TimeInterval {
     /// Create a new time interval.
     pub fn new(value: u64, unit: TimeUnit) -> Self {
-        Self { value, unit }
+        Self {
+            components: vec![(value, unit)],
+            negative: false,
+        }
+    }
+
+    /// Add a component to the interval, e.g. `1.days().and(12, TimeUnit::Hours)`
+    /// for `1 days 12 hours`.
+    ///
+    /// The component takes the sign of the interval.
+    pub fn and(mut self, value: u64, unit: TimeUnit) -> Self {
+        self.components.push((value, unit));
+        self
+    }
+
+    /// Get the same interval pointing in the opposite direction.
+    ///
+    /// Negating a zero interval has no effect.
+    pub fn negated(&self) -> Self {
+        Self {
+            components: self.components.clone(),
+            negative: !self.negative && !self.is_zero(),
+        }
+    }
+
+    /// Check whether the interval points backwards in time.
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// Check whether every component of the interval is zero.
+    pub fn is_zero(&self) -> bool {
+        self.components.iter().all(|(value, _)| *value == 0)
+    }
+
+    /// Reject an interval that is zero or negative, naming it as `what` in
+    /// the error.
+    pub(crate) fn require_positive(&self, what: &str) -> Result<(), ValidationError> {
+        if self.is_negative() || self.is_zero() {
+            return Err(ValidationError::InvalidInterval(format!(
+                "{} must be positive, got '{}'",
+                what,
+                self.to_postgres_interval()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Check whether the interval has more than one component.
+    pub fn is_composite(&self) -> bool {
+        self.components.len() > 1
+    }
+
+    /// Get the components of the interval as `(magnitude, unit)` pairs, in
+    /// the order they were given.
+    pub fn components(&self) -> &[(u64, TimeUnit)] {
+        &self.components
+    }
+
+    /// Get the magnitude of the interval in its unit.
+    ///
+    /// For a composite interval this is the magnitude of the first component.
+    pub fn value(&self) -> u64 {
+        self.components[0].0
+    }
+
+    /// Get the unit of the interval.
+    ///
+    /// For a composite interval this is the unit of the first component.
+    pub fn unit(&self) -> TimeUnit {
+        self.components[0].1
+    }
+
+    /// Get the smallest unit of any component of the interval.
+    pub fn smallest_unit(&self) -> TimeUnit {
+        self.components
+            .iter()
+            .map(|(_, unit)| *unit)
+            .min()
+            .unwrap_or(TimeUnit::Microseconds)
+    }
+
+    /// Get the length of the interval in microseconds, if it has a fixed length.
+    ///
+    /// Months and years vary in length and return `None`.
+    fn fixed_microseconds(&self) -> Option<i64> {
+        let mut magnitude: i64 = 0;
+        for (value, unit) in &self.components {
+            let micros = i64::try_from(*value)
+                .ok()?
+                .checked_mul(unit.microseconds_per_unit()?)?;
+            magnitude = magnitude.checked_add(micros)?;
+        }
+        Some(if self.negative { -magnitude } else { magnitude })
+    }
+
+    /// Get the length of the interval in microseconds for comparisons, with
+    /// months of 30 days and years of 12 months as PostgreSQL compares them.
+    fn comparable_microseconds(&self) -> i128 {
+        const MONTH: i128 = 30 * 86_400_000_000;
+        let magnitude: i128 = self
+            .components
+            .iter()
+            .map(|(value, unit)| {
+                let per_unit = match unit {
+                    TimeUnit::Months => MONTH,
+                    TimeUnit::Years => 12 * MONTH,
+                    fixed => i128::from(fixed.microseconds_per_unit().unwrap_or(0)),
+                };
+                i128::from(*value) * per_unit
+            })
+            .sum();
+        if self.negative {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    /// Compute the bucket start timestamps `time_bucket` produces for `[start, end)`.
+    ///
+    /// Buckets are aligned to TimescaleDB's default origin, midnight UTC on
+    /// Monday 2000-01-03, so the result matches `time_bucket(interval, ts)`
+    /// without an origin argument: hourly and daily buckets start on the hour
+    /// and at midnight UTC, weekly buckets on Mondays. The first entry is the
+    /// bucket containing `start`.
+    ///
+    /// Only positive intervals of weeks or smaller are supported; month and
+    /// year buckets follow the calendar and return an error.
+    pub fn bucket_starts(
+        &self,
+        start: chrono::DateTime<chrono::Utc>,
+        end: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<chrono::DateTime<chrono::Utc>>, ValidationError> {
+        // Validate the interval even for an empty range
+        self.bucket_width_micros()?;
+        if start >= end {
+            return Ok(Vec::new());
+        }
+
+        let range = crate::dsl::TimeRange::new(start, end)?;
+        Ok(crate::dsl::TimeBucketIterator::new(self, &range)?.collect())
+    }
+
+    /// Get the width of a client-side computed bucket in microseconds.
+    ///
+    /// Only positive intervals of weeks or smaller have a fixed width.
+    pub(crate) fn bucket_width_micros(&self) -> Result<i64, ValidationError> {
+        self.fixed_microseconds()
+            .filter(|width| *width > 0)
+            .ok_or_else(|| {
+                ValidationError::InvalidInterval(format!(
+                    "Bucket boundaries can only be computed for positive intervals of weeks or smaller, got '{}'",
+                    self.to_postgres_interval()
+                ))
+            })
     }
 
     /// Convert to a PostgreSQL interval string.
+    ///
+    /// Components are separated by spaces, e.g. `1 days 12 hours`. PostgreSQL
+    /// applies a sign to each component, so every component of a negative
+    /// interval is negated: `-1 days -12 hours`.
     pub fn to_postgres_interval(&self) -> String {
-        let unit_str = match self.unit {
-            TimeUnit::Microseconds => "microseconds",
-            TimeUnit::Milliseconds => "milliseconds",
-            TimeUnit::Seconds => "seconds",
-            TimeUnit::Minutes => "minutes",
-            TimeUnit::Hours => "hours",
-            TimeUnit::Days => "days",
-            TimeUnit::Weeks => "weeks",
-            TimeUnit::Months => "months",
-            TimeUnit::Years => "years",
-        };
-        format!("{} {}", self.value, unit_str)
+        let sign = if self.negative { "-" } else { "" };
+        self.components
+            .iter()
+            .map(|(value, unit)| {
+                let unit_str = match unit {
+                    TimeUnit::Microseconds => "microseconds",
+                    TimeUnit::Milliseconds => "milliseconds",
+                    TimeUnit::Seconds => "seconds",
+                    TimeUnit::Minutes => "minutes",
+                    TimeUnit::Hours => "hours",
+                    TimeUnit::Days => "days",
+                    TimeUnit::Weeks => "weeks",
+                    TimeUnit::Months => "months",
+                    TimeUnit::Years => "years",
+                };
+                format!("{}{} {}", sign, value, unit_str)
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
     }
 
     /// Parse a time interval from a string.
+    ///
+    /// The string is one or more `number unit` pairs, such as `90 minutes` or
+    /// `1 day 6 hours`. A negative interval negates every number
+    /// (`-1 day -6 hours`); mixing signs is rejected.
+    ///
+    /// Fractional numbers are split into the whole part and a remainder in
+    /// smaller units, as PostgreSQL does: `1.5 days` is `1 days 12 hours` and
+    /// `0.5 hours` is `30 minutes`. Fractions of months, and remainders
+    /// smaller than a microsecond, are rejected.
+    ///
+    /// ```rust
+    /// use diesel_timescaledb::schema::TimeInterval;
+    ///
+    /// let interval = TimeInterval::from_string("1 day 12 hours").unwrap();
+    /// assert_eq!(interval.to_postgres_interval(), "1 days 12 hours");
+    /// assert_eq!(TimeInterval::from_string(&interval.to_postgres_interval()).unwrap(), interval);
+    ///
+    /// let fractional = TimeInterval::from_string("1.5 seconds").unwrap();
+    /// assert_eq!(fractional.to_postgres_interval(), "1 seconds 500 milliseconds");
+    /// ```
     pub fn from_string(interval: &str) -> Result<Self, ValidationError> {
         validate_interval_string(interval)?;
 
         let parts: Vec<&str> = interval.split_whitespace().collect();
-        if parts.len() != 2 {
+        if parts.is_empty() || !parts.len().is_multiple_of(2) {
             return Err(ValidationError::InvalidInterval(
-                "Interval must have format 'number unit'".to_string(),
+                "Interval must have format 'number unit [number unit ...]'".to_string(),
             ));
         }
 
-        let value: u64 = parts[0]
-            .parse()
-            .map_err(|_| ValidationError::InvalidInterval("Invalid numeric value".to_string()))?;
-
-        let unit = match parts[1].to_lowercase().as_str() {
-            "microsecond" | "microseconds" | "us" => TimeUnit::Microseconds,
-            "millisecond" | "milliseconds" | "ms" => TimeUnit::Milliseconds,
-            "second" | "seconds" | "s" => TimeUnit::Seconds,
-            "minute" | "minutes" | "m" => TimeUnit::Minutes,
-            "hour" | "hours" | "h" => TimeUnit::Hours,
-            "day" | "days" | "d" => TimeUnit::Days,
-            "week" | "weeks" | "w" => TimeUnit::Weeks,
-            "month" | "months" => TimeUnit::Months,
-            "year" | "years" | "y" => TimeUnit::Years,
-            _ => {
-                return Err(ValidationError::InvalidInterval(format!(
-                    "Unknown time unit: {}",
-                    parts[1]
-                )))
+        let mut components = Vec::new();
+        let mut negative = None;
+        for pair in parts.chunks(2) {
+            let (component_negative, number) = match pair[0].strip_prefix('-') {
+                Some(number) => (true, number),
+                None => (false, pair[0]),
+            };
+            if *negative.get_or_insert(component_negative) != component_negative {
+                return Err(ValidationError::InvalidInterval(
+                    "Interval components must all have the same sign".to_string(),
+                ));
             }
+            let unit = parse_time_unit(pair[1])?;
+            components.extend(parse_component(number, unit)?);
+        }
+
+        let interval = TimeInterval {
+            components,
+            negative: false,
         };
+        Ok(if negative == Some(true) {
+            interval.negated()
+        } else {
+            interval
+        })
+    }
+
+    /// Parse a time interval from a string and check it against a policy.
+    pub fn from_string_with_policy(
+        interval: &str,
+        policy: &IntervalPolicy,
+    ) -> Result<Self, ValidationError> {
+        let interval = Self::from_string(interval)?;
+        policy.check(&interval)?;
+        Ok(interval)
+    }
+}
+
+/// Restrictions applied to parsed intervals.
+///
+/// The default policy accepts every interval. A minimum unit guards against
+/// accidents such as a `1 microsecond` chunk interval, which would create a
+/// chunk per row:
+///
+/// ```rust
+/// use diesel_timescaledb::schema::{IntervalPolicy, TimeInterval, TimeUnit};
+///
+/// let policy = IntervalPolicy::default().with_min_unit(TimeUnit::Seconds);
+/// assert!(TimeInterval::from_string_with_policy("1 hour", &policy).is_ok());
+/// assert!(TimeInterval::from_string_with_policy("1 microsecond", &policy).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IntervalPolicy {
+    /// Smallest unit accepted, compared by unit only: with a minimum of
+    /// seconds, `1000 milliseconds` is rejected.
+    pub min_unit: Option<TimeUnit>,
+}
+
+impl IntervalPolicy {
+    /// Reject intervals expressed in units smaller than `unit`.
+    pub fn with_min_unit(mut self, unit: TimeUnit) -> Self {
+        self.min_unit = Some(unit);
+        self
+    }
+
+    /// Check an interval against the policy.
+    pub fn check(&self, interval: &TimeInterval) -> Result<(), ValidationError> {
+        if let Some(min_unit) = self.min_unit {
+            if interval.smallest_unit() < min_unit {
+                return Err(ValidationError::InvalidInterval(format!(
+                    "Interval '{}' is smaller than the minimum unit {:?}",
+                    interval.to_postgres_interval(),
+                    min_unit
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<(u64, TimeUnit)> for TimeInterval {
+    fn from((value, unit): (u64, TimeUnit)) -> Self {
+        TimeInterval::new(value, unit)
+    }
+}
+
+/// Intervals are ordered by their length, so `90 minutes` is greater than
+/// `1 hour`. Months count as 30 days and years as 12 months, as in PostgreSQL.
+///
+/// Intervals of the same length written differently, like `60 minutes` and
+/// `1 hour`, are not equal, and are ordered by their components so that the
+/// order agrees with `==`.
+impl Ord for TimeInterval {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.comparable_microseconds()
+            .cmp(&other.comparable_microseconds())
+            .then_with(|| self.negative.cmp(&other.negative))
+            .then_with(|| self.components.cmp(&other.components))
+    }
+}
+
+impl PartialOrd for TimeInterval {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Serialized as the PostgreSQL interval string, such as `"1 days 12 hours"`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for TimeInterval {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_postgres_interval())
+    }
+}
+
+/// Deserialized from an interval string with [`TimeInterval::from_string`],
+/// so invalid intervals fail when the configuration is loaded.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for TimeInterval {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let interval = String::deserialize(deserializer)?;
+        TimeInterval::from_string(&interval).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Extension methods for building a [`TimeInterval`] from an integer.
+///
+/// ```rust
+/// use diesel_timescaledb::prelude::*;
+///
+/// assert_eq!(7.days().to_postgres_interval(), "7 days");
+/// ```
+pub trait IntervalExt {
+    fn microseconds(self) -> TimeInterval;
+    fn milliseconds(self) -> TimeInterval;
+    fn seconds(self) -> TimeInterval;
+    fn minutes(self) -> TimeInterval;
+    fn hours(self) -> TimeInterval;
+    fn days(self) -> TimeInterval;
+    fn weeks(self) -> TimeInterval;
+    fn months(self) -> TimeInterval;
+    fn years(self) -> TimeInterval;
+}
+
+impl IntervalExt for u64 {
+    fn microseconds(self) -> TimeInterval {
+        TimeInterval::new(self, TimeUnit::Microseconds)
+    }
+
+    fn milliseconds(self) -> TimeInterval {
+        TimeInterval::new(self, TimeUnit::Milliseconds)
+    }
+
+    fn seconds(self) -> TimeInterval {
+        TimeInterval::new(self, TimeUnit::Seconds)
+    }
+
+    fn minutes(self) -> TimeInterval {
+        TimeInterval::new(self, TimeUnit::Minutes)
+    }
+
+    fn hours(self) -> TimeInterval {
+        TimeInterval::new(self, TimeUnit::Hours)
+    }
+
+    fn days(self) -> TimeInterval {
+        TimeInterval::new(self, TimeUnit::Days)
+    }
+
+    fn weeks(self) -> TimeInterval {
+        TimeInterval::new(self, TimeUnit::Weeks)
+    }
+
+    fn months(self) -> TimeInterval {
+        TimeInterval::new(self, TimeUnit::Months)
+    }
 
-        Ok(TimeInterval::new(value, unit))
+    fn years(self) -> TimeInterval {
+        TimeInterval::new(self, TimeUnit::Years)
     }
 }
 
 /// Validation error types for SQL identifiers and intervals.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum ValidationError {
     InvalidIdentifier(String),
     InvalidInterval(String),
+    InvalidParameter(String),
+    InvalidTimezone(String),
 }
 
 impl fmt::Display for ValidationError {
@@ -125,6 +603,8 @@ impl fmt::Display for ValidationError {
         match self {
             ValidationError::InvalidIdentifier(msg) => write!(f, "Invalid SQL identifier: {}", msg),
             ValidationError::InvalidInterval(msg) => write!(f, "Invalid time interval: {}", msg),
+            ValidationError::InvalidParameter(msg) => write!(f, "Invalid parameter: {}", msg),
+            ValidationError::InvalidTimezone(msg) => write!(f, "Invalid time zone: {}", msg),
         }
     }
 }
@@ -202,6 +682,165 @@ fn validate_sql_identifier(identifier: &str) -> Result<(), ValidationError> {
     Ok(())
 }
 
+/// Check that a time zone is in the IANA time zone database, such as `UTC`,
+/// `America/Argentina/Buenos_Aires` or `Etc/GMT+3`.
+///
+/// This is the check applied by every API that renders a time zone into
+/// SQL, so a misspelled name like `Americ/New_York` is rejected before it
+/// reaches a query. Names are compared case-insensitively, like PostgreSQL
+/// does. The database is the one bundled with `chrono-tz`; use
+/// [`check_timezone`] to also check the server's time zone list.
+pub fn validate_timezone(timezone: &str) -> Result<(), ValidationError> {
+    let known = timezone.parse::<chrono_tz::Tz>().is_ok()
+        || chrono_tz::TZ_VARIANTS
+            .iter()
+            .any(|tz| tz.name().eq_ignore_ascii_case(timezone));
+    if known {
+        Ok(())
+    } else {
+        Err(ValidationError::InvalidTimezone(format!(
+            "'{}' is not a known time zone",
+            timezone
+        )))
+    }
+}
+
+#[derive(QueryableByName)]
+struct TimezoneExists {
+    #[diesel(sql_type = Bool)]
+    known: bool,
+}
+
+/// Check that PostgreSQL knows a time zone, from the IANA database it
+/// lists in `pg_timezone_names`.
+///
+/// The zone is first checked with [`validate_timezone`]. This also catches
+/// a zone the server's time zone database is missing, which would otherwise
+/// fail only when a query using it runs; an unknown zone is an
+/// `InvalidTimezone` validation error.
+pub fn check_timezone(conn: &mut PgConnection, timezone: &str) -> Result<(), TimescaleError> {
+    validate_timezone(timezone)?;
+
+    let row = diesel::sql_query(
+        "SELECT EXISTS (SELECT 1 FROM pg_timezone_names WHERE lower(name) = lower($1)) AS known",
+    )
+    .bind::<Text, _>(timezone)
+    .get_result::<TimezoneExists>(conn)?;
+
+    if !row.known {
+        return Err(ValidationError::InvalidTimezone(format!(
+            "'{}' is not a known time zone",
+            timezone
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// Parse the name or abbreviation of a time unit.
+fn parse_time_unit(unit: &str) -> Result<TimeUnit, ValidationError> {
+    Ok(match unit.to_lowercase().as_str() {
+        "microsecond" | "microseconds" | "us" => TimeUnit::Microseconds,
+        "millisecond" | "milliseconds" | "ms" => TimeUnit::Milliseconds,
+        "second" | "seconds" | "s" => TimeUnit::Seconds,
+        "minute" | "minutes" | "m" => TimeUnit::Minutes,
+        "hour" | "hours" | "h" => TimeUnit::Hours,
+        "day" | "days" | "d" => TimeUnit::Days,
+        "week" | "weeks" | "w" => TimeUnit::Weeks,
+        "month" | "months" => TimeUnit::Months,
+        "year" | "years" | "y" => TimeUnit::Years,
+        _ => {
+            return Err(ValidationError::InvalidInterval(format!(
+                "Unknown time unit: {}",
+                unit
+            )))
+        }
+    })
+}
+
+/// Parse the unsigned number of one interval component, splitting a
+/// fractional number into its whole part and the remainder in smaller units.
+fn parse_component(number: &str, unit: TimeUnit) -> Result<Vec<(u64, TimeUnit)>, ValidationError> {
+    let invalid = || ValidationError::InvalidInterval("Invalid numeric value".to_string());
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if (whole.is_empty() && fraction.is_empty())
+        || !whole.chars().all(|c| c.is_ascii_digit())
+        || !fraction.chars().all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+
+    let whole: u64 = if whole.is_empty() {
+        0
+    } else {
+        whole.parse().map_err(|_| invalid())?
+    };
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        return Ok(vec![(whole, unit)]);
+    }
+
+    // A fraction of a year is a whole number of months, like in PostgreSQL
+    let (per_unit, remainder_unit) = match unit {
+        TimeUnit::Years => (12, TimeUnit::Months),
+        TimeUnit::Months => {
+            return Err(ValidationError::InvalidInterval(format!(
+                "Fractional months are not supported: {}",
+                number
+            )))
+        }
+        _ => (
+            unit.microseconds_per_unit().unwrap_or(1),
+            TimeUnit::Microseconds,
+        ),
+    };
+    let digits = u32::try_from(fraction.len()).map_err(|_| invalid())?;
+    let scale = 10i128.checked_pow(digits).ok_or_else(invalid)?;
+    let scaled = fraction
+        .parse::<i128>()
+        .map_err(|_| invalid())?
+        .checked_mul(i128::from(per_unit))
+        .ok_or_else(invalid)?;
+    if scaled % scale != 0 {
+        return Err(ValidationError::InvalidInterval(format!(
+            "Fractional value is not a whole number of {}: {}",
+            if remainder_unit == TimeUnit::Months {
+                "months"
+            } else {
+                "microseconds"
+            },
+            number
+        )));
+    }
+    let remainder = i64::try_from(scaled / scale).map_err(|_| invalid())?;
+
+    let mut components = Vec::new();
+    if whole != 0 {
+        components.push((whole, unit));
+    }
+    if remainder_unit == TimeUnit::Months {
+        components.push((remainder as u64, TimeUnit::Months));
+        return Ok(components);
+    }
+
+    // Whole days of the remainder stay days, which PostgreSQL applies in the
+    // session time zone, and the rest is expressed in the largest exact unit
+    let day = TimeUnit::Days.microseconds_per_unit().unwrap_or(1);
+    let (days, micros) = match unit {
+        TimeUnit::Weeks | TimeUnit::Days => (remainder / day, remainder % day),
+        _ => (0, remainder),
+    };
+    if days != 0 {
+        components.push((days as u64, TimeUnit::Days));
+    }
+    if micros != 0 {
+        let (value, unit) =
+            TimeUnit::best_fit(chrono::Duration::microseconds(micros)).ok_or_else(invalid)?;
+        components.push((value, unit));
+    }
+    Ok(components)
+}
+
 /// Validate an interval string to prevent SQL injection.
 fn validate_interval_string(interval: &str) -> Result<(), ValidationError> {
     if interval.is_empty() {
@@ -229,275 +868,6408 @@ fn validate_interval_string(interval: &str) -> Result<(), ValidationError> {
     Ok(())
 }
 
-/// Trait for tables that can be converted to TimescaleDB hypertables.
-pub trait Hypertable {
-    /// Name of the table to convert to a hypertable.
-    const TABLE_NAME: &'static str;
-
-    /// Name of the time column to use for partitioning.
-    const TIME_COLUMN: &'static str;
-
-    /// Create a hypertable from this table.
-    fn create_hypertable(conn: &mut PgConnection) -> QueryResult<()> {
-        // These are compile-time constants, so they're safe to use directly
-        let query = "SELECT create_hypertable($1, $2);";
+#[derive(QueryableByName)]
+struct RetentionConfig {
+    #[diesel(sql_type = Nullable<Interval>)]
+    drop_after: Option<TimeInterval>,
+}
 
-        diesel::sql_query(query)
-            .bind::<Text, _>(Self::TABLE_NAME)
-            .bind::<Text, _>(Self::TIME_COLUMN)
-            .execute(conn)?;
-        Ok(())
-    }
+// The statement builders below take the table as quoted `regclass` text, as
+// rendered by `SqlIdentifier::escaped`, so that qualified and mixed-case names
+// are looked up as written
+fn create_hypertable_statement(table: &str, time_column: &str) -> SqlStatement {
+    SqlStatement::new("SELECT create_hypertable(")
+        .bind(table)
+        .sql(", ")
+        .bind(time_column)
+        .sql(");")
+}
 
-    /// Create a hypertable with a specific chunk time interval.
-    fn create_hypertable_with_interval(
-        conn: &mut PgConnection,
-        chunk_time_interval: TimeInterval,
-    ) -> QueryResult<()> {
-        let query = format!(
-            "SELECT create_hypertable($1, $2, chunk_time_interval => INTERVAL '{}');",
+fn create_hypertable_with_interval_statement(
+    table: &str,
+    time_column: &str,
+    chunk_time_interval: TimeInterval,
+) -> SqlStatement {
+    SqlStatement::new("SELECT create_hypertable(")
+        .bind(table)
+        .sql(", ")
+        .bind(time_column)
+        .sql(format!(
+            ", chunk_time_interval => INTERVAL '{}');",
             chunk_time_interval.to_postgres_interval()
-        );
+        ))
+}
 
-        diesel::sql_query(query)
-            .bind::<Text, _>(Self::TABLE_NAME)
-            .bind::<Text, _>(Self::TIME_COLUMN)
-            .execute(conn)?;
-        Ok(())
+fn create_hypertable_with_integer_interval_statement(
+    table: &str,
+    time_column: &str,
+    chunk_time_interval: i64,
+) -> Result<SqlStatement, ValidationError> {
+    if chunk_time_interval <= 0 {
+        return Err(ValidationError::InvalidInterval(
+            "Integer chunk interval must be positive".to_string(),
+        ));
     }
 
-    /// Create a hypertable with a specific chunk time interval from a string.
-    fn create_hypertable_with_interval_str(
-        conn: &mut PgConnection,
-        chunk_time_interval: &str,
-    ) -> QueryResult<()> {
-        let interval = TimeInterval::from_string(chunk_time_interval).map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::Unknown,
-                Box::new(e.to_string()),
-            )
-        })?;
-
-        Self::create_hypertable_with_interval(conn, interval)
-    }
+    Ok(SqlStatement::new("SELECT create_hypertable(")
+        .bind(table)
+        .sql(", ")
+        .bind(time_column)
+        .sql(format!(
+            ", chunk_time_interval => {});",
+            chunk_time_interval
+        )))
+}
 
-    /// Add a compression policy to the hypertable.
-    fn add_compression_policy(
-        conn: &mut PgConnection,
-        compress_after: TimeInterval,
-    ) -> QueryResult<()> {
-        let query = format!(
-            "SELECT add_compression_policy($1, INTERVAL '{}');",
+fn compression_policy_statement(table: &str, compress_after: TimeInterval) -> SqlStatement {
+    SqlStatement::new("SELECT add_compression_policy(")
+        .bind(table)
+        .sql(format!(
+            ", INTERVAL '{}');",
             compress_after.to_postgres_interval()
-        );
+        ))
+}
 
-        diesel::sql_query(query)
-            .bind::<Text, _>(Self::TABLE_NAME)
-            .execute(conn)?;
-        Ok(())
-    }
+fn remove_compression_policy_statement(table: &str) -> SqlStatement {
+    SqlStatement::new("SELECT remove_compression_policy(")
+        .bind(table)
+        .sql(", if_exists => true);")
+}
 
-    /// Add a compression policy from a string interval.
-    fn add_compression_policy_str(
-        conn: &mut PgConnection,
-        compress_after: &str,
-    ) -> QueryResult<()> {
-        let interval = TimeInterval::from_string(compress_after).map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::Unknown,
-                Box::new(e.to_string()),
-            )
-        })?;
+fn retention_policy_statement(table: &str, drop_after: TimeInterval) -> SqlStatement {
+    SqlStatement::new("SELECT add_retention_policy(")
+        .bind(table)
+        .sql(format!(
+            ", INTERVAL '{}');",
+            drop_after.to_postgres_interval()
+        ))
+}
 
-        Self::add_compression_policy(conn, interval)
-    }
+fn remove_retention_policy_statement(table: &str) -> SqlStatement {
+    SqlStatement::new("SELECT remove_retention_policy(")
+        .bind(table)
+        .sql(", if_exists => true);")
+}
 
-    /// Add a retention policy to automatically drop old data.
-    fn add_retention_policy(conn: &mut PgConnection, drop_after: TimeInterval) -> QueryResult<()> {
-        let query = format!(
-            "SELECT add_retention_policy($1, INTERVAL '{}');",
-            drop_after.to_postgres_interval()
-        );
+fn reorder_policy_statement(
+    table: &str,
+    index_name: &str,
+) -> Result<SqlStatement, ValidationError> {
+    // The index is in the hypertable's schema and passed by its bare name
+    validate_sql_identifier(index_name)?;
 
-        diesel::sql_query(query)
-            .bind::<Text, _>(Self::TABLE_NAME)
-            .execute(conn)?;
-        Ok(())
-    }
+    Ok(SqlStatement::new("SELECT add_reorder_policy(")
+        .bind(table)
+        .sql(", ")
+        .bind(index_name)
+        .sql(");"))
+}
 
-    /// Add a retention policy from a string interval.
-    fn add_retention_policy_str(conn: &mut PgConnection, drop_after: &str) -> QueryResult<()> {
-        let interval = TimeInterval::from_string(drop_after).map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::Unknown,
-                Box::new(e.to_string()),
-            )
-        })?;
+fn remove_reorder_policy_statement(table: &str) -> SqlStatement {
+    SqlStatement::new("SELECT remove_reorder_policy(")
+        .bind(table)
+        .sql(", if_exists => true);")
+}
 
-        Self::add_retention_policy(conn, interval)
+fn truncate_statement(table: &SqlIdentifier, restart_identity: bool) -> SqlStatement {
+    let table = table.escaped();
+    if restart_identity {
+        SqlStatement::new(format!("TRUNCATE TABLE {} RESTART IDENTITY;", table))
+    } else {
+        SqlStatement::new(format!("TRUNCATE TABLE {};", table))
     }
 }
 
-/// Macro to implement the Hypertable trait for a table.
-#[macro_export]
-macro_rules! hypertable {
-    ($table_name:ident, $time_column:ident) => {
-        impl $crate::schema::Hypertable for $table_name::table {
-            const TABLE_NAME: &'static str = stringify!($table_name);
-            const TIME_COLUMN: &'static str = stringify!($time_column);
-        }
-    };
+fn load_retention_interval(
+    conn: &mut PgConnection,
+    table: &str,
+) -> QueryResult<Option<TimeInterval>> {
+    let policy = diesel::sql_query(
+        "SELECT (config->>'drop_after')::interval AS drop_after 
+         FROM timescaledb_information.jobs 
+         WHERE proc_name = 'policy_retention' 
+           AND format('%I.%I', hypertable_schema, hypertable_name)::regclass = to_regclass($1)",
+    )
+    .bind::<Text, _>(table)
+    .get_result::<RetentionConfig>(conn)
+    .optional()?;
+
+    Ok(policy.and_then(|policy| policy.drop_after))
 }
 
-/// Configuration for continuous aggregates.
-#[derive(Debug, Clone)]
-pub struct ContinuousAggregateConfig {
+/// A compression, retention or reorder policy of a hypertable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyInfo {
+    pub job_id: i32,
+    /// The policy's `compress_after` or `drop_after` interval. `None` for
+    /// reorder policies, and for policies on integer time columns, whose
+    /// thresholds are integers.
+    pub interval: Option<TimeInterval>,
+    pub schedule_interval: TimeInterval,
+    /// Whether the job is scheduled to run automatically.
+    pub scheduled: bool,
+}
+
+/// A refresh policy of a continuous aggregate on a hypertable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RefreshPolicyInfo {
+    /// The schema-qualified name of the continuous aggregate.
     pub view_name: String,
-    pub query: String,
-    pub refresh_lag: Option<String>,
-    pub refresh_interval: Option<String>,
+    pub job_id: i32,
+    /// `None` when the window is unbounded or, on integer time columns,
+    /// given as an integer.
+    pub start_offset: Option<TimeInterval>,
+    pub end_offset: Option<TimeInterval>,
+    pub schedule_interval: TimeInterval,
+    /// Whether the job is scheduled to run automatically.
+    pub scheduled: bool,
 }
 
-impl ContinuousAggregateConfig {
-    /// Create a new continuous aggregate configuration.
-    pub fn new(view_name: impl Into<String>, query: impl Into<String>) -> Self {
-        Self {
-            view_name: view_name.into(),
-            query: query.into(),
-            refresh_lag: None,
-            refresh_interval: None,
+/// The policies of a hypertable, as read by [`Hypertable::policies`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PolicySet {
+    pub compression: Option<PolicyInfo>,
+    pub retention: Option<PolicyInfo>,
+    pub reorder: Option<PolicyInfo>,
+    /// The refresh policies of the continuous aggregates on the hypertable,
+    /// by job id.
+    pub continuous_aggregate_refresh: Vec<RefreshPolicyInfo>,
+}
+
+#[derive(QueryableByName)]
+struct PolicyRow {
+    #[diesel(sql_type = Integer)]
+    job_id: i32,
+    #[diesel(sql_type = Text)]
+    proc_name: String,
+    #[diesel(sql_type = Interval)]
+    schedule_interval: TimeInterval,
+    #[diesel(sql_type = Bool)]
+    scheduled: bool,
+    #[diesel(sql_type = Nullable<Text>)]
+    view_name: Option<String>,
+    #[diesel(sql_type = Nullable<Interval>)]
+    threshold: Option<TimeInterval>,
+    #[diesel(sql_type = Nullable<Interval>)]
+    start_offset: Option<TimeInterval>,
+    #[diesel(sql_type = Nullable<Interval>)]
+    end_offset: Option<TimeInterval>,
+}
+
+fn load_policies(conn: &mut PgConnection, table: &str) -> QueryResult<PolicySet> {
+    // Thresholds of integer time columns are integers in the job config
+    let rows = diesel::sql_query(
+        "WITH target AS ( 
+             SELECT n.nspname AS schema_name, c.relname AS table_name 
+             FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace 
+             WHERE c.oid = to_regclass($1) 
+         ), 
+         config AS ( 
+             SELECT j.job_id, j.proc_name::text AS proc_name, j.schedule_interval, j.scheduled, 
+                    j.hypertable_schema, j.hypertable_name, 
+                    COALESCE(j.config->>'compress_after', j.config->>'drop_after') AS threshold, 
+                    j.config->>'start_offset' AS start_offset, 
+                    j.config->>'end_offset' AS end_offset 
+             FROM timescaledb_information.jobs j 
+         ) 
+         SELECT j.job_id, j.proc_name, j.schedule_interval, j.scheduled, 
+                CASE WHEN ca.view_name IS NOT NULL THEN format('%I.%I', ca.view_schema, ca.view_name) END AS view_name, 
+                CASE WHEN j.threshold ~ '^-?[0-9]+$' THEN NULL ELSE j.threshold::interval END AS threshold, 
+                CASE WHEN j.start_offset ~ '^-?[0-9]+$' THEN NULL ELSE j.start_offset::interval END AS start_offset, 
+                CASE WHEN j.end_offset ~ '^-?[0-9]+$' THEN NULL ELSE j.end_offset::interval END AS end_offset 
+         FROM config j 
+         CROSS JOIN target t 
+         LEFT JOIN timescaledb_information.continuous_aggregates ca 
+           ON j.hypertable_schema = ca.materialization_hypertable_schema 
+          AND j.hypertable_name = ca.materialization_hypertable_name 
+         WHERE (j.proc_name IN ('policy_compression', 'policy_retention', 'policy_reorder') 
+                AND j.hypertable_schema = t.schema_name AND j.hypertable_name = t.table_name) 
+            OR (j.proc_name = 'policy_refresh_continuous_aggregate' 
+                AND ca.hypertable_schema = t.schema_name AND ca.hypertable_name = t.table_name) 
+         ORDER BY j.job_id",
+    )
+    .bind::<Text, _>(table)
+    .load::<PolicyRow>(conn)?;
+
+    let mut policies = PolicySet::default();
+    for row in rows {
+        if row.proc_name == "policy_refresh_continuous_aggregate" {
+            policies
+                .continuous_aggregate_refresh
+                .push(RefreshPolicyInfo {
+                    view_name: row.view_name.unwrap_or_default(),
+                    job_id: row.job_id,
+                    start_offset: row.start_offset,
+                    end_offset: row.end_offset,
+                    schedule_interval: row.schedule_interval,
+                    scheduled: row.scheduled,
+                });
+            continue;
+        }
+
+        let policy = Some(PolicyInfo {
+            job_id: row.job_id,
+            interval: row.threshold,
+            schedule_interval: row.schedule_interval,
+            scheduled: row.scheduled,
+        });
+        match row.proc_name.as_str() {
+            "policy_compression" => policies.compression = policy,
+            "policy_retention" => policies.retention = policy,
+            _ => policies.reorder = policy,
         }
     }
+    Ok(policies)
+}
 
-    /// Set the refresh lag for the continuous aggregate.
-    pub fn with_refresh_lag(mut self, lag: impl Into<String>) -> Self {
-        self.refresh_lag = Some(lag.into());
-        self
+#[derive(QueryableByName)]
+struct TablespaceName {
+    #[diesel(sql_type = Text)]
+    tablespace: String,
+}
+
+fn load_tablespaces(conn: &mut PgConnection, table: &str) -> QueryResult<Vec<String>> {
+    let tablespaces = diesel::sql_query(
+        "SELECT tablespace::text AS tablespace FROM show_tablespaces($1::regclass) AS tablespace",
+    )
+    .bind::<Text, _>(table)
+    .load::<TablespaceName>(conn)?;
+
+    Ok(tablespaces.into_iter().map(|row| row.tablespace).collect())
+}
+
+fn require_positive_partitions(number_partitions: i32) -> Result<(), ValidationError> {
+    if number_partitions <= 0 {
+        return Err(ValidationError::InvalidParameter(format!(
+            "Number of partitions must be positive, got {}",
+            number_partitions
+        )));
     }
+    Ok(())
+}
 
-    /// Set the refresh interval for the continuous aggregate.
-    pub fn with_refresh_interval(mut self, interval: impl Into<String>) -> Self {
-        self.refresh_interval = Some(interval.into());
-        self
+fn range_dimension_statement(
+    table: &str,
+    column: &str,
+    chunk_interval: TimeInterval,
+    version: TimescaleVersion,
+) -> Result<SqlStatement, ValidationError> {
+    let _column_identifier = SqlIdentifier::column(column)?;
+    chunk_interval.require_positive("Dimension chunk interval")?;
+    let interval = format!("INTERVAL '{}'", chunk_interval.to_postgres_interval());
+
+    let statement = SqlStatement::new("SELECT add_dimension(")
+        .bind(table)
+        .sql(", ");
+    Ok(if version.at_least(2, 13) {
+        statement
+            .sql("by_range(")
+            .bind(column)
+            .sql(format!(", {}));", interval))
+    } else {
+        statement
+            .bind(column)
+            .sql(format!(", chunk_time_interval => {});", interval))
+    })
+}
+
+fn hash_dimension_statement(
+    table: &str,
+    column: &str,
+    number_partitions: i32,
+    version: TimescaleVersion,
+) -> Result<SqlStatement, ValidationError> {
+    let _column_identifier = SqlIdentifier::column(column)?;
+    require_positive_partitions(number_partitions)?;
+
+    let statement = SqlStatement::new("SELECT add_dimension(")
+        .bind(table)
+        .sql(", ");
+    Ok(if version.at_least(2, 13) {
+        statement
+            .sql("by_hash(")
+            .bind(column)
+            .sql(format!(", {}));", number_partitions))
+    } else {
+        statement
+            .bind(column)
+            .sql(format!(", number_partitions => {});", number_partitions))
+    })
+}
+
+fn set_number_partitions_statement(
+    table: &str,
+    column: &str,
+    number_partitions: i32,
+) -> Result<SqlStatement, ValidationError> {
+    let _column_identifier = SqlIdentifier::column(column)?;
+    require_positive_partitions(number_partitions)?;
+
+    Ok(SqlStatement::new("SELECT set_number_partitions(")
+        .bind(table)
+        .sql(format!(", {}, ", number_partitions))
+        .bind(column)
+        .sql(");"))
+}
+
+fn replication_factor_statement(
+    table: &str,
+    replication_factor: i32,
+) -> Result<SqlStatement, ValidationError> {
+    if replication_factor < 1 {
+        return Err(ValidationError::InvalidParameter(format!(
+            "Replication factor must be at least 1, got {}",
+            replication_factor
+        )));
     }
 
-    /// Create the continuous aggregate.
-    pub fn create(&self, conn: &mut PgConnection) -> QueryResult<()> {
-        // Validate the view name
-        let view_identifier = SqlIdentifier::new(&self.view_name).map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::Unknown,
-                Box::new(e.to_string()),
-            )
-        })?;
+    Ok(SqlStatement::new("SELECT set_replication_factor(")
+        .bind(table)
+        .sql(format!(", {});", replication_factor)))
+}
 
-        // Note: We cannot parameterize the view name or query in CREATE MATERIALIZED VIEW
-        // because PostgreSQL doesn't support it. However, we validate the view name.
-        // The query parameter should be validated by the caller.
-        let create_sql = format!(
-            "CREATE MATERIALIZED VIEW {} WITH (timescaledb.continuous) AS {};",
-            view_identifier.escaped(),
-            self.query
-        );
+/// Check that the installed TimescaleDB supports distributed hypertables,
+/// which were added in 2.0 and removed with multi-node in 2.14.
+fn check_multinode_supported(conn: &mut PgConnection) -> Result<(), TimescaleError> {
+    require_multinode(installed_version(conn)?)
+}
 
-        diesel::sql_query(create_sql).execute(conn)?;
+/// The version check behind [`check_multinode_supported`].
+fn require_multinode(version: TimescaleVersion) -> Result<(), TimescaleError> {
+    if !version.at_least(2, 0) || version.at_least(2, 14) {
+        return Err(TimescaleError::UnsupportedVersion {
+            feature: "Distributed hypertables".to_string(),
+            required: TimescaleVersion::new(2, 0, 0),
+            removed: Some(TimescaleVersion::new(2, 14, 0)),
+            installed: version,
+        });
+    }
+    Ok(())
+}
 
-        // Add refresh policy if specified
-        if let (Some(interval_str), lag_opt) = (&self.refresh_interval, &self.refresh_lag) {
-            let interval = TimeInterval::from_string(interval_str).map_err(|e| {
-                diesel::result::Error::DatabaseError(
-                    diesel::result::DatabaseErrorKind::Unknown,
-                    Box::new(e.to_string()),
-                )
-            })?;
+/// Get the table name of a [`Hypertable`] quoted for a `regclass` argument.
+///
+/// The name is a compile-time constant and only ever bound as a parameter,
+/// so it doesn't go through validation; an invalid name fails the lookup in
+/// the database instead.
+fn quoted_table_name<T: Hypertable + ?Sized>() -> String {
+    SqlIdentifier(T::TABLE_NAME.to_string()).escaped()
+}
 
-            let mut refresh_sql = format!(
-                "SELECT add_continuous_aggregate_policy($1, start_offset => NULL, end_offset => INTERVAL '{}'",
-                interval.to_postgres_interval()
-            );
+/// Trait for tables that can be converted to TimescaleDB hypertables.
+pub trait Hypertable {
+    /// Name of the table to convert to a hypertable.
+    const TABLE_NAME: &'static str;
 
-            if let Some(lag_str) = lag_opt {
-                let lag = TimeInterval::from_string(lag_str).map_err(|e| {
-                    diesel::result::Error::DatabaseError(
-                        diesel::result::DatabaseErrorKind::Unknown,
-                        Box::new(e.to_string()),
-                    )
-                })?;
-                refresh_sql.push_str(&format!(
-                    ", schedule_interval => INTERVAL '{}'",
-                    lag.to_postgres_interval()
-                ));
+    /// Name of the time column to use for partitioning.
+    const TIME_COLUMN: &'static str;
+
+    /// Chunk time interval used by [`create_hypertable`](Self::create_hypertable),
+    /// such as `7 days`, TimescaleDB's default when unset.
+    const CHUNK_TIME_INTERVAL: Option<&'static str> = None;
+
+    /// Column to hash-partition by when creating the hypertable, together
+    /// with [`NUMBER_PARTITIONS`](Self::NUMBER_PARTITIONS).
+    const PARTITION_COLUMN: Option<&'static str> = None;
+
+    /// Number of hash partitions of [`PARTITION_COLUMN`](Self::PARTITION_COLUMN).
+    const NUMBER_PARTITIONS: Option<i32> = None;
+
+    /// Whether creating the hypertable succeeds when it already exists.
+    const IF_NOT_EXISTS: bool = false;
+
+    /// Build the statement that creates a hypertable from this table, with
+    /// the creation options declared with [`hypertable!`](crate::hypertable).
+    fn create_hypertable_sql() -> Result<SqlStatement, ValidationError> {
+        Self::hypertable_config()?.create_sql()
+    }
+
+    /// Get the configuration that creates the hypertable, with the creation
+    /// options declared with [`hypertable!`](crate::hypertable).
+    ///
+    /// Fails if the chunk time interval is not a valid interval, or if only
+    /// one of the partitioning column and the number of partitions is set.
+    fn hypertable_config() -> Result<HypertableConfig, ValidationError> {
+        let mut config = HypertableConfig::new(Self::TABLE_NAME, Self::TIME_COLUMN)
+            .with_if_not_exists(Self::IF_NOT_EXISTS);
+        if let Some(interval) = Self::CHUNK_TIME_INTERVAL {
+            config = config.with_chunk_time_interval(TimeInterval::from_string(interval)?);
+        }
+        match (Self::PARTITION_COLUMN, Self::NUMBER_PARTITIONS) {
+            (Some(column), Some(number_partitions)) => {
+                config = config.with_partitioning(column, number_partitions);
+            }
+            (None, None) => {}
+            _ => {
+                return Err(ValidationError::InvalidParameter(
+                    "A partitioning column needs a number of partitions, and vice versa"
+                        .to_string(),
+                ))
             }
+        }
+        Ok(config)
+    }
 
-            refresh_sql.push_str(");");
-            diesel::sql_query(refresh_sql)
-                .bind::<Text, _>(&self.view_name)
-                .execute(conn)?;
+    /// Create a hypertable from this table, with the creation options
+    /// declared with [`hypertable!`](crate::hypertable).
+    fn create_hypertable(conn: &mut PgConnection) -> Result<(), TimescaleError> {
+        Self::create_hypertable_sql()?.execute(conn)?;
+        Ok(())
+    }
+
+    /// Create a hypertable from this table on an async connection.
+    #[cfg(feature = "async")]
+    fn create_hypertable_async(
+        conn: &mut diesel_async::AsyncPgConnection,
+    ) -> impl std::future::Future<Output = Result<(), TimescaleError>> + Send {
+        async move {
+            Self::create_hypertable_sql()?.execute_async(conn).await?;
+            Ok(())
         }
+    }
+
+    /// Build the statement that creates a hypertable with a specific chunk time interval.
+    fn create_hypertable_with_interval_sql(chunk_time_interval: TimeInterval) -> SqlStatement {
+        create_hypertable_with_interval_statement(
+            &quoted_table_name::<Self>(),
+            Self::TIME_COLUMN,
+            chunk_time_interval,
+        )
+    }
 
+    /// Create a hypertable with a specific chunk time interval.
+    fn create_hypertable_with_interval(
+        conn: &mut PgConnection,
+        chunk_time_interval: TimeInterval,
+    ) -> Result<(), TimescaleError> {
+        Self::create_hypertable_with_interval_sql(chunk_time_interval).execute(conn)?;
         Ok(())
     }
-}
 
-/// Module for managing TimescaleDB chunks.
-pub mod chunks {
-    use super::*;
+    /// Build the statement that creates a hypertable on an integer time
+    /// column, with chunks spanning `chunk_time_interval` units of the column.
+    ///
+    /// For an epoch-milliseconds `BIGINT` column, `86_400_000` makes daily
+    /// chunks. The interval is rendered as a plain integer, without the
+    /// `INTERVAL` keyword, and must be positive.
+    fn create_hypertable_with_integer_interval_sql(
+        chunk_time_interval: i64,
+    ) -> Result<SqlStatement, ValidationError> {
+        create_hypertable_with_integer_interval_statement(
+            &quoted_table_name::<Self>(),
+            Self::TIME_COLUMN,
+            chunk_time_interval,
+        )
+    }
 
-    /// Information about a chunk in a hypertable.
-    #[derive(Debug, Clone, QueryableByName)]
-    pub struct ChunkInfo {
-        #[diesel(sql_type = Text)]
-        pub chunk_schema: String,
-        #[diesel(sql_type = Text)]
-        pub chunk_name: String,
-        #[diesel(sql_type = Text)]
-        pub table_name: String,
-        #[diesel(sql_type = Nullable<Timestamptz>)]
-        pub range_start: Option<chrono::DateTime<chrono::Utc>>,
-        #[diesel(sql_type = Nullable<Timestamptz>)]
-        pub range_end: Option<chrono::DateTime<chrono::Utc>>,
+    /// Create a hypertable on an integer time column with chunks spanning
+    /// `chunk_time_interval` units of the column.
+    fn create_hypertable_with_integer_interval(
+        conn: &mut PgConnection,
+        chunk_time_interval: i64,
+    ) -> Result<(), TimescaleError> {
+        Self::create_hypertable_with_integer_interval_sql(chunk_time_interval)?.execute(conn)?;
+        Ok(())
     }
 
-    /// Get information about chunks for a hypertable.
-    pub fn get_chunk_info(
+    /// Create a hypertable with a specific chunk time interval from a string.
+    fn create_hypertable_with_interval_str(
         conn: &mut PgConnection,
-        table_name: &str,
-    ) -> QueryResult<Vec<ChunkInfo>> {
-        // Validate table name
-        let _table_identifier = SqlIdentifier::new(table_name).map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::Unknown,
-                Box::new(e.to_string()),
-            )
-        })?;
+        chunk_time_interval: &str,
+    ) -> Result<(), TimescaleError> {
+        let interval = TimeInterval::from_string(chunk_time_interval)?;
 
-        diesel::sql_query(
-            "SELECT chunk_schema, chunk_name, table_name, range_start, range_end 
-             FROM timescaledb_information.chunks 
-             WHERE hypertable_name = $1",
+        Self::create_hypertable_with_interval(conn, interval)
+    }
+
+    /// Build the statement that adds a compression policy to the hypertable.
+    fn add_compression_policy_sql(compress_after: TimeInterval) -> SqlStatement {
+        compression_policy_statement(&quoted_table_name::<Self>(), compress_after)
+    }
+
+    /// Add a compression policy to the hypertable.
+    fn add_compression_policy(
+        conn: &mut PgConnection,
+        compress_after: TimeInterval,
+    ) -> Result<(), TimescaleError> {
+        Self::add_compression_policy_sql(compress_after).execute(conn)?;
+        Ok(())
+    }
+
+    /// Add a compression policy to the hypertable on an async connection.
+    #[cfg(feature = "async")]
+    fn add_compression_policy_async(
+        conn: &mut diesel_async::AsyncPgConnection,
+        compress_after: TimeInterval,
+    ) -> impl std::future::Future<Output = Result<(), TimescaleError>> + Send {
+        async move {
+            Self::add_compression_policy_sql(compress_after)
+                .execute_async(conn)
+                .await?;
+            Ok(())
+        }
+    }
+
+    /// Build the statement that removes the compression policy from the hypertable.
+    fn remove_compression_policy_sql() -> SqlStatement {
+        remove_compression_policy_statement(&quoted_table_name::<Self>())
+    }
+
+    /// Remove the compression policy from the hypertable, if there is one.
+    ///
+    /// Chunks that are already compressed stay compressed.
+    fn remove_compression_policy(conn: &mut PgConnection) -> Result<(), TimescaleError> {
+        Self::remove_compression_policy_sql().execute(conn)?;
+        Ok(())
+    }
+
+    /// Add a compression policy from a string interval.
+    fn add_compression_policy_str(
+        conn: &mut PgConnection,
+        compress_after: &str,
+    ) -> Result<(), TimescaleError> {
+        let interval = TimeInterval::from_string(compress_after)?;
+
+        Self::add_compression_policy(conn, interval)
+    }
+
+    /// Build the statement that adds a retention policy to the hypertable.
+    fn add_retention_policy_sql(drop_after: TimeInterval) -> SqlStatement {
+        retention_policy_statement(&quoted_table_name::<Self>(), drop_after)
+    }
+
+    /// Add a retention policy to automatically drop old data.
+    fn add_retention_policy(
+        conn: &mut PgConnection,
+        drop_after: TimeInterval,
+    ) -> Result<(), TimescaleError> {
+        Self::add_retention_policy_sql(drop_after).execute(conn)?;
+        Ok(())
+    }
+
+    /// Build the statement that removes the retention policy from the hypertable.
+    fn remove_retention_policy_sql() -> SqlStatement {
+        remove_retention_policy_statement(&quoted_table_name::<Self>())
+    }
+
+    /// Remove the retention policy from the hypertable, if there is one.
+    fn remove_retention_policy(conn: &mut PgConnection) -> Result<(), TimescaleError> {
+        Self::remove_retention_policy_sql().execute(conn)?;
+        Ok(())
+    }
+
+    /// Get the `drop_after` interval of the hypertable's retention policy.
+    ///
+    /// TimescaleDB stores the interval in the policy job's JSON config; it is
+    /// parsed back into the largest unit that represents it exactly. Returns
+    /// `None` if the hypertable has no retention policy.
+    fn retention_interval(conn: &mut PgConnection) -> Result<Option<TimeInterval>, TimescaleError> {
+        Ok(load_retention_interval(conn, &quoted_table_name::<Self>())?)
+    }
+
+    /// Replace the hypertable's retention policy with one using `drop_after`.
+    ///
+    /// The existing policy is removed and the new one added in a single
+    /// transaction, so the hypertable is never left without a policy.
+    fn set_retention_interval(
+        conn: &mut PgConnection,
+        drop_after: TimeInterval,
+    ) -> Result<(), TimescaleError> {
+        conn.transaction(|conn| {
+            Self::remove_retention_policy(conn)?;
+            Self::add_retention_policy(conn, drop_after)
+        })
+    }
+
+    /// Add a retention policy from a string interval.
+    fn add_retention_policy_str(
+        conn: &mut PgConnection,
+        drop_after: &str,
+    ) -> Result<(), TimescaleError> {
+        let interval = TimeInterval::from_string(drop_after)?;
+
+        Self::add_retention_policy(conn, interval)
+    }
+
+    /// Build the statement that adds a reorder policy to the hypertable.
+    fn add_reorder_policy_sql(index_name: &str) -> Result<SqlStatement, ValidationError> {
+        reorder_policy_statement(&quoted_table_name::<Self>(), index_name)
+    }
+
+    /// Add a policy that reorders chunks by an index once they are no longer
+    /// written to.
+    ///
+    /// Reordering clusters the rows of a chunk in index order, so range scans
+    /// over the index, e.g. per device and time, read fewer pages. The policy
+    /// skips the most recent chunks, which are still written to. `index_name`
+    /// is the bare name of an index on the hypertable. A hypertable can have
+    /// one reorder policy.
+    fn add_reorder_policy(conn: &mut PgConnection, index_name: &str) -> Result<(), TimescaleError> {
+        Self::add_reorder_policy_sql(index_name)?.execute(conn)?;
+        Ok(())
+    }
+
+    /// Build the statement that removes the reorder policy from the hypertable.
+    fn remove_reorder_policy_sql() -> SqlStatement {
+        remove_reorder_policy_statement(&quoted_table_name::<Self>())
+    }
+
+    /// Remove the reorder policy from the hypertable, if there is one.
+    fn remove_reorder_policy(conn: &mut PgConnection) -> Result<(), TimescaleError> {
+        Self::remove_reorder_policy_sql().execute(conn)?;
+        Ok(())
+    }
+
+    /// Build the statement that truncates the hypertable.
+    fn truncate_sql() -> Result<SqlStatement, ValidationError> {
+        let table_identifier = SqlIdentifier::new(Self::TABLE_NAME)?;
+        Ok(truncate_statement(&table_identifier, false))
+    }
+
+    /// Remove every row of the hypertable.
+    ///
+    /// TimescaleDB truncates the chunks along with the hypertable, which is
+    /// much faster than a `DELETE` and the usual way to reset a hypertable
+    /// between tests. The chunks themselves are dropped; compression,
+    /// retention and reorder policies stay in place.
+    fn truncate(conn: &mut PgConnection) -> Result<(), TimescaleError> {
+        Self::truncate_sql()?.execute(conn)?;
+        Ok(())
+    }
+
+    /// Build the statement that truncates the hypertable and restarts its
+    /// identity sequences.
+    fn truncate_restart_identity_sql() -> Result<SqlStatement, ValidationError> {
+        let table_identifier = SqlIdentifier::new(Self::TABLE_NAME)?;
+        Ok(truncate_statement(&table_identifier, true))
+    }
+
+    /// Remove every row of the hypertable, like [`truncate`](Self::truncate),
+    /// and restart the sequences of its serial and identity columns.
+    fn truncate_restart_identity(conn: &mut PgConnection) -> Result<(), TimescaleError> {
+        Self::truncate_restart_identity_sql()?.execute(conn)?;
+        Ok(())
+    }
+
+    /// Get the compression, retention and reorder policies of the hypertable
+    /// and the refresh policies of its continuous aggregates.
+    ///
+    /// All are read from `timescaledb_information.jobs` in one query, so
+    /// this is the read side of adding the policies one by one.
+    fn policies(conn: &mut PgConnection) -> Result<PolicySet, TimescaleError> {
+        Ok(load_policies(conn, &quoted_table_name::<Self>())?)
+    }
+
+    /// List the tablespaces attached to the hypertable.
+    ///
+    /// New chunks are placed in these tablespaces in turn; an empty list means
+    /// chunks are created in the table's own tablespace.
+    fn show_tablespaces(conn: &mut PgConnection) -> Result<Vec<String>, TimescaleError> {
+        Ok(load_tablespaces(conn, &quoted_table_name::<Self>())?)
+    }
+
+    /// Build the statement that adds a range (time) dimension to the hypertable.
+    ///
+    /// TimescaleDB 2.13 and later use the `by_range` dimension builder; older
+    /// versions use the positional `add_dimension` form.
+    fn add_range_dimension_sql(
+        column: &str,
+        chunk_interval: TimeInterval,
+        version: TimescaleVersion,
+    ) -> Result<SqlStatement, ValidationError> {
+        range_dimension_statement(
+            &quoted_table_name::<Self>(),
+            column,
+            chunk_interval,
+            version,
         )
-        .bind::<Text, _>(table_name)
-        .load::<ChunkInfo>(conn)
     }
 
-    /// Drop chunks older than a specified time.
-    pub fn drop_old_chunks(
+    /// Add a range (time) dimension to the hypertable.
+    ///
+    /// The dimension syntax is chosen from the installed TimescaleDB version,
+    /// see [`Hypertable::add_range_dimension_sql`].
+    fn add_range_dimension(
         conn: &mut PgConnection,
-        table_name: &str,
-        older_than: chrono::DateTime<chrono::Utc>,
-    ) -> QueryResult<()> {
-        // Validate table name
-        let _table_identifier = SqlIdentifier::new(table_name).map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::Unknown,
-                Box::new(e.to_string()),
-            )
-        })?;
+        column: &str,
+        chunk_interval: TimeInterval,
+    ) -> Result<(), TimescaleError> {
+        let version = installed_version(conn)?;
+        Self::add_range_dimension_sql(column, chunk_interval, version)?.execute(conn)?;
+        Ok(())
+    }
 
-        diesel::sql_query("SELECT drop_chunks($1, $2);")
-            .bind::<Text, _>(table_name)
-            .bind::<Timestamptz, _>(older_than)
-            .execute(conn)?;
+    /// Build the statement that adds a hash (space) dimension to the hypertable.
+    ///
+    /// TimescaleDB 2.13 and later use the `by_hash` dimension builder; older
+    /// versions use the positional `add_dimension` form.
+    fn add_hash_dimension_sql(
+        column: &str,
+        number_partitions: i32,
+        version: TimescaleVersion,
+    ) -> Result<SqlStatement, ValidationError> {
+        hash_dimension_statement(
+            &quoted_table_name::<Self>(),
+            column,
+            number_partitions,
+            version,
+        )
+    }
+
+    /// Add a hash (space) dimension to the hypertable.
+    ///
+    /// The dimension syntax is chosen from the installed TimescaleDB version,
+    /// see [`Hypertable::add_hash_dimension_sql`].
+    fn add_hash_dimension(
+        conn: &mut PgConnection,
+        column: &str,
+        number_partitions: i32,
+    ) -> Result<(), TimescaleError> {
+        let version = installed_version(conn)?;
+        Self::add_hash_dimension_sql(column, number_partitions, version)?.execute(conn)?;
+        Ok(())
+    }
+
+    /// Build the statement that changes the number of partitions of a hash dimension.
+    fn set_number_partitions_sql(
+        dimension_column: &str,
+        num_partitions: i32,
+    ) -> Result<SqlStatement, ValidationError> {
+        set_number_partitions_statement(
+            &quoted_table_name::<Self>(),
+            dimension_column,
+            num_partitions,
+        )
+    }
+
+    /// Change the number of partitions of a hash (space) dimension.
+    ///
+    /// Only chunks created afterwards use the new partitioning; existing
+    /// chunks keep their partitions.
+    fn set_number_partitions(
+        conn: &mut PgConnection,
+        dimension_column: &str,
+        num_partitions: i32,
+    ) -> Result<(), TimescaleError> {
+        Self::set_number_partitions_sql(dimension_column, num_partitions)?.execute(conn)?;
+        Ok(())
+    }
+
+    /// Build the statement that sets the replication factor of a distributed
+    /// hypertable.
+    fn set_replication_factor_sql(
+        replication_factor: i32,
+    ) -> Result<SqlStatement, ValidationError> {
+        replication_factor_statement(&quoted_table_name::<Self>(), replication_factor)
+    }
+
+    /// Set on how many data nodes each chunk of a distributed hypertable is
+    /// stored.
+    ///
+    /// The factor applies to chunks created afterwards. It cannot exceed the
+    /// number of data nodes attached to the hypertable. Distributed
+    /// hypertables only exist on TimescaleDB 2.0 to 2.13 with multi-node set
+    /// up, so other versions are rejected before the call is sent.
+    fn set_replication_factor(
+        conn: &mut PgConnection,
+        replication_factor: i32,
+    ) -> Result<(), TimescaleError> {
+        let statement = Self::set_replication_factor_sql(replication_factor)?;
+        check_multinode_supported(conn)?;
+        statement.execute(conn)?;
+        Ok(())
+    }
+}
+
+/// A hypertable whose table and time column are only known at runtime.
+///
+/// This provides the same operations as the [`Hypertable`] trait for tables
+/// that have no compile-time `table!` definition, such as per-tenant tables.
+/// The identifiers are validated once, when the value is constructed.
+#[derive(Debug, Clone)]
+pub struct DynamicHypertable {
+    pub table_name: SqlIdentifier,
+    pub time_column: SqlIdentifier,
+}
+
+impl DynamicHypertable {
+    /// Create a new dynamic hypertable from validated identifiers.
+    pub fn new(table_name: SqlIdentifier, time_column: SqlIdentifier) -> Self {
+        Self {
+            table_name,
+            time_column,
+        }
+    }
+
+    /// Create a new dynamic hypertable, validating the table and column names.
+    pub fn from_names(table_name: &str, time_column: &str) -> Result<Self, ValidationError> {
+        Ok(Self::new(
+            SqlIdentifier::new(table_name)?,
+            SqlIdentifier::column(time_column)?,
+        ))
+    }
+
+    /// Build the statement that creates a hypertable from this table.
+    pub fn create_hypertable_sql(&self) -> SqlStatement {
+        create_hypertable_statement(&self.table_name.escaped(), self.time_column.as_str())
+    }
+
+    /// Create a hypertable from this table.
+    pub fn create_hypertable(&self, conn: &mut PgConnection) -> Result<(), TimescaleError> {
+        self.create_hypertable_sql().execute(conn)?;
         Ok(())
     }
+
+    /// Build the statement that creates a hypertable with a specific chunk time interval.
+    pub fn create_hypertable_with_interval_sql(
+        &self,
+        chunk_time_interval: TimeInterval,
+    ) -> SqlStatement {
+        create_hypertable_with_interval_statement(
+            &self.table_name.escaped(),
+            self.time_column.as_str(),
+            chunk_time_interval,
+        )
+    }
+
+    /// Create a hypertable with a specific chunk time interval.
+    pub fn create_hypertable_with_interval(
+        &self,
+        conn: &mut PgConnection,
+        chunk_time_interval: TimeInterval,
+    ) -> Result<(), TimescaleError> {
+        self.create_hypertable_with_interval_sql(chunk_time_interval)
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Build the statement that creates a hypertable on an integer time
+    /// column.
+    ///
+    /// See [`Hypertable::create_hypertable_with_integer_interval_sql`].
+    pub fn create_hypertable_with_integer_interval_sql(
+        &self,
+        chunk_time_interval: i64,
+    ) -> Result<SqlStatement, ValidationError> {
+        create_hypertable_with_integer_interval_statement(
+            &self.table_name.escaped(),
+            self.time_column.as_str(),
+            chunk_time_interval,
+        )
+    }
+
+    /// Create a hypertable on an integer time column with chunks spanning
+    /// `chunk_time_interval` units of the column.
+    pub fn create_hypertable_with_integer_interval(
+        &self,
+        conn: &mut PgConnection,
+        chunk_time_interval: i64,
+    ) -> Result<(), TimescaleError> {
+        self.create_hypertable_with_integer_interval_sql(chunk_time_interval)?
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Build the statement that adds a compression policy to the hypertable.
+    pub fn add_compression_policy_sql(&self, compress_after: TimeInterval) -> SqlStatement {
+        compression_policy_statement(&self.table_name.escaped(), compress_after)
+    }
+
+    /// Add a compression policy to the hypertable.
+    pub fn add_compression_policy(
+        &self,
+        conn: &mut PgConnection,
+        compress_after: TimeInterval,
+    ) -> Result<(), TimescaleError> {
+        self.add_compression_policy_sql(compress_after)
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Build the statement that removes the compression policy from the hypertable.
+    pub fn remove_compression_policy_sql(&self) -> SqlStatement {
+        remove_compression_policy_statement(&self.table_name.escaped())
+    }
+
+    /// Remove the compression policy from the hypertable, if there is one.
+    pub fn remove_compression_policy(&self, conn: &mut PgConnection) -> Result<(), TimescaleError> {
+        self.remove_compression_policy_sql().execute(conn)?;
+        Ok(())
+    }
+
+    /// Build the statement that adds a retention policy to the hypertable.
+    pub fn add_retention_policy_sql(&self, drop_after: TimeInterval) -> SqlStatement {
+        retention_policy_statement(&self.table_name.escaped(), drop_after)
+    }
+
+    /// Add a retention policy to automatically drop old data.
+    pub fn add_retention_policy(
+        &self,
+        conn: &mut PgConnection,
+        drop_after: TimeInterval,
+    ) -> Result<(), TimescaleError> {
+        self.add_retention_policy_sql(drop_after).execute(conn)?;
+        Ok(())
+    }
+
+    /// Build the statement that removes the retention policy from the hypertable.
+    pub fn remove_retention_policy_sql(&self) -> SqlStatement {
+        remove_retention_policy_statement(&self.table_name.escaped())
+    }
+
+    /// Remove the retention policy from the hypertable, if there is one.
+    pub fn remove_retention_policy(&self, conn: &mut PgConnection) -> Result<(), TimescaleError> {
+        self.remove_retention_policy_sql().execute(conn)?;
+        Ok(())
+    }
+
+    /// Get the `drop_after` interval of the hypertable's retention policy.
+    ///
+    /// See [`Hypertable::retention_interval`].
+    pub fn retention_interval(
+        &self,
+        conn: &mut PgConnection,
+    ) -> Result<Option<TimeInterval>, TimescaleError> {
+        Ok(load_retention_interval(conn, &self.table_name.escaped())?)
+    }
+
+    /// Replace the hypertable's retention policy with one using `drop_after`.
+    ///
+    /// See [`Hypertable::set_retention_interval`].
+    pub fn set_retention_interval(
+        &self,
+        conn: &mut PgConnection,
+        drop_after: TimeInterval,
+    ) -> Result<(), TimescaleError> {
+        conn.transaction(|conn| {
+            self.remove_retention_policy(conn)?;
+            self.add_retention_policy(conn, drop_after)
+        })
+    }
+
+    /// Build the statement that adds a reorder policy to the hypertable.
+    pub fn add_reorder_policy_sql(
+        &self,
+        index_name: &str,
+    ) -> Result<SqlStatement, ValidationError> {
+        reorder_policy_statement(&self.table_name.escaped(), index_name)
+    }
+
+    /// Add a policy that reorders chunks by an index.
+    ///
+    /// See [`Hypertable::add_reorder_policy`].
+    pub fn add_reorder_policy(
+        &self,
+        conn: &mut PgConnection,
+        index_name: &str,
+    ) -> Result<(), TimescaleError> {
+        self.add_reorder_policy_sql(index_name)?.execute(conn)?;
+        Ok(())
+    }
+
+    /// Build the statement that removes the reorder policy from the hypertable.
+    pub fn remove_reorder_policy_sql(&self) -> SqlStatement {
+        remove_reorder_policy_statement(&self.table_name.escaped())
+    }
+
+    /// Remove the reorder policy from the hypertable, if there is one.
+    pub fn remove_reorder_policy(&self, conn: &mut PgConnection) -> Result<(), TimescaleError> {
+        self.remove_reorder_policy_sql().execute(conn)?;
+        Ok(())
+    }
+
+    /// Get the policies of the hypertable and its continuous aggregates.
+    ///
+    /// See [`Hypertable::policies`].
+    pub fn policies(&self, conn: &mut PgConnection) -> Result<PolicySet, TimescaleError> {
+        Ok(load_policies(conn, &self.table_name.escaped())?)
+    }
+
+    /// Build the statement that truncates the hypertable.
+    pub fn truncate_sql(&self) -> SqlStatement {
+        truncate_statement(&self.table_name, false)
+    }
+
+    /// Remove every row of the hypertable.
+    ///
+    /// See [`Hypertable::truncate`].
+    pub fn truncate(&self, conn: &mut PgConnection) -> Result<(), TimescaleError> {
+        self.truncate_sql().execute(conn)?;
+        Ok(())
+    }
+
+    /// Build the statement that truncates the hypertable and restarts its
+    /// identity sequences.
+    pub fn truncate_restart_identity_sql(&self) -> SqlStatement {
+        truncate_statement(&self.table_name, true)
+    }
+
+    /// Remove every row of the hypertable and restart its identity sequences.
+    ///
+    /// See [`Hypertable::truncate_restart_identity`].
+    pub fn truncate_restart_identity(&self, conn: &mut PgConnection) -> Result<(), TimescaleError> {
+        self.truncate_restart_identity_sql().execute(conn)?;
+        Ok(())
+    }
+
+    /// List the tablespaces attached to the hypertable.
+    ///
+    /// See [`Hypertable::show_tablespaces`].
+    pub fn show_tablespaces(&self, conn: &mut PgConnection) -> Result<Vec<String>, TimescaleError> {
+        Ok(load_tablespaces(conn, &self.table_name.escaped())?)
+    }
+
+    /// Build the statement that adds a range (time) dimension to the hypertable.
+    ///
+    /// See [`Hypertable::add_range_dimension_sql`].
+    pub fn add_range_dimension_sql(
+        &self,
+        column: &str,
+        chunk_interval: TimeInterval,
+        version: TimescaleVersion,
+    ) -> Result<SqlStatement, ValidationError> {
+        range_dimension_statement(&self.table_name.escaped(), column, chunk_interval, version)
+    }
+
+    /// Add a range (time) dimension to the hypertable.
+    pub fn add_range_dimension(
+        &self,
+        conn: &mut PgConnection,
+        column: &str,
+        chunk_interval: TimeInterval,
+    ) -> Result<(), TimescaleError> {
+        let version = installed_version(conn)?;
+        self.add_range_dimension_sql(column, chunk_interval, version)?
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Build the statement that adds a hash (space) dimension to the hypertable.
+    ///
+    /// See [`Hypertable::add_hash_dimension_sql`].
+    pub fn add_hash_dimension_sql(
+        &self,
+        column: &str,
+        number_partitions: i32,
+        version: TimescaleVersion,
+    ) -> Result<SqlStatement, ValidationError> {
+        hash_dimension_statement(
+            &self.table_name.escaped(),
+            column,
+            number_partitions,
+            version,
+        )
+    }
+
+    /// Add a hash (space) dimension to the hypertable.
+    pub fn add_hash_dimension(
+        &self,
+        conn: &mut PgConnection,
+        column: &str,
+        number_partitions: i32,
+    ) -> Result<(), TimescaleError> {
+        let version = installed_version(conn)?;
+        self.add_hash_dimension_sql(column, number_partitions, version)?
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Build the statement that changes the number of partitions of a hash dimension.
+    pub fn set_number_partitions_sql(
+        &self,
+        dimension_column: &str,
+        num_partitions: i32,
+    ) -> Result<SqlStatement, ValidationError> {
+        set_number_partitions_statement(
+            &self.table_name.escaped(),
+            dimension_column,
+            num_partitions,
+        )
+    }
+
+    /// Change the number of partitions of a hash (space) dimension.
+    ///
+    /// See [`Hypertable::set_number_partitions`].
+    pub fn set_number_partitions(
+        &self,
+        conn: &mut PgConnection,
+        dimension_column: &str,
+        num_partitions: i32,
+    ) -> Result<(), TimescaleError> {
+        self.set_number_partitions_sql(dimension_column, num_partitions)?
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Build the statement that sets the replication factor of a distributed
+    /// hypertable.
+    pub fn set_replication_factor_sql(
+        &self,
+        replication_factor: i32,
+    ) -> Result<SqlStatement, ValidationError> {
+        replication_factor_statement(&self.table_name.escaped(), replication_factor)
+    }
+
+    /// Set the replication factor of a distributed hypertable.
+    ///
+    /// See [`Hypertable::set_replication_factor`].
+    pub fn set_replication_factor(
+        &self,
+        conn: &mut PgConnection,
+        replication_factor: i32,
+    ) -> Result<(), TimescaleError> {
+        let statement = self.set_replication_factor_sql(replication_factor)?;
+        check_multinode_supported(conn)?;
+        statement.execute(conn)?;
+        Ok(())
+    }
+}
+
+/// Macro to implement the Hypertable trait for a table.
+///
+/// Creation options may follow the time column, in any order:
+///
+/// ```rust,ignore
+/// hypertable!(
+///     metrics,
+///     timestamp,
+///     chunk_interval = "7 days",
+///     partition_by = device_id,
+///     partitions = 4,
+///     if_not_exists = true
+/// );
+/// ```
+///
+/// They are used by `create_hypertable`, through the [`HypertableConfig`]
+/// returned by `hypertable_config`. `partition_by` must name a column of the
+/// table and requires `partitions`.
+#[macro_export]
+macro_rules! hypertable {
+    (@option $table_name:ident, chunk_interval = $interval:literal) => {
+        const CHUNK_TIME_INTERVAL: Option<&'static str> = Some($interval);
+    };
+    (@option $table_name:ident, partition_by = $column:ident) => {
+        const PARTITION_COLUMN: Option<&'static str> = Some({
+            // Fail to compile if the table has no such column
+            let _ = $table_name::$column;
+            stringify!($column)
+        });
+    };
+    (@option $table_name:ident, partitions = $partitions:literal) => {
+        const NUMBER_PARTITIONS: Option<i32> = Some($partitions);
+    };
+    (@option $table_name:ident, if_not_exists = $if_not_exists:literal) => {
+        const IF_NOT_EXISTS: bool = $if_not_exists;
+    };
+    ($table_name:ident, $time_column:ident $(, $option:ident = $value:tt)* $(,)?) => {
+        impl $crate::schema::Hypertable for $table_name::table {
+            const TABLE_NAME: &'static str = stringify!($table_name);
+            const TIME_COLUMN: &'static str = stringify!($time_column);
+            $($crate::hypertable!(@option $table_name, $option = $value);)*
+        }
+    };
+}
+
+/// Configuration for creating a hypertable.
+///
+/// Chunks are sized either by a time interval, for time-typed columns, or by
+/// an integer width, for integer-time hypertables. The two are mutually
+/// exclusive.
+#[derive(Debug, Clone)]
+pub struct HypertableConfig {
+    pub table_name: String,
+    pub time_column: String,
+    pub chunk_time_interval: Option<TimeInterval>,
+    pub chunk_time_interval_int: Option<i64>,
+    /// Schema the chunk tables are created in, `_timescaledb_internal` by
+    /// default.
+    pub associated_schema_name: Option<String>,
+    /// Prefix of the chunk table names, `_hyper` by default.
+    pub associated_table_prefix: Option<String>,
+    /// Column to hash-partition by, with its number of partitions.
+    pub partitioning: Option<(String, i32)>,
+    /// Whether creating the hypertable succeeds when it already exists.
+    pub if_not_exists: bool,
+}
+
+impl HypertableConfig {
+    /// Create a new hypertable configuration.
+    pub fn new(table_name: impl Into<String>, time_column: impl Into<String>) -> Self {
+        Self {
+            table_name: table_name.into(),
+            time_column: time_column.into(),
+            chunk_time_interval: None,
+            chunk_time_interval_int: None,
+            associated_schema_name: None,
+            associated_table_prefix: None,
+            partitioning: None,
+            if_not_exists: false,
+        }
+    }
+
+    /// Create the chunk tables in `schema` instead of `_timescaledb_internal`.
+    ///
+    /// The schema must exist when the hypertable is created.
+    pub fn with_associated_schema_name(mut self, schema: impl Into<String>) -> Self {
+        self.associated_schema_name = Some(schema.into());
+        self
+    }
+
+    /// Name the chunk tables `<prefix>_<hypertable id>_<chunk id>_chunk`
+    /// instead of starting them with `_hyper`.
+    pub fn with_associated_table_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.associated_table_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Set the chunk time interval for a time-typed column.
+    pub fn with_chunk_time_interval(mut self, interval: TimeInterval) -> Self {
+        self.chunk_time_interval = Some(interval);
+        self
+    }
+
+    /// Set the chunk width for an integer time column, in the column's own units.
+    pub fn with_chunk_time_interval_int(mut self, interval: i64) -> Self {
+        self.chunk_time_interval_int = Some(interval);
+        self
+    }
+
+    /// Hash-partition the hypertable by `column` into `number_partitions`
+    /// partitions.
+    pub fn with_partitioning(mut self, column: impl Into<String>, number_partitions: i32) -> Self {
+        self.partitioning = Some((column.into(), number_partitions));
+        self
+    }
+
+    /// Succeed without changes if the table is already a hypertable.
+    pub fn with_if_not_exists(mut self, if_not_exists: bool) -> Self {
+        self.if_not_exists = if_not_exists;
+        self
+    }
+
+    /// Build the statement that creates the hypertable.
+    pub fn create_sql(&self) -> Result<SqlStatement, ValidationError> {
+        // Validate identifiers
+        let table_identifier = SqlIdentifier::new(&self.table_name)?;
+        let _time_identifier = SqlIdentifier::column(&self.time_column)?;
+
+        let mut options = String::new();
+        match (&self.chunk_time_interval, self.chunk_time_interval_int) {
+            (Some(_), Some(_)) => {
+                return Err(ValidationError::InvalidInterval(
+                    "chunk_time_interval and chunk_time_interval_int are mutually exclusive"
+                        .to_string(),
+                ))
+            }
+            (Some(interval), None) => options.push_str(&format!(
+                ", chunk_time_interval => INTERVAL '{}'",
+                interval.to_postgres_interval()
+            )),
+            (None, Some(width)) => {
+                if width <= 0 {
+                    return Err(ValidationError::InvalidInterval(
+                        "Integer chunk interval must be positive".to_string(),
+                    ));
+                }
+                options.push_str(&format!(", chunk_time_interval => {}", width));
+            }
+            (None, None) => {}
+        }
+
+        let mut statement = SqlStatement::new("SELECT create_hypertable(")
+            .bind(table_identifier.escaped())
+            .sql(", ")
+            .bind(self.time_column.as_str());
+        if let Some((column, number_partitions)) = &self.partitioning {
+            let _column_identifier = SqlIdentifier::column(column)?;
+            require_positive_partitions(*number_partitions)?;
+            statement = statement
+                .sql(", partitioning_column => ")
+                .bind(column.as_str())
+                .sql(format!(", number_partitions => {}", number_partitions));
+        }
+        statement = statement.sql(options);
+        if let Some(schema) = &self.associated_schema_name {
+            validate_sql_identifier(schema)?;
+            statement = statement
+                .sql(", associated_schema_name => ")
+                .bind(schema.as_str());
+        }
+        if let Some(prefix) = &self.associated_table_prefix {
+            validate_sql_identifier(prefix)?;
+            statement = statement
+                .sql(", associated_table_prefix => ")
+                .bind(prefix.as_str());
+        }
+        if self.if_not_exists {
+            statement = statement.sql(", if_not_exists => true");
+        }
+        Ok(statement.sql(");"))
+    }
+
+    /// Create the hypertable.
+    pub fn create(&self, conn: &mut PgConnection) -> Result<(), TimescaleError> {
+        self.create_sql()?.execute(conn)?;
+        Ok(())
+    }
+}
+
+/// Sort direction of a `compress_orderby` column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+/// Position of `NULL` values in a `compress_orderby` column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NullsOrder {
+    First,
+    Last,
+}
+
+/// A `compress_orderby` column with its optional sort direction and `NULL`
+/// ordering, rendered like `"time" DESC NULLS LAST`.
+///
+/// Unset parts use PostgreSQL's defaults: ascending, with `NULL` values last
+/// when ascending and first when descending. A `(column, direction)` or
+/// `(column, direction, nulls)` tuple converts into an order-by column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressionOrderBy {
+    pub column: String,
+    pub direction: Option<SortDirection>,
+    pub nulls: Option<NullsOrder>,
+}
+
+impl CompressionOrderBy {
+    /// Order by `column` with PostgreSQL's default direction.
+    pub fn new(column: impl Into<String>) -> Self {
+        Self {
+            column: column.into(),
+            direction: None,
+            nulls: None,
+        }
+    }
+
+    /// Sort in ascending order.
+    pub fn asc(mut self) -> Self {
+        self.direction = Some(SortDirection::Asc);
+        self
+    }
+
+    /// Sort in descending order.
+    pub fn desc(mut self) -> Self {
+        self.direction = Some(SortDirection::Desc);
+        self
+    }
+
+    /// Sort `NULL` values first.
+    pub fn nulls_first(mut self) -> Self {
+        self.nulls = Some(NullsOrder::First);
+        self
+    }
+
+    /// Sort `NULL` values last.
+    pub fn nulls_last(mut self) -> Self {
+        self.nulls = Some(NullsOrder::Last);
+        self
+    }
+
+    fn to_sql(&self) -> Result<String, ValidationError> {
+        let mut sql = SqlIdentifier::column(&self.column)?.escaped();
+        match self.direction {
+            Some(SortDirection::Asc) => sql.push_str(" ASC"),
+            Some(SortDirection::Desc) => sql.push_str(" DESC"),
+            None => {}
+        }
+        match self.nulls {
+            Some(NullsOrder::First) => sql.push_str(" NULLS FIRST"),
+            Some(NullsOrder::Last) => sql.push_str(" NULLS LAST"),
+            None => {}
+        }
+        Ok(sql)
+    }
+}
+
+impl From<&str> for CompressionOrderBy {
+    fn from(column: &str) -> Self {
+        CompressionOrderBy::new(column)
+    }
+}
+
+impl From<(&str, SortDirection)> for CompressionOrderBy {
+    fn from((column, direction): (&str, SortDirection)) -> Self {
+        CompressionOrderBy {
+            direction: Some(direction),
+            ..CompressionOrderBy::new(column)
+        }
+    }
+}
+
+impl From<(&str, SortDirection, NullsOrder)> for CompressionOrderBy {
+    fn from((column, direction, nulls): (&str, SortDirection, NullsOrder)) -> Self {
+        CompressionOrderBy {
+            column: column.to_string(),
+            direction: Some(direction),
+            nulls: Some(nulls),
+        }
+    }
+}
+
+/// First TimescaleDB version with the columnstore names for compression
+/// options and policies.
+const COLUMNSTORE_VERSION: (u32, u32) = (2, 18);
+
+/// Configuration for enabling compression on a hypertable.
+///
+/// Rows are grouped into compressed batches per distinct value of the
+/// `segment_by` columns and sorted within a batch by the `order_by` columns.
+/// Segmenting by the columns queries filter on, such as a device id, is what
+/// makes compression effective for most schemas.
+///
+/// TimescaleDB 2.18 renamed compression to the columnstore. The statements
+/// emitted depend on the version passed to
+/// [`create_sql_for`](Self::create_sql_for):
+///
+/// | Version | Table options | Policy |
+/// |---------|---------------|--------|
+/// | before 2.18 | `timescaledb.compress`, `timescaledb.compress_segmentby`, `timescaledb.compress_orderby` | `SELECT add_compression_policy(...)` |
+/// | 2.18 and later | `timescaledb.enable_columnstore`, `timescaledb.segmentby`, `timescaledb.orderby` | `CALL add_columnstore_policy(...)` |
+///
+/// `timescaledb.compress_chunk_time_interval` has the same name in both.
+#[derive(Debug, Clone)]
+pub struct CompressionConfig {
+    pub table_name: String,
+    pub segment_by: Vec<String>,
+    pub order_by: Vec<CompressionOrderBy>,
+    /// Age after which chunks are compressed by a policy, if one is added.
+    pub compress_after: Option<TimeInterval>,
+    /// Interval of the compressed chunks, a multiple of the hypertable's
+    /// chunk interval, so that several chunks are merged into one when they
+    /// are compressed.
+    pub compress_chunk_time_interval: Option<TimeInterval>,
+    /// Time column of the hypertable, which cannot be a `segment_by`
+    /// column. [`create`](Self::create) looks it up if it is not set.
+    pub time_column: Option<String>,
+}
+
+impl CompressionConfig {
+    /// Create a new compression configuration with TimescaleDB's defaults.
+    pub fn new(table_name: impl Into<String>) -> Self {
+        Self {
+            table_name: table_name.into(),
+            segment_by: Vec::new(),
+            order_by: Vec::new(),
+            compress_after: None,
+            compress_chunk_time_interval: None,
+            time_column: None,
+        }
+    }
+
+    /// Set the time column of the hypertable, so that segmenting by it is
+    /// rejected when the statements are built.
+    pub fn with_time_column(mut self, column: impl Into<String>) -> Self {
+        self.time_column = Some(column.into());
+        self
+    }
+
+    /// Add a `compress_segmentby` column.
+    pub fn with_segment_by(mut self, column: impl Into<String>) -> Self {
+        self.segment_by.push(column.into());
+        self
+    }
+
+    /// Add a `compress_orderby` column, such as
+    /// `("time", SortDirection::Desc, NullsOrder::Last)`.
+    pub fn with_order_by(mut self, order_by: impl Into<CompressionOrderBy>) -> Self {
+        self.order_by.push(order_by.into());
+        self
+    }
+
+    /// Also add a compression policy compressing chunks older than `compress_after`.
+    pub fn with_policy(mut self, compress_after: TimeInterval) -> Self {
+        self.compress_after = Some(compress_after);
+        self
+    }
+
+    /// Merge chunks into compressed chunks covering `interval`.
+    pub fn with_compress_chunk_time_interval(mut self, interval: TimeInterval) -> Self {
+        self.compress_chunk_time_interval = Some(interval);
+        self
+    }
+
+    /// Build the statements that enable compression, with the option names
+    /// of TimescaleDB before 2.18.
+    ///
+    /// The first statement sets the compression options on the table; a
+    /// second statement adding the compression policy follows if one is
+    /// configured. Columns left unset use TimescaleDB's defaults. TimescaleDB
+    /// 2.18 and later still accept these names; use
+    /// [`create_sql_for`](Self::create_sql_for) for the columnstore syntax.
+    pub fn create_sql(&self) -> Result<Vec<SqlStatement>, ValidationError> {
+        self.create_sql_with(false)
+    }
+
+    /// Build the statements that enable compression with the syntax of the
+    /// given TimescaleDB version; see the table on [`CompressionConfig`].
+    pub fn create_sql_for(
+        &self,
+        version: TimescaleVersion,
+    ) -> Result<Vec<SqlStatement>, ValidationError> {
+        let (major, minor) = COLUMNSTORE_VERSION;
+        self.create_sql_with(version.at_least(major, minor))
+    }
+
+    fn create_sql_with(&self, columnstore: bool) -> Result<Vec<SqlStatement>, ValidationError> {
+        let table_identifier = SqlIdentifier::new(&self.table_name)?;
+        let (enable, segment_by, order_by) = if columnstore {
+            (
+                "timescaledb.enable_columnstore",
+                "timescaledb.segmentby",
+                "timescaledb.orderby",
+            )
+        } else {
+            (
+                "timescaledb.compress",
+                "timescaledb.compress_segmentby",
+                "timescaledb.compress_orderby",
+            )
+        };
+
+        // The column lists are string options, so the validated and quoted
+        // identifiers are rendered inside a string literal
+        let mut options = vec![enable.to_string()];
+        if !self.segment_by.is_empty() {
+            let columns = self
+                .segment_by
+                .iter()
+                .map(|column| SqlIdentifier::column(column).map(|column| column.escaped()))
+                .collect::<Result<Vec<_>, _>>()?;
+            // TimescaleDB rejects the time column with a less helpful error
+            if let Some(time_column) = &self.time_column {
+                if self.segment_by.contains(time_column) {
+                    return Err(ValidationError::InvalidParameter(format!(
+                        "The time column '{}' cannot be a segment_by column",
+                        time_column
+                    )));
+                }
+            }
+            options.push(format!("{} = '{}'", segment_by, columns.join(", ")));
+        }
+        if !self.order_by.is_empty() {
+            let columns = self
+                .order_by
+                .iter()
+                .map(CompressionOrderBy::to_sql)
+                .collect::<Result<Vec<_>, _>>()?;
+            options.push(format!("{} = '{}'", order_by, columns.join(", ")));
+        }
+        if let Some(interval) = &self.compress_chunk_time_interval {
+            interval.require_positive("Compressed chunk interval")?;
+            options.push(format!(
+                "timescaledb.compress_chunk_time_interval = '{}'",
+                interval.to_postgres_interval()
+            ));
+        }
+
+        let mut statements = vec![SqlStatement::new(format!(
+            "ALTER TABLE {} SET ({});",
+            table_identifier.escaped(),
+            options.join(", ")
+        ))];
+
+        if let Some(compress_after) = &self.compress_after {
+            statements.push(if columnstore {
+                SqlStatement::new("CALL add_columnstore_policy(")
+                    .bind(table_identifier.escaped())
+                    .sql(format!(
+                        ", after => INTERVAL '{}');",
+                        compress_after.to_postgres_interval()
+                    ))
+            } else {
+                compression_policy_statement(&table_identifier.escaped(), compress_after.clone())
+            });
+        }
+
+        Ok(statements)
+    }
+
+    /// Enable compression, and add the policy if one is configured, in one
+    /// transaction.
+    ///
+    /// The syntax is chosen for the installed TimescaleDB version, see
+    /// [`create_sql_for`](Self::create_sql_for). If no time column is set
+    /// and there are `segment_by` columns, the time column is read from
+    /// `timescaledb_information.dimensions` to validate them.
+    pub fn create(&self, conn: &mut PgConnection) -> Result<(), TimescaleError> {
+        let version = installed_version(conn)?;
+        let statements = if self.time_column.is_none() && !self.segment_by.is_empty() {
+            let table_identifier = SqlIdentifier::new(&self.table_name)?;
+            let config = CompressionConfig {
+                time_column: hypertable_time_column(conn, &table_identifier)?,
+                ..self.clone()
+            };
+            config.create_sql_for(version)?
+        } else {
+            self.create_sql_for(version)?
+        };
+        conn.transaction(|conn| {
+            for statement in &statements {
+                statement.execute(conn)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Build the statements that undo [`create`](Self::create): remove the
+    /// compression policy, if one is configured, and disable compression.
+    ///
+    /// TimescaleDB only disables compression on a hypertable without
+    /// compressed chunks, so they have to be decompressed first.
+    pub fn disable_sql(&self) -> Result<Vec<SqlStatement>, ValidationError> {
+        let table_identifier = SqlIdentifier::new(&self.table_name)?;
+
+        let mut statements = Vec::new();
+        if self.compress_after.is_some() {
+            statements.push(remove_compression_policy_statement(
+                &table_identifier.escaped(),
+            ));
+        }
+        statements.push(SqlStatement::new(format!(
+            "ALTER TABLE {} SET (timescaledb.compress = false);",
+            table_identifier.escaped()
+        )));
+        Ok(statements)
+    }
+}
+
+#[derive(QueryableByName)]
+struct TimeColumn {
+    #[diesel(sql_type = Text)]
+    column_name: String,
+}
+
+/// Look up the time column of a hypertable, the column of its first
+/// dimension. Returns `None` if the table is not a hypertable.
+fn hypertable_time_column(
+    conn: &mut PgConnection,
+    table_identifier: &SqlIdentifier,
+) -> QueryResult<Option<String>> {
+    // Bind the quoted name so the regclass cast keeps its case
+    let row = diesel::sql_query(
+        "SELECT column_name::text AS column_name 
+         FROM timescaledb_information.dimensions 
+         WHERE format('%I.%I', hypertable_schema, hypertable_name)::regclass = to_regclass($1) 
+           AND dimension_number = 1",
+    )
+    .bind::<Text, _>(table_identifier.escaped())
+    .get_result::<TimeColumn>(conn)
+    .optional()?;
+
+    Ok(row.map(|row| row.column_name))
+}
+
+/// Get the time column of a hypertable from the catalog, the column of its
+/// primary dimension.
+///
+/// This is the runtime counterpart of [`Hypertable::TIME_COLUMN`], for
+/// tables only known by name. Returns `NotFound` if the table is not a
+/// hypertable.
+pub fn time_column_of(conn: &mut PgConnection, table_name: &str) -> Result<String, TimescaleError> {
+    let table_identifier = SqlIdentifier::new(table_name)?;
+    hypertable_time_column(conn, &table_identifier)?
+        .ok_or(TimescaleError::Database(diesel::result::Error::NotFound))
+}
+
+/// A value together with the time it was recorded, as returned by
+/// [`first_with_time`] and [`last_with_time`].
+#[derive(Debug, Clone, PartialEq, QueryableByName)]
+pub struct ValueAt {
+    #[diesel(sql_type = Double)]
+    pub value: f64,
+    #[diesel(sql_type = Timestamptz)]
+    pub at: chrono::DateTime<chrono::Utc>,
+}
+
+fn value_at_statement(
+    table_name: &str,
+    value_column: &str,
+    time_column: &str,
+    direction: &str,
+) -> Result<SqlStatement, ValidationError> {
+    // Validate identifiers
+    let table_identifier = SqlIdentifier::new(table_name)?;
+    let value = SqlIdentifier::column(value_column)?.escaped();
+    let time = SqlIdentifier::column(time_column)?.escaped();
+
+    Ok(SqlStatement::new(format!(
+        "SELECT {value}::float8 AS value, {time} AS at
+         FROM {table}
+         WHERE {value} IS NOT NULL AND {time} IS NOT NULL
+         ORDER BY {time} {direction}
+         LIMIT 1;",
+        table = table_identifier.escaped(),
+    )))
+}
+
+/// Build the statement that selects the earliest value of a column and its
+/// time.
+///
+/// See [`first_with_time`].
+pub fn first_with_time_sql(
+    table_name: &str,
+    value_column: &str,
+    time_column: &str,
+) -> Result<SqlStatement, ValidationError> {
+    value_at_statement(table_name, value_column, time_column, "ASC")
+}
+
+/// Get the earliest value of a column together with its time.
+///
+/// Like [`last_with_time`], but for the first row in time order.
+pub fn first_with_time(
+    conn: &mut PgConnection,
+    table_name: &str,
+    value_column: &str,
+    time_column: &str,
+) -> Result<Option<ValueAt>, TimescaleError> {
+    Ok(first_with_time_sql(table_name, value_column, time_column)?
+        .get_result::<ValueAt>(conn)
+        .optional()?)
+}
+
+/// Build the statement that selects the latest value of a column and its
+/// time.
+///
+/// See [`last_with_time`].
+pub fn last_with_time_sql(
+    table_name: &str,
+    value_column: &str,
+    time_column: &str,
+) -> Result<SqlStatement, ValidationError> {
+    value_at_statement(table_name, value_column, time_column, "DESC")
+}
+
+/// Get the latest value of a column together with the time it was recorded,
+/// such as the latest reading of a table and when it was taken.
+///
+/// The `last(value, time)` aggregate returns only the value, and a second
+/// `max(time)` aggregate could pick a different row when times repeat, so
+/// this reads the row itself:
+///
+/// ```sql
+/// SELECT "value"::float8 AS value, "time" AS at
+/// FROM "metrics"
+/// WHERE "value" IS NOT NULL AND "time" IS NOT NULL
+/// ORDER BY "time" DESC
+/// LIMIT 1;
+/// ```
+///
+/// This uses the index on the time column and, on a hypertable, only reads
+/// the newest chunk that has a row. Rows with a `NULL` value are skipped.
+/// Returns `None` if there is no such row.
+pub fn last_with_time(
+    conn: &mut PgConnection,
+    table_name: &str,
+    value_column: &str,
+    time_column: &str,
+) -> Result<Option<ValueAt>, TimescaleError> {
+    Ok(last_with_time_sql(table_name, value_column, time_column)?
+        .get_result::<ValueAt>(conn)
+        .optional()?)
+}
+
+/// Build the statements that create a table from a query and convert it to a hypertable.
+///
+/// See [`create_hypertable_from_query`].
+pub fn create_hypertable_from_query_sql(
+    table_name: &str,
+    time_column: &str,
+    select_sql: &str,
+    chunk_interval: TimeInterval,
+) -> Result<Vec<SqlStatement>, ValidationError> {
+    // Validate identifiers
+    let table_identifier = SqlIdentifier::new(table_name)?;
+    let _time_column_identifier = SqlIdentifier::column(time_column)?;
+
+    // Note: CREATE TABLE AS cannot be parameterized, so the query is passed
+    // through as is and must be trusted by the caller.
+    Ok(vec![
+        SqlStatement::new(format!(
+            "CREATE TABLE {} AS {};",
+            table_identifier.escaped(),
+            select_sql
+        )),
+        SqlStatement::new("SELECT create_hypertable(")
+            .bind(table_identifier.escaped())
+            .sql(", ")
+            .bind(time_column)
+            .sql(format!(
+                ", chunk_time_interval => INTERVAL '{}', migrate_data => true);",
+                chunk_interval.to_postgres_interval()
+            )),
+    ])
+}
+
+/// Create a table from a query and convert it to a hypertable.
+///
+/// This runs `CREATE TABLE ... AS <select_sql>` and then `create_hypertable`
+/// with `migrate_data => true`, moving the selected rows into chunks, in a
+/// single transaction. It is the usual way to build derived or rollup tables.
+///
+/// # Security
+/// Like the query of a continuous aggregate, `select_sql` cannot be
+/// parameterized and is executed as given. It is the caller's trust boundary
+/// and must never contain unvalidated user input.
+pub fn create_hypertable_from_query(
+    conn: &mut PgConnection,
+    table_name: &str,
+    time_column: &str,
+    select_sql: &str,
+    chunk_interval: TimeInterval,
+) -> Result<(), TimescaleError> {
+    let statements =
+        create_hypertable_from_query_sql(table_name, time_column, select_sql, chunk_interval)?;
+
+    conn.transaction(|conn| {
+        for statement in statements {
+            statement.execute(conn)?;
+        }
+        Ok(())
+    })
+}
+
+/// Information about a hypertable.
+#[derive(Debug, Clone, QueryableByName)]
+pub struct HypertableInfo {
+    #[diesel(sql_type = Text)]
+    pub hypertable_schema: String,
+    #[diesel(sql_type = Text)]
+    pub hypertable_name: String,
+    /// Number of partitioning dimensions, including the time dimension.
+    #[diesel(sql_type = SmallInt)]
+    pub num_dimensions: i16,
+    /// Number of chunks, compressed or not.
+    #[diesel(sql_type = BigInt)]
+    pub num_chunks: i64,
+    /// Whether compression is enabled, which does not mean any chunk is compressed yet.
+    #[diesel(sql_type = Bool)]
+    pub compression_enabled: bool,
+}
+
+impl HypertableInfo {
+    /// Check whether compression is enabled on the hypertable.
+    pub fn is_compressed(&self) -> bool {
+        self.compression_enabled
+    }
+
+    /// Check whether the hypertable has no chunks, i.e. holds no data.
+    pub fn is_empty(&self) -> bool {
+        self.num_chunks == 0
+    }
+
+    /// Keep only the hypertables with compression enabled.
+    pub fn filter_compressed(hypertables: Vec<HypertableInfo>) -> Vec<HypertableInfo> {
+        hypertables
+            .into_iter()
+            .filter(HypertableInfo::is_compressed)
+            .collect()
+    }
+}
+
+/// List all hypertables in the database.
+pub fn list_hypertables(conn: &mut PgConnection) -> QueryResult<Vec<HypertableInfo>> {
+    diesel::sql_query(
+        "SELECT hypertable_schema, hypertable_name, num_dimensions, num_chunks, compression_enabled 
+         FROM timescaledb_information.hypertables 
+         ORDER BY hypertable_schema, hypertable_name",
+    )
+    .load::<HypertableInfo>(conn)
+}
+
+#[derive(QueryableByName)]
+struct HypertableExists {
+    #[diesel(sql_type = Bool)]
+    is_hypertable: bool,
+}
+
+/// Check whether a table is a hypertable.
+///
+/// Returns `false` for plain tables and for tables that do not exist.
+pub fn is_hypertable(conn: &mut PgConnection, table_name: &str) -> Result<bool, TimescaleError> {
+    let table_identifier = SqlIdentifier::new(table_name)?;
+    Ok(hypertable_exists(conn, &table_identifier.escaped())?)
+}
+
+/// Check whether the table named by the quoted, possibly schema-qualified
+/// `regclass` text is a hypertable.
+pub(crate) fn hypertable_exists(conn: &mut PgConnection, quoted_name: &str) -> QueryResult<bool> {
+    let row = diesel::sql_query(
+        "SELECT EXISTS (SELECT 1 FROM timescaledb_information.hypertables 
+                        WHERE format('%I.%I', hypertable_schema, hypertable_name)::regclass = to_regclass($1)) AS is_hypertable",
+    )
+    .bind::<Text, _>(quoted_name)
+    .get_result::<HypertableExists>(conn)?;
+    Ok(row.is_hypertable)
+}
+
+/// A retention policy as configured on a hypertable.
+#[derive(Debug, Clone, QueryableByName)]
+pub struct RetentionPolicyInfo {
+    #[diesel(sql_type = Integer)]
+    pub job_id: i32,
+    #[diesel(sql_type = Text)]
+    pub hypertable_schema: String,
+    #[diesel(sql_type = Text)]
+    pub hypertable_name: String,
+    /// Age after which chunks are dropped, for hypertables with a time column.
+    #[diesel(sql_type = Nullable<Interval>)]
+    pub drop_after: Option<TimeInterval>,
+    /// Age after which chunks are dropped, for hypertables with an integer column.
+    #[diesel(sql_type = Nullable<BigInt>)]
+    pub drop_after_int: Option<i64>,
+}
+
+/// List the retention policies of all hypertables.
+///
+/// TimescaleDB stores `drop_after` in the job's JSON config as an interval
+/// string such as `"7 days"`, or as a number for integer time columns. The
+/// string is cast to an `interval` by PostgreSQL, which accepts every format
+/// TimescaleDB writes, and loaded as a [`TimeInterval`] so it can be passed
+/// straight back to [`Hypertable::add_retention_policy`].
+pub fn list_retention_policies(conn: &mut PgConnection) -> QueryResult<Vec<RetentionPolicyInfo>> {
+    diesel::sql_query(
+        "SELECT job_id, hypertable_schema::text AS hypertable_schema, 
+                hypertable_name::text AS hypertable_name, 
+                CASE WHEN jsonb_typeof(config->'drop_after') = 'string' 
+                     THEN (config->>'drop_after')::interval END AS drop_after, 
+                CASE WHEN jsonb_typeof(config->'drop_after') = 'number' 
+                     THEN (config->>'drop_after')::int8 END AS drop_after_int 
+         FROM timescaledb_information.jobs 
+         WHERE proc_name = 'policy_retention' 
+         ORDER BY hypertable_schema, hypertable_name",
+    )
+    .load::<RetentionPolicyInfo>(conn)
+}
+
+/// Policies applied to several hypertables at once, see [`apply_policies_to_matching`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PolicyConfig {
+    /// Age after which chunks are compressed.
+    pub compress_after: Option<TimeInterval>,
+    /// Age after which chunks are dropped.
+    pub drop_after: Option<TimeInterval>,
+}
+
+impl PolicyConfig {
+    /// Add a compression policy compressing chunks older than `compress_after`.
+    pub fn with_compression(mut self, compress_after: TimeInterval) -> Self {
+        self.compress_after = Some(compress_after);
+        self
+    }
+
+    /// Add a retention policy dropping chunks older than `drop_after`.
+    pub fn with_retention(mut self, drop_after: TimeInterval) -> Self {
+        self.drop_after = Some(drop_after);
+        self
+    }
+}
+
+#[derive(QueryableByName)]
+struct MatchingHypertable {
+    #[diesel(sql_type = Text)]
+    hypertable: String,
+    #[diesel(sql_type = Bool)]
+    has_compression_policy: bool,
+    #[diesel(sql_type = Bool)]
+    has_retention_policy: bool,
+}
+
+/// Validate the output column aliases of a bucketed query, which sit next to
+/// its `bucket` column and so cannot repeat it or each other.
+///
+/// [`SqlIdentifier::column`] already rejects a qualified alias.
+fn validate_aliases<'a>(
+    kind: &str,
+    aliases: impl IntoIterator<Item = &'a str>,
+) -> Result<Vec<SqlIdentifier>, ValidationError> {
+    let mut seen = vec!["bucket"];
+    let mut identifiers = Vec::new();
+    for alias in aliases {
+        let identifier = SqlIdentifier::column(alias)?;
+        if seen.contains(&alias) {
+            return Err(ValidationError::InvalidParameter(format!(
+                "Duplicate {} '{}'",
+                kind, alias
+            )));
+        }
+        seen.push(alias);
+        identifiers.push(identifier);
+    }
+    Ok(identifiers)
+}
+
+/// Convert a name pattern with `*` wildcards into an escaped `LIKE` pattern.
+fn like_pattern(pattern: &str) -> Result<String, ValidationError> {
+    if pattern.is_empty()
+        || !pattern
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '*')
+    {
+        return Err(ValidationError::InvalidParameter(format!(
+            "Invalid hypertable name pattern: '{}'",
+            pattern
+        )));
+    }
+
+    Ok(pattern.replace('_', "\\_").replace('*', "%"))
+}
+
+/// Apply the same policies to every hypertable whose name matches `pattern`.
+///
+/// The pattern is a hypertable name in which `*` matches any sequence of
+/// characters, e.g. `tenant_*_metrics`; `_` and `%` have no special meaning.
+/// A hypertable that already has a compression or retention policy keeps it
+/// and only gets the policies it is missing. A compression policy requires
+/// compression to be enabled on the hypertable.
+///
+/// All policies are added in a single transaction. Returns the
+/// schema-qualified names of the hypertables that got at least one policy.
+pub fn apply_policies_to_matching(
+    conn: &mut PgConnection,
+    pattern: &str,
+    policies: PolicyConfig,
+) -> Result<Vec<String>, TimescaleError> {
+    let like = like_pattern(pattern)?;
+
+    conn.transaction(|conn| {
+        let hypertables = diesel::sql_query(
+            "SELECT format('%I.%I', h.hypertable_schema, h.hypertable_name) AS hypertable, 
+                    EXISTS (SELECT 1 FROM timescaledb_information.jobs j 
+                            WHERE j.proc_name = 'policy_compression' 
+                              AND j.hypertable_schema = h.hypertable_schema 
+                              AND j.hypertable_name = h.hypertable_name) AS has_compression_policy, 
+                    EXISTS (SELECT 1 FROM timescaledb_information.jobs j 
+                            WHERE j.proc_name = 'policy_retention' 
+                              AND j.hypertable_schema = h.hypertable_schema 
+                              AND j.hypertable_name = h.hypertable_name) AS has_retention_policy 
+             FROM timescaledb_information.hypertables h 
+             WHERE h.hypertable_name LIKE $1 ESCAPE '\\' 
+             ORDER BY hypertable",
+        )
+        .bind::<Text, _>(like)
+        .load::<MatchingHypertable>(conn)?;
+
+        let mut touched = Vec::new();
+        for hypertable in hypertables {
+            let mut statements = Vec::new();
+            if let (Some(compress_after), false) =
+                (&policies.compress_after, hypertable.has_compression_policy)
+            {
+                statements.push(compression_policy_statement(
+                    &hypertable.hypertable,
+                    compress_after.clone(),
+                ));
+            }
+            if let (Some(drop_after), false) =
+                (&policies.drop_after, hypertable.has_retention_policy)
+            {
+                statements.push(retention_policy_statement(
+                    &hypertable.hypertable,
+                    drop_after.clone(),
+                ));
+            }
+
+            if statements.is_empty() {
+                continue;
+            }
+            for statement in statements {
+                statement.execute(conn)?;
+            }
+            touched.push(hypertable.hypertable);
+        }
+
+        Ok(touched)
+    })
+}
+
+/// Find the columns used as the second argument of `time_bucket` calls.
+///
+/// Table qualifiers and double quotes are stripped, so `"m"."ts"` yields `ts`.
+fn time_bucket_columns(query: &str) -> Vec<String> {
+    let lower = query.to_ascii_lowercase();
+    let mut columns = Vec::new();
+
+    for (start, _) in lower.match_indices("time_bucket(") {
+        // Skip other functions ending in `time_bucket`
+        let preceding = lower[..start].chars().next_back();
+        if preceding.is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+            continue;
+        }
+
+        // Split the arguments at top-level commas
+        let mut args = Vec::new();
+        let mut depth = 0;
+        let mut in_string = false;
+        let args_start = start + "time_bucket(".len();
+        let mut arg_start = args_start;
+        for (offset, c) in query[args_start..].char_indices() {
+            let position = args_start + offset;
+            match c {
+                '\'' => in_string = !in_string,
+                _ if in_string => {}
+                '(' => depth += 1,
+                ')' if depth == 0 => {
+                    args.push(&query[arg_start..position]);
+                    break;
+                }
+                ')' => depth -= 1,
+                ',' if depth == 0 => {
+                    args.push(&query[arg_start..position]);
+                    arg_start = position + 1;
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(column) = args.get(1) {
+            let column = column.trim();
+            let column = column.rsplit('.').next().unwrap_or(column);
+            columns.push(column.trim_matches('"').to_string());
+        }
+    }
+
+    columns
+}
+
+/// Configuration for continuous aggregates.
+///
+/// The view name is validated as an identifier and always treated as quoted,
+/// so mixed-case names such as `DailyMetrics` keep their case. It is escaped
+/// into `CREATE MATERIALIZED VIEW`, which cannot take parameters, and bound as
+/// a quoted `regclass` name in the policy call.
+#[derive(Debug, Clone)]
+pub struct ContinuousAggregateConfig {
+    pub view_name: String,
+    pub query: String,
+    pub refresh_lag: Option<String>,
+    pub refresh_interval: Option<String>,
+    /// Whether to create indexes on the `GROUP BY` columns of the aggregate,
+    /// TimescaleDB's default when unset.
+    pub create_group_indexes: Option<bool>,
+    /// Whether queries only read materialized data, TimescaleDB's default
+    /// when unset.
+    pub materialized_only: Option<bool>,
+    /// Whether the source is a distributed (multi-node) hypertable.
+    pub distributed: bool,
+    /// Time column the query is expected to bucket, checked when set.
+    pub time_column: Option<String>,
+    /// Bucket column of a companion real-time view to create, if any.
+    pub realtime_view_bucket_column: Option<String>,
+    /// Number of buckets the refresh policy refreshes per batch.
+    pub buckets_per_batch: Option<i32>,
+    /// Maximum number of batches the refresh policy runs per execution.
+    pub max_batches_per_execution: Option<i32>,
+}
+
+/// First TimescaleDB version whose refresh policies accept
+/// `buckets_per_batch` and `max_batches_per_execution`.
+const REFRESH_BATCHING_VERSION: (u32, u32) = (2, 19);
+
+/// Suffix appended to a continuous aggregate's name for its companion
+/// real-time view, see [`ContinuousAggregateConfig::with_realtime_view`].
+pub const REALTIME_VIEW_SUFFIX: &str = "_realtime";
+
+impl ContinuousAggregateConfig {
+    /// Create a new continuous aggregate configuration.
+    pub fn new(view_name: impl Into<String>, query: impl Into<String>) -> Self {
+        Self {
+            view_name: view_name.into(),
+            query: query.into(),
+            refresh_lag: None,
+            refresh_interval: None,
+            create_group_indexes: None,
+            materialized_only: None,
+            distributed: false,
+            time_column: None,
+            realtime_view_bucket_column: None,
+            buckets_per_batch: None,
+            max_batches_per_execution: None,
+        }
+    }
+
+    /// Set whether indexes are created on the `GROUP BY` columns.
+    ///
+    /// On distributed hypertables the materialized data lives on the access
+    /// node, and disabling group indexes can reduce the cost of refreshes.
+    pub fn with_create_group_indexes(mut self, create_group_indexes: bool) -> Self {
+        self.create_group_indexes = Some(create_group_indexes);
+        self
+    }
+
+    /// Set whether queries only read materialized data.
+    ///
+    /// With `false`, real-time aggregation combines the materialized buckets
+    /// with the not yet materialized rows of the source hypertable. Real-time
+    /// aggregation is off by default from TimescaleDB 2.13 and on before.
+    pub fn with_materialized_only(mut self, materialized_only: bool) -> Self {
+        self.materialized_only = Some(materialized_only);
+        self
+    }
+
+    /// Mark the source as a distributed (multi-node) hypertable.
+    ///
+    /// Multi-node was added in TimescaleDB 2.0 and removed in 2.14, so
+    /// [`create`](Self::create) refuses to run against other versions when set.
+    pub fn with_distributed(mut self, distributed: bool) -> Self {
+        self.distributed = distributed;
+        self
+    }
+
+    /// Set the time column the query is expected to bucket.
+    ///
+    /// [`create_sql`](Self::create_sql) then checks that the query contains a
+    /// `time_bucket` call with this column as its second argument, which
+    /// catches queries copied from another aggregate. The query is not parsed,
+    /// so this is a targeted textual check only.
+    pub fn with_time_column(mut self, time_column: impl Into<String>) -> Self {
+        self.time_column = Some(time_column.into());
+        self
+    }
+
+    /// Also create a companion view combining materialized and raw data.
+    ///
+    /// The view is named after the aggregate with [`REALTIME_VIEW_SUFFIX`]
+    /// appended. It reads the buckets below the aggregate's materialization
+    /// watermark from the aggregate and computes the buckets from the
+    /// watermark onwards from the raw data with the aggregate's own query,
+    /// which is what real-time aggregation does, but as an ordinary view
+    /// whose definition can be inspected and changed. `bucket_column` is the
+    /// `time_bucket` column of the query.
+    pub fn with_realtime_view(mut self, bucket_column: impl Into<String>) -> Self {
+        self.realtime_view_bucket_column = Some(bucket_column.into());
+        self
+    }
+
+    /// Get the name of the companion real-time view, if one is configured.
+    pub fn realtime_view_name(&self) -> Option<String> {
+        self.realtime_view_bucket_column
+            .as_ref()
+            .map(|_| format!("{}{}", self.view_name, REALTIME_VIEW_SUFFIX))
+    }
+
+    /// Build the statement that creates the companion real-time view.
+    ///
+    /// Returns `None` unless [`with_realtime_view`](Self::with_realtime_view)
+    /// was set. The watermark functions are looked up in the schema used by
+    /// `version`.
+    pub fn realtime_view_sql(
+        &self,
+        version: TimescaleVersion,
+    ) -> Result<Option<SqlStatement>, ValidationError> {
+        let (Some(bucket_column), Some(realtime_view_name)) =
+            (&self.realtime_view_bucket_column, self.realtime_view_name())
+        else {
+            return Ok(None);
+        };
+
+        // Validate identifiers, including the generated view name
+        let view_identifier = SqlIdentifier::new(&self.view_name)?;
+        let realtime_identifier = SqlIdentifier::new(&realtime_view_name)?;
+        let bucket_identifier = SqlIdentifier::column(bucket_column)?;
+
+        // The watermark functions moved out of the internal schema in 2.12
+        let functions_schema = if version.at_least(2, 12) {
+            "_timescaledb_functions"
+        } else {
+            "_timescaledb_internal"
+        };
+        // Views cannot take parameters; the validated and quoted name cannot
+        // contain a quote, so it is safe inside the literal
+        let watermark = format!(
+            "COALESCE({schema}.to_timestamp({schema}.cagg_watermark(\
+             (SELECT mat_hypertable_id FROM _timescaledb_catalog.continuous_agg \
+             WHERE format('%I.%I', user_view_schema, user_view_name)::regclass = to_regclass('{view}')))), \
+             '-infinity'::timestamptz)",
+            schema = functions_schema,
+            view = view_identifier.escaped()
+        );
+
+        Ok(Some(SqlStatement::new(format!(
+            "CREATE VIEW {realtime} AS 
+             SELECT * FROM {view} WHERE {bucket} < {watermark} 
+             UNION ALL 
+             SELECT * FROM ({query}) AS recent WHERE {bucket} >= {watermark};",
+            realtime = realtime_identifier.escaped(),
+            view = view_identifier.escaped(),
+            bucket = bucket_identifier.escaped(),
+            watermark = watermark,
+            query = self.query
+        ))))
+    }
+
+    /// Set the refresh lag for the continuous aggregate.
+    pub fn with_refresh_lag(mut self, lag: impl Into<String>) -> Self {
+        self.refresh_lag = Some(lag.into());
+        self
+    }
+
+    /// Set the refresh interval for the continuous aggregate.
+    pub fn with_refresh_interval(mut self, interval: impl Into<String>) -> Self {
+        self.refresh_interval = Some(interval.into());
+        self
+    }
+
+    /// Refresh `buckets` buckets per batch in the refresh policy.
+    ///
+    /// A policy run then refreshes its range in several smaller transactions
+    /// instead of one, which shortens the locks held on large aggregates.
+    /// Zero refreshes the whole range in one batch. Requires TimescaleDB 2.19
+    /// or later; [`create`](Self::create) and
+    /// [`create_sql_for`](Self::create_sql_for) leave it out on older
+    /// versions, and [`create_sql`](Self::create_sql) always does.
+    pub fn with_buckets_per_batch(mut self, buckets: i32) -> Self {
+        self.buckets_per_batch = Some(buckets);
+        self
+    }
+
+    /// Run at most `batches` batches per execution of the refresh policy,
+    /// leaving the rest of the range to later runs.
+    ///
+    /// Zero runs all batches. Requires TimescaleDB 2.19 or later, and is left
+    /// out the same way as
+    /// [`with_buckets_per_batch`](Self::with_buckets_per_batch).
+    pub fn with_max_batches_per_execution(mut self, batches: i32) -> Self {
+        self.max_batches_per_execution = Some(batches);
+        self
+    }
+
+    /// Build the statements that create the continuous aggregate.
+    ///
+    /// The first statement creates the materialized view; a second statement
+    /// adding the refresh policy follows if a refresh interval is configured.
+    /// Batching options are left out, as not every TimescaleDB version
+    /// accepts them; use [`create_sql_for`](Self::create_sql_for) to render
+    /// them where supported.
+    pub fn create_sql(&self) -> Result<Vec<SqlStatement>, ValidationError> {
+        self.create_sql_with(false)
+    }
+
+    /// Build the statements that create the continuous aggregate on the
+    /// given TimescaleDB version.
+    ///
+    /// Batching options are rendered when `version` supports them and left
+    /// out otherwise.
+    pub fn create_sql_for(
+        &self,
+        version: TimescaleVersion,
+    ) -> Result<Vec<SqlStatement>, ValidationError> {
+        let (major, minor) = REFRESH_BATCHING_VERSION;
+        self.create_sql_with(version.at_least(major, minor))
+    }
+
+    fn create_sql_with(&self, batching: bool) -> Result<Vec<SqlStatement>, ValidationError> {
+        // Validate the view name
+        let view_identifier = SqlIdentifier::new(&self.view_name)?;
+
+        if let Some(time_column) = &self.time_column {
+            let _time_column_identifier = SqlIdentifier::column(time_column)?;
+            if !time_bucket_columns(&self.query)
+                .iter()
+                .any(|column| column == time_column)
+            {
+                return Err(ValidationError::InvalidParameter(format!(
+                    "Continuous aggregate query does not bucket the time column '{}'",
+                    time_column
+                )));
+            }
+        }
+
+        // Note: We cannot parameterize the view name or query in CREATE MATERIALIZED VIEW
+        // because PostgreSQL doesn't support it. However, we validate the view name.
+        // The query parameter should be validated by the caller.
+        let mut options = vec!["timescaledb.continuous".to_string()];
+        if let Some(create_group_indexes) = self.create_group_indexes {
+            options.push(format!(
+                "timescaledb.create_group_indexes = {}",
+                create_group_indexes
+            ));
+        }
+        if let Some(materialized_only) = self.materialized_only {
+            options.push(format!(
+                "timescaledb.materialized_only = {}",
+                materialized_only
+            ));
+        }
+
+        let mut statements = vec![SqlStatement::new(format!(
+            "CREATE MATERIALIZED VIEW {} WITH ({}) AS {};",
+            view_identifier.escaped(),
+            options.join(", "),
+            self.query
+        ))];
+
+        // Add refresh policy if specified
+        if let (Some(interval_str), lag_opt) = (&self.refresh_interval, &self.refresh_lag) {
+            let interval = TimeInterval::from_string(interval_str)?;
+
+            let mut refresh_sql = format!(
+                ", start_offset => NULL, end_offset => INTERVAL '{}'",
+                interval.to_postgres_interval()
+            );
+
+            if let Some(lag_str) = lag_opt {
+                let lag = TimeInterval::from_string(lag_str)?;
+                refresh_sql.push_str(&format!(
+                    ", schedule_interval => INTERVAL '{}'",
+                    lag.to_postgres_interval()
+                ));
+            }
+
+            for (name, value) in [
+                ("buckets_per_batch", self.buckets_per_batch),
+                ("max_batches_per_execution", self.max_batches_per_execution),
+            ] {
+                if let Some(value) = value {
+                    if value < 0 {
+                        return Err(ValidationError::InvalidParameter(format!(
+                            "{} must not be negative, got {}",
+                            name, value
+                        )));
+                    }
+                    if batching {
+                        refresh_sql.push_str(&format!(", {} => {}", name, value));
+                    }
+                }
+            }
+
+            refresh_sql.push_str(");");
+            // The name is cast to regclass, which folds unquoted names to
+            // lower case, so bind the quoted form created above
+            statements.push(
+                SqlStatement::new("SELECT add_continuous_aggregate_policy(")
+                    .bind(view_identifier.escaped())
+                    .sql(refresh_sql),
+            );
+        }
+
+        Ok(statements)
+    }
+
+    /// Create the continuous aggregate.
+    ///
+    /// The statements are built with [`create_sql_for`](Self::create_sql_for)
+    /// for the installed TimescaleDB version, so refresh batching options are
+    /// left out when it does not support them, with a warning logged if the
+    /// `log` feature is enabled.
+    pub fn create(&self, conn: &mut PgConnection) -> Result<(), TimescaleError> {
+        if self.distributed {
+            check_multinode_supported(conn)?;
+        }
+
+        let version = installed_version(conn)?;
+        let (major, minor) = REFRESH_BATCHING_VERSION;
+        if (self.buckets_per_batch.is_some() || self.max_batches_per_execution.is_some())
+            && self.refresh_interval.is_some()
+            && !version.at_least(major, minor)
+        {
+            crate::log_warning!(
+                "Ignoring refresh batching options of {}: they require TimescaleDB {}.{} or later (installed: {})",
+                self.view_name,
+                major,
+                minor,
+                version
+            );
+        }
+
+        let statements = self.create_sql_for(version)?;
+
+        let realtime_view = match &self.realtime_view_bucket_column {
+            Some(_) => self.realtime_view_sql(version)?,
+            None => None,
+        };
+
+        for statement in statements.into_iter().chain(realtime_view) {
+            statement.execute(conn)?;
+        }
+
+        Ok(())
+    }
+
+    /// Check whether the live continuous aggregate of this name is defined by
+    /// this configuration's query, for "create only if changed" migrations.
+    ///
+    /// PostgreSQL stores a rewritten definition rather than the text a view
+    /// was created with, so the configured query is rewritten the same way
+    /// first, by creating and dropping a temporary view of it in a
+    /// transaction. Both definitions are then compared with whitespace
+    /// collapsed. Returns `false` if no continuous aggregate of this name
+    /// exists.
+    ///
+    /// Only the query is compared, not the refresh policy or the view
+    /// options. Names are qualified in the deparsed text only where they are
+    /// not visible on the `search_path`, so the comparison should run with the
+    /// same `search_path` the aggregate was created with. Queries that
+    /// TimescaleDB rewrites on creation, such as those of aggregates in the
+    /// partial form, never match.
+    pub fn matches_live(&self, conn: &mut PgConnection) -> Result<bool, TimescaleError> {
+        let live = match continuous_aggregate_definition(conn, &self.view_name) {
+            Ok(definition) => definition,
+            Err(TimescaleError::Database(diesel::result::Error::NotFound)) => return Ok(false),
+            Err(e) => return Err(e),
+        };
+
+        let query = self.query.trim().trim_end_matches(';');
+        let desired = conn.transaction(|conn| {
+            diesel::sql_query(format!(
+                "CREATE TEMPORARY VIEW diesel_timescaledb_desired_definition AS {}",
+                query
+            ))
+            .execute(conn)?;
+            let row = diesel::sql_query(
+                "SELECT pg_get_viewdef('diesel_timescaledb_desired_definition'::regclass) AS view_definition",
+            )
+            .get_result::<ViewDefinition>(conn)?;
+            diesel::sql_query("DROP VIEW diesel_timescaledb_desired_definition").execute(conn)?;
+            Ok::<_, diesel::result::Error>(row.view_definition)
+        })?;
+
+        Ok(normalize_whitespace(&live) == normalize_whitespace(&desired))
+    }
+
+    /// Build the statement that refreshes the aggregate over `[start, end)`.
+    ///
+    /// A missing `start` refreshes from the oldest data, a missing `end` up
+    /// to the newest.
+    pub fn refresh_sql(
+        &self,
+        start: Option<chrono::DateTime<chrono::Utc>>,
+        end: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<SqlStatement, ValidationError> {
+        refresh_statement(&self.view_name, start, end, false)
+    }
+
+    /// Build the statement that refreshes the aggregate over `[start, end)`
+    /// with `force`, refreshing buckets that are already materialized.
+    ///
+    /// The argument is rendered regardless of the TimescaleDB version, see
+    /// [`refresh_forced`](Self::refresh_forced).
+    pub fn refresh_forced_sql(
+        &self,
+        start: Option<chrono::DateTime<chrono::Utc>>,
+        end: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<SqlStatement, ValidationError> {
+        refresh_statement(&self.view_name, start, end, true)
+    }
+
+    /// Refresh the aggregate over `[start, end)`.
+    ///
+    /// `refresh_continuous_aggregate` commits its own transaction, so this
+    /// must not be called inside a transaction. Use
+    /// [`refresh_continuous_aggregate_windowed`] to refresh a long range in
+    /// smaller steps.
+    pub fn refresh(
+        &self,
+        conn: &mut PgConnection,
+        start: Option<chrono::DateTime<chrono::Utc>>,
+        end: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<(), TimescaleError> {
+        self.refresh_sql(start, end)?.execute(conn)?;
+        Ok(())
+    }
+
+    /// Refresh the aggregate over `[start, end)`, including buckets that are
+    /// already materialized, as needed after backfilling data into them.
+    ///
+    /// `force` requires TimescaleDB 2.17 or later; on older versions it is
+    /// left out with a warning logged, and this is a plain
+    /// [`refresh`](Self::refresh).
+    pub fn refresh_forced(
+        &self,
+        conn: &mut PgConnection,
+        start: Option<chrono::DateTime<chrono::Utc>>,
+        end: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<(), TimescaleError> {
+        let force = refresh_force_supported(conn, &self.view_name, true)?;
+        refresh_statement(&self.view_name, start, end, force)?.execute(conn)?;
+        Ok(())
+    }
+
+    /// Build the statements that drop the aggregate.
+    ///
+    /// The companion real-time view, if configured, is dropped first since
+    /// it depends on the aggregate. Dropping the aggregate removes its
+    /// refresh policy and materialized data.
+    pub fn drop_sql(&self) -> Result<Vec<SqlStatement>, ValidationError> {
+        // Validate the view names
+        let view_identifier = SqlIdentifier::new(&self.view_name)?;
+
+        let mut statements = Vec::new();
+        if let Some(realtime_view_name) = self.realtime_view_name() {
+            let realtime_identifier = SqlIdentifier::new(&realtime_view_name)?;
+            statements.push(SqlStatement::new(format!(
+                "DROP VIEW IF EXISTS {};",
+                realtime_identifier.escaped()
+            )));
+        }
+        statements.push(SqlStatement::new(format!(
+            "DROP MATERIALIZED VIEW {};",
+            view_identifier.escaped()
+        )));
+
+        Ok(statements)
+    }
+
+    /// Drop the aggregate and its companion real-time view, if configured.
+    pub fn drop(&self, conn: &mut PgConnection) -> Result<(), TimescaleError> {
+        for statement in self.drop_sql()? {
+            statement.execute(conn)?;
+        }
+        Ok(())
+    }
+}
+
+/// Collapse runs of whitespace to single spaces and drop a trailing `;`.
+fn normalize_whitespace(sql: &str) -> String {
+    sql.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches(';')
+        .trim_end()
+        .to_string()
+}
+
+/// An aggregate computed per bucket.
+///
+/// Used by [`RollupSpec`], [`PivotQuery`], [`MultiMetric`] and
+/// [`BucketGapfill`], and re-exported as
+/// [`dsl::patterns::RollupAggregate`](crate::dsl::patterns::RollupAggregate)
+/// for the bucket spine and join queries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RollupAggregate {
+    Avg,
+    Sum,
+    Min,
+    Max,
+    Count,
+}
+
+impl RollupAggregate {
+    pub(crate) fn function_name(&self) -> &'static str {
+        match self {
+            RollupAggregate::Avg => "avg",
+            RollupAggregate::Sum => "sum",
+            RollupAggregate::Min => "min",
+            RollupAggregate::Max => "max",
+            RollupAggregate::Count => "count",
+        }
+    }
+}
+
+/// A structured description of a rollup continuous aggregate.
+///
+/// This is the safe alternative to a hand-written aggregate query: every name
+/// is validated and the query is generated, bucketing `time_column` into a
+/// `bucket` column and computing each aggregate of `value_column` into a
+/// column named like `avg_value`:
+///
+/// ```rust,ignore
+/// let config = RollupSpec {
+///     source: "metrics".to_string(),
+///     time_column: "ts".to_string(),
+///     value_column: "value".to_string(),
+///     bucket: 1.hours(),
+///     aggregates: vec![RollupAggregate::Avg, RollupAggregate::Max, RollupAggregate::Count],
+/// }
+/// .to_continuous_aggregate_config("metrics_hourly")?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct RollupSpec {
+    pub source: String,
+    pub time_column: String,
+    pub value_column: String,
+    pub bucket: TimeInterval,
+    pub aggregates: Vec<RollupAggregate>,
+}
+
+impl RollupSpec {
+    /// Build the continuous aggregate configuration for this rollup.
+    ///
+    /// The configuration checks the bucketed time column, and refresh
+    /// settings can be added with its builder methods.
+    pub fn to_continuous_aggregate_config(
+        &self,
+        view_name: &str,
+    ) -> Result<ContinuousAggregateConfig, ValidationError> {
+        let _view_identifier = SqlIdentifier::new(view_name)?;
+        let source_identifier = SqlIdentifier::new(&self.source)?;
+        let time_identifier = SqlIdentifier::column(&self.time_column)?;
+        let value_identifier = SqlIdentifier::column(&self.value_column)?;
+
+        self.bucket.require_positive("Bucket interval")?;
+        if self.aggregates.is_empty() {
+            return Err(ValidationError::InvalidParameter(
+                "Rollup needs at least one aggregate".to_string(),
+            ));
+        }
+
+        let mut aggregates: Vec<String> = Vec::new();
+        for aggregate in &self.aggregates {
+            let column = format!(
+                "{}({}) AS {}",
+                aggregate.function_name(),
+                value_identifier.escaped(),
+                SqlIdentifier::column(&format!(
+                    "{}_{}",
+                    aggregate.function_name(),
+                    self.value_column
+                ))?
+                .escaped()
+            );
+            if !aggregates.contains(&column) {
+                aggregates.push(column);
+            }
+        }
+
+        let query = format!(
+            "SELECT time_bucket(INTERVAL '{}', {}) AS bucket, {} 
+         FROM {} 
+         GROUP BY bucket",
+            self.bucket.to_postgres_interval(),
+            time_identifier.escaped(),
+            aggregates.join(", "),
+            source_identifier.escaped()
+        );
+
+        Ok(ContinuousAggregateConfig::new(view_name, query).with_time_column(&self.time_column))
+    }
+}
+
+/// Information about a continuous aggregate.
+#[derive(Debug, Clone, QueryableByName)]
+pub struct ContinuousAggregateInfo {
+    #[diesel(sql_type = Text)]
+    pub view_name: String,
+    #[diesel(sql_type = Text)]
+    pub hypertable_name: String,
+    /// Whether queries only read materialized data (real-time aggregation off).
+    #[diesel(sql_type = Bool)]
+    pub materialized_only: bool,
+    #[diesel(sql_type = Bool)]
+    pub compression_enabled: bool,
+    /// Whether the aggregate uses the finalized form introduced in TimescaleDB 2.7.
+    #[diesel(sql_type = Bool)]
+    pub finalized: bool,
+}
+
+/// List all continuous aggregates in the database.
+pub fn list_continuous_aggregates(
+    conn: &mut PgConnection,
+) -> QueryResult<Vec<ContinuousAggregateInfo>> {
+    diesel::sql_query(
+        "SELECT view_name, hypertable_name, materialized_only, compression_enabled, finalized 
+         FROM timescaledb_information.continuous_aggregates 
+         ORDER BY view_name",
+    )
+    .load::<ContinuousAggregateInfo>(conn)
+}
+
+#[derive(QueryableByName)]
+struct ViewDefinition {
+    #[diesel(sql_type = Text)]
+    view_definition: String,
+}
+
+/// Get the defining query of a continuous aggregate.
+///
+/// The query is returned as PostgreSQL deparses it, so it is normalized
+/// (qualified, reformatted) rather than the exact text it was created with.
+/// Returns `NotFound` if the view does not exist or is not a continuous
+/// aggregate.
+pub fn continuous_aggregate_definition(
+    conn: &mut PgConnection,
+    view_name: &str,
+) -> Result<String, TimescaleError> {
+    // Validate the view name
+    let view_identifier = SqlIdentifier::new(view_name)?;
+
+    // Bind the quoted name so the regclass cast keeps its case
+    let row = diesel::sql_query(
+        "SELECT view_definition 
+         FROM timescaledb_information.continuous_aggregates 
+         WHERE format('%I.%I', view_schema, view_name)::regclass = to_regclass($1)",
+    )
+    .bind::<Text, _>(view_identifier.escaped())
+    .get_result::<ViewDefinition>(conn)?;
+
+    Ok(row.view_definition)
+}
+
+#[derive(QueryableByName)]
+struct Finalized {
+    #[diesel(sql_type = Bool)]
+    finalized: bool,
+}
+
+/// Build the statement that migrates a continuous aggregate to the finalized form.
+pub fn finalize_continuous_aggregate_sql(view_name: &str) -> Result<SqlStatement, ValidationError> {
+    // Validate the view name
+    let view_identifier = SqlIdentifier::new(view_name)?;
+
+    // Bind the quoted name so the regclass cast keeps its case
+    Ok(SqlStatement::new("CALL cagg_migrate(")
+        .bind(view_identifier.escaped())
+        .sql("::regclass, override => true);"))
+}
+
+/// Migrate a continuous aggregate from the partial form to the finalized form.
+///
+/// Continuous aggregates created before TimescaleDB 2.7 store partial aggregate
+/// states, which prevents some query features and is no longer the default.
+/// This uses `cagg_migrate`, available from TimescaleDB 2.8, which creates a
+/// finalized copy of the aggregate, copies its policies, and swaps it in under
+/// the original name. The old aggregate is kept as `<view_name>_old` so it can
+/// be inspected before being dropped.
+///
+/// The migration commits internally, so it must not be called inside a
+/// transaction. It refreshes the new aggregate from the source hypertable,
+/// which can take a long time for large aggregates.
+///
+/// Returns `false` without doing anything if the aggregate is already finalized.
+pub fn finalize_continuous_aggregate(
+    conn: &mut PgConnection,
+    view_name: &str,
+) -> Result<bool, TimescaleError> {
+    let statement = finalize_continuous_aggregate_sql(view_name)?;
+
+    let version = installed_version(conn)?;
+    if !version.at_least(2, 8) {
+        return Err(TimescaleError::UnsupportedVersion {
+            feature: "Migrating continuous aggregates".to_string(),
+            required: TimescaleVersion::new(2, 8, 0),
+            removed: None,
+            installed: version,
+        });
+    }
+
+    // Bind the quoted name so the regclass cast keeps its case
+    let aggregate = diesel::sql_query(
+        "SELECT finalized 
+         FROM timescaledb_information.continuous_aggregates 
+         WHERE format('%I.%I', view_schema, view_name)::regclass = to_regclass($1)",
+    )
+    .bind::<Text, _>(SqlIdentifier::new(view_name)?.escaped())
+    .get_result::<Finalized>(conn)?;
+
+    if aggregate.finalized {
+        return Ok(false);
+    }
+
+    statement.execute(conn)?;
+    Ok(true)
+}
+
+/// Build the statement that turns real-time aggregation on or off for a
+/// continuous aggregate.
+pub fn set_continuous_aggregate_realtime_sql(
+    view_name: &str,
+    enabled: bool,
+) -> Result<SqlStatement, ValidationError> {
+    let view_identifier = SqlIdentifier::new(view_name)?;
+
+    Ok(SqlStatement::new(format!(
+        "ALTER MATERIALIZED VIEW {} SET (timescaledb.materialized_only = {});",
+        view_identifier.escaped(),
+        !enabled
+    )))
+}
+
+/// Turn real-time aggregation on or off for a continuous aggregate.
+///
+/// With real-time aggregation enabled, queries combine the materialized data
+/// with the not yet materialized rows of the source hypertable. This is a
+/// property of the view and applies to every query against it; use
+/// [`query_materialized_only`] to read only materialized data in a single query.
+pub fn set_continuous_aggregate_realtime(
+    conn: &mut PgConnection,
+    view_name: &str,
+    enabled: bool,
+) -> Result<(), TimescaleError> {
+    set_continuous_aggregate_realtime_sql(view_name, enabled)?.execute(conn)?;
+    Ok(())
+}
+
+/// Build the statement that adds a refresh policy to a continuous aggregate.
+///
+/// A missing `start_offset` refreshes from the oldest data, a missing
+/// `end_offset` up to the newest.
+pub fn continuous_aggregate_policy_sql(
+    view_name: &str,
+    start_offset: Option<TimeInterval>,
+    end_offset: Option<TimeInterval>,
+    schedule_interval: TimeInterval,
+) -> Result<SqlStatement, ValidationError> {
+    // Validate the view name
+    let view_identifier = SqlIdentifier::new(view_name)?;
+
+    let offset = |statement: SqlStatement, offset: Option<TimeInterval>| match offset {
+        Some(offset) => statement.bind(offset),
+        None => statement.sql("NULL"),
+    };
+
+    // Bind the quoted name so the regclass cast keeps its case
+    let statement = SqlStatement::new("SELECT add_continuous_aggregate_policy(")
+        .bind(view_identifier.escaped())
+        .sql(", start_offset => ");
+    let statement = offset(statement, start_offset).sql(", end_offset => ");
+    Ok(offset(statement, end_offset)
+        .sql(", schedule_interval => ")
+        .bind(schedule_interval)
+        .sql(");"))
+}
+
+/// Build the statement that removes the refresh policy of a continuous
+/// aggregate, if it has one.
+pub fn remove_continuous_aggregate_policy_sql(
+    view_name: &str,
+) -> Result<SqlStatement, ValidationError> {
+    // Validate the view name
+    let view_identifier = SqlIdentifier::new(view_name)?;
+
+    // Bind the quoted name so the regclass cast keeps its case
+    Ok(
+        SqlStatement::new("SELECT remove_continuous_aggregate_policy(")
+            .bind(view_identifier.escaped())
+            .sql(", if_exists => true);"),
+    )
+}
+
+#[derive(QueryableByName)]
+struct JobId {
+    #[diesel(sql_type = Integer)]
+    job_id: i32,
+}
+
+/// Add a refresh policy to a continuous aggregate, handling an existing one.
+///
+/// Two refresh policies on the same aggregate refresh overlapping ranges and
+/// conflict with each other, so an existing policy is looked up in
+/// `timescaledb_information.jobs` first. With `replace` it is removed and the
+/// new policy added in the same transaction; without it a
+/// [`ValidationError::InvalidParameter`] is returned and nothing is changed.
+pub fn add_or_replace_continuous_aggregate_policy(
+    conn: &mut PgConnection,
+    view_name: &str,
+    start_offset: Option<TimeInterval>,
+    end_offset: Option<TimeInterval>,
+    schedule_interval: TimeInterval,
+    replace: bool,
+) -> Result<(), TimescaleError> {
+    let statement =
+        continuous_aggregate_policy_sql(view_name, start_offset, end_offset, schedule_interval)?;
+    let view_identifier = SqlIdentifier::new(view_name)?;
+
+    conn.transaction(|conn| {
+        let existing = diesel::sql_query(
+            "SELECT j.job_id 
+             FROM timescaledb_information.jobs j 
+             JOIN timescaledb_information.continuous_aggregates ca 
+               ON j.hypertable_schema = ca.materialization_hypertable_schema 
+              AND j.hypertable_name = ca.materialization_hypertable_name 
+             WHERE j.proc_name = 'policy_refresh_continuous_aggregate' 
+               AND format('%I.%I', ca.view_schema, ca.view_name)::regclass = $1::regclass",
+        )
+        .bind::<Text, _>(view_identifier.escaped())
+        .load::<JobId>(conn)?;
+
+        if let Some(job) = existing.first() {
+            if !replace {
+                return Err(TimescaleError::Validation(
+                    ValidationError::InvalidParameter(format!(
+                        "Continuous aggregate {} already has a refresh policy (job {})",
+                        view_name, job.job_id
+                    )),
+                ));
+            }
+            for job in &existing {
+                diesel::sql_query("SELECT delete_job($1)")
+                    .bind::<Integer, _>(job.job_id)
+                    .execute(conn)?;
+            }
+        }
+
+        statement.execute(conn)?;
+        Ok(())
+    })
+}
+
+/// First TimescaleDB version whose `refresh_continuous_aggregate` accepts `force`.
+const REFRESH_FORCE_VERSION: (u32, u32) = (2, 17);
+
+/// Check whether a forced refresh of `view_name` can be requested, logging a
+/// warning if `force` is set but not supported by the installed TimescaleDB.
+fn refresh_force_supported(
+    conn: &mut PgConnection,
+    view_name: &str,
+    force: bool,
+) -> Result<bool, TimescaleError> {
+    if !force {
+        return Ok(false);
+    }
+
+    let version = installed_version(conn)?;
+    let (major, minor) = REFRESH_FORCE_VERSION;
+    if !version.at_least(major, minor) {
+        crate::log_warning!(
+            "Refreshing {} without force: it requires TimescaleDB {}.{} or later (installed: {})",
+            view_name,
+            major,
+            minor,
+            version
+        );
+        return Ok(false);
+    }
+
+    Ok(true)
+}
+
+/// Build a `refresh_continuous_aggregate` call, with `NULL` for missing bounds.
+fn refresh_statement(
+    view_name: &str,
+    start: Option<chrono::DateTime<chrono::Utc>>,
+    end: Option<chrono::DateTime<chrono::Utc>>,
+    force: bool,
+) -> Result<SqlStatement, ValidationError> {
+    // Validate the view name
+    let view_identifier = SqlIdentifier::new(view_name)?;
+
+    if let (Some(start), Some(end)) = (start, end) {
+        crate::dsl::TimeRange::new(start, end)?;
+    }
+
+    let bound = |statement: SqlStatement, bound: Option<chrono::DateTime<chrono::Utc>>| match bound
+    {
+        Some(bound) => statement.bind(bound),
+        None => statement.sql("NULL"),
+    };
+
+    // Bind the quoted name so the regclass cast keeps its case
+    let statement = SqlStatement::new("CALL refresh_continuous_aggregate(")
+        .bind(view_identifier.escaped())
+        .sql(", ");
+    let statement = bound(statement, start).sql(", ");
+    let statement = bound(statement, end);
+    Ok(if force {
+        statement.sql(", force => true);")
+    } else {
+        statement.sql(");")
+    })
+}
+
+/// Build the statement that refreshes a continuous aggregate over `[start, end)`.
+pub fn refresh_continuous_aggregate_sql(
+    view_name: &str,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+) -> Result<SqlStatement, ValidationError> {
+    refresh_statement(view_name, Some(start), Some(end), false)
+}
+
+/// Build the statement that refreshes a continuous aggregate over
+/// `[start, end)` with `force`.
+///
+/// The argument is rendered regardless of the TimescaleDB version, see
+/// [`ContinuousAggregateConfig::refresh_forced`].
+pub fn refresh_continuous_aggregate_forced_sql(
+    view_name: &str,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+) -> Result<SqlStatement, ValidationError> {
+    refresh_statement(view_name, Some(start), Some(end), true)
+}
+
+/// Progress of a windowed continuous aggregate refresh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RefreshProgress {
+    /// Start of the window that was just refreshed.
+    pub window_start: chrono::DateTime<chrono::Utc>,
+    /// Exclusive end of the window that was just refreshed.
+    pub window_end: chrono::DateTime<chrono::Utc>,
+    /// Number of windows refreshed so far, including this one.
+    pub completed_windows: usize,
+    pub total_windows: usize,
+}
+
+/// Refresh a continuous aggregate over `[start, end)` in consecutive windows.
+///
+/// `progress` is called after each window. `refresh_continuous_aggregate`
+/// is a procedure that commits its own transaction, so every window is
+/// materialized and committed independently: a failure leaves the windows
+/// before it refreshed, and the refresh can be resumed from the failed
+/// window. For the same reason this must not be called inside a transaction.
+///
+/// The window must be a positive interval of weeks or smaller. TimescaleDB
+/// only materializes buckets that fit entirely inside a refresh window, so the
+/// window should be a multiple of the aggregate's bucket width for the
+/// windows to cover every bucket.
+pub fn refresh_continuous_aggregate_windowed<F>(
+    conn: &mut PgConnection,
+    view_name: &str,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+    window: TimeInterval,
+    progress: F,
+) -> Result<(), TimescaleError>
+where
+    F: FnMut(RefreshProgress),
+{
+    refresh_windowed(conn, view_name, start, end, window, false, progress)
+}
+
+/// Refresh a continuous aggregate over `[start, end)` in consecutive windows,
+/// including windows that are already materialized.
+///
+/// This is [`refresh_continuous_aggregate_windowed`] with `force`, as in
+/// [`ContinuousAggregateConfig::refresh_forced`].
+pub fn refresh_continuous_aggregate_windowed_forced<F>(
+    conn: &mut PgConnection,
+    view_name: &str,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+    window: TimeInterval,
+    progress: F,
+) -> Result<(), TimescaleError>
+where
+    F: FnMut(RefreshProgress),
+{
+    refresh_windowed(conn, view_name, start, end, window, true, progress)
+}
+
+fn refresh_windowed<F>(
+    conn: &mut PgConnection,
+    view_name: &str,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+    window: TimeInterval,
+    force: bool,
+    mut progress: F,
+) -> Result<(), TimescaleError>
+where
+    F: FnMut(RefreshProgress),
+{
+    let _view_identifier = SqlIdentifier::new(view_name)?;
+    let range = crate::dsl::TimeRange::new(start, end)?;
+    let force = refresh_force_supported(conn, view_name, force)?;
+    let window_micros = window.bucket_width_micros()?;
+    let window = chrono::Duration::microseconds(window_micros);
+
+    let span_micros = (range.end() - range.start())
+        .num_microseconds()
+        .ok_or_else(|| {
+            ValidationError::InvalidParameter("Refresh range is too large".to_string())
+        })?;
+    let total_windows = usize::try_from((span_micros + window_micros - 1) / window_micros)
+        .map_err(|_| ValidationError::InvalidParameter("Refresh range is too large".to_string()))?;
+
+    let mut window_start = range.start();
+    let mut completed_windows = 0;
+    while window_start < range.end() {
+        let window_end = (window_start + window).min(range.end());
+        refresh_statement(view_name, Some(window_start), Some(window_end), force)?.execute(conn)?;
+
+        completed_windows += 1;
+        progress(RefreshProgress {
+            window_start,
+            window_end,
+            completed_windows,
+            total_windows,
+        });
+        window_start = window_end;
+    }
+
+    Ok(())
+}
+
+/// Query a continuous aggregate, reading only its materialized data.
+///
+/// The rows are filtered to buckets below the aggregate's materialization
+/// watermark, which excludes the real-time portion regardless of the view's
+/// `materialized_only` setting. Toggling the setting per query instead would
+/// take an exclusive lock on the view.
+///
+/// `bucket_column` is the `time_bucket` column of the aggregate, which must be
+/// a timestamp. Rows are loaded by column name into `U`.
+pub fn query_materialized_only<U>(
+    conn: &mut PgConnection,
+    view_name: &str,
+    bucket_column: &str,
+) -> Result<Vec<U>, TimescaleError>
+where
+    U: QueryableByName<diesel::pg::Pg> + 'static,
+{
+    // Validate identifiers
+    let view_identifier = SqlIdentifier::new(view_name)?;
+    let bucket_identifier = SqlIdentifier::column(bucket_column)?;
+
+    // The watermark functions moved out of the internal schema in 2.12
+    let functions_schema = if installed_version(conn)?.at_least(2, 12) {
+        "_timescaledb_functions"
+    } else {
+        "_timescaledb_internal"
+    };
+
+    // Bind the quoted name so the regclass cast keeps its case
+    SqlStatement::new(format!(
+        "SELECT * FROM {view} WHERE {bucket} < {schema}.to_timestamp({schema}.cagg_watermark(\
+         (SELECT mat_hypertable_id FROM _timescaledb_catalog.continuous_agg \
+         WHERE format('%I.%I', user_view_schema, user_view_name)::regclass = to_regclass(",
+        view = view_identifier.escaped(),
+        bucket = bucket_identifier.escaped(),
+        schema = functions_schema
+    ))
+    .bind(view_identifier.escaped())
+    .sql("))))")
+    .load(conn)
+    .map_err(TimescaleError::from)
+}
+
+#[derive(QueryableByName)]
+struct InvalidationCount {
+    #[diesel(sql_type = BigInt)]
+    entries: i64,
+}
+
+/// Count the pending invalidation log entries of a continuous aggregate.
+///
+/// Writes to the source hypertable below the aggregate's watermark are
+/// logged as invalidated ranges, first in a log per hypertable and then,
+/// once a refresh moves them, in a log per aggregate. Every refresh has to
+/// process them, so a growing count means refreshes fall behind the writes
+/// to old data. The count includes the entries of the source hypertable's
+/// log, which are shared by every aggregate on it.
+///
+/// This reads TimescaleDB's internal `_timescaledb_catalog` tables, whose
+/// layout is not a public interface and may change between versions.
+/// Returns `NotFound` if the view is not a continuous aggregate.
+pub fn continuous_aggregate_invalidation_size(
+    conn: &mut PgConnection,
+    view_name: &str,
+) -> Result<i64, TimescaleError> {
+    // Validate the view name
+    let view_identifier = SqlIdentifier::new(view_name)?;
+
+    // Bind the quoted name so the regclass cast keeps its case
+    let count = diesel::sql_query(
+        "SELECT (SELECT count(*) FROM _timescaledb_catalog.continuous_aggs_materialization_invalidation_log m 
+                 WHERE m.materialization_id = ca.mat_hypertable_id) 
+              + (SELECT count(*) FROM _timescaledb_catalog.continuous_aggs_hypertable_invalidation_log h 
+                 WHERE h.hypertable_id = ca.raw_hypertable_id) AS entries 
+         FROM _timescaledb_catalog.continuous_agg ca 
+         WHERE format('%I.%I', ca.user_view_schema, ca.user_view_name)::regclass = to_regclass($1)",
+    )
+    .bind::<Text, _>(view_identifier.escaped())
+    .get_result::<InvalidationCount>(conn)?;
+
+    Ok(count.entries)
+}
+
+/// Build the statement that counts the distinct values of a column.
+///
+/// See [`distinct_count`].
+pub fn distinct_count_sql(table_name: &str, column: &str) -> Result<SqlStatement, ValidationError> {
+    // Validate identifiers
+    let table_identifier = SqlIdentifier::new(table_name)?;
+    let column_identifier = SqlIdentifier::column(column)?;
+
+    Ok(SqlStatement::new(format!(
+        "SELECT count(*) AS distinct_count 
+         FROM (SELECT DISTINCT {column} FROM {table} WHERE {column} IS NOT NULL) AS distinct_values",
+        column = column_identifier.escaped(),
+        table = table_identifier.escaped()
+    )))
+}
+
+#[derive(QueryableByName)]
+struct DistinctCount {
+    #[diesel(sql_type = BigInt)]
+    distinct_count: i64,
+}
+
+/// Count the distinct values of a column of a hypertable.
+///
+/// `count(DISTINCT col)` always reads every row. The query is shaped as a
+/// count over `SELECT DISTINCT col` instead, which TimescaleDB can run with a
+/// SkipScan that jumps from one value to the next in an index. SkipScan
+/// requires a B-tree index whose leading column is `column`, such as the
+/// `(device_id, time DESC)` index of a hypertable partitioned by device, and
+/// pays off when the column has few distinct values compared to rows.
+/// Without such an index the query is still correct, just not faster. Like
+/// `count(DISTINCT col)`, `NULL` is not counted.
+pub fn distinct_count(
+    conn: &mut PgConnection,
+    table_name: &str,
+    column: &str,
+) -> Result<i64, TimescaleError> {
+    let row = distinct_count_sql(table_name, column)?
+        .load::<DistinctCount>(conn)?
+        .pop()
+        .ok_or(TimescaleError::Database(diesel::result::Error::NotFound))?;
+    Ok(row.distinct_count)
+}
+
+/// Build the statement that selects the latest row of each group.
+///
+/// See [`latest_per_group`].
+pub fn latest_per_group_sql(
+    table_name: &str,
+    group_column: &str,
+    time_column: &str,
+) -> Result<SqlStatement, ValidationError> {
+    // Validate identifiers
+    let table_identifier = SqlIdentifier::new(table_name)?;
+    let group_identifier = SqlIdentifier::column(group_column)?;
+    let time_identifier = SqlIdentifier::column(time_column)?;
+
+    Ok(SqlStatement::new(format!(
+        "SELECT DISTINCT ON ({group}) * FROM {table} ORDER BY {group}, {time} DESC",
+        group = group_identifier.escaped(),
+        table = table_identifier.escaped(),
+        time = time_identifier.escaped()
+    )))
+}
+
+/// Load the latest row of each group, such as the last reading per device.
+///
+/// This uses `SELECT DISTINCT ON (group) ... ORDER BY group, time DESC`, which
+/// Diesel cannot express directly. With an index on `(group_column,
+/// time_column DESC)`, TimescaleDB 2.2 and later can execute it with a
+/// SkipScan that jumps from group to group instead of reading every row.
+///
+/// Rows are loaded by column name into `U`.
+pub fn latest_per_group<U>(
+    conn: &mut PgConnection,
+    table_name: &str,
+    group_column: &str,
+    time_column: &str,
+) -> Result<Vec<U>, TimescaleError>
+where
+    U: QueryableByName<diesel::pg::Pg> + 'static,
+{
+    Ok(latest_per_group_sql(table_name, group_column, time_column)?.load(conn)?)
+}
+
+/// A row of a multi-granularity query, summarizing one bucket of one
+/// granularity.
+///
+/// See [`multi_granularity_sql`].
+#[derive(Debug, Clone, PartialEq, QueryableByName)]
+pub struct GranularityBucket {
+    /// The bucket width this row belongs to, as rendered by
+    /// [`TimeInterval::to_postgres_interval`], such as `1 hours`.
+    #[diesel(sql_type = Text)]
+    pub granularity: String,
+    #[diesel(sql_type = Timestamptz)]
+    pub bucket: chrono::DateTime<chrono::Utc>,
+    #[diesel(sql_type = Nullable<Double>)]
+    pub avg: Option<f64>,
+    #[diesel(sql_type = Nullable<Double>)]
+    pub min: Option<f64>,
+    #[diesel(sql_type = Nullable<Double>)]
+    pub max: Option<f64>,
+    #[diesel(sql_type = BigInt)]
+    pub count: i64,
+}
+
+impl GranularityBucket {
+    /// Parse the granularity of this row back into a [`TimeInterval`].
+    pub fn interval(&self) -> Result<TimeInterval, ValidationError> {
+        TimeInterval::from_string(&self.granularity)
+    }
+}
+
+/// Build the statement that summarizes a column at several bucket widths in
+/// one query, such as hourly and daily subtotals for a dashboard.
+///
+/// Each granularity is a grouping set of
+/// `GROUP BY GROUPING SETS ((time_bucket('1 hours', time)), (time_bucket('1 days', time)))`,
+/// and `GROUPING()` tells the rows of each set apart. Rows load into
+/// [`GranularityBucket`], ordered by granularity as given and then by
+/// bucket. `value_column` must be numeric; it is aggregated as
+/// `double precision`.
+///
+/// Granularities must be positive and distinct.
+pub fn multi_granularity_sql(
+    table_name: &str,
+    time_column: &str,
+    value_column: &str,
+    granularities: &[TimeInterval],
+) -> Result<SqlStatement, ValidationError> {
+    // Validate identifiers
+    let table_identifier = SqlIdentifier::new(table_name)?;
+    let time_identifier = SqlIdentifier::column(time_column)?;
+    let value_identifier = SqlIdentifier::column(value_column)?;
+
+    if granularities.is_empty() {
+        return Err(ValidationError::InvalidParameter(
+            "At least one granularity is required".to_string(),
+        ));
+    }
+    let mut intervals: Vec<String> = Vec::with_capacity(granularities.len());
+    for granularity in granularities {
+        granularity.require_positive("Granularity")?;
+        let interval = granularity.to_postgres_interval();
+        if intervals.contains(&interval) {
+            return Err(ValidationError::InvalidParameter(format!(
+                "Granularity '{}' is given more than once",
+                interval
+            )));
+        }
+        intervals.push(interval);
+    }
+
+    let buckets: Vec<String> = intervals
+        .iter()
+        .map(|interval| {
+            format!(
+                "time_bucket(INTERVAL '{}', {})",
+                interval,
+                time_identifier.escaped()
+            )
+        })
+        .collect();
+    let granularity_cases: String = buckets
+        .iter()
+        .zip(&intervals)
+        .map(|(bucket, interval)| format!(" WHEN GROUPING({}) = 0 THEN '{}'", bucket, interval))
+        .collect();
+    let order_cases: String = buckets
+        .iter()
+        .enumerate()
+        .map(|(index, bucket)| format!(" WHEN GROUPING({}) = 0 THEN {}", bucket, index))
+        .collect();
+    let grouping_sets = buckets
+        .iter()
+        .map(|bucket| format!("({})", bucket))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    Ok(SqlStatement::new(format!(
+        "SELECT CASE{granularity_cases} END AS granularity, 
+                COALESCE({buckets}) AS bucket, 
+                avg({value})::double precision AS avg, 
+                min({value})::double precision AS min, 
+                max({value})::double precision AS max, 
+                count(*) AS count 
+         FROM {table} 
+         GROUP BY GROUPING SETS ({grouping_sets}) 
+         ORDER BY CASE{order_cases} END, bucket",
+        buckets = buckets.join(", "),
+        value = value_identifier.escaped(),
+        table = table_identifier.escaped(),
+    )))
+}
+
+/// Summarize a column at several bucket widths in one query.
+///
+/// See [`multi_granularity_sql`].
+pub fn multi_granularity(
+    conn: &mut PgConnection,
+    table_name: &str,
+    time_column: &str,
+    value_column: &str,
+    granularities: &[TimeInterval],
+) -> Result<Vec<GranularityBucket>, TimescaleError> {
+    Ok(multi_granularity_sql(table_name, time_column, value_column, granularities)?.load(conn)?)
+}
+
+/// A bucketed aggregation with one column per group, for dashboards that
+/// chart every group as its own series.
+///
+/// Instead of a `(bucket, group, value)` row per group, every bucket is a
+/// single row with an aggregate per listed group value, computed with a
+/// `FILTER (WHERE group = value)` clause. The group values must be listed
+/// explicitly; they are bound as parameters and compared as text, so groups
+/// of any type can be pivoted. Each value becomes the name of its column
+/// unless another name is given:
+///
+/// ```rust,ignore
+/// let rows: Vec<DeviceAverages> = PivotQuery::new("metrics", "ts", "device_id", "value", 1.hours())
+///     .with_group("sensor_a")
+///     .with_group_as("sensor-b", "sensor_b")
+///     .load(&mut conn)?;
+/// ```
+///
+/// Rows are ordered by a `bucket` column, and groups without data in a
+/// bucket are `NULL` (or `0` for [`RollupAggregate::Count`]).
+#[derive(Debug, Clone)]
+pub struct PivotQuery {
+    pub table_name: String,
+    pub time_column: String,
+    pub group_column: String,
+    pub value_column: String,
+    pub bucket: TimeInterval,
+    /// The aggregate computed per group, [`RollupAggregate::Avg`] by default.
+    pub aggregate: RollupAggregate,
+    /// The group values and the names of their columns.
+    pub groups: Vec<(String, String)>,
+}
+
+impl PivotQuery {
+    /// Create a pivot query without groups.
+    pub fn new(
+        table_name: impl Into<String>,
+        time_column: impl Into<String>,
+        group_column: impl Into<String>,
+        value_column: impl Into<String>,
+        bucket: TimeInterval,
+    ) -> Self {
+        Self {
+            table_name: table_name.into(),
+            time_column: time_column.into(),
+            group_column: group_column.into(),
+            value_column: value_column.into(),
+            bucket,
+            aggregate: RollupAggregate::Avg,
+            groups: Vec::new(),
+        }
+    }
+
+    /// Set the aggregate computed per group.
+    pub fn with_aggregate(mut self, aggregate: RollupAggregate) -> Self {
+        self.aggregate = aggregate;
+        self
+    }
+
+    /// Add a group whose column is named after its value.
+    pub fn with_group(mut self, value: impl Into<String>) -> Self {
+        let value = value.into();
+        self.groups.push((value.clone(), value));
+        self
+    }
+
+    /// Add a group with an explicit column name, for values that are not
+    /// valid identifiers.
+    pub fn with_group_as(mut self, value: impl Into<String>, column: impl Into<String>) -> Self {
+        self.groups.push((value.into(), column.into()));
+        self
+    }
+
+    /// Build the pivot query.
+    pub fn query_sql(&self) -> Result<SqlStatement, ValidationError> {
+        // Validate identifiers
+        let table_identifier = SqlIdentifier::new(&self.table_name)?;
+        let time_identifier = SqlIdentifier::column(&self.time_column)?;
+        let group_identifier = SqlIdentifier::column(&self.group_column)?;
+        let value_identifier = SqlIdentifier::column(&self.value_column)?;
+
+        self.bucket.require_positive("Bucket interval")?;
+        if self.groups.is_empty() {
+            return Err(ValidationError::InvalidParameter(
+                "Pivot query needs at least one group value".to_string(),
+            ));
+        }
+
+        let column_identifiers = validate_aliases(
+            "pivot column",
+            self.groups.iter().map(|(_, column)| column.as_str()),
+        )?;
+        let mut statement = SqlStatement::new(format!(
+            "SELECT time_bucket(INTERVAL '{}', {}) AS bucket",
+            self.bucket.to_postgres_interval(),
+            time_identifier.escaped()
+        ));
+        for ((value, _), column_identifier) in self.groups.iter().zip(column_identifiers) {
+            // Compare as text so the bound value matches groups of any type
+            statement = statement
+                .sql(format!(
+                    ", {}({}) FILTER (WHERE {}::text = ",
+                    self.aggregate.function_name(),
+                    value_identifier.escaped(),
+                    group_identifier.escaped()
+                ))
+                .bind(value.as_str())
+                .sql(format!(") AS {}", column_identifier.escaped()));
+        }
+
+        Ok(statement.sql(format!(
+            " FROM {} GROUP BY bucket ORDER BY bucket",
+            table_identifier.escaped()
+        )))
+    }
+
+    /// Run the pivot query, loading the rows by column name into `U`.
+    pub fn load<U>(&self, conn: &mut PgConnection) -> Result<Vec<U>, TimescaleError>
+    where
+        U: QueryableByName<diesel::pg::Pg> + 'static,
+    {
+        Ok(self.query_sql()?.load(conn)?)
+    }
+}
+
+/// Several metrics of one table bucketed identically, computed in a single
+/// query instead of one query per metric.
+///
+/// Each metric is an aggregate of a column under an alias, and every bucket
+/// is one row with a column per alias. The values are cast to `float8`, so
+/// metrics of integer and numeric columns can be combined:
+///
+/// ```rust,ignore
+/// let buckets = MultiMetric::new("host_metrics", "ts", 5.minutes())
+///     .with_metric("cpu", "cpu_percent", RollupAggregate::Avg)
+///     .with_metric("mem", "mem_used", RollupAggregate::Max)
+///     .with_metric("samples", "cpu_percent", RollupAggregate::Count)
+///     .load_maps(&mut conn)?;
+/// let cpu = buckets[0].values.get("cpu");
+/// ```
+///
+/// Rows are ordered by a `bucket` column. [`query_sql`](Self::query_sql)
+/// selects the aliases as columns, for loading into a struct.
+#[derive(Debug, Clone)]
+pub struct MultiMetric {
+    pub table_name: String,
+    pub time_column: String,
+    pub bucket: TimeInterval,
+    /// The aliases, columns and aggregates of the metrics.
+    pub metrics: Vec<(String, String, RollupAggregate)>,
+}
+
+/// A bucket loaded by [`MultiMetric::load_maps`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricBucket {
+    pub bucket: chrono::DateTime<chrono::Utc>,
+    /// The value of each metric by alias; metrics without data in the
+    /// bucket are missing.
+    pub values: HashMap<String, f64>,
+}
+
+#[derive(QueryableByName)]
+struct MetricValues {
+    #[diesel(sql_type = Timestamptz)]
+    bucket: chrono::DateTime<chrono::Utc>,
+    #[diesel(sql_type = Array<Nullable<Double>>)]
+    metric_values: Vec<Option<f64>>,
+}
+
+impl MultiMetric {
+    /// Create a query without metrics.
+    pub fn new(
+        table_name: impl Into<String>,
+        time_column: impl Into<String>,
+        bucket: TimeInterval,
+    ) -> Self {
+        Self {
+            table_name: table_name.into(),
+            time_column: time_column.into(),
+            bucket,
+            metrics: Vec::new(),
+        }
+    }
+
+    /// Add a metric computing `aggregate` of `column` as `alias`.
+    pub fn with_metric(
+        mut self,
+        alias: impl Into<String>,
+        column: impl Into<String>,
+        aggregate: RollupAggregate,
+    ) -> Self {
+        self.metrics.push((alias.into(), column.into(), aggregate));
+        self
+    }
+
+    /// Build the query, with a column per metric.
+    pub fn query_sql(&self) -> Result<SqlStatement, ValidationError> {
+        let columns = self
+            .aggregates()?
+            .into_iter()
+            .map(|(alias, aggregate)| format!("{} AS {}", aggregate, alias.escaped()))
+            .collect::<Vec<_>>();
+        self.statement(&columns.join(", "))
+    }
+
+    /// Run the query and collect the metrics of every bucket by alias.
+    pub fn load_maps(&self, conn: &mut PgConnection) -> Result<Vec<MetricBucket>, TimescaleError> {
+        let aggregates = self
+            .aggregates()?
+            .into_iter()
+            .map(|(_, aggregate)| aggregate)
+            .collect::<Vec<_>>();
+        let rows: Vec<MetricValues> = self
+            .statement(&format!(
+                "ARRAY[{}] AS metric_values",
+                aggregates.join(", ")
+            ))?
+            .load(conn)?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| MetricBucket {
+                bucket: row.bucket,
+                values: self
+                    .metrics
+                    .iter()
+                    .zip(row.metric_values)
+                    .filter_map(|((alias, _, _), value)| Some((alias.clone(), value?)))
+                    .collect(),
+            })
+            .collect())
+    }
+
+    /// Run the query, loading the rows by column name into `U`.
+    pub fn load<U>(&self, conn: &mut PgConnection) -> Result<Vec<U>, TimescaleError>
+    where
+        U: QueryableByName<diesel::pg::Pg> + 'static,
+    {
+        Ok(self.query_sql()?.load(conn)?)
+    }
+
+    /// Validate the metrics and render their aliases and aggregate expressions.
+    fn aggregates(&self) -> Result<Vec<(SqlIdentifier, String)>, ValidationError> {
+        if self.metrics.is_empty() {
+            return Err(ValidationError::InvalidParameter(
+                "Multi-metric query needs at least one metric".to_string(),
+            ));
+        }
+
+        let alias_identifiers = validate_aliases(
+            "metric alias",
+            self.metrics.iter().map(|(alias, _, _)| alias.as_str()),
+        )?;
+        let mut aggregates = Vec::with_capacity(self.metrics.len());
+        for ((_, column, aggregate), alias_identifier) in self.metrics.iter().zip(alias_identifiers)
+        {
+            let column_identifier = SqlIdentifier::column(column)?;
+            aggregates.push((
+                alias_identifier,
+                format!(
+                    "{}({})::float8",
+                    aggregate.function_name(),
+                    column_identifier.escaped()
+                ),
+            ));
+        }
+        Ok(aggregates)
+    }
+
+    fn statement(&self, columns: &str) -> Result<SqlStatement, ValidationError> {
+        let table_identifier = SqlIdentifier::new(&self.table_name)?;
+        let time_identifier = SqlIdentifier::column(&self.time_column)?;
+
+        self.bucket.require_positive("Bucket interval")?;
+
+        Ok(SqlStatement::new(format!(
+            "SELECT time_bucket(INTERVAL '{}', {}) AS bucket, {} FROM {} GROUP BY bucket ORDER BY bucket",
+            self.bucket.to_postgres_interval(),
+            time_identifier.escaped(),
+            columns,
+            table_identifier.escaped()
+        )))
+    }
+}
+
+/// How a [`BucketGapfill`] fills the value of a bucket without rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GapfillFill {
+    /// Leave the value `NULL`.
+    #[default]
+    None,
+    /// Carry the last value before the bucket forward, with `locf`.
+    Locf,
+    /// Interpolate linearly between the values around the bucket, with
+    /// `interpolate`.
+    Interpolate,
+}
+
+/// A `time_bucket_gapfill` query over a single time range.
+///
+/// `time_bucket_gapfill` needs a start and finish, and they have to match the
+/// query's time predicate: a wider predicate adds rows outside the gapfilled
+/// buckets, and a narrower one leaves buckets empty that have data. Both are
+/// taken from the same [`TimeRange`](crate::dsl::TimeRange) here, so the
+/// buckets cover exactly the rows in `[start, end)`. Each value is an
+/// aggregate of a column under an alias, cast to `float8` and filled as
+/// configured:
+///
+/// ```rust,ignore
+/// let range = TimeRange::new(now - Duration::days(1), now)?;
+/// let rows: Vec<FilledReading> = BucketGapfill::new("metrics", "ts", 1.hours(), range)
+///     .with_value("temperature", "temperature", RollupAggregate::Avg, GapfillFill::Interpolate)
+///     .with_value("status", "status_code", RollupAggregate::Max, GapfillFill::Locf)
+///     .load(&mut conn)?;
+/// ```
+///
+/// Rows are ordered by a `bucket` column, one per bucket of the range.
+#[derive(Debug, Clone)]
+pub struct BucketGapfill {
+    pub table_name: String,
+    pub time_column: String,
+    pub bucket: TimeInterval,
+    pub range: crate::dsl::TimeRange,
+    /// The aliases, columns, aggregates and fills of the values.
+    pub values: Vec<(String, String, RollupAggregate, GapfillFill)>,
+}
+
+impl BucketGapfill {
+    /// Create a query without values.
+    pub fn new(
+        table_name: impl Into<String>,
+        time_column: impl Into<String>,
+        bucket: TimeInterval,
+        range: crate::dsl::TimeRange,
+    ) -> Self {
+        Self {
+            table_name: table_name.into(),
+            time_column: time_column.into(),
+            bucket,
+            range,
+            values: Vec::new(),
+        }
+    }
+
+    /// Add a value computing `aggregate` of `column` as `alias`, filled with
+    /// `fill` in buckets without rows.
+    pub fn with_value(
+        mut self,
+        alias: impl Into<String>,
+        column: impl Into<String>,
+        aggregate: RollupAggregate,
+        fill: GapfillFill,
+    ) -> Self {
+        self.values
+            .push((alias.into(), column.into(), aggregate, fill));
+        self
+    }
+
+    /// Build the query, with a column per value.
+    pub fn query_sql(&self) -> Result<SqlStatement, ValidationError> {
+        let table_identifier = SqlIdentifier::new(&self.table_name)?;
+        let time_identifier = SqlIdentifier::column(&self.time_column)?;
+
+        self.bucket.require_positive("Bucket interval")?;
+        if self.range.start() >= self.range.end() {
+            return Err(ValidationError::InvalidParameter(format!(
+                "Gapfill range start {} must be before its end {}",
+                self.range.start(),
+                self.range.end()
+            )));
+        }
+        if self.values.is_empty() {
+            return Err(ValidationError::InvalidParameter(
+                "Gapfill query needs at least one value".to_string(),
+            ));
+        }
+
+        let alias_identifiers = validate_aliases(
+            "value alias",
+            self.values.iter().map(|(alias, _, _, _)| alias.as_str()),
+        )?;
+        let mut columns = Vec::with_capacity(self.values.len());
+        for ((_, column, aggregate, fill), alias_identifier) in
+            self.values.iter().zip(alias_identifiers)
+        {
+            let column_identifier = SqlIdentifier::column(column)?;
+            let value = format!(
+                "{}({})::float8",
+                aggregate.function_name(),
+                column_identifier.escaped()
+            );
+            let value = match fill {
+                GapfillFill::None => value,
+                GapfillFill::Locf => format!("locf({})", value),
+                GapfillFill::Interpolate => format!("interpolate({})", value),
+            };
+            columns.push(format!("{} AS {}", value, alias_identifier.escaped()));
+        }
+
+        // The same bounds go into the gapfill call and the predicate, so the
+        // two cannot disagree
+        Ok(SqlStatement::new(format!(
+            "SELECT time_bucket_gapfill(INTERVAL '{}', {}, ",
+            self.bucket.to_postgres_interval(),
+            time_identifier.escaped()
+        ))
+        .bind(self.range.start())
+        .sql(", ")
+        .bind(self.range.end())
+        .sql(format!(
+            ") AS bucket, {} FROM {} WHERE {} >= ",
+            columns.join(", "),
+            table_identifier.escaped(),
+            time_identifier.escaped()
+        ))
+        .bind(self.range.start())
+        .sql(format!(" AND {} < ", time_identifier.escaped()))
+        .bind(self.range.end())
+        .sql(" GROUP BY bucket ORDER BY bucket"))
+    }
+
+    /// Run the query, loading the rows by column name into `U`.
+    pub fn load<U>(&self, conn: &mut PgConnection) -> Result<Vec<U>, TimescaleError>
+    where
+        U: QueryableByName<diesel::pg::Pg> + 'static,
+    {
+        Ok(self.query_sql()?.load(conn)?)
+    }
+}
+
+/// Maximum number of distinct values for a column to be suggested as a
+/// `compress_segmentby` column.
+pub const SEGMENTBY_MAX_DISTINCT: f32 = 1000.0;
+
+#[derive(QueryableByName)]
+struct ColumnDistinct {
+    #[diesel(sql_type = Text)]
+    attname: String,
+    #[diesel(sql_type = Float)]
+    n_distinct: f32,
+}
+
+/// Suggest `compress_segmentby` columns for a table from planner statistics.
+///
+/// Each candidate's cardinality is estimated from `n_distinct` in `pg_stats`,
+/// and the candidates with at most [`SEGMENTBY_MAX_DISTINCT`] distinct values
+/// are returned in the order given. Columns whose distinct count scales with
+/// the row count (negative `n_distinct`) are never suggested, and columns
+/// without statistics are skipped, so run `ANALYZE` on the table first.
+pub fn suggest_segmentby(
+    conn: &mut PgConnection,
+    table_name: &str,
+    candidate_columns: &[&str],
+) -> Result<Vec<String>, TimescaleError> {
+    // Validate table and column names
+    let table_identifier = SqlIdentifier::new(table_name)?;
+    for column in candidate_columns {
+        SqlIdentifier::column(column)?;
+    }
+
+    // Hypertable statistics are stored as inherited statistics on the root table
+    // Bind the quoted name so the regclass cast keeps its case
+    let stats = diesel::sql_query(
+        "SELECT DISTINCT ON (attname) attname, n_distinct 
+         FROM pg_stats 
+         WHERE format('%I.%I', schemaname, tablename)::regclass = to_regclass($1) 
+           AND attname = ANY($2) 
+         ORDER BY attname, inherited DESC",
+    )
+    .bind::<Text, _>(table_identifier.escaped())
+    .bind::<Array<Text>, _>(candidate_columns)
+    .load::<ColumnDistinct>(conn)?;
+
+    Ok(candidate_columns
+        .iter()
+        .filter(|column| {
+            stats.iter().any(|stat| {
+                stat.attname == **column
+                    && stat.n_distinct > 0.0
+                    && stat.n_distinct <= SEGMENTBY_MAX_DISTINCT
+            })
+        })
+        .map(|column| column.to_string())
+        .collect())
+}
+
+#[derive(QueryableByName)]
+struct TimeBounds {
+    #[diesel(sql_type = Nullable<Timestamptz>)]
+    earliest: Option<chrono::DateTime<chrono::Utc>>,
+    #[diesel(sql_type = Nullable<Timestamptz>)]
+    latest: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Get the earliest and latest timestamps in a table.
+///
+/// TimescaleDB answers `min`/`max` on the time column of a hypertable from
+/// chunk ordering, so this stays fast on large hypertables. Both values are
+/// `None` if the table is empty.
+#[allow(clippy::type_complexity)]
+pub fn time_bounds(
+    conn: &mut PgConnection,
+    table_name: &str,
+    time_column: &str,
+) -> Result<
+    (
+        Option<chrono::DateTime<chrono::Utc>>,
+        Option<chrono::DateTime<chrono::Utc>>,
+    ),
+    TimescaleError,
+> {
+    // Validate identifiers
+    let table_identifier = SqlIdentifier::new(table_name)?;
+    let time_identifier = SqlIdentifier::column(time_column)?;
+
+    let bounds = diesel::sql_query(format!(
+        "SELECT min({time}) AS earliest, max({time}) AS latest FROM {table}",
+        time = time_identifier.escaped(),
+        table = table_identifier.escaped()
+    ))
+    .get_result::<TimeBounds>(conn)?;
+
+    Ok((bounds.earliest, bounds.latest))
+}
+
+#[derive(QueryableByName)]
+struct RelationSize {
+    #[diesel(sql_type = Nullable<BigInt>)]
+    size: Option<i64>,
+}
+
+/// Get the approximate total size of a hypertable in bytes.
+///
+/// On TimescaleDB 2.13 and later this uses `hypertable_approximate_size`,
+/// which reads cached relation sizes instead of summing every chunk and stays
+/// fast on hypertables with many chunks. Older versions fall back to the
+/// exact `hypertable_size`.
+///
+/// Returns `NotFound` if the table is not a hypertable.
+pub fn hypertable_approximate_size(
+    conn: &mut PgConnection,
+    table_name: &str,
+) -> Result<i64, TimescaleError> {
+    // Validate table name
+    let table_identifier = SqlIdentifier::new(table_name)?;
+
+    let query = if installed_version(conn)?.at_least(2, 13) {
+        "SELECT hypertable_approximate_size($1::regclass) AS size"
+    } else {
+        "SELECT hypertable_size($1::regclass) AS size"
+    };
+
+    // Bind the quoted name so the regclass cast keeps its case
+    diesel::sql_query(query)
+        .bind::<Text, _>(table_identifier.escaped())
+        .get_result::<RelationSize>(conn)?
+        .size
+        .ok_or(TimescaleError::Database(diesel::result::Error::NotFound))
+}
+
+#[derive(QueryableByName)]
+struct HypertableSize {
+    #[diesel(sql_type = Text)]
+    hypertable: String,
+    #[diesel(sql_type = BigInt)]
+    size: i64,
+}
+
+/// Get the total size in bytes of every hypertable, largest first.
+///
+/// The sizes include indexes, TOAST and compressed data, as reported by
+/// `hypertable_size`, and are computed in a single query. Names are
+/// schema-qualified and quoted where needed, e.g. `public.metrics`.
+pub fn all_hypertable_sizes(conn: &mut PgConnection) -> QueryResult<Vec<(String, i64)>> {
+    let sizes = diesel::sql_query(
+        "SELECT format('%I.%I', hypertable_schema, hypertable_name) AS hypertable, 
+                coalesce(hypertable_size(format('%I.%I', hypertable_schema, hypertable_name)::regclass), 0)::int8 AS size 
+         FROM timescaledb_information.hypertables 
+         ORDER BY size DESC, hypertable",
+    )
+    .load::<HypertableSize>(conn)?;
+
+    Ok(sizes
+        .into_iter()
+        .map(|row| (row.hypertable, row.size))
+        .collect())
+}
+
+#[derive(QueryableByName)]
+struct CompressionRatio {
+    #[diesel(sql_type = Nullable<Double>)]
+    ratio: Option<f64>,
+}
+
+/// Get the overall compression ratio of a hypertable.
+///
+/// The ratio is the total size of all compressed chunks before compression
+/// divided by their total size after compression. Uncompressed chunks are not
+/// counted, and `None` is returned if no chunk is compressed.
+pub fn compression_ratio(
+    conn: &mut PgConnection,
+    table_name: &str,
+) -> Result<Option<f64>, TimescaleError> {
+    // Validate table name
+    let table_identifier = SqlIdentifier::new(table_name)?;
+
+    // Bind the quoted name so the regclass cast keeps its case
+    let row = diesel::sql_query(
+        "SELECT sum(before_compression_total_bytes)::float8 
+                / nullif(sum(after_compression_total_bytes), 0)::float8 AS ratio 
+         FROM chunk_compression_stats($1::regclass)",
+    )
+    .bind::<Text, _>(table_identifier.escaped())
+    .get_result::<CompressionRatio>(conn)?;
+
+    Ok(row.ratio)
+}
+
+/// Chunk counts and sizes of a hypertable, grouped by compression status.
+#[derive(Debug, Clone, PartialEq, Eq, QueryableByName)]
+pub struct CompressionSummary {
+    #[diesel(sql_type = BigInt)]
+    pub compressed_chunks: i64,
+    #[diesel(sql_type = BigInt)]
+    pub uncompressed_chunks: i64,
+    /// Total size of the compressed chunks after compression.
+    #[diesel(sql_type = BigInt)]
+    pub compressed_bytes: i64,
+    #[diesel(sql_type = BigInt)]
+    pub uncompressed_bytes: i64,
+}
+
+/// Get the number and total size of compressed and uncompressed chunks of a hypertable.
+pub fn compression_summary(
+    conn: &mut PgConnection,
+    table_name: &str,
+) -> Result<CompressionSummary, TimescaleError> {
+    // Validate table name
+    let table_identifier = SqlIdentifier::new(table_name)?;
+
+    // Bind the quoted name so the regclass cast keeps its case
+    diesel::sql_query(
+        "SELECT count(*) FILTER (WHERE s.compression_status = 'Compressed') AS compressed_chunks, 
+                count(*) FILTER (WHERE s.compression_status IS DISTINCT FROM 'Compressed') AS uncompressed_chunks, 
+                coalesce(sum(s.after_compression_total_bytes) 
+                    FILTER (WHERE s.compression_status = 'Compressed'), 0)::int8 AS compressed_bytes, 
+                coalesce(sum(d.total_bytes) 
+                    FILTER (WHERE s.compression_status IS DISTINCT FROM 'Compressed'), 0)::int8 AS uncompressed_bytes 
+         FROM chunk_compression_stats($1::regclass) s 
+         LEFT JOIN chunks_detailed_size($1::regclass) d 
+           ON d.chunk_schema = s.chunk_schema AND d.chunk_name = s.chunk_name",
+    )
+    .bind::<Text, _>(table_identifier.escaped())
+    .get_result(conn)
+    .map_err(TimescaleError::from)
+}
+
+/// Chunks of a hypertable that received rows after they were compressed.
+#[derive(Debug, Clone, PartialEq, Eq, QueryableByName)]
+pub struct OutOfOrderStats {
+    #[diesel(sql_type = BigInt)]
+    pub total_chunks: i64,
+    #[diesel(sql_type = BigInt)]
+    pub compressed_chunks: i64,
+    /// Compressed chunks holding uncompressed rows that were inserted, or
+    /// updated, after compression and wait for recompression.
+    #[diesel(sql_type = BigInt)]
+    pub partially_compressed_chunks: i64,
+    /// Compressed chunks whose compressed data is no longer in `ORDER BY`
+    /// order because of late rows. Queries on them cannot rely on the order.
+    #[diesel(sql_type = BigInt)]
+    pub unordered_chunks: i64,
+}
+
+impl OutOfOrderStats {
+    /// Whether any compressed chunk received late data.
+    pub fn has_late_data(&self) -> bool {
+        self.partially_compressed_chunks > 0 || self.unordered_chunks > 0
+    }
+}
+
+/// Count the compressed chunks of a hypertable that received late data.
+///
+/// Inserting into a compressed chunk is much slower than inserting into an
+/// uncompressed one, and the rows stay uncompressed until the chunk is
+/// recompressed with [`chunks::recompress_chunk`] or by the compression
+/// policy. A steadily growing count means data arrives later than the
+/// policy's `compress_after`, which should then be raised. The counts come
+/// from the chunk status flags in the catalog; nothing is modified.
+pub fn out_of_order_insert_stats(
+    conn: &mut PgConnection,
+    table_name: &str,
+) -> Result<OutOfOrderStats, TimescaleError> {
+    // Validate table name
+    let table_identifier = SqlIdentifier::new(table_name)?;
+
+    // Status flags: 1 compressed, 2 compressed unordered, 8 partially compressed
+    // Bind the quoted name so the regclass cast keeps its case
+    diesel::sql_query(
+        "SELECT count(*) AS total_chunks, 
+                count(*) FILTER (WHERE c.status & 1 != 0) AS compressed_chunks, 
+                count(*) FILTER (WHERE c.status & 8 != 0) AS partially_compressed_chunks, 
+                count(*) FILTER (WHERE c.status & 2 != 0) AS unordered_chunks 
+         FROM _timescaledb_catalog.chunk c 
+         JOIN _timescaledb_catalog.hypertable h ON h.id = c.hypertable_id 
+         WHERE format('%I.%I', h.schema_name, h.table_name)::regclass = to_regclass($1) 
+           AND NOT c.dropped",
+    )
+    .bind::<Text, _>(table_identifier.escaped())
+    .get_result(conn)
+    .map_err(TimescaleError::from)
+}
+
+/// A compression job that is currently running.
+#[derive(Debug, Clone, PartialEq, Eq, QueryableByName)]
+pub struct ActiveJob {
+    #[diesel(sql_type = Integer)]
+    pub job_id: i32,
+    /// The job procedure, `policy_compression` or `policy_recompression`.
+    #[diesel(sql_type = Text)]
+    pub proc_name: String,
+    #[diesel(sql_type = Text)]
+    pub hypertable_schema: String,
+    #[diesel(sql_type = Text)]
+    pub hypertable_name: String,
+    #[diesel(sql_type = Timestamptz)]
+    pub started_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// List the compression policy jobs that are running right now.
+///
+/// Only background jobs are reported. A `compress_chunk` or
+/// `decompress_chunk` call made directly by a session shows up in
+/// `pg_stat_activity` instead.
+pub fn active_compression_jobs(conn: &mut PgConnection) -> QueryResult<Vec<ActiveJob>> {
+    diesel::sql_query(
+        "SELECT j.job_id, j.proc_name::text AS proc_name, 
+                j.hypertable_schema::text AS hypertable_schema, 
+                j.hypertable_name::text AS hypertable_name, 
+                s.last_run_started_at AS started_at 
+         FROM timescaledb_information.jobs j 
+         JOIN timescaledb_information.job_stats s ON s.job_id = j.job_id 
+         WHERE s.job_status = 'Running' 
+           AND j.proc_name IN ('policy_compression', 'policy_recompression') 
+           AND j.hypertable_name IS NOT NULL 
+         ORDER BY s.last_run_started_at",
+    )
+    .load::<ActiveJob>(conn)
+}
+
+/// Number of chunks above which a hypertable is considered to have too many chunks.
+pub const CHUNK_COUNT_WARNING_THRESHOLD: i64 = 10_000;
+
+/// Chunk count health of a hypertable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkHealth {
+    pub chunk_count: i64,
+    /// Whether the chunk count exceeds [`CHUNK_COUNT_WARNING_THRESHOLD`],
+    /// which usually means the chunk time interval is too small.
+    pub too_many_chunks: bool,
+}
+
+#[derive(QueryableByName)]
+struct ChunkCount {
+    #[diesel(sql_type = BigInt)]
+    chunk_count: i64,
+}
+
+/// Check whether a hypertable has accumulated too many chunks.
+///
+/// Every chunk adds planning overhead, so millions of tiny chunks from a too
+/// small chunk time interval degrade query performance. If `too_many_chunks`
+/// is set, consider a larger interval via `set_chunk_time_interval`.
+pub fn chunk_count_health(
+    conn: &mut PgConnection,
+    table_name: &str,
+) -> Result<ChunkHealth, TimescaleError> {
+    // Validate table name
+    let table_identifier = SqlIdentifier::new(table_name)?;
+
+    // Bind the quoted name so the regclass cast keeps its case
+    let row = diesel::sql_query(
+        "SELECT count(*) AS chunk_count 
+         FROM timescaledb_information.chunks 
+         WHERE format('%I.%I', hypertable_schema, hypertable_name)::regclass = to_regclass($1)",
+    )
+    .bind::<Text, _>(table_identifier.escaped())
+    .get_result::<ChunkCount>(conn)?;
+
+    Ok(ChunkHealth {
+        chunk_count: row.chunk_count,
+        too_many_chunks: row.chunk_count > CHUNK_COUNT_WARNING_THRESHOLD,
+    })
+}
+
+#[derive(QueryableByName)]
+struct InconsistentChunk {
+    #[diesel(sql_type = Text)]
+    chunk_name: String,
+}
+
+/// Find chunks of a hypertable whose dimension constraints are out of sync
+/// with the catalog.
+///
+/// A chunk is reported if it has no dimension slice for one of the
+/// hypertable's dimensions, or if the `CHECK` constraint the catalog records
+/// for one of its slices does not bound the chunk to the slice's
+/// `range_start` and `range_end`. That covers constraints that are missing,
+/// as they can be after manual changes to chunk tables, and constraints
+/// whose bounds were altered. Slices unbounded on both ends have no
+/// constraint. Nothing is repaired.
+pub fn verify_chunk_constraints(
+    conn: &mut PgConnection,
+    table_name: &str,
+) -> Result<Vec<String>, TimescaleError> {
+    // Validate table name
+    let table_identifier = SqlIdentifier::new(table_name)?;
+
+    // The bounds in a constraint definition are literals of the column
+    // type, or of the hash for space dimensions; they are converted to the
+    // catalog's internal form, microseconds since the Unix epoch for time
+    // types, before comparing. Bounds at the ends of the bigint range stand
+    // for an unbounded side, which the constraint leaves out.
+    // Bind the quoted name so the regclass cast keeps its case
+    let chunks = diesel::sql_query(
+        "WITH chunks AS (
+             SELECT c.id, h.id AS hypertable_id,
+                    format('%I.%I', c.schema_name, c.table_name)::regclass AS chunk
+             FROM _timescaledb_catalog.chunk c
+             JOIN _timescaledb_catalog.hypertable h ON h.id = c.hypertable_id
+             WHERE format('%I.%I', h.schema_name, h.table_name)::regclass = to_regclass($1)
+               AND NOT c.dropped
+         ),
+         slices AS (
+             SELECT c.chunk, d.column_type, d.num_slices, ds.range_start, ds.range_end,
+                    pg_get_constraintdef(pc.oid) AS definition
+             FROM chunks c
+             JOIN _timescaledb_catalog.chunk_constraint cc ON cc.chunk_id = c.id
+             JOIN _timescaledb_catalog.dimension_slice ds ON ds.id = cc.dimension_slice_id
+             JOIN _timescaledb_catalog.dimension d ON d.id = ds.dimension_id
+             LEFT JOIN pg_constraint pc ON pc.conrelid = c.chunk
+                                       AND pc.conname = cc.constraint_name
+                                       AND pc.contype = 'c'
+         ),
+         bounds AS (
+             SELECT s.chunk, s.column_type, s.num_slices,
+                    NULLIF(CASE WHEN b.is_start THEN s.range_start ELSE s.range_end END,
+                           CASE WHEN b.is_start THEN -9223372036854775808
+                                ELSE 9223372036854775807 END) AS expected,
+                    coalesce(b.literal[1], b.literal[2]) AS literal
+             FROM slices s
+             CROSS JOIN LATERAL (VALUES
+                 (true, regexp_match(s.definition, '>= \\(?(?:''([^'']*)''|(-?\\d+))')),
+                 (false, regexp_match(s.definition, '< \\(?(?:''([^'']*)''|(-?\\d+))'))
+             ) AS b(is_start, literal)
+         )
+         SELECT chunk::text AS chunk_name
+         FROM bounds
+         WHERE expected IS DISTINCT FROM (
+             CASE
+                 WHEN literal IS NULL THEN NULL
+                 WHEN num_slices IS NOT NULL THEN literal::bigint
+                 WHEN column_type = 'timestamptz'::regtype
+                     THEN round(extract(epoch FROM literal::timestamptz) * 1000000)::bigint
+                 WHEN column_type = 'timestamp'::regtype
+                     THEN round(extract(epoch FROM literal::timestamp) * 1000000)::bigint
+                 WHEN column_type = 'date'::regtype
+                     THEN round(extract(epoch FROM literal::date) * 1000000)::bigint
+                 ELSE literal::bigint
+             END)
+         UNION
+         SELECT c.chunk::text AS chunk_name
+         FROM chunks c
+         JOIN _timescaledb_catalog.dimension d ON d.hypertable_id = c.hypertable_id
+         WHERE NOT EXISTS (SELECT 1
+                           FROM _timescaledb_catalog.chunk_constraint cc
+                           JOIN _timescaledb_catalog.dimension_slice ds ON ds.id = cc.dimension_slice_id
+                           WHERE cc.chunk_id = c.id AND ds.dimension_id = d.id)
+         ORDER BY chunk_name",
+    )
+    .bind::<Text, _>(table_identifier.escaped())
+    .load::<InconsistentChunk>(conn)?;
+
+    Ok(chunks.into_iter().map(|chunk| chunk.chunk_name).collect())
+}
+
+/// Typical chunk size of a hypertable, for judging its chunk time interval.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkSizingReport {
+    pub chunk_count: i64,
+    /// Average estimated number of rows per chunk, from planner statistics.
+    ///
+    /// Compressed chunks keep their rows in a separate table and count as
+    /// empty, and chunks that were never analyzed are not included.
+    pub avg_rows_per_chunk: Option<f64>,
+    /// Average total size of a chunk, including indexes and TOAST.
+    pub avg_bytes_per_chunk: Option<f64>,
+    /// The configured chunk time interval of a time-typed time column.
+    pub chunk_time_interval: Option<TimeInterval>,
+    /// The configured chunk width of an integer time column.
+    pub chunk_time_interval_int: Option<i64>,
+}
+
+#[derive(QueryableByName)]
+struct ChunkSizing {
+    #[diesel(sql_type = BigInt)]
+    chunk_count: i64,
+    #[diesel(sql_type = Nullable<Double>)]
+    avg_rows_per_chunk: Option<f64>,
+    #[diesel(sql_type = Nullable<Double>)]
+    avg_bytes_per_chunk: Option<f64>,
+    #[diesel(sql_type = Nullable<Interval>)]
+    time_interval: Option<TimeInterval>,
+    #[diesel(sql_type = Nullable<BigInt>)]
+    integer_interval: Option<i64>,
+}
+
+/// Report the average row count and size of a hypertable's chunks together
+/// with its configured chunk time interval.
+///
+/// Chunks, including their indexes, should fit comfortably in memory; this is
+/// the data needed to decide whether the interval should be changed for new
+/// chunks.
+pub fn chunk_sizing_report(
+    conn: &mut PgConnection,
+    table_name: &str,
+) -> Result<ChunkSizingReport, TimescaleError> {
+    // Validate table name
+    let table_identifier = SqlIdentifier::new(table_name)?;
+
+    // Bind the quoted name so the regclass cast keeps its case
+    let row = diesel::sql_query(
+        "SELECT (SELECT count(*) FROM show_chunks($1::regclass)) AS chunk_count, 
+                (SELECT avg(c.reltuples)::float8 
+                 FROM show_chunks($1::regclass) chunk 
+                 JOIN pg_class c ON c.oid = chunk 
+                 WHERE c.reltuples >= 0) AS avg_rows_per_chunk, 
+                (SELECT avg(total_bytes)::float8 
+                 FROM chunks_detailed_size($1::regclass)) AS avg_bytes_per_chunk, 
+                d.time_interval, 
+                d.integer_interval 
+         FROM timescaledb_information.dimensions d 
+         WHERE format('%I.%I', d.hypertable_schema, d.hypertable_name)::regclass = $1::regclass 
+           AND d.dimension_number = 1",
+    )
+    .bind::<Text, _>(table_identifier.escaped())
+    .get_result::<ChunkSizing>(conn)?;
+
+    Ok(ChunkSizingReport {
+        chunk_count: row.chunk_count,
+        avg_rows_per_chunk: row.avg_rows_per_chunk,
+        avg_bytes_per_chunk: row.avg_bytes_per_chunk,
+        chunk_time_interval: row.time_interval,
+        chunk_time_interval_int: row.integer_interval,
+    })
+}
+
+/// Module for managing TimescaleDB chunks.
+pub mod chunks {
+    use super::*;
+
+    /// Information about a chunk in a hypertable.
+    #[derive(Debug, Clone, QueryableByName)]
+    pub struct ChunkInfo {
+        #[diesel(sql_type = Text)]
+        pub chunk_schema: String,
+        #[diesel(sql_type = Text)]
+        pub chunk_name: String,
+        #[diesel(sql_type = Text)]
+        pub table_name: String,
+        #[diesel(sql_type = Nullable<Timestamptz>)]
+        pub range_start: Option<chrono::DateTime<chrono::Utc>>,
+        #[diesel(sql_type = Nullable<Timestamptz>)]
+        pub range_end: Option<chrono::DateTime<chrono::Utc>>,
+    }
+
+    #[derive(QueryableByName)]
+    struct ChunkName {
+        #[diesel(sql_type = Text)]
+        chunk_name: String,
+    }
+
+    /// Build the statement that lists the chunks of a hypertable.
+    pub fn get_chunk_info_sql(table_name: &str) -> Result<SqlStatement, ValidationError> {
+        // Validate table name
+        let table_identifier = SqlIdentifier::new(table_name)?;
+
+        // Bind the quoted name so the regclass cast keeps its case
+        Ok(SqlStatement::new(
+            "SELECT chunk_schema, chunk_name, table_name, range_start, range_end 
+             FROM timescaledb_information.chunks 
+             WHERE format('%I.%I', hypertable_schema, hypertable_name)::regclass = to_regclass(",
+        )
+        .bind(table_identifier.escaped())
+        .sql(")"))
+    }
+
+    /// Get information about chunks for a hypertable.
+    pub fn get_chunk_info(
+        conn: &mut PgConnection,
+        table_name: &str,
+    ) -> Result<Vec<ChunkInfo>, TimescaleError> {
+        Ok(get_chunk_info_sql(table_name)?.load(conn)?)
+    }
+
+    /// Get information about chunks for a hypertable on an async connection.
+    ///
+    /// See [`get_chunk_info`].
+    #[cfg(feature = "async")]
+    pub async fn get_chunk_info_async(
+        conn: &mut diesel_async::AsyncPgConnection,
+        table_name: &str,
+    ) -> Result<Vec<ChunkInfo>, TimescaleError> {
+        Ok(get_chunk_info_sql(table_name)?.load_async(conn).await?)
+    }
+
+    /// A chunk of a hypertable with its compression state.
+    #[derive(Debug, Clone, QueryableByName)]
+    pub struct ChunkDetail {
+        #[diesel(sql_type = Text)]
+        pub chunk_schema: String,
+        #[diesel(sql_type = Text)]
+        pub chunk_name: String,
+        #[diesel(sql_type = Nullable<Timestamptz>)]
+        pub range_start: Option<chrono::DateTime<chrono::Utc>>,
+        #[diesel(sql_type = Nullable<Timestamptz>)]
+        pub range_end: Option<chrono::DateTime<chrono::Utc>>,
+        #[diesel(sql_type = Bool)]
+        pub is_compressed: bool,
+        /// Total size of the chunk after compression, `None` unless it is
+        /// compressed.
+        #[diesel(sql_type = Nullable<BigInt>)]
+        pub compressed_total_bytes: Option<i64>,
+    }
+
+    impl ChunkDetail {
+        /// Get the schema-qualified name of the chunk, as accepted by
+        /// [`compress_chunks`] and [`is_chunk_compressed`].
+        pub fn qualified_name(&self) -> String {
+            format!("{}.{}", self.chunk_schema, self.chunk_name)
+        }
+    }
+
+    /// List the chunks of a hypertable within a time window, ordered by
+    /// their start.
+    ///
+    /// This uses `show_chunks`: `newer_than` keeps chunks whose range starts
+    /// at or after it, `older_than` chunks whose range ends at or before it,
+    /// the chunks [`drop_old_chunks`] would drop. A missing bound leaves that
+    /// side of the window open.
+    ///
+    /// The bounds are timestamps, so they cannot be given for a hypertable
+    /// with an integer time column; that is rejected with a
+    /// [`ValidationError`]. Without bounds its chunks are listed with `None`
+    /// ranges.
+    pub fn show_chunks(
+        conn: &mut PgConnection,
+        table_name: &str,
+        newer_than: Option<chrono::DateTime<chrono::Utc>>,
+        older_than: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<Vec<ChunkDetail>, TimescaleError> {
+        // Validate table name
+        let table_identifier = SqlIdentifier::new(table_name)?;
+        if let (Some(newer_than), Some(older_than)) = (newer_than, older_than) {
+            crate::dsl::TimeRange::new(newer_than, older_than)?;
+        }
+
+        // Bind the quoted name so the regclass cast keeps its case
+        if newer_than.is_some() || older_than.is_some() {
+            let interval = diesel::sql_query(
+                "SELECT time_interval
+                 FROM timescaledb_information.dimensions
+                 WHERE format('%I.%I', hypertable_schema, hypertable_name)::regclass = to_regclass($1)
+                   AND dimension_number = 1",
+            )
+            .bind::<Text, _>(table_identifier.escaped())
+            .get_result::<ChunkTimeInterval>(conn)?;
+            if interval.time_interval.is_none() {
+                return Err(ValidationError::InvalidParameter(format!(
+                    "Cannot bound the chunks of '{}' by time, it has an integer time column",
+                    table_name
+                ))
+                .into());
+            }
+        }
+
+        Ok(diesel::sql_query(
+            "SELECT c.chunk_schema::text, c.chunk_name::text, c.range_start, c.range_end, c.is_compressed,
+                    s.after_compression_total_bytes AS compressed_total_bytes
+             FROM show_chunks($1::regclass, older_than => $2, newer_than => $3) AS chunk
+             JOIN timescaledb_information.chunks c
+               ON format('%I.%I', c.chunk_schema, c.chunk_name)::regclass = chunk
+             LEFT JOIN chunk_compression_stats($1::regclass) s
+               ON s.chunk_schema = c.chunk_schema AND s.chunk_name = c.chunk_name
+             ORDER BY c.range_start",
+        )
+        .bind::<Text, _>(table_identifier.escaped())
+        .bind::<Nullable<Timestamptz>, _>(older_than)
+        .bind::<Nullable<Timestamptz>, _>(newer_than)
+        .load(conn)?)
+    }
+
+    /// Build the statement that drops chunks older than a specified time.
+    pub fn drop_old_chunks_sql(
+        table_name: &str,
+        older_than: chrono::DateTime<chrono::Utc>,
+    ) -> Result<SqlStatement, ValidationError> {
+        // Validate table name
+        let table_identifier = SqlIdentifier::new(table_name)?;
+
+        Ok(SqlStatement::new("SELECT drop_chunks(")
+            .bind(table_identifier.escaped())
+            .sql(", ")
+            .bind(older_than)
+            .sql(");"))
+    }
+
+    /// Drop chunks older than a specified time.
+    pub fn drop_old_chunks(
+        conn: &mut PgConnection,
+        table_name: &str,
+        older_than: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), TimescaleError> {
+        drop_old_chunks_sql(table_name, older_than)?.execute(conn)?;
+        Ok(())
+    }
+
+    /// Drop chunks older than a specified time on an async connection.
+    ///
+    /// See [`drop_old_chunks`].
+    #[cfg(feature = "async")]
+    pub async fn drop_old_chunks_async(
+        conn: &mut diesel_async::AsyncPgConnection,
+        table_name: &str,
+        older_than: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), TimescaleError> {
+        drop_old_chunks_sql(table_name, older_than)?
+            .execute_async(conn)
+            .await?;
+        Ok(())
+    }
+
+    /// List the chunks [`drop_old_chunks`] would drop for `cutoff`, without dropping them.
+    ///
+    /// Only chunks whose whole time range ends before the cutoff are dropped,
+    /// so a chunk that straddles it is kept and not listed. This uses
+    /// `show_chunks`, which applies the same rule as `drop_chunks`.
+    pub fn chunks_droppable_before(
+        conn: &mut PgConnection,
+        table_name: &str,
+        cutoff: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<String>, TimescaleError> {
+        // Validate table name
+        let table_identifier = SqlIdentifier::new(table_name)?;
+
+        let chunks = diesel::sql_query(
+            "SELECT chunk::text AS chunk_name 
+             FROM show_chunks($1::regclass, older_than => $2) AS chunk 
+             ORDER BY chunk",
+        )
+        .bind::<Text, _>(table_identifier.escaped())
+        .bind::<Timestamptz, _>(cutoff)
+        .load::<ChunkName>(conn)?;
+
+        Ok(chunks.into_iter().map(|chunk| chunk.chunk_name).collect())
+    }
+
+    /// Maximum number of days [`drop_chunks_for_days`] accepts in one call.
+    pub const MAX_DROP_DAYS: usize = 366;
+
+    /// Drop every chunk whose time range overlaps one of the given UTC days,
+    /// and return the dropped chunks.
+    ///
+    /// Each timestamp stands for the whole UTC day containing it. This is
+    /// meant for reprocessing specific days: a chunk is dropped entirely, so
+    /// with chunks longer than a day the data of neighbouring days in the same
+    /// chunk is dropped too. All chunks are dropped in one transaction.
+    ///
+    /// `days` must not be empty and may contain at most [`MAX_DROP_DAYS`]
+    /// entries, so a mistaken call cannot drop the whole hypertable.
+    pub fn drop_chunks_for_days(
+        conn: &mut PgConnection,
+        table_name: &str,
+        days: &[chrono::DateTime<chrono::Utc>],
+    ) -> Result<Vec<String>, TimescaleError> {
+        // Validate table name
+        let table_identifier = SqlIdentifier::new(table_name)?;
+        if days.is_empty() || days.len() > MAX_DROP_DAYS {
+            return Err(ValidationError::InvalidParameter(format!(
+                "Expected between 1 and {} days, got {}",
+                MAX_DROP_DAYS,
+                days.len()
+            ))
+            .into());
+        }
+
+        let day_starts: Vec<chrono::DateTime<chrono::Utc>> = days
+            .iter()
+            .map(|day| day.date_naive().and_time(chrono::NaiveTime::MIN).and_utc())
+            .collect();
+        let day_ends: Vec<chrono::DateTime<chrono::Utc>> = day_starts
+            .iter()
+            .map(|start| *start + chrono::Duration::days(1))
+            .collect();
+
+        conn.transaction(|conn| {
+            let chunks = diesel::sql_query(
+                "SELECT DISTINCT format('%I.%I', c.chunk_schema, c.chunk_name) AS chunk_name 
+                 FROM timescaledb_information.chunks c, unnest($2, $3) AS d(day_start, day_end) 
+                 WHERE format('%I.%I', c.hypertable_schema, c.hypertable_name)::regclass = $1::regclass 
+                   AND c.range_start < d.day_end 
+                   AND c.range_end > d.day_start 
+                 ORDER BY chunk_name",
+            )
+            .bind::<Text, _>(table_identifier.escaped())
+            .bind::<Array<Timestamptz>, _>(&day_starts)
+            .bind::<Array<Timestamptz>, _>(&day_ends)
+            .load::<ChunkName>(conn)?;
+
+            // The names were quoted by format('%I.%I') above
+            for chunk in &chunks {
+                diesel::sql_query(format!("DROP TABLE {}", chunk.chunk_name)).execute(conn)?;
+            }
+
+            Ok(chunks.into_iter().map(|chunk| chunk.chunk_name).collect())
+        })
+    }
+
+    /// Validate a chunk name, which may be qualified with its schema.
+    fn validate_chunk_name(chunk: &str) -> Result<(), ValidationError> {
+        let parts: Vec<&str> = chunk.split('.').collect();
+        if parts.len() > 2 {
+            return Err(ValidationError::InvalidIdentifier(format!(
+                "'{}' is not a valid chunk name",
+                chunk
+            )));
+        }
+
+        for part in parts {
+            SqlIdentifier::new(part)?;
+        }
+
+        Ok(())
+    }
+
+    /// Chunk status flag of a compressed chunk.
+    const CHUNK_STATUS_COMPRESSED: i32 = 1;
+
+    /// Chunk status flag of a compressed chunk with uncompressed rows.
+    const CHUNK_STATUS_PARTIAL: i32 = 8;
+
+    #[derive(QueryableByName)]
+    struct ChunkStatus {
+        #[diesel(sql_type = Integer)]
+        status: i32,
+    }
+
+    /// Get the status flags of a chunk from the catalog.
+    fn chunk_status(conn: &mut PgConnection, chunk: &str) -> Result<i32, TimescaleError> {
+        let row = diesel::sql_query(
+            "SELECT status 
+             FROM _timescaledb_catalog.chunk 
+             WHERE format('%I.%I', schema_name, table_name)::regclass = $1::regclass",
+        )
+        .bind::<Text, _>(chunk)
+        .get_result::<ChunkStatus>(conn)?;
+        Ok(row.status)
+    }
+
+    /// Recompress a partially compressed chunk.
+    ///
+    /// Rows inserted into a compressed chunk are kept uncompressed, leaving
+    /// the chunk partially compressed until it is recompressed. Before
+    /// TimescaleDB 2.14 this is done with the `recompress_chunk` procedure;
+    /// from 2.14 `compress_chunk` recompresses partially compressed chunks
+    /// itself and is used instead.
+    ///
+    /// `chunk` may be schema-qualified, e.g. `_timescaledb_internal._hyper_1_1_chunk`.
+    /// Returns `false` without doing anything if the chunk was not partially
+    /// compressed.
+    pub fn recompress_chunk(conn: &mut PgConnection, chunk: &str) -> Result<bool, TimescaleError> {
+        validate_chunk_name(chunk)?;
+
+        if chunk_status(conn, chunk)? & CHUNK_STATUS_PARTIAL == 0 {
+            return Ok(false);
+        }
+
+        let query = if installed_version(conn)?.at_least(2, 14) {
+            "SELECT compress_chunk($1::regclass)"
+        } else {
+            "CALL recompress_chunk($1::regclass)"
+        };
+
+        diesel::sql_query(query)
+            .bind::<Text, _>(chunk)
+            .execute(conn)?;
+        Ok(true)
+    }
+
+    /// Reorder a chunk by an index, once.
+    ///
+    /// This rewrites the chunk in index order like a reorder policy does,
+    /// and takes an exclusive lock on the chunk while doing so. `chunk` and
+    /// `index` may be schema-qualified; the index must be the chunk's own
+    /// index, or an index on its hypertable.
+    pub fn reorder_chunk(
+        conn: &mut PgConnection,
+        chunk: &str,
+        index: &str,
+    ) -> Result<(), TimescaleError> {
+        validate_chunk_name(chunk)?;
+        let _index_identifier = SqlIdentifier::new(index)?;
+
+        diesel::sql_query("SELECT reorder_chunk($1::regclass, $2::regclass)")
+            .bind::<Text, _>(chunk)
+            .bind::<Text, _>(index)
+            .execute(conn)?;
+        Ok(())
+    }
+
+    #[derive(QueryableByName)]
+    struct ChunkCompressed {
+        #[diesel(sql_type = Bool)]
+        is_compressed: bool,
+    }
+
+    /// Check whether a chunk is compressed.
+    ///
+    /// `chunk` may be schema-qualified. Returns `NotFound` if there is no such chunk.
+    pub fn is_chunk_compressed(
+        conn: &mut PgConnection,
+        chunk: &str,
+    ) -> Result<bool, TimescaleError> {
+        validate_chunk_name(chunk)?;
+
+        let row = diesel::sql_query(
+            "SELECT is_compressed 
+             FROM timescaledb_information.chunks 
+             WHERE format('%I.%I', chunk_schema, chunk_name)::regclass = $1::regclass",
+        )
+        .bind::<Text, _>(chunk)
+        .get_result::<ChunkCompressed>(conn)?;
+
+        Ok(row.is_compressed)
+    }
+
+    /// Compress the given chunks.
+    ///
+    /// Every name is validated before anything is compressed, and the chunks
+    /// are compressed in a single transaction. Chunks that are already
+    /// compressed are skipped. Returns the number of chunks compressed.
+    pub fn compress_chunks(
+        conn: &mut PgConnection,
+        chunks: &[&str],
+    ) -> Result<usize, TimescaleError> {
+        for chunk in chunks {
+            validate_chunk_name(chunk)?;
+        }
+
+        conn.transaction(|conn| {
+            let mut compressed = 0;
+            for chunk in chunks {
+                if chunk_status(conn, chunk)? & CHUNK_STATUS_COMPRESSED != 0 {
+                    continue;
+                }
+
+                diesel::sql_query("SELECT compress_chunk($1::regclass)")
+                    .bind::<Text, _>(*chunk)
+                    .execute(conn)?;
+                compressed += 1;
+            }
+            Ok(compressed)
+        })
+    }
+
+    /// Get the schema-qualified name of the oldest chunk of a hypertable that
+    /// is not compressed, or `None` if every chunk is compressed.
+    ///
+    /// This is meant for compression loops that compress one chunk at a time,
+    /// with [`compress_chunks`], until a time budget runs out. The chunk
+    /// receiving current writes is returned too once it is the oldest
+    /// uncompressed one, so check its range before compressing it.
+    pub fn oldest_uncompressed_chunk(
+        conn: &mut PgConnection,
+        table_name: &str,
+    ) -> Result<Option<String>, TimescaleError> {
+        // Validate table name
+        let table_identifier = SqlIdentifier::new(table_name)?;
+
+        // Bind the quoted name so the regclass cast keeps its case
+        let chunk = diesel::sql_query(
+            "SELECT chunk_schema || '.' || chunk_name AS chunk_name
+             FROM timescaledb_information.chunks
+             WHERE format('%I.%I', hypertable_schema, hypertable_name)::regclass = to_regclass($1)
+               AND NOT is_compressed
+             ORDER BY range_start, chunk_name
+             LIMIT 1",
+        )
+        .bind::<Text, _>(table_identifier.escaped())
+        .get_result::<ChunkName>(conn)
+        .optional()?;
+
+        Ok(chunk.map(|chunk| chunk.chunk_name))
+    }
+
+    /// Compress the chunk `name` in `schema`, as listed by [`show_chunks`].
+    ///
+    /// Returns `false` without doing anything if the chunk is already
+    /// compressed.
+    pub fn compress_chunk_by_name(
+        conn: &mut PgConnection,
+        schema: &str,
+        name: &str,
+    ) -> Result<bool, TimescaleError> {
+        let chunk = SqlIdentifier::qualified(schema, name)?;
+        Ok(compress_chunks(conn, &[chunk.as_str()])? == 1)
+    }
+
+    /// List compressed chunks whose compression settings differ from the
+    /// hypertable's current settings.
+    ///
+    /// Changing `compress_segmentby` or `compress_orderby` only affects chunks
+    /// compressed afterwards; the returned chunks keep the old settings until
+    /// they are decompressed and compressed again. Per-chunk settings are only
+    /// recorded from TimescaleDB 2.14, so older versions return an error.
+    pub fn compression_settings_drift(
+        conn: &mut PgConnection,
+        table_name: &str,
+    ) -> Result<Vec<String>, TimescaleError> {
+        // Validate table name
+        let table_identifier = SqlIdentifier::new(table_name)?;
+
+        let version = installed_version(conn)?;
+        if !version.at_least(2, 14) {
+            return Err(TimescaleError::UnsupportedVersion {
+                feature: "Per-chunk compression settings".to_string(),
+                required: TimescaleVersion::new(2, 14, 0),
+                removed: None,
+                installed: version,
+            });
+        }
+
+        let chunks = diesel::sql_query(
+            "SELECT c.chunk::text AS chunk_name 
+             FROM timescaledb_information.chunk_compression_settings c 
+             JOIN timescaledb_information.hypertable_compression_settings h ON h.hypertable = c.hypertable 
+             WHERE c.hypertable = $1::regclass 
+               AND (c.segmentby IS DISTINCT FROM h.segmentby OR c.orderby IS DISTINCT FROM h.orderby) 
+             ORDER BY chunk_name",
+        )
+        .bind::<Text, _>(table_identifier.escaped())
+        .load::<ChunkName>(conn)?;
+
+        Ok(chunks.into_iter().map(|chunk| chunk.chunk_name).collect())
+    }
+
+    #[derive(QueryableByName)]
+    struct ChunkWidth {
+        #[diesel(sql_type = Text)]
+        chunk_name: String,
+        #[diesel(sql_type = BigInt)]
+        width: i64,
+    }
+
+    /// List the chunks of a hypertable whose time range differs in width from
+    /// the hypertable's current chunk interval, with their actual width.
+    ///
+    /// `set_chunk_time_interval` only applies to chunks created afterwards,
+    /// so older chunks keep the previous width. Widths are in microseconds
+    /// for timestamp time columns and in the column's own units for integer
+    /// time columns. Chunk names are schema-qualified, in time order.
+    pub fn interval_drift(
+        conn: &mut PgConnection,
+        table_name: &str,
+    ) -> Result<Vec<(String, i64)>, TimescaleError> {
+        // Validate table name
+        let table_identifier = SqlIdentifier::new(table_name)?;
+
+        // Bind the quoted name so the regclass cast keeps its case
+        let chunks = diesel::sql_query(
+            "SELECT chunk_name, width FROM ( 
+                 SELECT format('%I.%I', c.chunk_schema, c.chunk_name) AS chunk_name, 
+                        COALESCE((extract(epoch FROM c.range_end - c.range_start) * 1000000)::bigint, 
+                                 c.range_end_integer - c.range_start_integer) AS width, 
+                        COALESCE((extract(epoch FROM d.time_interval) * 1000000)::bigint, 
+                                 d.integer_interval) AS configured_width, 
+                        COALESCE(extract(epoch FROM c.range_start)::numeric, c.range_start_integer) AS position 
+                 FROM timescaledb_information.chunks c 
+                 JOIN timescaledb_information.dimensions d 
+                   ON d.hypertable_schema = c.hypertable_schema 
+                  AND d.hypertable_name = c.hypertable_name 
+                  AND d.dimension_number = 1 
+                 WHERE format('%I.%I', c.hypertable_schema, c.hypertable_name)::regclass = to_regclass($1) 
+             ) widths 
+             WHERE width IS DISTINCT FROM configured_width 
+             ORDER BY position, chunk_name",
+        )
+        .bind::<Text, _>(table_identifier.escaped())
+        .load::<ChunkWidth>(conn)?;
+
+        Ok(chunks
+            .into_iter()
+            .map(|chunk| (chunk.chunk_name, chunk.width))
+            .collect())
+    }
+
+    #[derive(QueryableByName)]
+    struct ChunkTimeInterval {
+        #[diesel(sql_type = Nullable<Interval>)]
+        time_interval: Option<TimeInterval>,
+    }
+
+    #[derive(QueryableByName)]
+    struct ChunkTotal {
+        #[diesel(sql_type = BigInt)]
+        chunk_count: i64,
+    }
+
+    #[derive(QueryableByName)]
+    struct SentinelRow {
+        #[diesel(sql_type = BigInt)]
+        chunk_oid: i64,
+        #[diesel(sql_type = Text)]
+        row_id: String,
+    }
+
+    /// Create the chunks of a hypertable from now up to `until`, so that
+    /// inserts into them later do not wait for chunk creation, and return
+    /// the number of chunks created.
+    ///
+    /// TimescaleDB has no supported function for creating a chunk ahead of
+    /// time. Its internal `create_chunk` moved between schemas and needs the
+    /// slices of every dimension, so this inserts a sentinel row per chunk
+    /// interval instead, with only `time_column` set, and deletes the rows
+    /// again in the same transaction. The chunks stay, empty. This works on
+    /// every TimescaleDB version, with these caveats:
+    ///
+    /// - every other column must have a default or accept `NULL`,
+    /// - insert and delete triggers on the table fire for the sentinel rows,
+    ///   and continuous aggregates on the table record the range as
+    ///   invalidated,
+    /// - on a hypertable with a space dimension only the chunks of the
+    ///   partition the default value hashes to are created.
+    ///
+    /// Integer-time hypertables are not supported.
+    pub fn precreate_chunks(
+        conn: &mut PgConnection,
+        table_name: &str,
+        time_column: &str,
+        until: chrono::DateTime<chrono::Utc>,
+    ) -> Result<usize, TimescaleError> {
+        // Validate identifiers
+        let table_identifier = SqlIdentifier::new(table_name)?;
+        let time_identifier = SqlIdentifier::column(time_column)?;
+
+        if until <= chrono::Utc::now() {
+            return Ok(0);
+        }
+
+        // Bind the quoted name so the regclass cast keeps its case
+        let interval = diesel::sql_query(
+            "SELECT time_interval 
+             FROM timescaledb_information.dimensions 
+             WHERE format('%I.%I', hypertable_schema, hypertable_name)::regclass = to_regclass($1) 
+               AND dimension_number = 1",
+        )
+        .bind::<Text, _>(table_identifier.escaped())
+        .get_result::<ChunkTimeInterval>(conn)?
+        .time_interval
+        .ok_or_else(|| {
+            ValidationError::InvalidParameter(format!(
+                "Cannot precreate chunks of '{}', which has an integer time column",
+                table_name
+            ))
+        })?;
+
+        conn.transaction(|conn| {
+            let count_chunks = |conn: &mut PgConnection| {
+                diesel::sql_query(
+                    "SELECT count(*) AS chunk_count FROM show_chunks(to_regclass($1))",
+                )
+                .bind::<Text, _>(table_identifier.escaped())
+                .get_result::<ChunkTotal>(conn)
+                .map(|total| total.chunk_count)
+            };
+            let before = count_chunks(conn)?;
+
+            // One row per chunk interval, and one at `until` itself
+            let rows = diesel::sql_query(format!(
+                "INSERT INTO {table} ({time}) 
+                 SELECT t FROM generate_series(now(), $1, INTERVAL '{interval}') AS t 
+                 UNION SELECT $1 
+                 RETURNING tableoid::bigint AS chunk_oid, ctid::text AS row_id",
+                table = table_identifier.escaped(),
+                time = time_identifier.escaped(),
+                interval = interval.to_postgres_interval(),
+            ))
+            .bind::<Timestamptz, _>(until)
+            .load::<SentinelRow>(conn)?;
+
+            let mut by_chunk: std::collections::BTreeMap<i64, Vec<String>> =
+                std::collections::BTreeMap::new();
+            for row in rows {
+                by_chunk.entry(row.chunk_oid).or_default().push(row.row_id);
+            }
+            for (chunk_oid, row_ids) in by_chunk {
+                diesel::sql_query(format!(
+                    "DELETE FROM {} WHERE tableoid = $1::oid AND ctid = ANY($2::tid[])",
+                    table_identifier.escaped()
+                ))
+                .bind::<BigInt, _>(chunk_oid)
+                .bind::<Array<Text>, _>(row_ids)
+                .execute(conn)?;
+            }
+
+            let after = count_chunks(conn)?;
+            Ok(usize::try_from(after - before).unwrap_or(0))
+        })
+    }
+}
+
+/// Background job utilities.
+pub mod jobs {
+    use super::*;
+
+    /// A failed run of a background job.
+    #[derive(Debug, Clone, QueryableByName)]
+    pub struct JobError {
+        #[diesel(sql_type = Integer)]
+        pub job_id: i32,
+        /// The error message reported by the failed run.
+        #[diesel(sql_type = Nullable<Text>)]
+        pub error_data: Option<String>,
+        #[diesel(sql_type = Nullable<Timestamptz>)]
+        pub start_time: Option<chrono::DateTime<chrono::Utc>>,
+        #[diesel(sql_type = Nullable<Timestamptz>)]
+        pub finish_time: Option<chrono::DateTime<chrono::Utc>>,
+    }
+
+    /// Get the recorded failures of one job, or of all jobs, newest first.
+    ///
+    /// TimescaleDB 2.15 replaced the `job_errors` view with `job_history`,
+    /// which records successful runs as well; only failed runs are returned
+    /// from either. The history is pruned by TimescaleDB after a month by
+    /// default.
+    pub fn job_errors(
+        conn: &mut PgConnection,
+        job_id: Option<i32>,
+    ) -> Result<Vec<JobError>, TimescaleError> {
+        let version = installed_version(conn)?;
+        let source = if version.at_least(2, 15) {
+            "timescaledb_information.job_history WHERE succeeded IS FALSE AND"
+        } else {
+            "timescaledb_information.job_errors WHERE"
+        };
+
+        diesel::sql_query(format!(
+            "SELECT job_id, err_message AS error_data, start_time, finish_time 
+             FROM {} ($1 IS NULL OR job_id = $1) 
+             ORDER BY start_time DESC NULLS LAST",
+            source
+        ))
+        .bind::<Nullable<Integer>, _>(job_id)
+        .load::<JobError>(conn)
+        .map_err(TimescaleError::from)
+    }
+
+    /// A background job, such as a compression, retention or refresh policy.
+    #[derive(Debug, Clone, QueryableByName)]
+    pub struct JobInfo {
+        #[diesel(sql_type = Integer)]
+        pub job_id: i32,
+        /// The name TimescaleDB gave the job, like `Retention Policy [1000]`.
+        #[diesel(sql_type = Text)]
+        pub application_name: String,
+        /// The job procedure, like `policy_retention`.
+        #[diesel(sql_type = Text)]
+        pub proc_name: String,
+        #[diesel(sql_type = Interval)]
+        pub schedule_interval: TimeInterval,
+        /// Whether the job is scheduled to run automatically.
+        #[diesel(sql_type = Bool)]
+        pub scheduled: bool,
+        /// The schema of the hypertable the job works on, if any.
+        #[diesel(sql_type = Nullable<Text>)]
+        pub hypertable_schema: Option<String>,
+        /// The hypertable the job works on, if any.
+        #[diesel(sql_type = Nullable<Text>)]
+        pub hypertable_name: Option<String>,
+    }
+
+    /// Build the statement that lists the background jobs, of one hypertable
+    /// or of all, by job id.
+    pub fn list_jobs_sql(hypertable_name: Option<&str>) -> Result<SqlStatement, ValidationError> {
+        let statement = SqlStatement::new(
+            "SELECT job_id, application_name::text, proc_name::text, schedule_interval, scheduled,
+                    hypertable_schema::text, hypertable_name::text
+             FROM timescaledb_information.jobs",
+        );
+        let statement = match hypertable_name {
+            Some(hypertable_name) => {
+                // Validate table name
+                let table_identifier = SqlIdentifier::new(hypertable_name)?;
+                // Jobs without a hypertable have no schema; quote_ident keeps
+                // their NULL names from failing the cast. Bind the quoted
+                // name so the regclass cast keeps its case
+                statement
+                    .sql(
+                        " WHERE to_regclass(quote_ident(hypertable_schema) || '.' || \
+                         quote_ident(hypertable_name)) = to_regclass(",
+                    )
+                    .bind(table_identifier.escaped())
+                    .sql(")")
+            }
+            None => statement,
+        };
+        Ok(statement.sql(" ORDER BY job_id"))
+    }
+
+    /// List the background jobs, of one hypertable or of all, by job id.
+    pub fn list_jobs(
+        conn: &mut PgConnection,
+        hypertable_name: Option<&str>,
+    ) -> Result<Vec<JobInfo>, TimescaleError> {
+        Ok(list_jobs_sql(hypertable_name)?.load(conn)?)
+    }
+
+    /// Build the statement that deletes a background job.
+    pub fn delete_job_sql(job_id: i32) -> SqlStatement {
+        SqlStatement::new(format!("SELECT delete_job({});", job_id))
+    }
+
+    /// Delete a background job, which removes the policy it implements.
+    pub fn delete_job(conn: &mut PgConnection, job_id: i32) -> Result<(), TimescaleError> {
+        delete_job_sql(job_id).execute(conn)?;
+        Ok(())
+    }
+
+    /// Build the statement that changes the schedule of a background job.
+    ///
+    /// Settings passed as `None` are left unchanged.
+    pub fn alter_job_sql(job_id: i32, schedule_interval: Option<TimeInterval>) -> SqlStatement {
+        let statement = SqlStatement::new(format!("SELECT alter_job({}", job_id));
+        let statement = match schedule_interval {
+            Some(schedule_interval) => statement
+                .sql(", schedule_interval => ")
+                .bind(schedule_interval),
+            None => statement,
+        };
+        statement.sql(");")
+    }
+
+    /// Change the schedule of a background job.
+    ///
+    /// Settings passed as `None` are left unchanged.
+    pub fn alter_job(
+        conn: &mut PgConnection,
+        job_id: i32,
+        schedule_interval: Option<TimeInterval>,
+    ) -> Result<(), TimescaleError> {
+        alter_job_sql(job_id, schedule_interval).execute(conn)?;
+        Ok(())
+    }
+
+    #[derive(QueryableByName)]
+    struct CompressedChunkCount {
+        #[diesel(sql_type = BigInt)]
+        compressed_chunks: i64,
+    }
+
+    fn compressed_chunk_count(conn: &mut PgConnection, job_id: i32) -> QueryResult<i64> {
+        let row = diesel::sql_query(
+            "SELECT count(*) AS compressed_chunks 
+             FROM timescaledb_information.jobs j, 
+                  chunk_compression_stats(format('%I.%I', j.hypertable_schema, j.hypertable_name)::regclass) s 
+             WHERE j.job_id = $1 AND s.compression_status = 'Compressed'",
+        )
+        .bind::<Integer, _>(job_id)
+        .get_result::<CompressedChunkCount>(conn)?;
+        Ok(row.compressed_chunks)
+    }
+
+    /// Run a compression policy job now and return the number of chunks it
+    /// compressed.
+    ///
+    /// `run_job` reports nothing, so the compressed chunks of the job's
+    /// hypertable are counted before and after the run. Chunks compressed or
+    /// decompressed concurrently by other sessions are included in the
+    /// difference, which is never negative.
+    ///
+    /// The job commits after each chunk, so this must not be called inside a
+    /// transaction. Fails with an invalid parameter error if `job_id` is not a
+    /// compression policy job.
+    pub fn run_compression_job(
+        conn: &mut PgConnection,
+        job_id: i32,
+    ) -> Result<i64, TimescaleError> {
+        let jobs = diesel::sql_query(
+            "SELECT job_id FROM timescaledb_information.jobs 
+             WHERE job_id = $1 AND proc_name = 'policy_compression'",
+        )
+        .bind::<Integer, _>(job_id)
+        .load::<JobId>(conn)?;
+        if jobs.is_empty() {
+            return Err(ValidationError::InvalidParameter(format!(
+                "Job {} is not a compression policy job",
+                job_id
+            ))
+            .into());
+        }
+
+        let before = compressed_chunk_count(conn, job_id)?;
+        diesel::sql_query("CALL run_job($1)")
+            .bind::<Integer, _>(job_id)
+            .execute(conn)?;
+        let after = compressed_chunk_count(conn, job_id)?;
+
+        Ok((after - before).max(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::statement::BindValue;
+    use chrono::TimeZone;
+
+    fn utc(
+        year: i32,
+        month: u32,
+        day: u32,
+        hour: u32,
+        minute: u32,
+    ) -> chrono::DateTime<chrono::Utc> {
+        chrono::Utc
+            .with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn hourly_bucket_starts_are_on_the_hour() {
+        let starts = TimeInterval::new(1, TimeUnit::Hours)
+            .bucket_starts(utc(2026, 3, 14, 10, 37), utc(2026, 3, 14, 13, 0))
+            .unwrap();
+        assert_eq!(
+            starts,
+            vec![
+                utc(2026, 3, 14, 10, 0),
+                utc(2026, 3, 14, 11, 0),
+                utc(2026, 3, 14, 12, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn daily_bucket_starts_are_at_midnight_utc() {
+        let starts = TimeInterval::new(1, TimeUnit::Days)
+            .bucket_starts(utc(2026, 3, 14, 10, 37), utc(2026, 3, 16, 0, 1))
+            .unwrap();
+        assert_eq!(
+            starts,
+            vec![
+                utc(2026, 3, 14, 0, 0),
+                utc(2026, 3, 15, 0, 0),
+                utc(2026, 3, 16, 0, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn bucket_starts_follow_the_default_origin() {
+        // 2026-03-14 is a Saturday; weeks start on Mondays like the origin
+        let weeks = TimeInterval::new(1, TimeUnit::Weeks)
+            .bucket_starts(utc(2026, 3, 14, 0, 0), utc(2026, 3, 15, 0, 0))
+            .unwrap();
+        assert_eq!(weeks, vec![utc(2026, 3, 9, 0, 0)]);
+
+        let ninety_minutes = TimeInterval::new(90, TimeUnit::Minutes)
+            .bucket_starts(utc(2026, 3, 14, 10, 37), utc(2026, 3, 14, 11, 0))
+            .unwrap();
+        assert_eq!(ninety_minutes, vec![utc(2026, 3, 14, 10, 30)]);
+    }
+
+    #[test]
+    fn bucket_starts_of_an_empty_range_are_empty() {
+        let start = utc(2026, 3, 14, 10, 0);
+        assert!(TimeInterval::new(1, TimeUnit::Hours)
+            .bucket_starts(start, start)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn calendar_bucket_starts_are_rejected() {
+        let result = TimeInterval::new(1, TimeUnit::Months)
+            .bucket_starts(utc(2026, 1, 1, 0, 0), utc(2026, 6, 1, 0, 0));
+        assert!(matches!(result, Err(ValidationError::InvalidInterval(_))));
+    }
+
+    #[test]
+    fn integer_chunk_interval_is_rendered_without_interval_keyword() {
+        let statement = HypertableConfig::new("events", "ts")
+            .with_chunk_time_interval_int(86_400_000)
+            .create_sql()
+            .unwrap();
+        assert_eq!(
+            statement.to_string(),
+            "SELECT create_hypertable('\"events\"', 'ts', chunk_time_interval => 86400000);"
+        );
+    }
+
+    #[test]
+    fn chunk_interval_forms_are_mutually_exclusive() {
+        let result = HypertableConfig::new("events", "ts")
+            .with_chunk_time_interval(TimeInterval::new(1, TimeUnit::Days))
+            .with_chunk_time_interval_int(86_400_000)
+            .create_sql();
+        assert!(matches!(result, Err(ValidationError::InvalidInterval(_))));
+    }
+
+    #[test]
+    fn integer_chunk_interval_must_be_positive() {
+        for width in [0, -1] {
+            let result = HypertableConfig::new("events", "ts")
+                .with_chunk_time_interval_int(width)
+                .create_sql();
+            assert!(matches!(result, Err(ValidationError::InvalidInterval(_))));
+        }
+    }
+
+    diesel::table! {
+        sensor_readings (timestamp) {
+            timestamp -> Timestamptz,
+            device_id -> Integer,
+        }
+    }
+
+    crate::hypertable!(
+        sensor_readings,
+        timestamp,
+        chunk_interval = "1 day",
+        partition_by = device_id,
+        partitions = 4,
+        if_not_exists = true
+    );
+
+    #[test]
+    fn hypertable_macro_options_are_rendered_by_the_config() {
+        type Readings = sensor_readings::table;
+        let sql = Readings::create_hypertable_sql().unwrap().to_string();
+        assert!(sql.contains("chunk_time_interval"));
+        assert!(sql.contains("partitioning_column"));
+        assert!(sql.contains("if_not_exists"));
+        assert_eq!(
+            sql,
+            Readings::hypertable_config()
+                .unwrap()
+                .create_sql()
+                .unwrap()
+                .to_string()
+        );
+        assert_eq!(
+            sql,
+            "SELECT create_hypertable('\"sensor_readings\"', 'timestamp', \
+             partitioning_column => 'device_id', number_partitions => 4, \
+             chunk_time_interval => INTERVAL '1 days', if_not_exists => true);"
+        );
+    }
+
+    #[test]
+    fn hypertable_partitioning_needs_a_positive_number_of_partitions() {
+        for partitions in [0, -1] {
+            let result = HypertableConfig::new("events", "ts")
+                .with_partitioning("device_id", partitions)
+                .create_sql();
+            assert!(matches!(result, Err(ValidationError::InvalidParameter(_))));
+        }
+    }
+
+    struct HalfPartitioned;
+
+    impl Hypertable for HalfPartitioned {
+        const TABLE_NAME: &'static str = "events";
+        const TIME_COLUMN: &'static str = "ts";
+        const PARTITION_COLUMN: Option<&'static str> = Some("device_id");
+    }
+
+    #[test]
+    fn hypertable_config_rejects_a_partition_column_without_partitions() {
+        assert!(matches!(
+            HalfPartitioned::hypertable_config(),
+            Err(ValidationError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn continuous_aggregate_policy_binds_the_quoted_view_name() {
+        let statements = ContinuousAggregateConfig::new(
+            "DailyMetrics",
+            "SELECT time_bucket('1 day', time) AS bucket, avg(value) FROM metrics GROUP BY bucket",
+        )
+        .with_refresh_interval("1 hour")
+        .create_sql()
+        .unwrap();
+        assert!(statements[0]
+            .to_string()
+            .starts_with("CREATE MATERIALIZED VIEW \"DailyMetrics\" WITH"));
+        assert_eq!(
+            statements[1].to_string(),
+            "SELECT add_continuous_aggregate_policy('\"DailyMetrics\"', \
+             start_offset => NULL, end_offset => INTERVAL '1 hours');"
+        );
+    }
+
+    #[test]
+    fn finalize_binds_the_quoted_view_name() {
+        assert_eq!(
+            finalize_continuous_aggregate_sql("DailyMetrics")
+                .unwrap()
+                .to_string(),
+            "CALL cagg_migrate('\"DailyMetrics\"'::regclass, override => true);"
+        );
+    }
+
+    #[test]
+    fn interval_policy_compares_units_not_lengths() {
+        let policy = IntervalPolicy::default().with_min_unit(TimeUnit::Seconds);
+        assert!(TimeInterval::from_string_with_policy("1 second", &policy).is_ok());
+        assert!(TimeInterval::from_string_with_policy("2 days", &policy).is_ok());
+        assert!(matches!(
+            TimeInterval::from_string_with_policy("1000 milliseconds", &policy),
+            Err(ValidationError::InvalidInterval(_))
+        ));
+    }
+
+    #[test]
+    fn default_interval_policy_accepts_every_interval() {
+        let policy = IntervalPolicy::default();
+        assert!(TimeInterval::from_string_with_policy("1 microsecond", &policy).is_ok());
+        assert!(TimeInterval::from_string_with_policy("soon", &policy).is_err());
+    }
+
+    #[test]
+    fn time_units_are_ordered_by_size() {
+        assert!(TimeUnit::Microseconds < TimeUnit::Milliseconds);
+        assert!(TimeUnit::Hours < TimeUnit::Days);
+        assert!(TimeUnit::Weeks < TimeUnit::Months);
+        assert!(TimeUnit::Months < TimeUnit::Years);
+    }
+
+    #[test]
+    fn best_fit_picks_the_largest_exact_unit() {
+        let cases = [
+            (chrono::Duration::minutes(90), Some((90, TimeUnit::Minutes))),
+            (chrono::Duration::days(14), Some((2, TimeUnit::Weeks))),
+            (
+                chrono::Duration::milliseconds(1500),
+                Some((1500, TimeUnit::Milliseconds)),
+            ),
+            (chrono::Duration::zero(), Some((0, TimeUnit::Microseconds))),
+            (chrono::Duration::nanoseconds(1500), None),
+            (chrono::Duration::hours(-1), None),
+        ];
+        for (duration, expected) in cases {
+            assert_eq!(TimeUnit::best_fit(duration), expected, "{:?}", duration);
+        }
+    }
+
+    #[test]
+    fn chrono_durations_round_trip_through_best_fit() {
+        for (value, unit) in [
+            (36, TimeUnit::Hours),
+            (3, TimeUnit::Weeks),
+            (7, TimeUnit::Seconds),
+        ] {
+            let duration = unit.to_chrono_duration(value).unwrap();
+            let (fit_value, fit_unit) = TimeUnit::best_fit(duration).unwrap();
+            assert_eq!(fit_unit.to_chrono_duration(fit_value), Some(duration));
+        }
+    }
+
+    #[test]
+    fn calendar_units_have_no_chrono_duration() {
+        assert_eq!(TimeUnit::Months.to_chrono_duration(1), None);
+        assert_eq!(TimeUnit::Years.to_chrono_duration(1), None);
+        assert_eq!(TimeUnit::Weeks.to_chrono_duration(u64::MAX), None);
+    }
+
+    struct Events;
+
+    impl Hypertable for Events {
+        const TABLE_NAME: &'static str = "events";
+        const TIME_COLUMN: &'static str = "ts";
+    }
+
+    #[test]
+    fn trait_integer_interval_renders_a_plain_integer() {
+        assert_eq!(
+            Events::create_hypertable_with_integer_interval_sql(86_400_000)
+                .unwrap()
+                .to_string(),
+            "SELECT create_hypertable('\"events\"', 'ts', chunk_time_interval => 86400000);"
+        );
+        assert!(matches!(
+            Events::create_hypertable_with_integer_interval_sql(0),
+            Err(ValidationError::InvalidInterval(_))
+        ));
+    }
+
+    #[test]
+    fn dynamic_integer_interval_matches_the_trait() {
+        let hypertable = DynamicHypertable::from_names("events", "ts").unwrap();
+        assert_eq!(
+            hypertable
+                .create_hypertable_with_integer_interval_sql(86_400_000)
+                .unwrap(),
+            Events::create_hypertable_with_integer_interval_sql(86_400_000).unwrap()
+        );
+        assert!(hypertable
+            .create_hypertable_with_integer_interval_sql(-5)
+            .is_err());
+    }
+
+    #[test]
+    fn chunk_info_statement_binds_the_quoted_table_name() {
+        let statement = chunks::get_chunk_info_sql("Metrics").unwrap();
+        assert!(statement
+            .placeholder_sql()
+            .ends_with("::regclass = to_regclass($1)"));
+        assert_eq!(
+            statement.binds(),
+            vec![&BindValue::Text("\"Metrics\"".to_string())]
+        );
+    }
+
+    #[test]
+    fn chunk_info_statement_rejects_invalid_table_names() {
+        assert!(matches!(
+            chunks::get_chunk_info_sql("metrics; DROP TABLE metrics"),
+            Err(ValidationError::InvalidIdentifier(_))
+        ));
+    }
+
+    #[test]
+    fn refresh_batching_options_are_rendered_only_where_supported() {
+        let config = ContinuousAggregateConfig::new(
+            "metrics_hourly",
+            "SELECT time_bucket('1 hour', ts) AS bucket FROM metrics GROUP BY bucket",
+        )
+        .with_refresh_interval("1 hour")
+        .with_buckets_per_batch(10)
+        .with_max_batches_per_execution(2);
+
+        let policy = |statements: Vec<SqlStatement>| statements[1].placeholder_sql();
+        assert_eq!(
+            policy(
+                config
+                    .create_sql_for(TimescaleVersion::new(2, 19, 0))
+                    .unwrap()
+            ),
+            "SELECT add_continuous_aggregate_policy($1, start_offset => NULL, \
+             end_offset => INTERVAL '1 hours', buckets_per_batch => 10, \
+             max_batches_per_execution => 2);"
+        );
+        let without_batching = "SELECT add_continuous_aggregate_policy($1, \
+             start_offset => NULL, end_offset => INTERVAL '1 hours');";
+        assert_eq!(
+            policy(
+                config
+                    .create_sql_for(TimescaleVersion::new(2, 18, 2))
+                    .unwrap()
+            ),
+            without_batching
+        );
+        assert_eq!(policy(config.create_sql().unwrap()), without_batching);
+    }
+
+    #[test]
+    fn negative_refresh_batching_options_are_rejected_on_every_version() {
+        let config = ContinuousAggregateConfig::new(
+            "metrics_hourly",
+            "SELECT time_bucket('1 hour', ts) AS bucket FROM metrics GROUP BY bucket",
+        )
+        .with_refresh_interval("1 hour")
+        .with_buckets_per_batch(-1);
+
+        assert!(matches!(
+            config.create_sql(),
+            Err(ValidationError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            config.create_sql_for(TimescaleVersion::new(2, 19, 0)),
+            Err(ValidationError::InvalidParameter(_))
+        ));
+    }
+
+    fn device_compression() -> CompressionConfig {
+        CompressionConfig::new("metrics")
+            .with_time_column("ts")
+            .with_segment_by("device_id")
+            .with_segment_by("Region")
+            .with_order_by(("ts", SortDirection::Desc, NullsOrder::Last))
+            .with_order_by("sensor")
+            .with_policy(TimeInterval::new(7, TimeUnit::Days))
+    }
+
+    #[test]
+    fn compression_ddl_quotes_multi_column_segment_by() {
+        let statements = device_compression().create_sql().unwrap();
+        assert_eq!(statements.len(), 2);
+        assert_eq!(
+            statements[0].to_string(),
+            "ALTER TABLE \"metrics\" SET (timescaledb.compress, \
+             timescaledb.compress_segmentby = '\"device_id\", \"Region\"', \
+             timescaledb.compress_orderby = '\"ts\" DESC NULLS LAST, \"sensor\"');"
+        );
+        assert_eq!(
+            statements[1].to_string(),
+            "SELECT add_compression_policy('\"metrics\"', INTERVAL '7 days');"
+        );
+    }
+
+    #[test]
+    fn columnstore_ddl_uses_the_renamed_options() {
+        let statements = device_compression()
+            .create_sql_for(TimescaleVersion::new(2, 18, 0))
+            .unwrap();
+        assert_eq!(
+            statements[0].to_string(),
+            "ALTER TABLE \"metrics\" SET (timescaledb.enable_columnstore, \
+             timescaledb.segmentby = '\"device_id\", \"Region\"', \
+             timescaledb.orderby = '\"ts\" DESC NULLS LAST, \"sensor\"');"
+        );
+        assert_eq!(
+            statements[1].to_string(),
+            "CALL add_columnstore_policy('\"metrics\"', after => INTERVAL '7 days');"
+        );
+    }
+
+    #[test]
+    fn compression_rejects_injected_columns_and_the_time_column() {
+        let injected = CompressionConfig::new("metrics").with_segment_by("device_id'; DROP");
+        assert!(matches!(
+            injected.create_sql(),
+            Err(ValidationError::InvalidIdentifier(_))
+        ));
+
+        let time_segment = CompressionConfig::new("metrics")
+            .with_time_column("ts")
+            .with_segment_by("ts");
+        assert!(matches!(
+            time_segment.create_sql(),
+            Err(ValidationError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn job_statements_render_the_job_id_and_schedule() {
+        assert_eq!(
+            jobs::delete_job_sql(1000).to_string(),
+            "SELECT delete_job(1000);"
+        );
+        assert_eq!(
+            jobs::alter_job_sql(1000, None).to_string(),
+            "SELECT alter_job(1000);"
+        );
+
+        let statement = jobs::alter_job_sql(1000, Some(TimeInterval::new(30, TimeUnit::Minutes)));
+        assert_eq!(
+            statement.placeholder_sql(),
+            "SELECT alter_job(1000, schedule_interval => $1);"
+        );
+        assert_eq!(
+            statement.binds(),
+            vec![&BindValue::Interval(TimeInterval::new(
+                30,
+                TimeUnit::Minutes
+            ))]
+        );
+    }
+
+    #[test]
+    fn composite_intervals_round_trip_through_their_postgres_form() {
+        for (input, rendered) in [
+            ("1 day 12 hours", "1 days 12 hours"),
+            ("1 day 6 hours 30 minutes", "1 days 6 hours 30 minutes"),
+            ("90 minutes", "90 minutes"),
+            ("2 weeks 3 days", "2 weeks 3 days"),
+            ("-1 day -6 hours", "-1 days -6 hours"),
+        ] {
+            let interval = TimeInterval::from_string(input).unwrap();
+            assert_eq!(interval.to_postgres_interval(), rendered, "{}", input);
+            assert_eq!(
+                TimeInterval::from_string(rendered).unwrap(),
+                interval,
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn fractional_intervals_are_normalized_to_smaller_units() {
+        for (input, rendered) in [
+            ("0.5 hours", "30 minutes"),
+            ("1.5 days", "1 days 12 hours"),
+            ("2.25 minutes", "2 minutes 15 seconds"),
+            ("1.5 seconds", "1 seconds 500 milliseconds"),
+        ] {
+            assert_eq!(
+                TimeInterval::from_string(input)
+                    .unwrap()
+                    .to_postgres_interval(),
+                rendered,
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_composite_intervals_are_rejected() {
+        for input in ["1 day 12", "1 day -6 hours", "0.5 months", "1 fortnight"] {
+            assert!(
+                matches!(
+                    TimeInterval::from_string(input),
+                    Err(ValidationError::InvalidInterval(_))
+                ),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn single_unit_constructor_matches_the_parsed_interval() {
+        let interval = TimeInterval::new(7, TimeUnit::Days);
+        assert_eq!(interval.to_postgres_interval(), "7 days");
+        assert_eq!(TimeInterval::from_string("7 days").unwrap(), interval);
+    }
+
+    #[test]
+    fn qualified_identifiers_quote_each_part() {
+        let identifier = SqlIdentifier::new("analytics.Metrics").unwrap();
+        assert_eq!(identifier.escaped(), "\"analytics\".\"Metrics\"");
+        assert_eq!(identifier.schema(), Some("analytics"));
+        assert_eq!(identifier.name(), "Metrics");
+        assert_eq!(
+            SqlIdentifier::qualified("analytics", "Metrics").unwrap(),
+            identifier
+        );
+        assert_eq!(SqlIdentifier::new("metrics").unwrap().schema(), None);
+    }
+
+    #[test]
+    fn identifiers_with_too_many_parts_are_rejected() {
+        for identifier in [
+            "db.analytics.metrics",
+            "analytics.",
+            ".metrics",
+            "analytics..metrics",
+        ] {
+            assert!(
+                matches!(
+                    SqlIdentifier::new(identifier),
+                    Err(ValidationError::InvalidIdentifier(_))
+                ),
+                "{}",
+                identifier
+            );
+        }
+    }
+
+    #[test]
+    fn column_identifiers_cannot_be_qualified() {
+        assert_eq!(SqlIdentifier::column("ts").unwrap().escaped(), "\"ts\"");
+        assert!(matches!(
+            SqlIdentifier::column("metrics.ts"),
+            Err(ValidationError::InvalidIdentifier(_))
+        ));
+        assert!(matches!(
+            DynamicHypertable::from_names("analytics.metrics", "metrics.ts"),
+            Err(ValidationError::InvalidIdentifier(_))
+        ));
+        assert!(DynamicHypertable::from_names("analytics.metrics", "ts").is_ok());
+    }
+
+    #[test]
+    fn jobs_of_a_qualified_hypertable_are_looked_up_by_regclass() {
+        let statement = jobs::list_jobs_sql(Some("analytics.Metrics")).unwrap();
+        assert!(statement.placeholder_sql().contains(
+            "WHERE to_regclass(quote_ident(hypertable_schema) || '.' || \
+             quote_ident(hypertable_name)) = to_regclass($1) ORDER BY job_id"
+        ));
+        assert_eq!(
+            statement.binds(),
+            vec![&BindValue::Text("\"analytics\".\"Metrics\"".to_string())]
+        );
+
+        let all = jobs::list_jobs_sql(None).unwrap();
+        assert!(!all.placeholder_sql().contains("WHERE"));
+        assert!(all.binds().is_empty());
+
+        assert!(matches!(
+            jobs::list_jobs_sql(Some("metrics; DROP TABLE metrics")),
+            Err(ValidationError::InvalidIdentifier(_))
+        ));
+    }
+
+    #[test]
+    fn realtime_view_finds_its_aggregate_by_regclass() {
+        let statement = ContinuousAggregateConfig::new(
+            "analytics.Metrics_hourly",
+            "SELECT time_bucket('1 hour', ts) AS bucket FROM metrics GROUP BY bucket",
+        )
+        .with_realtime_view("bucket")
+        .realtime_view_sql(TimescaleVersion::new(2, 14, 0))
+        .unwrap()
+        .unwrap();
+        let sql = statement.to_string();
+        assert!(sql.starts_with("CREATE VIEW \"analytics\".\"Metrics_hourly_realtime\" AS"));
+        assert!(sql.contains(
+            "WHERE format('%I.%I', user_view_schema, user_view_name)::regclass = \
+             to_regclass('\"analytics\".\"Metrics_hourly\"')"
+        ));
+        assert!(!sql.contains("current_schemas"));
+    }
+
+    fn hourly_aggregate() -> ContinuousAggregateConfig {
+        ContinuousAggregateConfig::new(
+            "Metrics_hourly",
+            "SELECT time_bucket('1 hour', ts) AS bucket, avg(value) FROM metrics GROUP BY bucket",
+        )
+    }
+
+    #[test]
+    fn refresh_binds_the_quoted_view_name_and_window() {
+        let statement = hourly_aggregate()
+            .refresh_sql(Some(utc(2026, 3, 14, 10, 0)), None)
+            .unwrap();
+        assert_eq!(
+            statement.placeholder_sql(),
+            "CALL refresh_continuous_aggregate($1, $2, NULL);"
+        );
+        assert_eq!(
+            statement.binds(),
+            vec![
+                &BindValue::Text("\"Metrics_hourly\"".to_string()),
+                &BindValue::Timestamptz(utc(2026, 3, 14, 10, 0)),
+            ]
+        );
+
+        assert!(matches!(
+            hourly_aggregate()
+                .refresh_sql(Some(utc(2026, 3, 14, 11, 0)), Some(utc(2026, 3, 14, 10, 0))),
+            Err(ValidationError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn forced_refresh_renders_the_force_argument() {
+        let statement = hourly_aggregate()
+            .refresh_forced_sql(Some(utc(2026, 3, 14, 10, 0)), Some(utc(2026, 3, 14, 11, 0)))
+            .unwrap();
+        assert_eq!(
+            statement.placeholder_sql(),
+            "CALL refresh_continuous_aggregate($1, $2, $3, force => true);"
+        );
+
+        let statement = refresh_continuous_aggregate_forced_sql(
+            "Metrics_hourly",
+            utc(2026, 3, 14, 10, 0),
+            utc(2026, 3, 14, 11, 0),
+        )
+        .unwrap();
+        assert_eq!(
+            statement.placeholder_sql(),
+            "CALL refresh_continuous_aggregate($1, $2, $3, force => true);"
+        );
+        assert_eq!(
+            refresh_continuous_aggregate_sql(
+                "Metrics_hourly",
+                utc(2026, 3, 14, 10, 0),
+                utc(2026, 3, 14, 11, 0),
+            )
+            .unwrap()
+            .placeholder_sql(),
+            "CALL refresh_continuous_aggregate($1, $2, $3);"
+        );
+    }
+
+    #[test]
+    fn drop_removes_the_realtime_view_before_the_aggregate() {
+        let statements: Vec<String> = hourly_aggregate()
+            .with_realtime_view("bucket")
+            .drop_sql()
+            .unwrap()
+            .iter()
+            .map(|statement| statement.to_string())
+            .collect();
+        assert_eq!(
+            statements,
+            vec![
+                "DROP VIEW IF EXISTS \"Metrics_hourly_realtime\";",
+                "DROP MATERIALIZED VIEW \"Metrics_hourly\";",
+            ]
+        );
+    }
+
+    #[test]
+    fn intervals_are_ordered_by_their_length() {
+        assert!(90.minutes() > 1.hours());
+        assert!(59.minutes() < 1.hours());
+        assert!(1.months() > 29.days());
+        assert!(1.hours().negated() < 1.seconds());
+
+        let mut intervals = vec![1.days(), 90.minutes(), 1.hours(), 2.seconds()];
+        intervals.sort();
+        assert_eq!(
+            intervals,
+            vec![2.seconds(), 1.hours(), 90.minutes(), 1.days()]
+        );
+    }
+
+    #[test]
+    fn equally_long_intervals_are_ordered_consistently_with_eq() {
+        assert_ne!(60.minutes(), 1.hours());
+        assert_ne!(60.minutes().cmp(&1.hours()), std::cmp::Ordering::Equal);
+        assert_eq!(
+            60.minutes().cmp(&1.hours()),
+            1.hours().cmp(&60.minutes()).reverse()
+        );
+        assert_eq!(1.hours().cmp(&1.hours()), std::cmp::Ordering::Equal);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn intervals_serialize_as_postgres_interval_strings() {
+        let interval = TimeInterval::from_string("1 day 12 hours").unwrap();
+        let json = serde_json::to_string(&interval).unwrap();
+        assert_eq!(json, "\"1 days 12 hours\"");
+        assert_eq!(
+            serde_json::from_str::<TimeInterval>(&json).unwrap(),
+            interval
+        );
+
+        for interval in [90.minutes(), 3.months(), 15.seconds().negated()] {
+            let json = serde_json::to_string(&interval).unwrap();
+            assert_eq!(
+                serde_json::from_str::<TimeInterval>(&json).unwrap(),
+                interval
+            );
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn invalid_interval_strings_fail_to_deserialize() {
+        for json in ["\"every tuesday\"", "\"\"", "7"] {
+            assert!(
+                serde_json::from_str::<TimeInterval>(json).is_err(),
+                "{}",
+                json
+            );
+        }
+    }
+
+    #[test]
+    fn validate_timezone_accepts_iana_names_in_any_case() {
+        for timezone in [
+            "UTC",
+            "Europe/Berlin",
+            "America/Argentina/Buenos_Aires",
+            "Etc/GMT+3",
+            "europe/berlin",
+            "AMERICA/NEW_YORK",
+        ] {
+            assert!(validate_timezone(timezone).is_ok(), "{}", timezone);
+        }
+    }
+
+    #[test]
+    fn validate_timezone_rejects_unknown_names() {
+        for timezone in [
+            "Americ/New_York",
+            "Europe/Berlinn",
+            "Mars/Olympus_Mons",
+            "",
+            "/Berlin",
+            "UTC'; DROP TABLE metrics; --",
+        ] {
+            assert!(
+                matches!(
+                    validate_timezone(timezone),
+                    Err(ValidationError::InvalidTimezone(_))
+                ),
+                "{:?}",
+                timezone
+            );
+        }
+    }
+
+    #[test]
+    fn truncate_quotes_the_validated_table_name() {
+        assert_eq!(
+            sensor_readings::table::truncate_sql().unwrap().to_string(),
+            "TRUNCATE TABLE \"sensor_readings\";"
+        );
+        assert_eq!(
+            HalfPartitioned::truncate_restart_identity_sql()
+                .unwrap()
+                .to_string(),
+            "TRUNCATE TABLE \"events\" RESTART IDENTITY;"
+        );
+
+        let hypertable = DynamicHypertable::from_names("analytics.Metrics", "ts").unwrap();
+        assert_eq!(
+            hypertable.truncate_sql().to_string(),
+            "TRUNCATE TABLE \"analytics\".\"Metrics\";"
+        );
+    }
+
+    struct InjectedName;
+
+    impl Hypertable for InjectedName {
+        const TABLE_NAME: &'static str = "metrics; DROP TABLE users";
+        const TIME_COLUMN: &'static str = "ts";
+    }
+
+    #[test]
+    fn truncate_rejects_an_invalid_table_name_constant() {
+        assert!(InjectedName::truncate_sql().is_err());
+        assert!(InjectedName::truncate_restart_identity_sql().is_err());
+    }
+
+    #[test]
+    fn value_at_statements_quote_the_columns_and_order_by_time() {
+        assert_eq!(
+            last_with_time_sql("Metrics", "value", "time")
+                .unwrap()
+                .to_string(),
+            "SELECT \"value\"::float8 AS value, \"time\" AS at\n         FROM \"Metrics\"\n         \
+             WHERE \"value\" IS NOT NULL AND \"time\" IS NOT NULL\n         \
+             ORDER BY \"time\" DESC\n         LIMIT 1;"
+        );
+        assert!(first_with_time_sql("metrics", "value", "time")
+            .unwrap()
+            .to_string()
+            .contains("ORDER BY \"time\" ASC"));
+    }
+
+    #[test]
+    fn value_at_statements_reject_qualified_and_injected_columns() {
+        for (value, time) in [
+            ("metrics.value", "time"),
+            ("value", "time; DROP TABLE metrics"),
+            ("", "time"),
+        ] {
+            assert!(
+                last_with_time_sql("metrics", value, time).is_err(),
+                "{} {}",
+                value,
+                time
+            );
+        }
+    }
+
+    #[test]
+    fn require_positive_names_the_interval() {
+        assert!(TimeInterval::new(1, TimeUnit::Hours)
+            .require_positive("Bucket interval")
+            .is_ok());
+        for interval in [
+            TimeInterval::new(0, TimeUnit::Hours),
+            TimeInterval::new(1, TimeUnit::Hours).negated(),
+        ] {
+            match interval.require_positive("Bucket interval") {
+                Err(ValidationError::InvalidInterval(message)) => {
+                    assert!(message.starts_with("Bucket interval must be positive"))
+                }
+                other => panic!("expected an invalid interval, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn validate_aliases_rejects_bucket_and_repeated_aliases() {
+        let identifiers = validate_aliases("metric alias", ["avg_temp", "max_temp"]).unwrap();
+        assert_eq!(identifiers[1].escaped(), "\"max_temp\"");
+
+        for aliases in [["bucket", "avg_temp"], ["avg_temp", "avg_temp"]] {
+            match validate_aliases("metric alias", aliases) {
+                Err(ValidationError::InvalidParameter(message)) => {
+                    assert!(message.starts_with("Duplicate metric alias"))
+                }
+                other => panic!("expected a duplicate alias, got {:?}", other),
+            }
+        }
+        assert!(matches!(
+            validate_aliases("metric alias", ["metrics.avg_temp"]),
+            Err(ValidationError::InvalidIdentifier(_))
+        ));
+    }
+
+    #[test]
+    fn bucketed_queries_share_alias_validation() {
+        let hour = TimeInterval::new(1, TimeUnit::Hours);
+        let range =
+            crate::dsl::TimeRange::new(utc(2026, 3, 1, 0, 0), utc(2026, 3, 2, 0, 0)).unwrap();
+
+        let pivot = PivotQuery::new("metrics", "ts", "device", "temp", hour.clone())
+            .with_group_as("a", "bucket");
+        let metrics = MultiMetric::new("metrics", "ts", hour.clone())
+            .with_metric("temp", "temp", RollupAggregate::Avg)
+            .with_metric("temp", "temp", RollupAggregate::Max);
+        let gapfill = BucketGapfill::new("metrics", "ts", hour, range)
+            .with_value("temp", "temp", RollupAggregate::Avg, GapfillFill::None)
+            .with_value("temp", "temp", RollupAggregate::Avg, GapfillFill::Locf);
+
+        for result in [pivot.query_sql(), metrics.query_sql(), gapfill.query_sql()] {
+            assert!(matches!(result, Err(ValidationError::InvalidParameter(_))));
+        }
+    }
+
+    #[test]
+    fn bucket_gapfill_binds_the_range_into_the_call_and_the_predicate() {
+        let range =
+            crate::dsl::TimeRange::new(utc(2026, 3, 14, 10, 0), utc(2026, 3, 14, 14, 0)).unwrap();
+        let query = BucketGapfill::new(
+            "Metrics",
+            "ts",
+            TimeInterval::new(1, TimeUnit::Hours),
+            range,
+        )
+        .with_value("avg_temp", "temp", RollupAggregate::Avg, GapfillFill::None)
+        .with_value("last_temp", "temp", RollupAggregate::Max, GapfillFill::Locf)
+        .with_value(
+            "mid_temp",
+            "temp",
+            RollupAggregate::Min,
+            GapfillFill::Interpolate,
+        )
+        .query_sql()
+        .unwrap();
+        assert_eq!(
+            query.to_string(),
+            "SELECT time_bucket_gapfill(INTERVAL '1 hours', \"ts\", \
+             TIMESTAMPTZ '2026-03-14T10:00:00+00:00', TIMESTAMPTZ '2026-03-14T14:00:00+00:00') \
+             AS bucket, avg(\"temp\")::float8 AS \"avg_temp\", \
+             locf(max(\"temp\")::float8) AS \"last_temp\", \
+             interpolate(min(\"temp\")::float8) AS \"mid_temp\" \
+             FROM \"Metrics\" WHERE \"ts\" >= TIMESTAMPTZ '2026-03-14T10:00:00+00:00' \
+             AND \"ts\" < TIMESTAMPTZ '2026-03-14T14:00:00+00:00' \
+             GROUP BY bucket ORDER BY bucket"
+        );
+        assert_eq!(
+            query.binds(),
+            vec![
+                &BindValue::Timestamptz(utc(2026, 3, 14, 10, 0)),
+                &BindValue::Timestamptz(utc(2026, 3, 14, 14, 0)),
+                &BindValue::Timestamptz(utc(2026, 3, 14, 10, 0)),
+                &BindValue::Timestamptz(utc(2026, 3, 14, 14, 0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn bucket_gapfill_rejects_an_empty_range() {
+        let at = utc(2026, 3, 14, 10, 0);
+        let range = crate::dsl::TimeRange::new(at, at).unwrap();
+        let result = BucketGapfill::new(
+            "metrics",
+            "ts",
+            TimeInterval::new(1, TimeUnit::Hours),
+            range,
+        )
+        .with_value("temp", "temp", RollupAggregate::Avg, GapfillFill::None)
+        .query_sql();
+        assert!(matches!(result, Err(ValidationError::InvalidParameter(_))));
+    }
+
+    struct MixedCaseReadings;
+
+    impl Hypertable for MixedCaseReadings {
+        const TABLE_NAME: &'static str = "Readings";
+        const TIME_COLUMN: &'static str = "ts";
+    }
+
+    #[test]
+    fn regclass_arguments_keep_qualified_mixed_case_names() {
+        let quoted = BindValue::Text("\"analytics\".\"Metrics\"".to_string());
+        let hypertable = DynamicHypertable::from_names("analytics.Metrics", "ts").unwrap();
+        let version = TimescaleVersion::new(2, 13, 0);
+        let statements = vec![
+            hypertable.create_hypertable_sql(),
+            hypertable.create_hypertable_with_interval_sql(1.days()),
+            hypertable.add_compression_policy_sql(7.days()),
+            hypertable.remove_compression_policy_sql(),
+            hypertable.add_retention_policy_sql(30.days()),
+            hypertable.remove_retention_policy_sql(),
+            hypertable.add_reorder_policy_sql("metrics_ts_idx").unwrap(),
+            hypertable.remove_reorder_policy_sql(),
+            hypertable
+                .add_range_dimension_sql("ts", 1.days(), version)
+                .unwrap(),
+            hypertable
+                .add_hash_dimension_sql("device_id", 4, version)
+                .unwrap(),
+            HypertableConfig::new("analytics.Metrics", "ts")
+                .create_sql()
+                .unwrap(),
+            chunks::drop_old_chunks_sql("analytics.Metrics", utc(2026, 3, 1, 0, 0)).unwrap(),
+        ];
+        for statement in &statements {
+            assert_eq!(statement.binds()[0], &quoted, "{}", statement);
+        }
+
+        let policies = CompressionConfig::new("analytics.Metrics")
+            .with_policy(7.days())
+            .create_sql()
+            .unwrap();
+        assert_eq!(policies[1].binds()[0], &quoted);
+    }
+
+    #[test]
+    fn hypertable_trait_quotes_a_mixed_case_table_name() {
+        let quoted = BindValue::Text("\"Readings\"".to_string());
+        for statement in [
+            MixedCaseReadings::create_hypertable_sql().unwrap(),
+            MixedCaseReadings::add_compression_policy_sql(7.days()),
+            MixedCaseReadings::add_retention_policy_sql(30.days()),
+            MixedCaseReadings::remove_reorder_policy_sql(),
+        ] {
+            assert_eq!(statement.binds()[0], &quoted, "{}", statement);
+        }
+    }
+
+    #[test]
+    fn dimension_statements_reject_non_positive_intervals_and_partitions() {
+        let hypertable = DynamicHypertable::from_names("readings", "ts").unwrap();
+        let version = TimescaleVersion::new(2, 13, 0);
+
+        for interval in [0.days(), 1.days().negated()] {
+            assert!(matches!(
+                hypertable.add_range_dimension_sql("ts", interval, version),
+                Err(ValidationError::InvalidInterval(message))
+                    if message.starts_with("Dimension chunk interval must be positive")
+            ));
+        }
+        for partitions in [0, -1] {
+            assert_eq!(
+                hypertable
+                    .add_hash_dimension_sql("device_id", partitions, version)
+                    .unwrap_err()
+                    .to_string(),
+                ValidationError::InvalidParameter(format!(
+                    "Number of partitions must be positive, got {}",
+                    partitions
+                ))
+                .to_string()
+            );
+            assert!(Events::add_hash_dimension_sql("device_id", partitions, version).is_err());
+        }
+        assert!(
+            Events::add_range_dimension_sql("ts", 0.days(), TimescaleVersion::new(2, 12, 0))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn multinode_is_only_supported_from_2_0_until_2_14() {
+        assert!(require_multinode(TimescaleVersion::new(2, 0, 0)).is_ok());
+        assert!(require_multinode(TimescaleVersion::new(2, 13, 1)).is_ok());
+
+        for installed in [
+            TimescaleVersion::new(1, 7, 5),
+            TimescaleVersion::new(2, 14, 0),
+        ] {
+            assert!(matches!(
+                require_multinode(installed),
+                Err(TimescaleError::UnsupportedVersion {
+                    required,
+                    removed: Some(removed),
+                    installed: reported,
+                    ..
+                }) if required == TimescaleVersion::new(2, 0, 0)
+                    && removed == TimescaleVersion::new(2, 14, 0)
+                    && reported == installed
+            ));
+        }
+    }
 }
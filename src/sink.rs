@@ -0,0 +1,271 @@
+//! Streaming inserts on the async connection.
+//!
+//! [`InsertSink`] accepts rows one at a time, for example from a stream of
+//! readings, and writes them to a table in multi-row `INSERT` statements. A
+//! batch is written when it reaches the batch size or when its oldest row
+//! has waited for the flush interval, whichever comes first.
+//!
+//! The connection is moved into a background task that writes the batches,
+//! so rows can be sent while the previous batch is being inserted. At most
+//! one batch of rows is queued for the task; once the queue is full, the
+//! sink stops accepting rows until the task catches up.
+
+use crate::error::TimescaleError;
+use crate::schema::ValidationError;
+use diesel::pg::Pg;
+use diesel::query_builder::{InsertStatement, QueryFragment, QueryId};
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use diesel::{Insertable, QueryResult, Table};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use futures_sink::Sink;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tokio_util::sync::PollSender;
+
+enum Message<R> {
+    Row(R),
+    Flush(oneshot::Sender<()>),
+}
+
+/// A [`Sink`] that batches rows into multi-row inserts on an
+/// `AsyncPgConnection`.
+///
+/// Rows are `Insertable` values for the table, usually a struct deriving
+/// `Insertable`. Flushing the sink writes the buffered rows and waits for
+/// them to be inserted; closing it writes the remaining rows and stops the
+/// background task. An error from an insert ends the task, and is returned
+/// by the next call on the sink; the rows of the failed batch are not
+/// retried.
+///
+/// Dropping the sink without closing it still writes the buffered rows, as
+/// long as the Tokio runtime keeps running, but an error from that last
+/// insert cannot be reported. Use `close` or [`InsertSink::finish`] to wait
+/// for the rows and see the error.
+///
+/// ```rust,ignore
+/// use futures::{SinkExt, StreamExt};
+///
+/// let mut sink = InsertSink::new(conn, metrics::table, 1000, Duration::from_secs(1))?;
+/// sink.send_all(&mut readings.map(Ok)).await?;
+/// sink.close().await?;
+/// ```
+pub struct InsertSink<R> {
+    sender: PollSender<Message<R>>,
+    writer: Option<JoinHandle<QueryResult<usize>>>,
+    flushed: Option<oneshot::Receiver<()>>,
+}
+
+impl<R: Send + 'static> InsertSink<R> {
+    /// Move `conn` into a background task that inserts rows into `table`.
+    ///
+    /// A batch is written once it holds `batch_size` rows, or
+    /// `flush_interval` after its first row was sent. Postgres accepts at
+    /// most 65535 bind parameters per statement, so `batch_size` times the
+    /// number of columns must stay below that.
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub fn new<T>(
+        conn: AsyncPgConnection,
+        table: T,
+        batch_size: usize,
+        flush_interval: Duration,
+    ) -> Result<Self, ValidationError>
+    where
+        T: Table + Copy + Send + 'static,
+        Vec<R>: Insertable<T>,
+        InsertStatement<T, <Vec<R> as Insertable<T>>::Values>: QueryFragment<Pg> + QueryId + Send,
+    {
+        if batch_size == 0 {
+            return Err(ValidationError::InvalidParameter(
+                "Batch size must be greater than zero".to_string(),
+            ));
+        }
+        if flush_interval.is_zero() {
+            return Err(ValidationError::InvalidParameter(
+                "Flush interval must be greater than zero".to_string(),
+            ));
+        }
+
+        let (sender, receiver) = mpsc::channel(batch_size);
+        let writer = tokio::spawn(write_batches(
+            conn,
+            table,
+            receiver,
+            batch_size,
+            flush_interval,
+        ));
+
+        Ok(InsertSink {
+            sender: PollSender::new(sender),
+            writer: Some(writer),
+            flushed: None,
+        })
+    }
+
+    /// Write the remaining rows, stop the background task and return the
+    /// number of rows written by the sink.
+    pub async fn finish(mut self) -> Result<usize, TimescaleError> {
+        self.sender.close();
+        match self.writer.take() {
+            Some(writer) => Ok(join_writer(writer.await)?),
+            None => Err(writer_stopped().into()),
+        }
+    }
+
+    /// Wait for the background task after it stopped and return its error.
+    fn poll_writer_error(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), TimescaleError>> {
+        let Some(writer) = self.writer.as_mut() else {
+            return Poll::Ready(Err(writer_stopped().into()));
+        };
+        let result = ready!(Pin::new(writer).poll(cx));
+        self.writer = None;
+        Poll::Ready(Err(match join_writer(result) {
+            Ok(_) => writer_stopped(),
+            Err(e) => e,
+        }
+        .into()))
+    }
+}
+
+impl<R: Send + 'static> Sink<R> for InsertSink<R> {
+    type Error = TimescaleError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        match ready!(this.sender.poll_reserve(cx)) {
+            Ok(()) => Poll::Ready(Ok(())),
+            Err(_) => this.poll_writer_error(cx),
+        }
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: R) -> Result<(), Self::Error> {
+        self.get_mut()
+            .sender
+            .send_item(Message::Row(item))
+            .map_err(|_| writer_stopped().into())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        if this.flushed.is_none() {
+            if ready!(this.sender.poll_reserve(cx)).is_err() {
+                return this.poll_writer_error(cx);
+            }
+            let (ack, flushed) = oneshot::channel();
+            if this.sender.send_item(Message::Flush(ack)).is_err() {
+                return this.poll_writer_error(cx);
+            }
+            this.flushed = Some(flushed);
+        }
+
+        let flushed = this.flushed.as_mut().expect("flush was requested");
+        let result = ready!(Pin::new(flushed).poll(cx));
+        this.flushed = None;
+        match result {
+            Ok(()) => Poll::Ready(Ok(())),
+            // The task dropped the acknowledgement because an insert failed
+            Err(_) => this.poll_writer_error(cx),
+        }
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        // Closing the channel makes the task write the remaining rows and stop
+        this.sender.close();
+        let Some(writer) = this.writer.as_mut() else {
+            return Poll::Ready(Ok(()));
+        };
+        let result = ready!(Pin::new(writer).poll(cx));
+        this.writer = None;
+        Poll::Ready(join_writer(result).map(|_| ()).map_err(Into::into))
+    }
+}
+
+async fn write_batches<T, R>(
+    mut conn: AsyncPgConnection,
+    table: T,
+    mut receiver: mpsc::Receiver<Message<R>>,
+    batch_size: usize,
+    flush_interval: Duration,
+) -> QueryResult<usize>
+where
+    T: Table + Copy + Send + 'static,
+    R: Send + 'static,
+    Vec<R>: Insertable<T>,
+    InsertStatement<T, <Vec<R> as Insertable<T>>::Values>: QueryFragment<Pg> + QueryId + Send,
+{
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut deadline = None;
+    let mut written = 0;
+
+    loop {
+        let message = match deadline {
+            Some(at) => match tokio::time::timeout_at(at, receiver.recv()).await {
+                Ok(message) => message,
+                Err(_) => {
+                    written += write_batch(&mut conn, table, &mut batch).await?;
+                    deadline = None;
+                    continue;
+                }
+            },
+            None => receiver.recv().await,
+        };
+
+        match message {
+            Some(Message::Row(row)) => {
+                batch.push(row);
+                deadline = deadline.or_else(|| Some(Instant::now() + flush_interval));
+                if batch.len() < batch_size {
+                    continue;
+                }
+                written += write_batch(&mut conn, table, &mut batch).await?;
+            }
+            Some(Message::Flush(ack)) => {
+                written += write_batch(&mut conn, table, &mut batch).await?;
+                let _ = ack.send(());
+            }
+            None => {
+                written += write_batch(&mut conn, table, &mut batch).await?;
+                return Ok(written);
+            }
+        }
+        deadline = None;
+    }
+}
+
+async fn write_batch<T, R>(
+    conn: &mut AsyncPgConnection,
+    table: T,
+    batch: &mut Vec<R>,
+) -> QueryResult<usize>
+where
+    T: Table,
+    Vec<R>: Insertable<T>,
+    InsertStatement<T, <Vec<R> as Insertable<T>>::Values>: QueryFragment<Pg> + QueryId + Send,
+{
+    if batch.is_empty() {
+        return Ok(0);
+    }
+    let rows = std::mem::take(batch);
+    diesel::insert_into(table).values(rows).execute(conn).await
+}
+
+fn join_writer(result: Result<QueryResult<usize>, tokio::task::JoinError>) -> QueryResult<usize> {
+    match result {
+        Ok(result) => result,
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(_) => Err(writer_stopped()),
+    }
+}
+
+fn writer_stopped() -> DieselError {
+    DieselError::DatabaseError(
+        DatabaseErrorKind::ClosedConnection,
+        Box::new("The insert sink is closed".to_string()),
+    )
+}
@@ -0,0 +1,264 @@
+//! SQL statements generated by the crate's DDL helpers.
+
+use crate::schema::{SqlIdentifier, TimeInterval, ValidationError};
+use chrono::{DateTime, Utc};
+use diesel::pg::{Pg, PgConnection};
+use diesel::prelude::*;
+use diesel::query_builder::{BoxedSqlQuery, SqlQuery};
+use diesel::sql_types::{Interval, Text, Timestamptz};
+use std::fmt;
+
+/// A value bound to a placeholder in a [`SqlStatement`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindValue {
+    Text(String),
+    Timestamptz(DateTime<Utc>),
+    Interval(TimeInterval),
+}
+
+impl BindValue {
+    /// Render the value as a SQL literal.
+    fn to_literal(&self) -> String {
+        match self {
+            BindValue::Text(value) => format!("'{}'", value.replace('\'', "''")),
+            BindValue::Timestamptz(value) => format!("TIMESTAMPTZ '{}'", value.to_rfc3339()),
+            BindValue::Interval(value) => format!("INTERVAL '{}'", value.to_postgres_interval()),
+        }
+    }
+}
+
+impl From<&str> for BindValue {
+    fn from(value: &str) -> Self {
+        BindValue::Text(value.to_string())
+    }
+}
+
+impl From<String> for BindValue {
+    fn from(value: String) -> Self {
+        BindValue::Text(value)
+    }
+}
+
+impl From<DateTime<Utc>> for BindValue {
+    fn from(value: DateTime<Utc>) -> Self {
+        BindValue::Timestamptz(value)
+    }
+}
+
+impl From<TimeInterval> for BindValue {
+    fn from(value: TimeInterval) -> Self {
+        BindValue::Interval(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Sql(String),
+    Bind(BindValue),
+}
+
+/// A SQL statement together with its bound values.
+///
+/// Every DDL helper builds its statement through this type and executes it,
+/// and exposes the same statement through a `*_sql()` method. A dry run
+/// therefore always shows exactly what would be executed.
+///
+/// The `Display` implementation renders the statement with the bound values
+/// inlined as SQL literals, which is suitable for review or migration files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SqlStatement {
+    segments: Vec<Segment>,
+}
+
+impl SqlStatement {
+    /// Create a new statement starting with the given SQL text.
+    pub fn new(sql: impl Into<String>) -> Self {
+        Self {
+            segments: vec![Segment::Sql(sql.into())],
+        }
+    }
+
+    /// Append raw SQL text to the statement.
+    pub fn sql(mut self, sql: impl Into<String>) -> Self {
+        self.segments.push(Segment::Sql(sql.into()));
+        self
+    }
+
+    /// Append a bound value to the statement.
+    pub fn bind(mut self, value: impl Into<BindValue>) -> Self {
+        self.segments.push(Segment::Bind(value.into()));
+        self
+    }
+
+    /// Get the statement text with `$n` placeholders for bound values.
+    pub fn placeholder_sql(&self) -> String {
+        let mut sql = String::new();
+        let mut index = 0;
+        for segment in &self.segments {
+            match segment {
+                Segment::Sql(text) => sql.push_str(text),
+                Segment::Bind(_) => {
+                    index += 1;
+                    sql.push_str(&format!("${}", index));
+                }
+            }
+        }
+        sql
+    }
+
+    /// Get the values bound to the statement, in placeholder order.
+    pub fn binds(&self) -> Vec<&BindValue> {
+        self.segments
+            .iter()
+            .filter_map(|segment| match segment {
+                Segment::Bind(value) => Some(value),
+                Segment::Sql(_) => None,
+            })
+            .collect()
+    }
+
+    /// Execute the statement.
+    pub fn execute(&self, conn: &mut PgConnection) -> QueryResult<usize> {
+        self.to_query().execute(conn)
+    }
+
+    /// Execute the statement and load the returned rows.
+    pub fn load<U>(&self, conn: &mut PgConnection) -> QueryResult<Vec<U>>
+    where
+        U: QueryableByName<Pg> + 'static,
+    {
+        self.to_query().load(conn)
+    }
+
+    /// Execute the statement and load the single returned row.
+    ///
+    /// Returns `NotFound` if the statement returns no row.
+    pub fn get_result<U>(&self, conn: &mut PgConnection) -> QueryResult<U>
+    where
+        U: QueryableByName<Pg> + 'static,
+    {
+        self.to_query().get_result(conn)
+    }
+
+    /// Execute the statement on an async connection.
+    #[cfg(feature = "async")]
+    pub async fn execute_async(
+        &self,
+        conn: &mut diesel_async::AsyncPgConnection,
+    ) -> QueryResult<usize> {
+        diesel_async::RunQueryDsl::execute(self.to_query(), conn).await
+    }
+
+    /// Execute the statement on an async connection and load the returned rows.
+    #[cfg(feature = "async")]
+    pub async fn load_async<U>(
+        &self,
+        conn: &mut diesel_async::AsyncPgConnection,
+    ) -> QueryResult<Vec<U>>
+    where
+        U: QueryableByName<Pg> + Send + 'static,
+    {
+        diesel_async::RunQueryDsl::load(self.to_query(), conn).await
+    }
+
+    fn to_query(&self) -> BoxedSqlQuery<'static, Pg, SqlQuery> {
+        let mut query = diesel::sql_query(self.placeholder_sql()).into_boxed::<Pg>();
+        for value in self.binds() {
+            query = match value.clone() {
+                BindValue::Text(value) => query.bind::<Text, _>(value),
+                BindValue::Timestamptz(value) => query.bind::<Timestamptz, _>(value),
+                BindValue::Interval(value) => query.bind::<Interval, _>(value),
+            };
+        }
+        query
+    }
+}
+
+impl fmt::Display for SqlStatement {
+    /// Format the statement with bound values inlined as SQL literals.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in &self.segments {
+            match segment {
+                Segment::Sql(text) => f.write_str(text)?,
+                Segment::Bind(value) => f.write_str(&value.to_literal())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A call of an arbitrary TimescaleDB function with bound arguments.
+///
+/// This is an escape hatch for functions the crate does not wrap. The
+/// function and argument names are validated as identifiers and every
+/// argument value is bound as a parameter, so the call is safe to build from
+/// untrusted values:
+///
+/// ```rust,ignore
+/// let statement = TimescaleFunctionCall::new("add_retention_policy")
+///     .arg("metrics")
+///     .arg(30.days())
+///     .named_arg("initial_start", Utc::now())
+///     .to_statement()?;
+/// statement.execute(&mut conn)?;
+/// ```
+///
+/// The statement is `SELECT fn(...)`, so the result column is named after the
+/// function. Positional arguments are always passed before named arguments.
+/// Text arguments are implicitly cast to `regclass` and `name` parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimescaleFunctionCall {
+    function: String,
+    args: Vec<BindValue>,
+    named_args: Vec<(String, BindValue)>,
+}
+
+impl TimescaleFunctionCall {
+    /// Start a call of the given function.
+    pub fn new(function: impl Into<String>) -> Self {
+        Self {
+            function: function.into(),
+            args: Vec::new(),
+            named_args: Vec::new(),
+        }
+    }
+
+    /// Add a positional argument.
+    pub fn arg(mut self, value: impl Into<BindValue>) -> Self {
+        self.args.push(value.into());
+        self
+    }
+
+    /// Add a named argument, passed as `name => value`.
+    pub fn named_arg(mut self, name: impl Into<String>, value: impl Into<BindValue>) -> Self {
+        self.named_args.push((name.into(), value.into()));
+        self
+    }
+
+    /// Build the statement, validating the function and argument names.
+    pub fn to_statement(&self) -> Result<SqlStatement, ValidationError> {
+        let function = SqlIdentifier::new(&self.function)?;
+
+        let mut statement = SqlStatement::new(format!("SELECT {}(", function.as_str()));
+        let mut first = true;
+        for value in &self.args {
+            if !first {
+                statement = statement.sql(", ");
+            }
+            statement = statement.bind(value.clone());
+            first = false;
+        }
+        for (name, value) in &self.named_args {
+            let name = SqlIdentifier::new(name)?;
+            if !first {
+                statement = statement.sql(", ");
+            }
+            statement = statement
+                .sql(format!("{} => ", name.as_str()))
+                .bind(value.clone());
+            first = false;
+        }
+
+        Ok(statement.sql(")"))
+    }
+}
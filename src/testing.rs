@@ -0,0 +1,123 @@
+//! Helpers for trying out a database setup.
+//!
+//! [`ingest_benchmark`] loads synthetic rows through [`bulk::copy_in`] and
+//! reports the throughput, as a quick check of how fast a server and a
+//! hypertable's configuration ingest data before planning capacity.
+
+use crate::bulk::{self, CopyRow};
+use crate::error::TimescaleError;
+use crate::schema::ValidationError;
+use diesel::pg::{CopyTarget, PgConnection};
+use diesel::Table;
+use std::time::{Duration, Instant};
+
+/// Throughput of an [`ingest_benchmark`] run.
+///
+/// Latencies are per batch, from the start of its `COPY` until the server
+/// confirmed it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IngestReport {
+    /// Number of rows written.
+    pub rows: usize,
+    /// Number of `COPY` statements the rows were sent in.
+    pub batches: usize,
+    /// Time spent generating and writing all rows.
+    pub elapsed: Duration,
+    /// Rows written per second over the whole run.
+    pub rows_per_second: f64,
+    /// Median batch latency.
+    pub p50: Duration,
+    /// 95th percentile batch latency.
+    pub p95: Duration,
+    /// 99th percentile batch latency.
+    pub p99: Duration,
+    /// Slowest batch.
+    pub max: Duration,
+}
+
+/// Write `rows` synthetic rows into `columns` of a table in batches of
+/// `batch_size`, and time every batch.
+///
+/// `generate` is called with the index of each row, from 0, and returns the
+/// row to write, for example a tuple of a timestamp, a device name and a
+/// reading. Every batch is a separate [`bulk::copy_in`] call, committed on
+/// its own unless `conn` is inside a transaction. The rows stay in the
+/// table, so run the benchmark against a table meant for it, and truncate
+/// it afterwards.
+///
+/// ```rust,ignore
+/// let start = Utc::now() - Duration::days(1);
+/// let report = testing::ingest_benchmark(
+///     &mut conn,
+///     metrics::table,
+///     (metrics::timestamp, metrics::device_id, metrics::value),
+///     1_000_000,
+///     10_000,
+///     |i| (start + Duration::milliseconds(i as i64), format!("device-{}", i % 100), i as f64),
+/// )?;
+/// println!("{:.0} rows/s, p99 {:?}", report.rows_per_second, report.p99);
+/// ```
+pub fn ingest_benchmark<T, C, R, F>(
+    conn: &mut PgConnection,
+    table: T,
+    columns: C,
+    rows: usize,
+    batch_size: usize,
+    mut generate: F,
+) -> Result<IngestReport, TimescaleError>
+where
+    T: Table + Copy,
+    C: CopyTarget<Table = T> + Copy,
+    R: CopyRow,
+    F: FnMut(usize) -> R,
+{
+    if rows == 0 {
+        return Err(ValidationError::InvalidParameter(
+            "Number of rows must be greater than zero".to_string(),
+        )
+        .into());
+    }
+    if batch_size == 0 {
+        return Err(ValidationError::InvalidParameter(
+            "Batch size must be greater than zero".to_string(),
+        )
+        .into());
+    }
+
+    let mut latencies = Vec::with_capacity(rows.div_ceil(batch_size));
+    let mut written = 0;
+    let started = Instant::now();
+    for batch_start in (0..rows).step_by(batch_size) {
+        let batch_end = rows.min(batch_start + batch_size);
+        let batch_started = Instant::now();
+        written += bulk::copy_in(
+            conn,
+            table,
+            columns,
+            (batch_start..batch_end).map(&mut generate),
+        )?;
+        latencies.push(batch_started.elapsed());
+    }
+    let elapsed = started.elapsed();
+
+    latencies.sort();
+    Ok(IngestReport {
+        rows: written,
+        batches: latencies.len(),
+        elapsed,
+        rows_per_second: written as f64 / elapsed.as_secs_f64(),
+        p50: percentile(&latencies, 50),
+        p95: percentile(&latencies, 95),
+        p99: percentile(&latencies, 99),
+        max: latencies.last().copied().unwrap_or_default(),
+    })
+}
+
+/// Get the nearest-rank percentile of sorted latencies.
+fn percentile(sorted: &[Duration], percent: usize) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (sorted.len() * percent).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
@@ -1,13 +1,18 @@
 //! TimescaleDB-specific types and type mappings for Diesel.
 
+use crate::schema::{TimeInterval, TimeUnit, ValidationError};
 use chrono::{DateTime, Utc};
-use diesel::deserialize::{self, FromSql};
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::pg::data_types::PgInterval;
 use diesel::pg::Pg;
 use diesel::serialize::{self, Output, ToSql};
 use diesel::sql_types::*;
+use std::ops::Bound;
 
 /// A timestamp with timezone type optimized for time-series data.
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Timestamptz)]
 pub struct TimestampTz(pub DateTime<Utc>);
 
 impl TimestampTz {
@@ -22,6 +27,41 @@ impl TimestampTz {
     pub fn inner(&self) -> &DateTime<Utc> {
         &self.0
     }
+
+    /// Convert the timestamp to another time zone for display.
+    ///
+    /// The timestamp itself stays in UTC; this only changes how it is
+    /// rendered. Any `chrono` time zone works, such as `chrono::Local`, a
+    /// `FixedOffset` or a `chrono_tz::Tz` parsed from
+    /// [`session_timezone`](crate::connection::TimescaleDbConnection::session_timezone).
+    pub fn with_timezone<Tz: chrono::TimeZone>(&self, tz: &Tz) -> DateTime<Tz> {
+        self.0.with_timezone(tz)
+    }
+
+    /// Create a timestamp from seconds since the Unix epoch.
+    pub fn from_unix_seconds(seconds: i64) -> Result<Self, ValidationError> {
+        DateTime::from_timestamp(seconds, 0)
+            .map(Self)
+            .ok_or_else(|| out_of_range_timestamp(seconds, "seconds"))
+    }
+
+    /// Create a timestamp from milliseconds since the Unix epoch.
+    pub fn from_unix_millis(millis: i64) -> Result<Self, ValidationError> {
+        DateTime::from_timestamp_millis(millis)
+            .map(Self)
+            .ok_or_else(|| out_of_range_timestamp(millis, "milliseconds"))
+    }
+
+    /// Create a timestamp from microseconds since the Unix epoch.
+    pub fn from_unix_micros(micros: i64) -> Result<Self, ValidationError> {
+        DateTime::from_timestamp_micros(micros)
+            .map(Self)
+            .ok_or_else(|| out_of_range_timestamp(micros, "microseconds"))
+    }
+}
+
+fn out_of_range_timestamp(value: i64, unit: &str) -> ValidationError {
+    ValidationError::InvalidParameter(format!("Unix timestamp out of range: {} {}", value, unit))
 }
 
 impl From<DateTime<Utc>> for TimestampTz {
@@ -49,6 +89,307 @@ impl FromSql<Timestamptz, Pg> for TimestampTz {
     }
 }
 
+/// A PostgreSQL `tstzrange`, such as the time range covered by a chunk.
+///
+/// A missing `start` or `end` is an unbounded side of the range. An empty
+/// range has two exclusive bounds at the same instant, see
+/// [`TimestamptzRange::is_empty`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Tstzrange)]
+pub struct TimestamptzRange {
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub lower_inclusive: bool,
+    pub upper_inclusive: bool,
+}
+
+impl TimestamptzRange {
+    /// Create a half-open range `[start, end)`, the form TimescaleDB uses for chunks.
+    pub fn half_open(start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        Self {
+            start: Some(start),
+            end: Some(end),
+            lower_inclusive: true,
+            upper_inclusive: false,
+        }
+    }
+
+    /// Check whether the range contains no instant at all.
+    pub fn is_empty(&self) -> bool {
+        match (self.start, self.end) {
+            (Some(start), Some(end)) => {
+                start > end || (start == end && !(self.lower_inclusive && self.upper_inclusive))
+            }
+            _ => false,
+        }
+    }
+
+    /// Check whether the range contains `timestamp`.
+    pub fn contains(&self, timestamp: DateTime<Utc>) -> bool {
+        let after_start = match self.start {
+            Some(start) if self.lower_inclusive => timestamp >= start,
+            Some(start) => timestamp > start,
+            None => true,
+        };
+        let before_end = match self.end {
+            Some(end) if self.upper_inclusive => timestamp <= end,
+            Some(end) => timestamp < end,
+            None => true,
+        };
+        after_start && before_end
+    }
+
+    fn to_bounds(self) -> (Bound<DateTime<Utc>>, Bound<DateTime<Utc>>) {
+        let bound = |value: Option<DateTime<Utc>>, inclusive: bool| match value {
+            Some(value) if inclusive => Bound::Included(value),
+            Some(value) => Bound::Excluded(value),
+            None => Bound::Unbounded,
+        };
+        (
+            bound(self.start, self.lower_inclusive),
+            bound(self.end, self.upper_inclusive),
+        )
+    }
+}
+
+impl From<(Bound<DateTime<Utc>>, Bound<DateTime<Utc>>)> for TimestamptzRange {
+    fn from((lower, upper): (Bound<DateTime<Utc>>, Bound<DateTime<Utc>>)) -> Self {
+        let split = |bound: Bound<DateTime<Utc>>| match bound {
+            Bound::Included(value) => (Some(value), true),
+            Bound::Excluded(value) => (Some(value), false),
+            Bound::Unbounded => (None, false),
+        };
+        let (start, lower_inclusive) = split(lower);
+        let (end, upper_inclusive) = split(upper);
+        Self {
+            start,
+            end,
+            lower_inclusive,
+            upper_inclusive,
+        }
+    }
+}
+
+impl FromSql<Tstzrange, Pg> for TimestamptzRange {
+    fn from_sql(bytes: diesel::pg::PgValue<'_>) -> deserialize::Result<Self> {
+        let bounds =
+            <(Bound<DateTime<Utc>>, Bound<DateTime<Utc>>) as FromSql<Tstzrange, Pg>>::from_sql(
+                bytes,
+            )?;
+        Ok(bounds.into())
+    }
+}
+
+impl ToSql<Tstzrange, Pg> for TimestamptzRange {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        let bounds = self.to_bounds();
+        <(Bound<DateTime<Utc>>, Bound<DateTime<Utc>>) as ToSql<Tstzrange, Pg>>::to_sql(
+            &bounds,
+            &mut out.reborrow(),
+        )
+    }
+}
+
+/// A JSONB tags column value, such as `{"host": "a", "region": "eu"}`.
+///
+/// Tags are always a JSON object, so every row of a wide-event table has the
+/// same shape and can be grouped by `tags->>'key'`; scalars and arrays are
+/// rejected when the value is created.
+#[cfg(feature = "json")]
+#[derive(Debug, Clone, PartialEq, Eq, AsExpression)]
+#[diesel(sql_type = Jsonb)]
+pub struct InsertableTags(serde_json::Value);
+
+#[cfg(feature = "json")]
+impl InsertableTags {
+    /// Create tags from a JSON value, which must be an object.
+    pub fn new(value: serde_json::Value) -> Result<Self, ValidationError> {
+        if !value.is_object() {
+            return Err(ValidationError::InvalidParameter(format!(
+                "Tags must be a JSON object, got {}",
+                value
+            )));
+        }
+        Ok(Self(value))
+    }
+
+    /// Get the tags as a JSON value.
+    pub fn as_value(&self) -> &serde_json::Value {
+        &self.0
+    }
+
+    /// Get the tags as a JSON value, consuming them.
+    pub fn into_inner(self) -> serde_json::Value {
+        self.0
+    }
+}
+
+#[cfg(feature = "json")]
+impl TryFrom<serde_json::Value> for InsertableTags {
+    type Error = ValidationError;
+
+    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+#[cfg(feature = "json")]
+impl From<serde_json::Map<String, serde_json::Value>> for InsertableTags {
+    fn from(map: serde_json::Map<String, serde_json::Value>) -> Self {
+        Self(serde_json::Value::Object(map))
+    }
+}
+
+#[cfg(feature = "json")]
+impl ToSql<Jsonb, Pg> for InsertableTags {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        <serde_json::Value as ToSql<Jsonb, Pg>>::to_sql(&self.0, &mut out.reborrow())
+    }
+}
+
+impl TryFrom<&TimeInterval> for PgInterval {
+    type Error = ValidationError;
+
+    /// Convert to the representation PostgreSQL uses for an `interval`.
+    ///
+    /// Units up to hours are stored as microseconds, days and weeks in the day
+    /// field, and months and years in the month field, exactly as PostgreSQL
+    /// parses `INTERVAL '...'` literals. The components of a composite
+    /// interval are added up field by field. A year is 12 months and no
+    /// calendar unit is approximated by a fixed duration.
+    fn try_from(interval: &TimeInterval) -> Result<Self, Self::Error> {
+        let out_of_range = || {
+            ValidationError::InvalidInterval(format!(
+                "Interval out of range: {}",
+                interval.to_postgres_interval()
+            ))
+        };
+
+        let mut microseconds: i64 = 0;
+        let mut days: i32 = 0;
+        let mut months: i32 = 0;
+        for (value, unit) in interval.components() {
+            let magnitude = i64::try_from(*value).map_err(|_| out_of_range())?;
+            let value = if interval.is_negative() {
+                -magnitude
+            } else {
+                magnitude
+            };
+
+            let add_microseconds = |total: i64, per_unit: i64| {
+                value
+                    .checked_mul(per_unit)
+                    .and_then(|value| total.checked_add(value))
+                    .ok_or_else(out_of_range)
+            };
+            let add_field = |total: i32, per_unit: i32| {
+                i32::try_from(value)
+                    .ok()
+                    .and_then(|value| value.checked_mul(per_unit))
+                    .and_then(|value| total.checked_add(value))
+                    .ok_or_else(out_of_range)
+            };
+
+            match unit {
+                TimeUnit::Microseconds => microseconds = add_microseconds(microseconds, 1)?,
+                TimeUnit::Milliseconds => microseconds = add_microseconds(microseconds, 1_000)?,
+                TimeUnit::Seconds => microseconds = add_microseconds(microseconds, 1_000_000)?,
+                TimeUnit::Minutes => microseconds = add_microseconds(microseconds, 60_000_000)?,
+                TimeUnit::Hours => microseconds = add_microseconds(microseconds, 3_600_000_000)?,
+                TimeUnit::Days => days = add_field(days, 1)?,
+                TimeUnit::Weeks => days = add_field(days, 7)?,
+                TimeUnit::Months => months = add_field(months, 1)?,
+                TimeUnit::Years => months = add_field(months, 12)?,
+            }
+        }
+
+        Ok(PgInterval::new(microseconds, days, months))
+    }
+}
+
+impl TryFrom<PgInterval> for TimeInterval {
+    type Error = ValidationError;
+
+    /// Convert a PostgreSQL `interval` into the largest units that represent
+    /// it exactly, e.g. 90 minutes stays `90 minutes`, 14 days becomes
+    /// `2 weeks` and `1 day 12:00:00` becomes `1 days 12 hours`.
+    ///
+    /// Each of the month, day and time fields becomes one component, so
+    /// `1 year 6 months` is `18 months`. Intervals whose fields have different
+    /// signs (such as `1 day -01:00:00`) are rejected.
+    fn try_from(interval: PgInterval) -> Result<Self, Self::Error> {
+        let fields = [
+            i64::from(interval.months),
+            i64::from(interval.days),
+            interval.microseconds,
+        ];
+        if fields.iter().any(|field| *field < 0) && fields.iter().any(|field| *field > 0) {
+            return Err(ValidationError::InvalidInterval(format!(
+                "Interval of {} months, {} days and {} microseconds mixes signs",
+                interval.months, interval.days, interval.microseconds
+            )));
+        }
+
+        let mut components = Vec::new();
+        match interval.months.unsigned_abs() {
+            0 => {}
+            months if months % 12 == 0 => {
+                components.push((u64::from(months / 12), TimeUnit::Years))
+            }
+            months => components.push((u64::from(months), TimeUnit::Months)),
+        }
+        match interval.days.unsigned_abs() {
+            0 => {}
+            days if days % 7 == 0 => components.push((u64::from(days / 7), TimeUnit::Weeks)),
+            days => components.push((u64::from(days), TimeUnit::Days)),
+        }
+        let microseconds = interval.microseconds.unsigned_abs();
+        if microseconds != 0 || components.is_empty() {
+            let units = [
+                (3_600_000_000, TimeUnit::Hours),
+                (60_000_000, TimeUnit::Minutes),
+                (1_000_000, TimeUnit::Seconds),
+                (1_000, TimeUnit::Milliseconds),
+            ];
+            components.push(
+                units
+                    .into_iter()
+                    .find(|(per_unit, _)| {
+                        microseconds != 0 && microseconds.is_multiple_of(*per_unit)
+                    })
+                    .map(|(per_unit, unit)| (microseconds / per_unit, unit))
+                    .unwrap_or((microseconds, TimeUnit::Microseconds)),
+            );
+        }
+
+        let (value, unit) = components[0];
+        let magnitude = components[1..]
+            .iter()
+            .fold(TimeInterval::new(value, unit), |interval, (value, unit)| {
+                interval.and(*value, *unit)
+            });
+        Ok(if fields.iter().any(|field| *field < 0) {
+            magnitude.negated()
+        } else {
+            magnitude
+        })
+    }
+}
+
+impl FromSql<Interval, Pg> for TimeInterval {
+    fn from_sql(bytes: diesel::pg::PgValue<'_>) -> deserialize::Result<Self> {
+        let interval = <PgInterval as FromSql<Interval, Pg>>::from_sql(bytes)?;
+        Ok(TimeInterval::try_from(interval)?)
+    }
+}
+
+impl ToSql<Interval, Pg> for TimeInterval {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        let interval = PgInterval::try_from(self)?;
+        <PgInterval as ToSql<Interval, Pg>>::to_sql(&interval, &mut out.reborrow())
+    }
+}
+
 /// Trait for types that can be used as time dimensions in TimescaleDB.
 pub trait TimeDimension {
     /// The SQL type of this time dimension.
@@ -73,3 +414,168 @@ impl TimeDimension for DateTime<Utc> {
         *self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pg_interval(interval: &str) -> PgInterval {
+        PgInterval::try_from(&TimeInterval::from_string(interval).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn sub_day_units_are_exact_microseconds() {
+        assert_eq!(pg_interval("250 microseconds"), PgInterval::new(250, 0, 0));
+        assert_eq!(pg_interval("3 milliseconds"), PgInterval::new(3_000, 0, 0));
+        assert_eq!(
+            pg_interval("90 minutes"),
+            PgInterval::new(5_400_000_000, 0, 0)
+        );
+        assert_eq!(
+            pg_interval("36 hours"),
+            PgInterval::new(129_600_000_000, 0, 0)
+        );
+    }
+
+    #[test]
+    fn days_and_weeks_use_the_day_field() {
+        assert_eq!(pg_interval("1 day"), PgInterval::new(0, 1, 0));
+        assert_eq!(pg_interval("2 weeks"), PgInterval::new(0, 14, 0));
+    }
+
+    #[test]
+    fn months_and_years_use_the_month_field() {
+        assert_eq!(pg_interval("1 month"), PgInterval::new(0, 0, 1));
+        assert_eq!(pg_interval("1 year"), PgInterval::new(0, 0, 12));
+    }
+
+    #[test]
+    fn out_of_range_values_are_rejected() {
+        let interval = TimeInterval::new(u64::from(u32::MAX), TimeUnit::Years);
+        assert!(matches!(
+            PgInterval::try_from(&interval),
+            Err(ValidationError::InvalidInterval(_))
+        ));
+    }
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        use chrono::TimeZone;
+        Utc.with_ymd_and_hms(2026, 3, 14, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn half_open_range_contains_its_start_but_not_its_end() {
+        let range = TimestamptzRange::half_open(at(10), at(12));
+        assert!(range.contains(at(10)));
+        assert!(range.contains(at(11)));
+        assert!(!range.contains(at(12)));
+        assert!(!range.contains(at(9)));
+    }
+
+    #[test]
+    fn unbounded_sides_contain_everything_beyond() {
+        let range = TimestamptzRange::from((Bound::Unbounded, Bound::Included(at(12))));
+        assert!(range.contains(at(0)));
+        assert!(range.contains(at(12)));
+        assert!(!range.contains(at(13)));
+        assert!(!range.is_empty());
+    }
+
+    #[test]
+    fn ranges_without_instants_are_empty() {
+        assert!(TimestamptzRange::half_open(at(10), at(10)).is_empty());
+        assert!(TimestamptzRange::half_open(at(11), at(10)).is_empty());
+        let point = TimestamptzRange::from((Bound::Included(at(10)), Bound::Included(at(10))));
+        assert!(!point.is_empty());
+    }
+
+    #[test]
+    fn bounds_convert_back_and_forth() {
+        let bounds = (Bound::Excluded(at(10)), Bound::Unbounded);
+        assert_eq!(TimestamptzRange::from(bounds).to_bounds(), bounds);
+    }
+
+    #[test]
+    fn unix_constructors_agree_on_the_same_instant() {
+        let expected = at(10);
+        let seconds = expected.timestamp();
+        assert_eq!(
+            TimestampTz::from_unix_seconds(seconds).unwrap().inner(),
+            &expected
+        );
+        assert_eq!(
+            TimestampTz::from_unix_millis(seconds * 1_000)
+                .unwrap()
+                .inner(),
+            &expected
+        );
+        assert_eq!(
+            TimestampTz::from_unix_micros(seconds * 1_000_000)
+                .unwrap()
+                .inner(),
+            &expected
+        );
+    }
+
+    #[test]
+    fn unix_timestamps_out_of_range_are_rejected() {
+        assert!(matches!(
+            TimestampTz::from_unix_seconds(i64::MAX),
+            Err(ValidationError::InvalidParameter(_))
+        ));
+        assert!(TimestampTz::from_unix_millis(i64::MIN).is_err());
+        assert!(TimestampTz::from_unix_seconds(-1).is_ok());
+
+        // chrono's bounds are accepted and one unit past them is not
+        let (max, min) = (DateTime::<Utc>::MAX_UTC, DateTime::<Utc>::MIN_UTC);
+        type FromUnix = fn(i64) -> Result<TimestampTz, ValidationError>;
+        let units: [(FromUnix, i64, i64); 3] = [
+            (
+                TimestampTz::from_unix_seconds,
+                max.timestamp(),
+                min.timestamp(),
+            ),
+            (
+                TimestampTz::from_unix_millis,
+                max.timestamp_millis(),
+                min.timestamp_millis(),
+            ),
+            (
+                TimestampTz::from_unix_micros,
+                max.timestamp_micros(),
+                min.timestamp_micros(),
+            ),
+        ];
+        for (from_unix, last, first) in units {
+            assert!(from_unix(last).is_ok(), "{}", last);
+            assert!(from_unix(first).is_ok(), "{}", first);
+            assert!(from_unix(last + 1).is_err(), "{}", last + 1);
+            assert!(from_unix(first - 1).is_err(), "{}", first - 1);
+        }
+    }
+
+    #[test]
+    fn with_timezone_keeps_the_instant() {
+        let timestamp = TimestampTz::from(at(10));
+        let offset = chrono::FixedOffset::east_opt(2 * 3600).unwrap();
+        let local = timestamp.with_timezone(&offset);
+        assert_eq!(local.to_rfc3339(), "2026-03-14T12:00:00+02:00");
+        assert_eq!(local.with_timezone(&Utc), at(10));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn tags_must_be_a_json_object() {
+        assert!(InsertableTags::new(serde_json::json!({"host": "a"})).is_ok());
+        for value in [
+            serde_json::json!("a"),
+            serde_json::json!(["a"]),
+            serde_json::Value::Null,
+        ] {
+            assert!(matches!(
+                InsertableTags::try_from(value),
+                Err(ValidationError::InvalidParameter(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! Detection of the installed TimescaleDB extension version.
+
+use crate::error::TimescaleError;
+use crate::schema::ValidationError;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::sql_types::Text;
+use std::fmt;
+
+/// A TimescaleDB extension version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TimescaleVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl TimescaleVersion {
+    /// Create a new version.
+    pub fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Parse a version string as reported by `pg_extension`, such as `2.13.1`.
+    ///
+    /// Pre-release suffixes like `-dev` or `-rc1` are ignored, and a missing
+    /// patch component is treated as zero.
+    pub fn parse(version: &str) -> Option<Self> {
+        let numeric = version.trim().split('-').next()?;
+        let mut parts = numeric.split('.');
+
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next()?.parse().ok()?;
+        let patch = match parts.next() {
+            Some(patch) => patch.parse().ok()?,
+            None => 0,
+        };
+
+        if parts.next().is_some() {
+            return None;
+        }
+
+        Some(Self::new(major, minor, patch))
+    }
+
+    /// Check if this version is at least `major.minor`.
+    pub fn at_least(&self, major: u32, minor: u32) -> bool {
+        (self.major, self.minor) >= (major, minor)
+    }
+
+    /// Fail with `UnsupportedVersion` for `feature` unless this version is
+    /// at least `major.minor`.
+    pub(crate) fn require(
+        self,
+        feature: &str,
+        major: u32,
+        minor: u32,
+    ) -> Result<Self, TimescaleError> {
+        if !self.at_least(major, minor) {
+            return Err(TimescaleError::UnsupportedVersion {
+                feature: feature.to_string(),
+                required: TimescaleVersion::new(major, minor, 0),
+                removed: None,
+                installed: self,
+            });
+        }
+        Ok(self)
+    }
+}
+
+impl fmt::Display for TimescaleVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[derive(QueryableByName)]
+struct ExtensionVersion {
+    #[diesel(sql_type = Text)]
+    extversion: String,
+}
+
+/// Get the version of the TimescaleDB extension installed in the current database.
+///
+/// Returns `NotFound` if the extension is not installed, and a
+/// `DeserializationError` holding a [`ValidationError`] if its version cannot
+/// be parsed.
+pub fn installed_version(conn: &mut PgConnection) -> QueryResult<TimescaleVersion> {
+    let version = extension_version(conn)?;
+
+    // The version was read but cannot be parsed, which is what Diesel's
+    // deserialization errors report
+    TimescaleVersion::parse(&version).ok_or_else(|| {
+        diesel::result::Error::DeserializationError(Box::new(ValidationError::InvalidParameter(
+            format!("Unrecognized TimescaleDB version: {}", version),
+        )))
+    })
+}
+
+/// Get the version string of the installed extension, as recorded in
+/// `pg_extension`.
+pub(crate) fn extension_version(conn: &mut PgConnection) -> QueryResult<String> {
+    let row =
+        diesel::sql_query("SELECT extversion FROM pg_extension WHERE extname = 'timescaledb'")
+            .get_result::<ExtensionVersion>(conn)?;
+    Ok(row.extversion)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reads_release_and_pre_release_versions() {
+        assert_eq!(
+            TimescaleVersion::parse("2.14.2"),
+            Some(TimescaleVersion::new(2, 14, 2))
+        );
+        assert_eq!(
+            TimescaleVersion::parse(" 2.13 "),
+            Some(TimescaleVersion::new(2, 13, 0))
+        );
+        assert_eq!(
+            TimescaleVersion::parse("2.18.0-dev"),
+            Some(TimescaleVersion::new(2, 18, 0))
+        );
+        assert_eq!(
+            TimescaleVersion::parse("2.17.1-rc1"),
+            Some(TimescaleVersion::new(2, 17, 1))
+        );
+    }
+
+    #[test]
+    fn parse_rejects_malformed_versions() {
+        for version in ["", "2", "2.x", "2.14.2.1", "v2.14.2", "2..1"] {
+            assert_eq!(TimescaleVersion::parse(version), None, "{}", version);
+        }
+    }
+
+    #[test]
+    fn versions_compare_by_major_then_minor() {
+        let version = TimescaleVersion::new(2, 14, 2);
+        assert!(version.at_least(2, 14));
+        assert!(version.at_least(2, 9));
+        assert!(version.at_least(1, 20));
+        assert!(!version.at_least(2, 15));
+        assert!(!version.at_least(3, 0));
+        assert!(TimescaleVersion::new(2, 9, 0) < TimescaleVersion::new(2, 10, 0));
+    }
+
+    #[test]
+    fn require_names_the_feature_and_both_versions() {
+        let version = TimescaleVersion::new(2, 13, 1);
+        assert_eq!(version.require("Columnstore", 2, 13).unwrap(), version);
+
+        let error = version.require("Columnstore", 2, 18).unwrap_err();
+        assert!(matches!(
+            error,
+            TimescaleError::UnsupportedVersion {
+                required: TimescaleVersion {
+                    major: 2,
+                    minor: 18,
+                    patch: 0
+                },
+                installed,
+                ..
+            } if installed == version
+        ));
+        assert_eq!(
+            error.to_string(),
+            "Columnstore requires TimescaleDB 2.18.0 or later (installed: 2.13.1)"
+        );
+    }
+}
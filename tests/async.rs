@@ -0,0 +1,129 @@
+//! Statements and helpers run on a `diesel-async` connection.
+#![cfg(feature = "async")]
+
+mod common;
+
+use diesel::sql_types::{Integer, Text};
+use diesel::QueryableByName;
+use diesel_async::{AsyncConnection, AsyncPgConnection};
+use diesel_timescaledb::schema::chunks::{drop_old_chunks_async, get_chunk_info_async};
+use diesel_timescaledb::schema::{Hypertable, ValidationError};
+use diesel_timescaledb::statement::SqlStatement;
+use diesel_timescaledb::TimescaleError;
+
+#[derive(QueryableByName)]
+struct Echo {
+    #[diesel(sql_type = Text)]
+    text: String,
+    #[diesel(sql_type = Integer)]
+    length: i32,
+}
+
+async fn connection() -> Option<AsyncPgConnection> {
+    let url = common::database_url()?;
+    Some(
+        AsyncPgConnection::establish(&url)
+            .await
+            .unwrap_or_else(|e| panic!("Cannot connect to DATABASE_URL: {}", e)),
+    )
+}
+
+#[tokio::test]
+async fn statements_bind_and_load_asynchronously() {
+    let Some(mut conn) = connection().await else {
+        return;
+    };
+    let rows: Vec<Echo> = SqlStatement::new("SELECT ")
+        .bind("it's")
+        .sql("::text AS text, length(")
+        .bind("it's")
+        .sql(") AS length")
+        .load_async(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].text, "it's");
+    assert_eq!(rows[0].length, 4);
+
+    let changed = SqlStatement::new("SELECT 1")
+        .execute_async(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(changed, 1);
+}
+
+#[tokio::test]
+async fn invalid_names_fail_before_reaching_the_database() {
+    let Some(mut conn) = connection().await else {
+        return;
+    };
+    let result = get_chunk_info_async(&mut conn, "metrics; DROP TABLE metrics").await;
+    assert!(matches!(
+        result,
+        Err(TimescaleError::Validation(
+            ValidationError::InvalidIdentifier(_)
+        ))
+    ));
+}
+
+diesel::table! {
+    it_async_metrics (ts) {
+        ts -> Timestamptz,
+        value -> Double,
+    }
+}
+
+diesel_timescaledb::hypertable!(it_async_metrics, ts);
+
+#[tokio::test]
+async fn hypertable_created_and_chunks_listed_asynchronously() {
+    if common::timescale_connection().is_none() {
+        return;
+    }
+    let mut conn = connection().await.unwrap();
+    SqlStatement::new("DROP TABLE IF EXISTS it_async_metrics")
+        .execute_async(&mut conn)
+        .await
+        .unwrap();
+    SqlStatement::new(
+        "CREATE TABLE it_async_metrics (ts TIMESTAMPTZ NOT NULL, value DOUBLE PRECISION)",
+    )
+    .execute_async(&mut conn)
+    .await
+    .unwrap();
+
+    it_async_metrics::table::create_hypertable_async(&mut conn)
+        .await
+        .unwrap();
+    SqlStatement::new("INSERT INTO it_async_metrics VALUES ('2020-01-01', 1), (now(), 2)")
+        .execute_async(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(
+        get_chunk_info_async(&mut conn, "it_async_metrics")
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+
+    drop_old_chunks_async(
+        &mut conn,
+        "it_async_metrics",
+        chrono::Utc::now() - chrono::Duration::days(365),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        get_chunk_info_async(&mut conn, "it_async_metrics")
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+
+    SqlStatement::new("DROP TABLE it_async_metrics")
+        .execute_async(&mut conn)
+        .await
+        .unwrap();
+}
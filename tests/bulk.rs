@@ -0,0 +1,239 @@
+//! Bulk loading with `COPY`.
+
+mod common;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+use diesel_timescaledb::bulk::{copy_in, copy_in_named};
+use diesel_timescaledb::error::TimescaleError;
+use std::time::Instant;
+
+diesel::table! {
+    it_bulk_readings (timestamp, device_id) {
+        timestamp -> Timestamptz,
+        device_id -> Text,
+        value -> Nullable<Float8>,
+    }
+}
+
+diesel::table! {
+    it_bulk_speed (timestamp, device_id) {
+        timestamp -> Timestamptz,
+        device_id -> Text,
+        value -> Nullable<Float8>,
+    }
+}
+
+fn create_table(conn: &mut PgConnection, name: &str) {
+    diesel::sql_query(format!("DROP TABLE IF EXISTS {}", name))
+        .execute(conn)
+        .unwrap();
+    diesel::sql_query(format!(
+        "CREATE TABLE {} (
+             timestamp TIMESTAMPTZ NOT NULL,
+             device_id TEXT NOT NULL,
+             value DOUBLE PRECISION,
+             PRIMARY KEY (timestamp, device_id)
+         )",
+        name
+    ))
+    .execute(conn)
+    .unwrap();
+}
+
+fn readings(count: usize) -> Vec<(DateTime<Utc>, String, Option<f64>)> {
+    let start = Utc.with_ymd_and_hms(2026, 3, 14, 0, 0, 0).unwrap();
+    (0..count)
+        .map(|i| {
+            (
+                start + Duration::seconds(i as i64),
+                format!("device-{}", i % 4),
+                (i % 3 != 0).then_some(i as f64 / 2.0),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn copy_writes_every_row_and_rolls_back_on_a_bad_one() {
+    let Some(mut conn) = common::connection() else {
+        return;
+    };
+    create_table(&mut conn, "it_bulk_readings");
+
+    let rows = readings(1_000);
+    let written = copy_in(
+        &mut conn,
+        it_bulk_readings::table,
+        (
+            it_bulk_readings::timestamp,
+            it_bulk_readings::device_id,
+            it_bulk_readings::value,
+        ),
+        rows.iter().map(|(t, d, v)| (*t, d.as_str(), *v)),
+    )
+    .unwrap();
+    assert_eq!(written, 1_000);
+
+    let loaded: Vec<(DateTime<Utc>, String, Option<f64>)> = it_bulk_readings::table
+        .select((
+            it_bulk_readings::timestamp,
+            it_bulk_readings::device_id,
+            it_bulk_readings::value,
+        ))
+        .order(it_bulk_readings::timestamp)
+        .load(&mut conn)
+        .unwrap();
+    assert_eq!(loaded, rows);
+
+    // The last row repeats a key in the middle of a fresh batch
+    diesel::delete(it_bulk_readings::table)
+        .execute(&mut conn)
+        .unwrap();
+    let mut failing = readings(500);
+    failing.push(failing[250].clone());
+    let result = copy_in(
+        &mut conn,
+        it_bulk_readings::table,
+        (
+            it_bulk_readings::timestamp,
+            it_bulk_readings::device_id,
+            it_bulk_readings::value,
+        ),
+        failing.iter().map(|(t, d, v)| (*t, d.as_str(), *v)),
+    );
+    assert!(result.is_err());
+    let count: i64 = it_bulk_readings::table
+        .count()
+        .get_result(&mut conn)
+        .unwrap();
+    assert_eq!(count, 0);
+
+    diesel::sql_query("DROP TABLE it_bulk_readings")
+        .execute(&mut conn)
+        .unwrap();
+}
+
+#[test]
+fn copy_by_name_writes_into_a_qualified_mixed_case_table() {
+    let Some(mut conn) = common::connection() else {
+        return;
+    };
+    conn.batch_execute(
+        "DROP SCHEMA IF EXISTS it_bulk_named CASCADE;
+         CREATE SCHEMA it_bulk_named;
+         CREATE TABLE it_bulk_named.\"Readings\" (
+             \"Timestamp\" TIMESTAMPTZ NOT NULL,
+             device_id TEXT NOT NULL,
+             value DOUBLE PRECISION
+         );",
+    )
+    .unwrap();
+
+    let rows = readings(100);
+    let written = copy_in_named(
+        &mut conn,
+        "it_bulk_named.Readings",
+        &["Timestamp", "device_id", "value"],
+        rows.iter().map(|(t, d, v)| (*t, d.as_str(), *v)),
+    )
+    .unwrap();
+    assert_eq!(written, 100);
+
+    let loaded: Vec<(DateTime<Utc>, String, Option<f64>)> = diesel::sql_query(
+        "SELECT \"Timestamp\" AS timestamp, device_id, value
+         FROM it_bulk_named.\"Readings\" ORDER BY \"Timestamp\"",
+    )
+    .load::<NamedReading>(&mut conn)
+    .unwrap()
+    .into_iter()
+    .map(|row| (row.timestamp, row.device_id, row.value))
+    .collect();
+    assert_eq!(loaded, rows);
+
+    // Names are validated before the statement is sent
+    for (table, columns) in [
+        ("it_bulk_named.Readings; DROP TABLE x", &["device_id"][..]),
+        ("it_bulk_named.Readings", &["device_id; --"][..]),
+        ("it_bulk_named.Readings", &[][..]),
+    ] {
+        let result = copy_in_named(&mut conn, table, columns, std::iter::empty::<(&str,)>());
+        assert!(
+            matches!(result, Err(TimescaleError::Validation(_))),
+            "{} {:?}: {:?}",
+            table,
+            columns,
+            result
+        );
+    }
+
+    conn.batch_execute("DROP SCHEMA it_bulk_named CASCADE")
+        .unwrap();
+}
+
+#[derive(QueryableByName)]
+struct NamedReading {
+    #[diesel(sql_type = diesel::sql_types::Timestamptz)]
+    timestamp: DateTime<Utc>,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    device_id: String,
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Float8>)]
+    value: Option<f64>,
+}
+
+// Compares wall-clock times, which is too noisy for every test run; run it
+// with `cargo test --test bulk -- --ignored`
+#[test]
+#[ignore]
+fn copy_is_faster_than_inserting_row_by_row() {
+    let Some(mut conn) = common::connection() else {
+        return;
+    };
+    create_table(&mut conn, "it_bulk_speed");
+    let rows = readings(2_000);
+
+    let started = Instant::now();
+    for (timestamp, device_id, value) in &rows {
+        diesel::insert_into(it_bulk_speed::table)
+            .values((
+                it_bulk_speed::timestamp.eq(timestamp),
+                it_bulk_speed::device_id.eq(device_id),
+                it_bulk_speed::value.eq(value),
+            ))
+            .execute(&mut conn)
+            .unwrap();
+    }
+    let inserted_in = started.elapsed();
+
+    diesel::delete(it_bulk_speed::table)
+        .execute(&mut conn)
+        .unwrap();
+    let started = Instant::now();
+    let written = copy_in(
+        &mut conn,
+        it_bulk_speed::table,
+        (
+            it_bulk_speed::timestamp,
+            it_bulk_speed::device_id,
+            it_bulk_speed::value,
+        ),
+        rows.iter().map(|(t, d, v)| (*t, d.as_str(), *v)),
+    )
+    .unwrap();
+    let copied_in = started.elapsed();
+    assert_eq!(written, rows.len());
+
+    // A round trip per row against one statement for all of them; the
+    // margin leaves room for noise on a busy machine
+    assert!(
+        copied_in * 3 < inserted_in,
+        "COPY took {:?}, row-by-row inserts {:?}",
+        copied_in,
+        inserted_in
+    );
+
+    diesel::sql_query("DROP TABLE it_bulk_speed")
+        .execute(&mut conn)
+        .unwrap();
+}
@@ -0,0 +1,52 @@
+//! Setup shared by the tests that run against a database.
+//!
+//! These tests run when `DATABASE_URL` is set and are skipped otherwise,
+//! except on CI (`CI` set), where a missing URL fails them instead.
+//! The TimescaleDB tests expect the extension to be available on that
+//! server; each test works on tables named after itself, so the tests can
+//! run in parallel against the same database.
+#![allow(dead_code)]
+
+use diesel::prelude::*;
+
+/// Get `DATABASE_URL`, or `None` if it is not set.
+///
+/// Panics on CI if it is not set, so the tests cannot silently pass there.
+pub fn database_url() -> Option<String> {
+    match std::env::var("DATABASE_URL") {
+        Ok(url) => Some(url),
+        Err(_) if std::env::var_os("CI").is_some() => {
+            panic!("DATABASE_URL must be set to run the database tests on CI")
+        }
+        Err(_) => None,
+    }
+}
+
+/// Connect to `DATABASE_URL`, or get `None` if it is not set.
+pub fn connection() -> Option<PgConnection> {
+    let url = database_url()?;
+    Some(
+        PgConnection::establish(&url)
+            .unwrap_or_else(|e| panic!("Cannot connect to DATABASE_URL: {}", e)),
+    )
+}
+
+/// Connect to `DATABASE_URL` with the TimescaleDB extension installed, or
+/// get `None` if it is not set.
+pub fn timescale_connection() -> Option<PgConnection> {
+    let mut conn = connection()?;
+    diesel::sql_query("CREATE EXTENSION IF NOT EXISTS timescaledb")
+        .execute(&mut conn)
+        .expect("DATABASE_URL must point at a server with TimescaleDB");
+    Some(conn)
+}
+
+/// Connect to `DATABASE_URL` with the TimescaleDB Toolkit extension
+/// installed, or get `None` if it is not set.
+pub fn toolkit_connection() -> Option<PgConnection> {
+    let mut conn = timescale_connection()?;
+    diesel::sql_query("CREATE EXTENSION IF NOT EXISTS timescaledb_toolkit")
+        .execute(&mut conn)
+        .expect("DATABASE_URL must point at a server with the TimescaleDB Toolkit");
+    Some(conn)
+}
@@ -0,0 +1,154 @@
+//! Session helpers on `TimescaleDbConnection`.
+
+mod common;
+
+use diesel::prelude::*;
+use diesel_timescaledb::connection::TimescaleDbConnection;
+use diesel_timescaledb::TimescaleError;
+
+#[test]
+fn query_excluding_every_chunk_counts_as_excluding() {
+    let Some(conn) = common::timescale_connection() else {
+        return;
+    };
+    let mut conn = TimescaleDbConnection::new(conn);
+    conn.batch_execute(
+        "DROP TABLE IF EXISTS it_excluded_metrics;
+         CREATE TABLE it_excluded_metrics (time TIMESTAMPTZ NOT NULL, value DOUBLE PRECISION);
+         SELECT create_hypertable('it_excluded_metrics', 'time',
+                                  chunk_time_interval => INTERVAL '1 day');
+         INSERT INTO it_excluded_metrics
+         SELECT t, 1.0 FROM generate_series(TIMESTAMPTZ '2024-01-01',
+                                            TIMESTAMPTZ '2024-01-05',
+                                            INTERVAL '1 hour') AS t;",
+    )
+    .unwrap();
+
+    assert!(conn
+        .query_excludes_chunks(
+            "SELECT * FROM it_excluded_metrics WHERE time < TIMESTAMPTZ '2000-01-01'"
+        )
+        .unwrap());
+    assert!(conn
+        .query_excludes_chunks(
+            "SELECT * FROM it_excluded_metrics WHERE time < TIMESTAMPTZ '2024-01-02'"
+        )
+        .unwrap());
+    assert!(!conn
+        .query_excludes_chunks("SELECT * FROM it_excluded_metrics")
+        .unwrap());
+
+    diesel::sql_query("DROP TABLE it_excluded_metrics")
+        .execute(&mut *conn)
+        .unwrap();
+}
+
+#[test]
+fn session_timezone_follows_set_time_zone() {
+    let Some(conn) = common::connection() else {
+        return;
+    };
+    let mut conn = TimescaleDbConnection::new(conn);
+    diesel::sql_query("SET TIME ZONE 'Europe/Berlin'")
+        .execute(&mut *conn)
+        .unwrap();
+    assert_eq!(conn.session_timezone().unwrap(), "Europe/Berlin");
+}
+
+#[test]
+fn edition_requires_timescaledb_in_the_session() {
+    let Some(conn) = common::connection() else {
+        return;
+    };
+    let mut conn = TimescaleDbConnection::new(conn);
+    let loaded: bool = diesel::select(diesel::dsl::sql::<diesel::sql_types::Bool>(
+        "EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'timescaledb')",
+    ))
+    .get_result(conn.connection_mut())
+    .unwrap();
+
+    match conn.edition() {
+        Ok(edition) => {
+            assert!(loaded);
+            assert!(edition == "apache" || edition == "timescale", "{}", edition);
+        }
+        Err(error) => {
+            assert!(!loaded);
+            assert!(matches!(error, TimescaleError::ExtensionNotInstalled));
+        }
+    }
+}
+
+#[test]
+fn require_min_version_gates_on_the_enabled_extension() {
+    let Some(conn) = common::connection() else {
+        return;
+    };
+    let mut conn = TimescaleDbConnection::new(conn);
+    let loaded: bool = diesel::select(diesel::dsl::sql::<diesel::sql_types::Bool>(
+        "EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'timescaledb')",
+    ))
+    .get_result(conn.connection_mut())
+    .unwrap();
+
+    let result = conn.require_min_version("Time travel", 99, 0);
+    if loaded {
+        assert!(conn.require_min_version("Hypertables", 1, 0).is_ok());
+        assert!(matches!(
+            result,
+            Err(TimescaleError::UnsupportedVersion { ref feature, .. }) if feature == "Time travel"
+        ));
+    } else {
+        assert!(matches!(result, Err(TimescaleError::ExtensionNotInstalled)));
+    }
+}
+
+#[test]
+fn chunk_scan_stats_counts_only_the_queried_hypertable() {
+    let Some(conn) = common::timescale_connection() else {
+        return;
+    };
+    let mut conn = TimescaleDbConnection::new(conn);
+    // A hypertable of the same name in another schema must not be counted
+    conn.batch_execute(
+        "DROP TABLE IF EXISTS it_scan_stats;
+         DROP SCHEMA IF EXISTS it_scan_stats_other CASCADE;
+         CREATE SCHEMA it_scan_stats_other;
+         CREATE TABLE it_scan_stats (time TIMESTAMPTZ NOT NULL, value DOUBLE PRECISION);
+         CREATE TABLE it_scan_stats_other.it_scan_stats
+             (time TIMESTAMPTZ NOT NULL, value DOUBLE PRECISION);
+         SELECT create_hypertable('it_scan_stats', 'time',
+                                  chunk_time_interval => INTERVAL '1 day');
+         SELECT create_hypertable('it_scan_stats_other.it_scan_stats', 'time',
+                                  chunk_time_interval => INTERVAL '1 day');
+         INSERT INTO it_scan_stats
+         SELECT t, 1.0 FROM generate_series(TIMESTAMPTZ '2024-01-01 12:00+00',
+                                            TIMESTAMPTZ '2024-01-04 12:00+00',
+                                            INTERVAL '1 day') AS t;
+         INSERT INTO it_scan_stats_other.it_scan_stats
+         SELECT t, 1.0 FROM generate_series(TIMESTAMPTZ '2024-01-01 12:00+00',
+                                            TIMESTAMPTZ '2024-01-10 12:00+00',
+                                            INTERVAL '1 day') AS t;",
+    )
+    .unwrap();
+
+    let stats = conn
+        .chunk_scan_stats("SELECT * FROM it_scan_stats WHERE time >= TIMESTAMPTZ '2024-01-03'")
+        .unwrap();
+    assert_eq!(stats.total_chunks, 4);
+    assert_eq!(stats.scanned_chunks, 2);
+    assert_eq!(stats.excluded_chunks, 2);
+
+    let stats = conn
+        .chunk_scan_stats("SELECT * FROM it_scan_stats WHERE time < TIMESTAMPTZ '2000-01-01'")
+        .unwrap();
+    assert_eq!(stats.total_chunks, 4);
+    assert_eq!(stats.scanned_chunks, 0);
+    assert_eq!(stats.exclusion_ratio(), 1.0);
+
+    conn.batch_execute(
+        "DROP TABLE it_scan_stats;
+         DROP SCHEMA it_scan_stats_other CASCADE;",
+    )
+    .unwrap();
+}
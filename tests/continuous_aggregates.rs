@@ -0,0 +1,149 @@
+//! Creating, refreshing, listing and dropping continuous aggregates.
+
+mod common;
+
+use chrono::{TimeZone, Utc};
+use diesel::prelude::*;
+use diesel::sql_types::{Double, Nullable, Timestamptz};
+use diesel_timescaledb::schema::{
+    finalize_continuous_aggregate, list_continuous_aggregates, query_materialized_only,
+    ContinuousAggregateConfig, DynamicHypertable,
+};
+
+#[derive(QueryableByName, Debug, PartialEq)]
+struct HourlyAverage {
+    #[diesel(sql_type = Timestamptz)]
+    bucket: chrono::DateTime<Utc>,
+    #[diesel(sql_type = Nullable<Double>)]
+    average: Option<f64>,
+}
+
+fn hourly_averages(conn: &mut PgConnection) -> Vec<HourlyAverage> {
+    diesel::sql_query("SELECT bucket, average FROM it_cagg_hourly ORDER BY bucket")
+        .load(conn)
+        .unwrap()
+}
+
+#[test]
+fn continuous_aggregate_is_created_refreshed_listed_and_dropped() {
+    let Some(mut conn) = common::timescale_connection() else {
+        return;
+    };
+    diesel::sql_query("DROP MATERIALIZED VIEW IF EXISTS it_cagg_hourly")
+        .execute(&mut conn)
+        .unwrap();
+    diesel::sql_query("DROP TABLE IF EXISTS it_cagg")
+        .execute(&mut conn)
+        .unwrap();
+    diesel::sql_query("CREATE TABLE it_cagg (ts TIMESTAMPTZ NOT NULL, value DOUBLE PRECISION)")
+        .execute(&mut conn)
+        .unwrap();
+    DynamicHypertable::from_names("it_cagg", "ts")
+        .unwrap()
+        .create_hypertable(&mut conn)
+        .unwrap();
+
+    let config = ContinuousAggregateConfig::new(
+        "it_cagg_hourly",
+        "SELECT time_bucket('1 hour', ts) AS bucket, avg(value) AS average \
+         FROM it_cagg GROUP BY bucket",
+    )
+    .with_materialized_only(true);
+    config.create(&mut conn).unwrap();
+    assert!(hourly_averages(&mut conn).is_empty());
+
+    diesel::sql_query(
+        "INSERT INTO it_cagg VALUES \
+         ('2026-03-14 10:15+00', 1), ('2026-03-14 10:45+00', 3), ('2026-03-14 12:15+00', 5)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+
+    // Only the window of the 10:00 bucket is materialized
+    let hour = |h| Utc.with_ymd_and_hms(2026, 3, 14, h, 0, 0).unwrap();
+    config
+        .refresh(&mut conn, Some(hour(10)), Some(hour(11)))
+        .unwrap();
+    assert_eq!(
+        hourly_averages(&mut conn),
+        vec![HourlyAverage {
+            bucket: hour(10),
+            average: Some(2.0)
+        }]
+    );
+
+    let listed = list_continuous_aggregates(&mut conn)
+        .unwrap()
+        .into_iter()
+        .find(|aggregate| aggregate.view_name == "it_cagg_hourly")
+        .expect("the aggregate is listed");
+    assert_eq!(listed.hypertable_name, "it_cagg");
+    assert!(listed.materialized_only);
+
+    config.drop(&mut conn).unwrap();
+    assert!(list_continuous_aggregates(&mut conn)
+        .unwrap()
+        .iter()
+        .all(|aggregate| aggregate.view_name != "it_cagg_hourly"));
+
+    diesel::sql_query("DROP TABLE it_cagg")
+        .execute(&mut conn)
+        .unwrap();
+}
+
+#[test]
+fn qualified_mixed_case_aggregates_are_queried_and_finalized() {
+    let Some(mut conn) = common::timescale_connection() else {
+        return;
+    };
+    diesel::sql_query("DROP SCHEMA IF EXISTS it_cagg_case CASCADE")
+        .execute(&mut conn)
+        .unwrap();
+    diesel::sql_query("CREATE SCHEMA it_cagg_case")
+        .execute(&mut conn)
+        .unwrap();
+    diesel::sql_query(
+        "CREATE TABLE it_cagg_case.\"Readings\" (ts TIMESTAMPTZ NOT NULL, value DOUBLE PRECISION)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+    DynamicHypertable::from_names("it_cagg_case.Readings", "ts")
+        .unwrap()
+        .create_hypertable(&mut conn)
+        .unwrap();
+    let config = ContinuousAggregateConfig::new(
+        "it_cagg_case.Hourly",
+        "SELECT time_bucket('1 hour', ts) AS bucket, avg(value) AS average \
+         FROM it_cagg_case.\"Readings\" GROUP BY bucket",
+    )
+    .with_materialized_only(false);
+    config.create(&mut conn).unwrap();
+    diesel::sql_query(
+        "INSERT INTO it_cagg_case.\"Readings\" VALUES \
+         ('2026-03-14 10:15+00', 1), ('2026-03-14 10:45+00', 3), ('2026-03-14 12:15+00', 5)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+
+    // The real-time part above the watermark is left out
+    let hour = |h| Utc.with_ymd_and_hms(2026, 3, 14, h, 0, 0).unwrap();
+    config
+        .refresh(&mut conn, Some(hour(10)), Some(hour(11)))
+        .unwrap();
+    let rows: Vec<HourlyAverage> =
+        query_materialized_only(&mut conn, "it_cagg_case.Hourly", "bucket").unwrap();
+    assert_eq!(
+        rows,
+        vec![HourlyAverage {
+            bucket: hour(10),
+            average: Some(2.0)
+        }]
+    );
+
+    // Aggregates are created finalized, so there is nothing to migrate
+    assert!(!finalize_continuous_aggregate(&mut conn, "it_cagg_case.Hourly").unwrap());
+
+    diesel::sql_query("DROP SCHEMA it_cagg_case CASCADE")
+        .execute(&mut conn)
+        .unwrap();
+}
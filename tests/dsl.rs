@@ -0,0 +1,403 @@
+//! DSL filters run against the database.
+
+mod common;
+
+use chrono::{TimeZone, Utc};
+use diesel::prelude::*;
+use diesel_timescaledb::dsl::{IntegerBucketWidth, TimeRange, TimeSpine, TimescaleQueryDsl};
+use diesel_timescaledb::functions::utilities::{
+    time_bucket_gapfill_expr, time_bucket_integer_expr, time_bucket_tz_expr,
+};
+use diesel_timescaledb::functions::{interpolate, locf};
+use diesel_timescaledb::schema::{BucketGapfill, GapfillFill, IntervalExt, RollupAggregate};
+use diesel_timescaledb::types::TimestampTz;
+
+diesel::table! {
+    it_recent (id) {
+        id -> Integer,
+        timestamp -> Timestamptz,
+    }
+}
+
+#[test]
+fn recent_keeps_rows_within_the_interval_of_now() {
+    let Some(mut conn) = common::connection() else {
+        return;
+    };
+    diesel::sql_query("DROP TABLE IF EXISTS it_recent")
+        .execute(&mut conn)
+        .unwrap();
+    diesel::sql_query(
+        "CREATE TABLE it_recent (id INTEGER PRIMARY KEY, timestamp TIMESTAMPTZ NOT NULL)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+    diesel::sql_query(
+        "INSERT INTO it_recent VALUES \
+         (1, now() - INTERVAL '1 hour'), (2, now() - INTERVAL '6 days'), \
+         (3, now() - INTERVAL '8 days')",
+    )
+    .execute(&mut conn)
+    .unwrap();
+
+    let ids: Vec<i32> = it_recent::table
+        .recent(it_recent::timestamp, 7.days())
+        .select(it_recent::id)
+        .order(it_recent::id)
+        .load(&mut conn)
+        .unwrap();
+    assert_eq!(ids, vec![1, 2]);
+
+    diesel::sql_query("DROP TABLE it_recent")
+        .execute(&mut conn)
+        .unwrap();
+}
+
+diesel::table! {
+    it_bucketed (timestamp) {
+        timestamp -> Timestamptz,
+        value -> Double,
+    }
+}
+
+#[test]
+fn select_bucketed_loads_one_row_per_bucket() {
+    let Some(mut conn) = common::timescale_connection() else {
+        return;
+    };
+    diesel::sql_query("DROP TABLE IF EXISTS it_bucketed")
+        .execute(&mut conn)
+        .unwrap();
+    diesel::sql_query(
+        "CREATE TABLE it_bucketed (timestamp TIMESTAMPTZ NOT NULL, value DOUBLE PRECISION NOT NULL)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+    diesel::sql_query(
+        "INSERT INTO it_bucketed VALUES \
+         ('2026-03-14 10:05+00', 1), ('2026-03-14 10:40+00', 3), ('2026-03-14 11:10+00', 5)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+
+    let mut rows: Vec<(TimestampTz, Option<f64>)> = it_bucketed::table
+        .time_bucket(it_bucketed::timestamp, 1.hours())
+        .select_bucketed(diesel::dsl::avg(it_bucketed::value))
+        .load(&mut conn)
+        .unwrap();
+    rows.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        rows,
+        vec![
+            (
+                TimestampTz(Utc.with_ymd_and_hms(2026, 3, 14, 10, 0, 0).unwrap()),
+                Some(2.0)
+            ),
+            (
+                TimestampTz(Utc.with_ymd_and_hms(2026, 3, 14, 11, 0, 0).unwrap()),
+                Some(5.0)
+            ),
+        ]
+    );
+
+    diesel::sql_query("DROP TABLE it_bucketed")
+        .execute(&mut conn)
+        .unwrap();
+}
+
+diesel::table! {
+    it_gapfill (timestamp) {
+        timestamp -> Timestamptz,
+        value -> Double,
+    }
+}
+
+#[test]
+fn gapfill_returns_a_row_for_every_bucket() {
+    let Some(mut conn) = common::timescale_connection() else {
+        return;
+    };
+    diesel::sql_query("DROP TABLE IF EXISTS it_gapfill")
+        .execute(&mut conn)
+        .unwrap();
+    diesel::sql_query(
+        "CREATE TABLE it_gapfill (timestamp TIMESTAMPTZ NOT NULL, value DOUBLE PRECISION NOT NULL)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+    diesel::sql_query(
+        "INSERT INTO it_gapfill VALUES ('2026-03-14 10:15+00', 1), ('2026-03-14 13:15+00', 4)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+
+    let hour = |h| Utc.with_ymd_and_hms(2026, 3, 14, h, 0, 0).unwrap();
+    let range = TimeRange::new(hour(10), hour(14)).unwrap();
+    let bucket = time_bucket_gapfill_expr(1.hours(), it_gapfill::timestamp, range);
+    let rows: Vec<(chrono::DateTime<Utc>, Option<f64>, Option<f64>)> = it_gapfill::table
+        .filter(it_gapfill::timestamp.ge(hour(10)))
+        .filter(it_gapfill::timestamp.lt(hour(14)))
+        .group_by(bucket.clone())
+        .select((
+            bucket.clone(),
+            locf(diesel::dsl::avg(it_gapfill::value)),
+            interpolate(diesel::dsl::avg(it_gapfill::value)),
+        ))
+        .order(bucket)
+        .load(&mut conn)
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            (hour(10), Some(1.0), Some(1.0)),
+            (hour(11), Some(1.0), Some(2.0)),
+            (hour(12), Some(1.0), Some(3.0)),
+            (hour(13), Some(4.0), Some(4.0)),
+        ]
+    );
+
+    diesel::sql_query("DROP TABLE it_gapfill")
+        .execute(&mut conn)
+        .unwrap();
+}
+
+#[derive(QueryableByName, Debug, PartialEq)]
+struct GapfilledRow {
+    #[diesel(sql_type = diesel::sql_types::Timestamptz)]
+    bucket: chrono::DateTime<Utc>,
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Double>)]
+    last_value: Option<f64>,
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Double>)]
+    mid_value: Option<f64>,
+}
+
+#[test]
+fn bucket_gapfill_loads_a_filled_row_for_every_bucket() {
+    let Some(mut conn) = common::timescale_connection() else {
+        return;
+    };
+    diesel::sql_query("DROP TABLE IF EXISTS it_bucket_gapfill")
+        .execute(&mut conn)
+        .unwrap();
+    diesel::sql_query(
+        "CREATE TABLE it_bucket_gapfill (ts TIMESTAMPTZ NOT NULL, value DOUBLE PRECISION NOT NULL)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+    // The rows outside the range must not leak into the edge buckets
+    diesel::sql_query(
+        "INSERT INTO it_bucket_gapfill VALUES ('2026-03-14 09:15+00', 100), \
+         ('2026-03-14 10:15+00', 1), ('2026-03-14 13:15+00', 4), ('2026-03-14 14:15+00', 100)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+
+    let hour = |h| Utc.with_ymd_and_hms(2026, 3, 14, h, 0, 0).unwrap();
+    let range = TimeRange::new(hour(10), hour(14)).unwrap();
+    let rows: Vec<GapfilledRow> = BucketGapfill::new("it_bucket_gapfill", "ts", 1.hours(), range)
+        .with_value(
+            "last_value",
+            "value",
+            RollupAggregate::Avg,
+            GapfillFill::Locf,
+        )
+        .with_value(
+            "mid_value",
+            "value",
+            RollupAggregate::Avg,
+            GapfillFill::Interpolate,
+        )
+        .load(&mut conn)
+        .unwrap();
+    let row = |h, last_value, mid_value| GapfilledRow {
+        bucket: hour(h),
+        last_value: Some(last_value),
+        mid_value: Some(mid_value),
+    };
+    assert_eq!(
+        rows,
+        vec![
+            row(10, 1.0, 1.0),
+            row(11, 1.0, 2.0),
+            row(12, 1.0, 3.0),
+            row(13, 4.0, 4.0),
+        ]
+    );
+
+    diesel::sql_query("DROP TABLE it_bucket_gapfill")
+        .execute(&mut conn)
+        .unwrap();
+}
+
+diesel::table! {
+    it_bucket_tz (timestamp) {
+        timestamp -> Timestamptz,
+        value -> Double,
+    }
+}
+
+#[test]
+fn local_day_buckets_shift_by_an_hour_across_daylight_saving_time() {
+    let Some(mut conn) = common::timescale_connection() else {
+        return;
+    };
+    diesel::sql_query("DROP TABLE IF EXISTS it_bucket_tz")
+        .execute(&mut conn)
+        .unwrap();
+    diesel::sql_query(
+        "CREATE TABLE it_bucket_tz (timestamp TIMESTAMPTZ NOT NULL, value DOUBLE PRECISION NOT NULL)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+    // Berlin moves from UTC+1 to UTC+2 on 2026-03-29
+    diesel::sql_query(
+        "INSERT INTO it_bucket_tz VALUES \
+         ('2026-03-28 12:00+00', 1), ('2026-03-29 12:00+00', 2), ('2026-03-30 12:00+00', 3)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+
+    let day = time_bucket_tz_expr(1.days(), it_bucket_tz::timestamp, "Europe/Berlin").unwrap();
+    let buckets: Vec<chrono::DateTime<Utc>> = it_bucket_tz::table
+        .group_by(day.clone())
+        .select(day.clone())
+        .order(day)
+        .load(&mut conn)
+        .unwrap();
+    assert_eq!(
+        buckets,
+        vec![
+            Utc.with_ymd_and_hms(2026, 3, 27, 23, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2026, 3, 28, 23, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2026, 3, 29, 22, 0, 0).unwrap(),
+        ]
+    );
+
+    diesel::sql_query("DROP TABLE it_bucket_tz")
+        .execute(&mut conn)
+        .unwrap();
+}
+
+#[test]
+fn time_spine_loads_every_step_including_the_end() {
+    let Some(mut conn) = common::connection() else {
+        return;
+    };
+    let hour = |h| Utc.with_ymd_and_hms(2026, 3, 14, h, 0, 0).unwrap();
+    let spine = TimeSpine::new(hour(10), hour(13), 1.hours()).unwrap();
+    assert_eq!(
+        spine.load(&mut conn).unwrap(),
+        vec![hour(10), hour(11), hour(12), hour(13)]
+    );
+
+    // An end between steps is left out
+    let uneven = TimeSpine::new(hour(10), hour(13), 2.hours()).unwrap();
+    assert_eq!(uneven.load(&mut conn).unwrap(), vec![hour(10), hour(12)]);
+}
+
+diesel::table! {
+    it_time_range (id) {
+        id -> Integer,
+        timestamp -> Timestamptz,
+    }
+}
+
+#[test]
+fn time_range_returns_only_rows_inside_the_range() {
+    let Some(mut conn) = common::connection() else {
+        return;
+    };
+    diesel::sql_query("DROP TABLE IF EXISTS it_time_range")
+        .execute(&mut conn)
+        .unwrap();
+    diesel::sql_query(
+        "CREATE TABLE it_time_range (id INTEGER PRIMARY KEY, timestamp TIMESTAMPTZ NOT NULL)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+    // A row just before the start, on the start, inside, on the end and
+    // just after the end
+    diesel::sql_query(
+        "INSERT INTO it_time_range VALUES \
+         (1, '2026-03-14 09:59:59.999999+00'), (2, '2026-03-14 10:00+00'), \
+         (3, '2026-03-14 11:00+00'), (4, '2026-03-14 12:00+00'), \
+         (5, '2026-03-14 12:00:00.000001+00')",
+    )
+    .execute(&mut conn)
+    .unwrap();
+
+    let hour = |h| Utc.with_ymd_and_hms(2026, 3, 14, h, 0, 0).unwrap();
+    let ids = |rows: Vec<(i32, chrono::DateTime<Utc>)>| {
+        let mut ids: Vec<i32> = rows.into_iter().map(|(id, _)| id).collect();
+        ids.sort();
+        ids
+    };
+
+    let half_open = it_time_range::table
+        .time_range(it_time_range::timestamp, hour(10), hour(12))
+        .load(&mut conn)
+        .unwrap();
+    assert_eq!(ids(half_open), vec![2, 3]);
+
+    let closed = it_time_range::table
+        .time_range_closed(it_time_range::timestamp, hour(10), hour(12))
+        .load(&mut conn)
+        .unwrap();
+    assert_eq!(ids(closed), vec![2, 3, 4]);
+
+    let reopened = it_time_range::table
+        .time_range_closed(it_time_range::timestamp, hour(10), hour(12))
+        .exclusive_end()
+        .load(&mut conn)
+        .unwrap();
+    assert_eq!(ids(reopened), vec![2, 3]);
+
+    diesel::sql_query("DROP TABLE it_time_range")
+        .execute(&mut conn)
+        .unwrap();
+}
+
+diesel::table! {
+    it_nanosecond_events (id) {
+        id -> Integer,
+        ts -> BigInt,
+    }
+}
+
+#[test]
+fn nanosecond_columns_bucket_by_their_integer_width() {
+    let Some(mut conn) = common::timescale_connection() else {
+        return;
+    };
+    diesel::sql_query("DROP TABLE IF EXISTS it_nanosecond_events")
+        .execute(&mut conn)
+        .unwrap();
+    diesel::sql_query(
+        "CREATE TABLE it_nanosecond_events (id INTEGER PRIMARY KEY, ts BIGINT NOT NULL)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+    // Two events in the first second, one in the third
+    diesel::sql_query(
+        "INSERT INTO it_nanosecond_events VALUES \
+         (1, 1000), (2, 999999999), (3, 2500000000)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+
+    let second = time_bucket_integer_expr(
+        IntegerBucketWidth::nanoseconds(&1.seconds()).unwrap(),
+        it_nanosecond_events::ts,
+    );
+    let rows: Vec<(i64, i64)> = it_nanosecond_events::table
+        .group_by(second.clone())
+        .select((second.clone(), diesel::dsl::count(it_nanosecond_events::id)))
+        .order(second)
+        .load(&mut conn)
+        .unwrap();
+    assert_eq!(rows, vec![(0, 2), (2_000_000_000, 1)]);
+
+    diesel::sql_query("DROP TABLE it_nanosecond_events")
+        .execute(&mut conn)
+        .unwrap();
+}
@@ -0,0 +1,32 @@
+//! Validation errors surfacing from the database helpers.
+
+mod common;
+
+use diesel_timescaledb::schema::{Hypertable, ValidationError};
+use diesel_timescaledb::TimescaleError;
+
+diesel::table! {
+    it_errors (ts) {
+        ts -> Timestamptz,
+    }
+}
+
+diesel_timescaledb::hypertable!(it_errors, ts);
+
+#[test]
+fn invalid_interval_is_a_validation_error_not_a_database_error() {
+    let Some(mut conn) = common::connection() else {
+        return;
+    };
+    // The interval is rejected before the table is looked up, so the table
+    // need not exist
+    let error = it_errors::table::add_retention_policy_str(&mut conn, "every tuesday").unwrap_err();
+    assert!(
+        matches!(
+            error,
+            TimescaleError::Validation(ValidationError::InvalidInterval(_))
+        ),
+        "{:?}",
+        error
+    );
+}
@@ -0,0 +1,84 @@
+//! TimescaleDB aggregate functions run against the database.
+
+mod common;
+
+use diesel::prelude::*;
+use diesel_timescaledb::functions::{first, last};
+
+diesel::table! {
+    it_first_last (device, timestamp) {
+        device -> Integer,
+        timestamp -> Timestamptz,
+        value -> Double,
+        label -> Text,
+    }
+}
+
+/// A device with the first and last of its value and label.
+type FirstLastRow = (
+    i32,
+    Option<f64>,
+    Option<f64>,
+    Option<String>,
+    Option<String>,
+);
+
+#[test]
+fn first_and_last_pick_values_by_time_per_group() {
+    let Some(mut conn) = common::timescale_connection() else {
+        return;
+    };
+    diesel::sql_query("DROP TABLE IF EXISTS it_first_last")
+        .execute(&mut conn)
+        .unwrap();
+    diesel::sql_query(
+        "CREATE TABLE it_first_last (device INTEGER NOT NULL, timestamp TIMESTAMPTZ NOT NULL, \
+         value DOUBLE PRECISION NOT NULL, label TEXT NOT NULL)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+    // Insert out of time order so the result cannot come from insertion order.
+    diesel::sql_query(
+        "INSERT INTO it_first_last VALUES \
+         (1, '2026-03-14 10:30+00', 2.5, 'middle'), (1, '2026-03-14 10:00+00', 1.5, 'early'), \
+         (1, '2026-03-14 11:00+00', 3.5, 'late'), (2, '2026-03-14 12:00+00', 9.0, 'only')",
+    )
+    .execute(&mut conn)
+    .unwrap();
+
+    let rows: Vec<FirstLastRow> = it_first_last::table
+        .group_by(it_first_last::device)
+        .select((
+            it_first_last::device,
+            first(it_first_last::value, it_first_last::timestamp),
+            last(it_first_last::value, it_first_last::timestamp),
+            first(it_first_last::label, it_first_last::timestamp),
+            last(it_first_last::label, it_first_last::timestamp),
+        ))
+        .order(it_first_last::device)
+        .load(&mut conn)
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            (
+                1,
+                Some(1.5),
+                Some(3.5),
+                Some("early".to_string()),
+                Some("late".to_string())
+            ),
+            (
+                2,
+                Some(9.0),
+                Some(9.0),
+                Some("only".to_string()),
+                Some("only".to_string())
+            ),
+        ]
+    );
+
+    diesel::sql_query("DROP TABLE it_first_last")
+        .execute(&mut conn)
+        .unwrap();
+}
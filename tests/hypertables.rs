@@ -0,0 +1,423 @@
+//! Creating hypertables and reading back their dimensions.
+
+mod common;
+
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Nullable};
+use diesel_timescaledb::schema::chunks::{compress_chunks, show_chunks};
+use diesel_timescaledb::schema::{
+    chunk_sizing_report, compression_ratio, compression_summary, hypertable_approximate_size,
+    list_retention_policies, suggest_segmentby, verify_chunk_constraints, CompressionConfig,
+    DynamicHypertable, Hypertable, HypertableConfig, IntervalExt, ValidationError,
+};
+use diesel_timescaledb::TimescaleError;
+
+#[derive(QueryableByName)]
+struct IntegerInterval {
+    #[diesel(sql_type = Nullable<BigInt>)]
+    integer_interval: Option<i64>,
+}
+
+#[test]
+fn integer_time_hypertable_uses_integer_chunk_interval() {
+    let Some(mut conn) = common::timescale_connection() else {
+        return;
+    };
+    diesel::sql_query("DROP TABLE IF EXISTS it_integer_hypertable")
+        .execute(&mut conn)
+        .unwrap();
+    diesel::sql_query(
+        "CREATE TABLE it_integer_hypertable (ts BIGINT NOT NULL, value DOUBLE PRECISION)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+
+    HypertableConfig::new("it_integer_hypertable", "ts")
+        .with_chunk_time_interval_int(1_000_000)
+        .create(&mut conn)
+        .unwrap();
+
+    let dimension: IntegerInterval = diesel::sql_query(
+        "SELECT integer_interval FROM timescaledb_information.dimensions \
+         WHERE hypertable_name = 'it_integer_hypertable'",
+    )
+    .get_result(&mut conn)
+    .unwrap();
+    assert_eq!(dimension.integer_interval, Some(1_000_000));
+
+    diesel::sql_query("DROP TABLE it_integer_hypertable")
+        .execute(&mut conn)
+        .unwrap();
+}
+
+#[test]
+fn retention_policies_list_their_drop_after_interval() {
+    let Some(mut conn) = common::timescale_connection() else {
+        return;
+    };
+    diesel::sql_query("DROP TABLE IF EXISTS it_retention")
+        .execute(&mut conn)
+        .unwrap();
+    diesel::sql_query(
+        "CREATE TABLE it_retention (ts TIMESTAMPTZ NOT NULL, value DOUBLE PRECISION)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+
+    let hypertable = DynamicHypertable::from_names("it_retention", "ts").unwrap();
+    hypertable.create_hypertable(&mut conn).unwrap();
+    hypertable
+        .add_retention_policy(&mut conn, 90.days())
+        .unwrap();
+
+    let policy = list_retention_policies(&mut conn)
+        .unwrap()
+        .into_iter()
+        .find(|policy| policy.hypertable_name == "it_retention")
+        .expect("the retention policy is listed");
+    assert_eq!(
+        policy
+            .drop_after
+            .map(|interval| interval.to_postgres_interval()),
+        Some("90 days".to_string())
+    );
+    assert_eq!(policy.drop_after_int, None);
+
+    diesel::sql_query("DROP TABLE it_retention")
+        .execute(&mut conn)
+        .unwrap();
+}
+
+#[test]
+fn bigint_hypertable_created_from_a_dynamic_hypertable() {
+    let Some(mut conn) = common::timescale_connection() else {
+        return;
+    };
+    diesel::sql_query("DROP TABLE IF EXISTS it_bigint_hypertable")
+        .execute(&mut conn)
+        .unwrap();
+    diesel::sql_query(
+        "CREATE TABLE it_bigint_hypertable (ts BIGINT NOT NULL, value DOUBLE PRECISION)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+
+    DynamicHypertable::from_names("it_bigint_hypertable", "ts")
+        .unwrap()
+        .create_hypertable_with_integer_interval(&mut conn, 86_400_000)
+        .unwrap();
+    diesel::sql_query("INSERT INTO it_bigint_hypertable VALUES (0, 1.0), (86400000, 2.0)")
+        .execute(&mut conn)
+        .unwrap();
+
+    let dimension: IntegerInterval = diesel::sql_query(
+        "SELECT integer_interval FROM timescaledb_information.dimensions \
+         WHERE hypertable_name = 'it_bigint_hypertable'",
+    )
+    .get_result(&mut conn)
+    .unwrap();
+    assert_eq!(dimension.integer_interval, Some(86_400_000));
+
+    diesel::sql_query("DROP TABLE it_bigint_hypertable")
+        .execute(&mut conn)
+        .unwrap();
+}
+
+#[test]
+fn chunk_constraints_are_checked_against_their_slices() {
+    let Some(mut conn) = common::timescale_connection() else {
+        return;
+    };
+    // The catalog change below is rolled back with the transaction
+    conn.begin_test_transaction().unwrap();
+    diesel::sql_query("SET LOCAL timezone = 'Europe/Berlin'")
+        .execute(&mut conn)
+        .unwrap();
+    diesel::sql_query(
+        "CREATE TABLE it_chunk_constraints (ts TIMESTAMPTZ NOT NULL, device TEXT NOT NULL)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+    diesel::sql_query(
+        "SELECT create_hypertable('it_chunk_constraints', 'ts', \
+         partitioning_column => 'device', number_partitions => 2)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+    diesel::sql_query(
+        "INSERT INTO it_chunk_constraints VALUES \
+         ('2026-03-14 10:00+00', 'a'), ('2026-03-14 10:00+00', 'b'), ('2026-06-01 10:00+00', 'a')",
+    )
+    .execute(&mut conn)
+    .unwrap();
+
+    assert_eq!(
+        verify_chunk_constraints(&mut conn, "it_chunk_constraints").unwrap(),
+        Vec::<String>::new()
+    );
+
+    // Move the end of the time slice of the newest chunk by a microsecond
+    diesel::sql_query(
+        "UPDATE _timescaledb_catalog.dimension_slice SET range_end = range_end + 1 \
+         WHERE id IN (SELECT cc.dimension_slice_id \
+                      FROM _timescaledb_catalog.chunk_constraint cc \
+                      JOIN _timescaledb_catalog.dimension_slice ds ON ds.id = cc.dimension_slice_id \
+                      JOIN _timescaledb_catalog.dimension d ON d.id = ds.dimension_id \
+                      JOIN _timescaledb_catalog.hypertable h ON h.id = d.hypertable_id \
+                      WHERE h.table_name = 'it_chunk_constraints' AND d.column_name = 'ts' \
+                      ORDER BY ds.range_start DESC LIMIT 1)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+
+    let inconsistent = verify_chunk_constraints(&mut conn, "it_chunk_constraints").unwrap();
+    assert_eq!(inconsistent.len(), 1);
+}
+
+#[test]
+fn show_chunks_reports_compressed_chunks() {
+    let Some(mut conn) = common::timescale_connection() else {
+        return;
+    };
+    diesel::sql_query("DROP TABLE IF EXISTS it_show_chunks")
+        .execute(&mut conn)
+        .unwrap();
+    diesel::sql_query(
+        "CREATE TABLE it_show_chunks (ts TIMESTAMPTZ NOT NULL, value DOUBLE PRECISION)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+    HypertableConfig::new("it_show_chunks", "ts")
+        .with_chunk_time_interval(1.days())
+        .create(&mut conn)
+        .unwrap();
+    CompressionConfig::new("it_show_chunks")
+        .create(&mut conn)
+        .unwrap();
+    diesel::sql_query(
+        "INSERT INTO it_show_chunks VALUES ('2026-03-14 10:00+00', 1.0), ('2026-03-15 10:00+00', 2.0)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+
+    let chunks = show_chunks(&mut conn, "it_show_chunks", None, None).unwrap();
+    assert_eq!(chunks.len(), 2);
+    assert!(chunks.iter().all(|chunk| !chunk.is_compressed));
+
+    let first = chunks[0].qualified_name();
+    assert_eq!(compress_chunks(&mut conn, &[first.as_str()]).unwrap(), 1);
+
+    let chunks = show_chunks(&mut conn, "it_show_chunks", None, None).unwrap();
+    assert!(chunks[0].is_compressed);
+    assert!(chunks[0].compressed_total_bytes.is_some());
+    assert!(!chunks[1].is_compressed);
+    assert_eq!(chunks[1].compressed_total_bytes, None);
+
+    diesel::sql_query("DROP TABLE it_show_chunks")
+        .execute(&mut conn)
+        .unwrap();
+}
+
+#[test]
+fn show_chunks_rejects_time_bounds_on_integer_hypertables() {
+    let Some(mut conn) = common::timescale_connection() else {
+        return;
+    };
+    diesel::sql_query("DROP TABLE IF EXISTS it_show_integer_chunks")
+        .execute(&mut conn)
+        .unwrap();
+    diesel::sql_query(
+        "CREATE TABLE it_show_integer_chunks (ts BIGINT NOT NULL, value DOUBLE PRECISION)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+    HypertableConfig::new("it_show_integer_chunks", "ts")
+        .with_chunk_time_interval_int(1_000)
+        .create(&mut conn)
+        .unwrap();
+    diesel::sql_query("INSERT INTO it_show_integer_chunks VALUES (0, 1.0)")
+        .execute(&mut conn)
+        .unwrap();
+
+    let chunks = show_chunks(&mut conn, "it_show_integer_chunks", None, None).unwrap();
+    assert_eq!(chunks.len(), 1);
+    assert_eq!(chunks[0].range_start, None);
+    assert!(matches!(
+        show_chunks(
+            &mut conn,
+            "it_show_integer_chunks",
+            Some(chrono::Utc::now()),
+            None
+        ),
+        Err(TimescaleError::Validation(
+            ValidationError::InvalidParameter(_)
+        ))
+    ));
+
+    diesel::sql_query("DROP TABLE it_show_integer_chunks")
+        .execute(&mut conn)
+        .unwrap();
+}
+
+diesel::table! {
+    it_macro_hypertable (ts) {
+        ts -> Timestamptz,
+        device_id -> Integer,
+        value -> Double,
+    }
+}
+
+diesel_timescaledb::hypertable!(
+    it_macro_hypertable,
+    ts,
+    chunk_interval = "6 hours",
+    partition_by = device_id,
+    partitions = 3,
+    if_not_exists = true
+);
+
+#[derive(QueryableByName)]
+struct TimeDimension {
+    #[diesel(sql_type = Nullable<BigInt>)]
+    interval_seconds: Option<i64>,
+}
+
+#[derive(QueryableByName)]
+struct SpaceDimension {
+    #[diesel(sql_type = Nullable<diesel::sql_types::Integer>)]
+    num_partitions: Option<i32>,
+}
+
+#[test]
+fn hypertable_macro_options_take_effect() {
+    let Some(mut conn) = common::timescale_connection() else {
+        return;
+    };
+    diesel::sql_query("DROP TABLE IF EXISTS it_macro_hypertable")
+        .execute(&mut conn)
+        .unwrap();
+    diesel::sql_query(
+        "CREATE TABLE it_macro_hypertable (ts TIMESTAMPTZ NOT NULL, device_id INTEGER NOT NULL, \
+         value DOUBLE PRECISION)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+
+    it_macro_hypertable::table::create_hypertable(&mut conn).unwrap();
+    // `if_not_exists` makes a second call a no-op
+    it_macro_hypertable::table::create_hypertable(&mut conn).unwrap();
+
+    let time: TimeDimension = diesel::sql_query(
+        "SELECT extract(epoch FROM time_interval)::bigint AS interval_seconds \
+         FROM timescaledb_information.dimensions \
+         WHERE hypertable_name = 'it_macro_hypertable' AND column_name = 'ts'",
+    )
+    .get_result(&mut conn)
+    .unwrap();
+    assert_eq!(time.interval_seconds, Some(6 * 60 * 60));
+    let space: SpaceDimension = diesel::sql_query(
+        "SELECT num_partitions FROM timescaledb_information.dimensions \
+         WHERE hypertable_name = 'it_macro_hypertable' AND column_name = 'device_id'",
+    )
+    .get_result(&mut conn)
+    .unwrap();
+    assert_eq!(space.num_partitions, Some(3));
+
+    diesel::sql_query("DROP TABLE it_macro_hypertable")
+        .execute(&mut conn)
+        .unwrap();
+}
+
+#[test]
+fn qualified_mixed_case_hypertables_are_found_by_every_lookup() {
+    let Some(mut conn) = common::timescale_connection() else {
+        return;
+    };
+    diesel::sql_query("DROP SCHEMA IF EXISTS it_qualified_case CASCADE")
+        .execute(&mut conn)
+        .unwrap();
+    conn.batch_execute(
+        "CREATE SCHEMA it_qualified_case; \
+         CREATE TABLE it_qualified_case.\"Readings\" (ts TIMESTAMPTZ NOT NULL, value DOUBLE PRECISION); \
+         CREATE TABLE it_qualified_case.readings (ts TIMESTAMPTZ NOT NULL, value DOUBLE PRECISION)",
+    )
+    .unwrap();
+
+    // The lower-case table next to it must not be picked up instead
+    let name = "it_qualified_case.Readings";
+    let hypertable = DynamicHypertable::from_names(name, "ts").unwrap();
+    hypertable
+        .create_hypertable_with_interval(&mut conn, 1.days())
+        .unwrap();
+    hypertable
+        .add_retention_policy(&mut conn, 90.days())
+        .unwrap();
+    CompressionConfig::new(name).create(&mut conn).unwrap();
+    diesel::sql_query(
+        "INSERT INTO it_qualified_case.\"Readings\" VALUES \
+         ('2026-03-14 10:00+00', 1.0), ('2026-03-15 10:00+00', 2.0)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+
+    assert_eq!(
+        hypertable
+            .retention_interval(&mut conn)
+            .unwrap()
+            .map(|interval| interval.to_postgres_interval()),
+        Some("90 days".to_string())
+    );
+    assert!(hypertable.policies(&mut conn).unwrap().retention.is_some());
+    assert!(hypertable.show_tablespaces(&mut conn).unwrap().is_empty());
+
+    let chunks = show_chunks(&mut conn, name, None, None).unwrap();
+    let first = chunks[0].qualified_name();
+    compress_chunks(&mut conn, &[first.as_str()]).unwrap();
+
+    assert!(compression_ratio(&mut conn, name).unwrap().is_some());
+    let summary = compression_summary(&mut conn, name).unwrap();
+    assert_eq!(
+        (summary.compressed_chunks, summary.uncompressed_chunks),
+        (1, 1)
+    );
+    assert!(hypertable_approximate_size(&mut conn, name).unwrap() > 0);
+    let report = chunk_sizing_report(&mut conn, name).unwrap();
+    assert_eq!(report.chunk_count, 2);
+
+    diesel::sql_query("DROP SCHEMA it_qualified_case CASCADE")
+        .execute(&mut conn)
+        .unwrap();
+}
+
+#[test]
+fn segmentby_suggestions_read_the_statistics_of_a_qualified_mixed_case_table() {
+    let Some(mut conn) = common::connection() else {
+        return;
+    };
+    diesel::sql_query("DROP SCHEMA IF EXISTS it_segmentby_case CASCADE")
+        .execute(&mut conn)
+        .unwrap();
+    conn.batch_execute(
+        "CREATE SCHEMA it_segmentby_case; \
+         CREATE TABLE it_segmentby_case.\"Readings\" (device INTEGER, value DOUBLE PRECISION); \
+         INSERT INTO it_segmentby_case.\"Readings\" \
+         SELECT i % 4, random() FROM generate_series(1, 1000) AS i; \
+         ANALYZE it_segmentby_case.\"Readings\"",
+    )
+    .unwrap();
+
+    assert_eq!(
+        suggest_segmentby(
+            &mut conn,
+            "it_segmentby_case.Readings",
+            &["device", "value"]
+        )
+        .unwrap(),
+        vec!["device".to_string()]
+    );
+
+    diesel::sql_query("DROP SCHEMA it_segmentby_case CASCADE")
+        .execute(&mut conn)
+        .unwrap();
+}
@@ -0,0 +1,118 @@
+//! `TimeInterval` values bound as and loaded from PostgreSQL intervals.
+
+mod common;
+
+use diesel::pg::data_types::PgInterval;
+use diesel::prelude::*;
+use diesel::sql_types::{Interval, Text};
+use diesel_timescaledb::schema::TimeInterval;
+
+#[derive(QueryableByName)]
+struct Rendered {
+    #[diesel(sql_type = Text)]
+    bound: String,
+    #[diesel(sql_type = Text)]
+    literal: String,
+}
+
+#[derive(QueryableByName)]
+struct Loaded {
+    #[diesel(sql_type = Interval)]
+    interval: TimeInterval,
+}
+
+const LITERALS: &[&str] = &[
+    "250 microseconds",
+    "1500 milliseconds",
+    "90 minutes",
+    "36 hours",
+    "2 weeks",
+    "1 month",
+    "18 months",
+    "1 year",
+];
+
+#[test]
+fn bound_intervals_match_their_literals() {
+    let Some(mut conn) = common::connection() else {
+        return;
+    };
+
+    for literal in LITERALS {
+        let interval = TimeInterval::from_string(literal).unwrap();
+        // Compare the text output, since `=` treats `1 day` and `24 hours` as equal
+        let row: Rendered = diesel::sql_query(format!(
+            "SELECT $1::text AS bound, (INTERVAL '{}')::text AS literal",
+            literal
+        ))
+        .bind::<Interval, _>(&interval)
+        .get_result(&mut conn)
+        .unwrap();
+        assert_eq!(row.bound, row.literal, "binding '{}'", literal);
+    }
+}
+
+#[test]
+fn loaded_intervals_round_trip() {
+    let Some(mut conn) = common::connection() else {
+        return;
+    };
+
+    for literal in LITERALS {
+        let interval = TimeInterval::from_string(literal).unwrap();
+        let row: Loaded = diesel::sql_query("SELECT $1 AS interval")
+            .bind::<Interval, _>(&interval)
+            .get_result(&mut conn)
+            .unwrap();
+        assert_eq!(
+            PgInterval::try_from(&row.interval).unwrap(),
+            PgInterval::try_from(&interval).unwrap(),
+            "loading '{}'",
+            literal
+        );
+    }
+}
+
+#[test]
+fn policy_interval_strings_load_in_their_largest_units() {
+    let Some(mut conn) = common::connection() else {
+        return;
+    };
+
+    // Formats TimescaleDB writes into job configs, cast as list_retention_policies does
+    for (config, expected) in [
+        ("7 days", "1 weeks"),
+        ("01:30:00", "90 minutes"),
+        ("1 day 12:00:00", "1 days 12 hours"),
+        ("1 mon", "1 months"),
+        ("P1Y", "1 years"),
+    ] {
+        let row: Loaded = diesel::sql_query(
+            "SELECT (jsonb_build_object('drop_after', $1::text)->>'drop_after')::interval \
+             AS interval",
+        )
+        .bind::<Text, _>(config)
+        .get_result(&mut conn)
+        .unwrap();
+        assert_eq!(
+            row.interval.to_postgres_interval(),
+            expected,
+            "loading '{}'",
+            config
+        );
+    }
+}
+
+#[test]
+fn mixed_sign_intervals_fail_to_load() {
+    let Some(mut conn) = common::connection() else {
+        return;
+    };
+
+    let result = diesel::sql_query("SELECT INTERVAL '1 day -01:00:00' AS interval")
+        .get_result::<Loaded>(&mut conn);
+    assert!(matches!(
+        result,
+        Err(diesel::result::Error::DeserializationError(_))
+    ));
+}
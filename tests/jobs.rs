@@ -0,0 +1,58 @@
+//! Listing, altering and deleting background jobs.
+
+mod common;
+
+use diesel::prelude::*;
+use diesel_timescaledb::schema::jobs::{alter_job, delete_job, job_errors, list_jobs};
+use diesel_timescaledb::schema::{DynamicHypertable, IntervalExt, TimeInterval, TimeUnit};
+
+#[test]
+fn policy_jobs_are_listed_altered_and_deleted() {
+    let Some(mut conn) = common::timescale_connection() else {
+        return;
+    };
+    diesel::sql_query("DROP TABLE IF EXISTS it_jobs")
+        .execute(&mut conn)
+        .unwrap();
+    diesel::sql_query("CREATE TABLE it_jobs (ts TIMESTAMPTZ NOT NULL, value DOUBLE PRECISION)")
+        .execute(&mut conn)
+        .unwrap();
+
+    // Adding a policy adds its job
+    let hypertable = DynamicHypertable::from_names("it_jobs", "ts").unwrap();
+    hypertable.create_hypertable(&mut conn).unwrap();
+    hypertable
+        .add_retention_policy(&mut conn, 30.days())
+        .unwrap();
+
+    let jobs = list_jobs(&mut conn, Some("it_jobs")).unwrap();
+    assert_eq!(jobs.len(), 1);
+    let job = &jobs[0];
+    assert_eq!(job.proc_name, "policy_retention");
+    assert_eq!(job.hypertable_name.as_deref(), Some("it_jobs"));
+    assert!(job.scheduled);
+    assert!(job_errors(&mut conn, Some(job.job_id)).unwrap().is_empty());
+
+    alter_job(
+        &mut conn,
+        job.job_id,
+        Some(TimeInterval::new(30, TimeUnit::Minutes)),
+    )
+    .unwrap();
+    let altered = list_jobs(&mut conn, Some("it_jobs")).unwrap();
+    assert_eq!(
+        altered[0].schedule_interval,
+        TimeInterval::new(30, TimeUnit::Minutes)
+    );
+
+    delete_job(&mut conn, job.job_id).unwrap();
+    assert!(list_jobs(&mut conn, Some("it_jobs")).unwrap().is_empty());
+    assert!(list_jobs(&mut conn, None)
+        .unwrap()
+        .iter()
+        .all(|listed| listed.job_id != job.job_id));
+
+    diesel::sql_query("DROP TABLE it_jobs")
+        .execute(&mut conn)
+        .unwrap();
+}
@@ -0,0 +1,10 @@
+//! Compile checks of the `hypertable!` macro.
+
+#[test]
+fn hypertable_macro_accepts_its_forms() {
+    let cases = trybuild::TestCases::new();
+    cases.pass("tests/ui/hypertable_two_arguments.rs");
+    cases.pass("tests/ui/hypertable_options.rs");
+    cases.compile_fail("tests/ui/hypertable_unknown_partition_column.rs");
+    cases.compile_fail("tests/ui/hypertable_unknown_option.rs");
+}
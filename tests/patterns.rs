@@ -0,0 +1,120 @@
+//! Loading the result rows of the aggregation patterns.
+
+mod common;
+
+use chrono::{TimeZone, Utc};
+use diesel::prelude::*;
+use diesel_timescaledb::dsl::patterns::{BucketHistogramRow, TimeSeriesAggregation};
+use diesel_timescaledb::schema::{first_with_time, last_with_time, ValueAt};
+
+#[test]
+fn bucket_histogram_row_deserializes() {
+    let Some(mut conn) = common::connection() else {
+        return;
+    };
+
+    let row: BucketHistogramRow = diesel::sql_query(
+        "SELECT TIMESTAMPTZ '2026-03-14 10:00:00+00' AS bucket, ARRAY[0, 2, 1]::integer[] AS histogram",
+    )
+    .get_result(&mut conn)
+    .unwrap();
+    assert_eq!(
+        row,
+        BucketHistogramRow {
+            bucket: Utc.with_ymd_and_hms(2026, 3, 14, 10, 0, 0).unwrap(),
+            histogram: vec![0, 2, 1],
+        }
+    );
+}
+
+#[test]
+fn histogram_query_loads_one_row_per_bucket() {
+    let Some(mut conn) = common::timescale_connection() else {
+        return;
+    };
+    diesel::sql_query("DROP TABLE IF EXISTS it_histogram")
+        .execute(&mut conn)
+        .unwrap();
+    diesel::sql_query(
+        "CREATE TABLE it_histogram (timestamp TIMESTAMPTZ NOT NULL, value DOUBLE PRECISION)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+    diesel::sql_query(
+        "INSERT INTO it_histogram VALUES \
+         ('2026-03-14 10:05+00', 1), ('2026-03-14 10:10+00', 6), ('2026-03-14 10:20+00', 12), \
+         ('2026-03-14 11:05+00', 3)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+
+    let sql = TimeSeriesAggregation::new("it_histogram", "timestamp", "value", "1 hours")
+        .unwrap()
+        .histogram_query(0.0, 10.0, 2)
+        .unwrap();
+    let rows: Vec<BucketHistogramRow> = diesel::sql_query(sql).load(&mut conn).unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            BucketHistogramRow {
+                bucket: Utc.with_ymd_and_hms(2026, 3, 14, 10, 0, 0).unwrap(),
+                histogram: vec![0, 1, 1, 1],
+            },
+            BucketHistogramRow {
+                bucket: Utc.with_ymd_and_hms(2026, 3, 14, 11, 0, 0).unwrap(),
+                histogram: vec![0, 1, 0, 0],
+            },
+        ]
+    );
+
+    diesel::sql_query("DROP TABLE it_histogram")
+        .execute(&mut conn)
+        .unwrap();
+}
+
+#[test]
+fn first_and_last_with_time_read_the_outermost_rows() {
+    let Some(mut conn) = common::connection() else {
+        return;
+    };
+    diesel::sql_query("DROP TABLE IF EXISTS it_value_at")
+        .execute(&mut conn)
+        .unwrap();
+    diesel::sql_query("CREATE TABLE it_value_at (time TIMESTAMPTZ, value DOUBLE PRECISION)")
+        .execute(&mut conn)
+        .unwrap();
+
+    assert_eq!(
+        last_with_time(&mut conn, "it_value_at", "value", "time").unwrap(),
+        None
+    );
+
+    // The NULL value at the newest time is skipped
+    diesel::sql_query(
+        "INSERT INTO it_value_at VALUES \
+         ('2026-03-14 11:00+00', 2.0), ('2026-03-14 10:00+00', 1.0), \
+         ('2026-03-14 12:00+00', 3.0), ('2026-03-14 13:00+00', NULL)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+
+    let hour = |h| Utc.with_ymd_and_hms(2026, 3, 14, h, 0, 0).unwrap();
+    assert_eq!(
+        first_with_time(&mut conn, "it_value_at", "value", "time").unwrap(),
+        Some(ValueAt {
+            value: 1.0,
+            at: hour(10)
+        })
+    );
+    assert_eq!(
+        last_with_time(&mut conn, "it_value_at", "value", "time").unwrap(),
+        Some(ValueAt {
+            value: 3.0,
+            at: hour(12)
+        })
+    );
+
+    diesel::sql_query("DROP TABLE it_value_at")
+        .execute(&mut conn)
+        .unwrap();
+}
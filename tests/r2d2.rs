@@ -0,0 +1,68 @@
+//! Pools of `TimescaleDbConnection`s.
+#![cfg(feature = "r2d2")]
+
+mod common;
+
+use diesel::prelude::*;
+use diesel::r2d2::Pool;
+use diesel::sql_types::Integer;
+use diesel_timescaledb::r2d2::{build_r2d2_pool, TimescaleConnectionManager, TimescaleCustomizer};
+use std::sync::{Arc, Barrier};
+use std::thread;
+
+#[derive(QueryableByName)]
+struct Backend {
+    #[diesel(sql_type = Integer)]
+    pid: i32,
+}
+
+#[test]
+fn concurrent_checkouts_get_separate_sessions() {
+    let Some(url) = common::database_url() else {
+        return;
+    };
+    let pool = Pool::builder()
+        .max_size(4)
+        .build(TimescaleConnectionManager::new(url))
+        .unwrap();
+
+    // Hold every connection at once, so none is handed out twice
+    let barrier = Arc::new(Barrier::new(4));
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let pool = pool.clone();
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                let mut conn = pool.get().unwrap();
+                let backend: Backend = diesel::sql_query("SELECT pg_backend_pid() AS pid")
+                    .get_result(conn.connection_mut())
+                    .unwrap();
+                barrier.wait();
+                backend.pid
+            })
+        })
+        .collect();
+
+    let mut pids: Vec<i32> = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .collect();
+    pids.sort();
+    pids.dedup();
+    assert_eq!(pids.len(), 4);
+    assert_eq!(pool.state().connections, 4);
+}
+
+#[test]
+fn customized_pool_hands_out_timescale_connections() {
+    if common::timescale_connection().is_none() {
+        return;
+    }
+    let url = common::database_url().unwrap();
+    let pool = build_r2d2_pool(url, 2, TimescaleCustomizer::new()).unwrap();
+
+    let mut conn = pool.get().unwrap();
+    assert!(conn.timescaledb_version().unwrap().starts_with('2'));
+    let other = conn.try_clone_via_pool(&pool).unwrap();
+    drop(other);
+}
@@ -0,0 +1,71 @@
+//! `TimestamptzRange` values bound as and loaded from `tstzrange`.
+
+mod common;
+
+use chrono::{TimeZone, Utc};
+use diesel::prelude::*;
+use diesel::sql_types::{Bool, Text, Tstzrange};
+use diesel_timescaledb::types::TimestamptzRange;
+
+#[derive(QueryableByName)]
+struct Rendered {
+    #[diesel(sql_type = Text)]
+    range: String,
+}
+
+#[derive(QueryableByName)]
+struct Loaded {
+    #[diesel(sql_type = Tstzrange)]
+    range: TimestamptzRange,
+    #[diesel(sql_type = Bool)]
+    empty: bool,
+}
+
+#[test]
+fn half_open_range_binds_as_a_tstzrange() {
+    let Some(mut conn) = common::connection() else {
+        return;
+    };
+    let start = Utc.with_ymd_and_hms(2026, 3, 14, 0, 0, 0).unwrap();
+    let end = Utc.with_ymd_and_hms(2026, 3, 15, 0, 0, 0).unwrap();
+
+    diesel::sql_query("SET TIME ZONE 'UTC'")
+        .execute(&mut conn)
+        .unwrap();
+    let rendered: Rendered = diesel::sql_query("SELECT $1::text AS range")
+        .bind::<Tstzrange, _>(TimestamptzRange::half_open(start, end))
+        .get_result(&mut conn)
+        .unwrap();
+    assert_eq!(
+        rendered.range,
+        r#"["2026-03-14 00:00:00+00","2026-03-15 00:00:00+00")"#
+    );
+}
+
+#[test]
+fn tstzrange_loads_with_its_bounds() {
+    let Some(mut conn) = common::connection() else {
+        return;
+    };
+    let loaded: Loaded = diesel::sql_query(
+        "SELECT tstzrange('2026-03-14 00:00+00', NULL, '(]') AS range, false AS empty",
+    )
+    .get_result(&mut conn)
+    .unwrap();
+    assert_eq!(
+        loaded.range.start,
+        Some(Utc.with_ymd_and_hms(2026, 3, 14, 0, 0, 0).unwrap())
+    );
+    assert_eq!(loaded.range.end, None);
+    assert!(!loaded.range.lower_inclusive);
+    assert!(!loaded.empty);
+
+    let loaded: Loaded = diesel::sql_query(
+        "SELECT tstzrange('2026-03-14 00:00+00', '2026-03-14 00:00+00') AS range, \
+         isempty(tstzrange('2026-03-14 00:00+00', '2026-03-14 00:00+00')) AS empty",
+    )
+    .get_result(&mut conn)
+    .unwrap();
+    assert!(loaded.empty);
+    assert!(loaded.range.is_empty());
+}
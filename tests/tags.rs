@@ -0,0 +1,27 @@
+//! `InsertableTags` values inserted into JSONB columns.
+#![cfg(feature = "json")]
+
+mod common;
+
+use diesel::prelude::*;
+use diesel::sql_types::{Jsonb, Text};
+use diesel_timescaledb::types::InsertableTags;
+
+#[derive(QueryableByName)]
+struct Host {
+    #[diesel(sql_type = Text)]
+    host: String,
+}
+
+#[test]
+fn tags_bind_as_a_groupable_jsonb_object() {
+    let Some(mut conn) = common::connection() else {
+        return;
+    };
+    let tags = InsertableTags::new(serde_json::json!({"host": "a", "region": "eu"})).unwrap();
+    let row: Host = diesel::sql_query("SELECT $1->>'host' AS host")
+        .bind::<Jsonb, _>(&tags)
+        .get_result(&mut conn)
+        .unwrap();
+    assert_eq!(row.host, "a");
+}
@@ -0,0 +1,149 @@
+//! Toolkit hyperfunctions compared with exact results.
+#![cfg(feature = "toolkit")]
+
+mod common;
+
+use diesel::prelude::*;
+use diesel::sql_types::Double;
+use diesel_timescaledb::functions::toolkit::{
+    approx_percentile, approx_percentile_tdigest, counter_agg, counter_rate_query, delta,
+    percentile_agg, rate, tdigest, BucketRate,
+};
+use diesel_timescaledb::schema::{TimeInterval, TimeUnit};
+
+table! {
+    it_toolkit_latencies (id) {
+        id -> Int4,
+        latency_ms -> Float8,
+    }
+}
+
+table! {
+    it_toolkit_counters (ts) {
+        ts -> Timestamptz,
+        total -> Float8,
+    }
+}
+
+#[derive(QueryableByName)]
+struct ExactPercentile {
+    #[diesel(sql_type = Double)]
+    p95: f64,
+}
+
+#[test]
+fn approximate_p95_is_close_to_the_exact_percentile() {
+    let Some(mut conn) = common::toolkit_connection() else {
+        return;
+    };
+    diesel::sql_query("DROP TABLE IF EXISTS it_toolkit_latencies")
+        .execute(&mut conn)
+        .unwrap();
+    diesel::sql_query(
+        "CREATE TABLE it_toolkit_latencies (id SERIAL PRIMARY KEY, latency_ms DOUBLE PRECISION NOT NULL)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+    // A long-tailed distribution, as latencies usually are
+    diesel::sql_query(
+        "INSERT INTO it_toolkit_latencies (latency_ms) \
+         SELECT 5 + power(i::double precision / 10000, 4) * 500 FROM generate_series(1, 10000) i",
+    )
+    .execute(&mut conn)
+    .unwrap();
+
+    let exact = diesel::sql_query(
+        "SELECT percentile_cont(0.95) WITHIN GROUP (ORDER BY latency_ms) AS p95 \
+         FROM it_toolkit_latencies",
+    )
+    .get_result::<ExactPercentile>(&mut conn)
+    .unwrap()
+    .p95;
+
+    let uddsketch: Option<f64> = it_toolkit_latencies::table
+        .select(approx_percentile(
+            0.95,
+            percentile_agg(it_toolkit_latencies::latency_ms),
+        ))
+        .get_result(&mut conn)
+        .unwrap();
+    let digest: Option<f64> = it_toolkit_latencies::table
+        .select(approx_percentile_tdigest(
+            0.95,
+            tdigest(100, it_toolkit_latencies::latency_ms),
+        ))
+        .get_result(&mut conn)
+        .unwrap();
+
+    // The default UddSketch bounds the relative error at 0.1%; allow 1%
+    // for both summaries
+    for estimate in [uddsketch.unwrap(), digest.unwrap()] {
+        assert!(
+            (estimate - exact).abs() <= exact * 0.01,
+            "estimate {} is not within 1% of {}",
+            estimate,
+            exact
+        );
+    }
+
+    diesel::sql_query("DROP TABLE it_toolkit_latencies")
+        .execute(&mut conn)
+        .unwrap();
+}
+
+#[test]
+fn counter_rate_accounts_for_a_reset() {
+    let Some(mut conn) = common::toolkit_connection() else {
+        return;
+    };
+    diesel::sql_query("DROP TABLE IF EXISTS it_toolkit_counters")
+        .execute(&mut conn)
+        .unwrap();
+    diesel::sql_query(
+        "CREATE TABLE it_toolkit_counters (ts TIMESTAMPTZ NOT NULL, total DOUBLE PRECISION NOT NULL)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+    // The counter restarts from zero between 20 and 5: the increase across
+    // the reset is 5, for a total of 20 + 5 + 10 over 40 seconds
+    diesel::sql_query(
+        "INSERT INTO it_toolkit_counters VALUES \
+         ('2026-03-14 10:00:00+00', 0), ('2026-03-14 10:00:10+00', 10), \
+         ('2026-03-14 10:00:20+00', 20), ('2026-03-14 10:00:30+00', 5), \
+         ('2026-03-14 10:00:40+00', 15)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+
+    let (increase, per_second): (Option<f64>, Option<f64>) = it_toolkit_counters::table
+        .select((
+            delta(counter_agg(
+                it_toolkit_counters::ts,
+                it_toolkit_counters::total,
+            )),
+            rate(counter_agg(
+                it_toolkit_counters::ts,
+                it_toolkit_counters::total,
+            )),
+        ))
+        .get_result(&mut conn)
+        .unwrap();
+    assert_eq!(increase, Some(35.0));
+    assert_eq!(per_second, Some(35.0 / 40.0));
+
+    let buckets: Vec<BucketRate> = counter_rate_query(
+        "it_toolkit_counters",
+        "ts",
+        "total",
+        TimeInterval::new(1, TimeUnit::Hours),
+    )
+    .unwrap()
+    .load(&mut conn)
+    .unwrap();
+    assert_eq!(buckets.len(), 1);
+    assert_eq!(buckets[0].rate, Some(35.0 / 40.0));
+
+    diesel::sql_query("DROP TABLE it_toolkit_counters")
+        .execute(&mut conn)
+        .unwrap();
+}
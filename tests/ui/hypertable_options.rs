@@ -0,0 +1,26 @@
+use diesel_timescaledb::hypertable;
+use diesel_timescaledb::schema::Hypertable;
+
+diesel::table! {
+    metrics (timestamp) {
+        timestamp -> Timestamptz,
+        device_id -> Integer,
+        value -> Double,
+    }
+}
+
+hypertable!(
+    metrics,
+    timestamp,
+    chunk_interval = "7 days",
+    partition_by = device_id,
+    partitions = 4,
+    if_not_exists = true,
+);
+
+fn main() {
+    assert_eq!(<metrics::table as Hypertable>::CHUNK_TIME_INTERVAL, Some("7 days"));
+    assert_eq!(<metrics::table as Hypertable>::PARTITION_COLUMN, Some("device_id"));
+    assert_eq!(<metrics::table as Hypertable>::NUMBER_PARTITIONS, Some(4));
+    assert!(<metrics::table as Hypertable>::IF_NOT_EXISTS);
+}
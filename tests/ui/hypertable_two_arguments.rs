@@ -0,0 +1,16 @@
+use diesel_timescaledb::hypertable;
+use diesel_timescaledb::schema::Hypertable;
+
+diesel::table! {
+    metrics (timestamp) {
+        timestamp -> Timestamptz,
+        value -> Double,
+    }
+}
+
+hypertable!(metrics, timestamp);
+
+fn main() {
+    assert_eq!(<metrics::table as Hypertable>::CHUNK_TIME_INTERVAL, None);
+    assert!(!<metrics::table as Hypertable>::IF_NOT_EXISTS);
+}
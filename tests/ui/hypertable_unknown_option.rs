@@ -0,0 +1,12 @@
+use diesel_timescaledb::hypertable;
+
+diesel::table! {
+    metrics (timestamp) {
+        timestamp -> Timestamptz,
+        value -> Double,
+    }
+}
+
+hypertable!(metrics, timestamp, chunk_size = "7 days");
+
+fn main() {}
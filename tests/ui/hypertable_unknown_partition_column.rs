@@ -0,0 +1,12 @@
+use diesel_timescaledb::hypertable;
+
+diesel::table! {
+    metrics (timestamp) {
+        timestamp -> Timestamptz,
+        value -> Double,
+    }
+}
+
+hypertable!(metrics, timestamp, partition_by = device_id, partitions = 4);
+
+fn main() {}